@@ -0,0 +1,69 @@
+//! Measures how fast instructions run, which is what matters when the clock
+//! speed is turned up far past what the original hardware could do
+
+use chip_8::Chip8;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+/// How many instructions run in each iteration of the benchmark
+const CYCLES: u64 = 100_000;
+
+/// A loop that counts, does some math, and draws, so it runs a mix of
+/// instructions like a game would
+const ROM: [u16; 10] = [
+    0x7001, // add v0, 1
+    0x8104, // addy v1, v0
+    0x8213, // xor v2, v1
+    0xa000, // ldi 0x000
+    0xf029, // ldf v0
+    0x6300, // ld v3, 0
+    0xd335, // drw v3, v3, 5
+    0x3000, // se v0, 0
+    0x1200, // jp 0x200
+    0x1200, // jp 0x200
+];
+
+/// Loads the rom into an interpreter, with the decode cache or without it
+fn start(decode_cache: bool) -> Chip8 {
+    let rom: Vec<u8> = ROM.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    let mut chip8 = Chip8::with_seed(0);
+    if decode_cache {
+        chip8.enable_decode_cache();
+    }
+    chip8.load(&rom).unwrap();
+    chip8
+}
+
+fn clock(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clock");
+    group.throughput(Throughput::Elements(CYCLES));
+    for (name, decode_cache) in [("dispatch table", false), ("decode cache", true)] {
+        group.bench_function(name, |b| {
+            b.iter_batched_ref(
+                || start(decode_cache),
+                |chip8| {
+                    for _ in 0..CYCLES {
+                        chip8.clock().unwrap();
+                    }
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    // The blocks are compiled the first time through, and then kept for every
+    // iteration after that
+    #[cfg(feature = "jit")]
+    {
+        let mut jit = chip_8::jit::Jit::new().unwrap();
+        group.bench_function("jit", |b| {
+            b.iter_batched_ref(
+                || start(false),
+                |chip8| jit.run(chip8, CYCLES as usize).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, clock);
+criterion_main!(benches);
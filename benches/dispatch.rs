@@ -0,0 +1,82 @@
+//! Measures how fast each kind of instruction gets decoded and run, so that a
+//! change to the dispatch table or the decode cache can be checked against the
+//! instructions that it is meant to speed up
+
+use chip_8::Chip8;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+/// How many instructions run in each iteration of the benchmark
+const CYCLES: u64 = 100_000;
+
+/// Loops of one kind of instruction, with a jump back to the start at the
+/// end, so almost everything that runs is the kind being measured
+const LOOPS: [(&str, [u16; 8]); 5] = [
+    (
+        "arithmetic",
+        [
+            0x7001, 0x8104, 0x8213, 0x8325, 0x8406, 0x8501, 0x8632, 0x1200,
+        ],
+    ),
+    (
+        "skips",
+        // None of them skip, with register 1 set to 1 and the rest at 0
+        [
+            0x6101, 0x3001, 0x4000, 0x5010, 0x9000, 0x4200, 0x5120, 0x1200,
+        ],
+    ),
+    (
+        "memory",
+        // The index is left pointing at the same place, since the memory
+        // increment quirk is off
+        [
+            0xa300, 0xf355, 0xf365, 0xf333, 0xa300, 0xf31e, 0xf029, 0x1200,
+        ],
+    ),
+    (
+        "calls",
+        [
+            0x2206, 0x2206, 0x1200, 0x00ee, 0x00ee, 0x00ee, 0x00ee, 0x00ee,
+        ],
+    ),
+    (
+        "timers",
+        [
+            0x6010, 0xf015, 0xf107, 0xf018, 0xf207, 0xf015, 0xf307, 0x1200,
+        ],
+    ),
+];
+
+/// Loads the rom into an interpreter, with the decode cache or without it
+fn start(rom: &[u16], decode_cache: bool) -> Chip8 {
+    let rom: Vec<u8> = rom.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    let mut chip8 = Chip8::with_seed(0);
+    if decode_cache {
+        chip8.enable_decode_cache();
+    }
+    chip8.load(&rom).unwrap();
+    chip8
+}
+
+fn dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dispatch");
+    group.throughput(Throughput::Elements(CYCLES));
+    for (kind, rom) in LOOPS.iter() {
+        for (name, decode_cache) in [("dispatch table", false), ("decode cache", true)] {
+            group.bench_with_input(BenchmarkId::new(name, kind), rom, |b, rom| {
+                b.iter_batched_ref(
+                    || start(rom, decode_cache),
+                    |chip8| {
+                        for _ in 0..CYCLES {
+                            chip8.clock().unwrap();
+                        }
+                    },
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, dispatch);
+criterion_main!(benches);
@@ -0,0 +1,76 @@
+//! Measures how fast `dxyn` draws sprites, which is the slowest instruction
+//! that most roms run every frame
+
+use chip_8::{Chip8, Quirks};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+/// How many sprites get drawn in each iteration of the benchmark
+const SPRITES: u64 = 10_000;
+
+/// Where the sprite is drawn, which decides how much of it gets cut off or
+/// wrapped around, with the quirks that it is drawn with
+const PLACEMENTS: [(&str, u8, u8, bool); 4] = [
+    // Lined up with the bytes of the screen, so each row only touches one
+    ("aligned", 8, 8, false),
+    // Half way between two bytes, so each row touches two
+    ("unaligned", 12, 8, false),
+    // Over the bottom right corner, so most of it gets cut off
+    ("clipped", 60, 28, false),
+    // The same, but the rest of it wraps around to the other side
+    ("wrapped", 60, 28, true),
+];
+
+/// A rom that draws a sprite with `rows` rows at the place over and over, the
+/// sprite is the bytes right after the code, which are all on
+fn start(rows: u8, x: u8, y: u8, wrap: bool) -> Chip8 {
+    let opcodes = [
+        0x6000 | x as u16,
+        0x6100 | y as u16,
+        0xa20a,
+        0xd010 | rows as u16,
+        0x1206,
+    ];
+    let mut rom: Vec<u8> = opcodes
+        .iter()
+        .flat_map(|opcode| opcode.to_be_bytes())
+        .collect();
+    rom.resize(rom.len() + rows as usize, 0xff);
+    let mut chip8 = Chip8::with_seed(0);
+    // The default quirks don't wait for the next frame to draw
+    chip8.quirks = Quirks {
+        wrap,
+        ..Quirks::default()
+    };
+    chip8.load(&rom).unwrap();
+    // Gets past the setup, so only the drawing and the jump are measured
+    for _ in 0..3 {
+        chip8.clock().unwrap();
+    }
+    chip8
+}
+
+fn draw(c: &mut Criterion) {
+    let mut group = c.benchmark_group("draw");
+    group.throughput(Throughput::Elements(SPRITES));
+    for rows in [1, 5, 15] {
+        for (placement, x, y, wrap) in PLACEMENTS {
+            let id = BenchmarkId::new(placement, format!("{} rows", rows));
+            group.bench_function(id, |b| {
+                b.iter_batched_ref(
+                    || start(rows, x, y, wrap),
+                    |chip8| {
+                        // Each sprite is a draw and then the jump back to it
+                        for _ in 0..SPRITES * 2 {
+                            chip8.clock().unwrap();
+                        }
+                    },
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, draw);
+criterion_main!(benches);
@@ -0,0 +1,70 @@
+//! Measures how fast a whole frame of the screen gets turned into something
+//! that can be drawn, which every frontend does each time the screen changes.
+//! The screen is packed 8 pixels to a byte, so this is how long it takes to
+//! get them back out the ways that the frontends do.
+
+use chip_8::Screen;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// The sizes of screen there are, Chip-8's, hi-res Chip-8's, and MEGA-CHIP's
+const SIZES: [(usize, usize); 3] = [(64, 32), (64, 64), (256, 192)];
+
+/// A screen with about half of it's pixels on, in a pattern that isn't lined
+/// up with the bytes
+fn screen((width, height): (usize, usize)) -> Screen {
+    let mut screen = Screen::new(width, height).unwrap();
+    for y in 0..height {
+        for x in 0..width {
+            screen.set_pixel(x, y, (x * 3 + y * 5) % 7 < 3);
+        }
+    }
+    screen
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    for size in SIZES {
+        let screen = screen(size);
+        let (width, height) = size;
+        let size = format!("{}x{}", width, height);
+        group.throughput(Throughput::Elements((width * height) as u64));
+
+        // What the browser and the GPU window do, every pixel gets looked up
+        // and turned into a color
+        let mut image = vec![0u8; width * height * 4];
+        group.bench_function(BenchmarkId::new("rgba", &size), |b| {
+            b.iter(|| {
+                for (i, pixel) in image.chunks_exact_mut(4).enumerate() {
+                    let color = if screen.get_pixel(i % width, i / width) {
+                        0xff
+                    } else {
+                        0x00
+                    };
+                    pixel.copy_from_slice(&[color, color, color, 0xff]);
+                }
+            })
+        });
+
+        // What the SDL window does, it only draws the pixels that are on
+        group.bench_function(BenchmarkId::new("lit pixels", &size), |b| {
+            b.iter(|| screen.lit_pixels().count())
+        });
+
+        // Reading the packed bytes straight out, which is what the C API hands
+        // out, unpacked into one byte a pixel
+        let mut pixels = vec![0u8; width * height];
+        group.bench_function(BenchmarkId::new("unpacked bytes", &size), |b| {
+            b.iter(|| {
+                for (byte, eight) in screen.as_bytes().iter().zip(pixels.chunks_exact_mut(8)) {
+                    for (bit, pixel) in eight.iter_mut().enumerate() {
+                        *pixel = byte >> (7 - bit) & 1;
+                    }
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+//! Fills the interpreter's memory with whatever the fuzzer comes up with, and
+//! runs it with every set of quirks. A malformed rom is allowed to stop with an
+//! error, but it should never be able to make the interpreter panic.
+//!
+//! Run it with `cargo fuzz run execute` from the repo's root.
+
+#![no_main]
+
+use chip_8::{Chip8, Quirks};
+use libfuzzer_sys::fuzz_target;
+
+/// How many instructions each memory image gets to run
+const CYCLES: usize = 10_000;
+
+fuzz_target!(|data: &[u8]| {
+    for quirks in [Quirks::default(), Quirks::VIP, Quirks::SCHIP].iter() {
+        let mut chip8 = Chip8::with_seed(0);
+        chip8.quirks = *quirks;
+        // The whole memory gets replaced, font and all, and execution still
+        // starts at 0x200
+        for (byte, value) in chip8.memory.iter_mut().zip(data.iter()) {
+            *byte = *value;
+        }
+
+        for cycle in 0..CYCLES {
+            // Ticks the timers and holds down a different key every frame, so
+            // the key and timer instructions don't just wait forever
+            if cycle % 16 == 0 {
+                chip8.tick_timers();
+                chip8.release_all_keys();
+                chip8.press_key((cycle / 16) as u8);
+            }
+            if chip8.clock().is_err() {
+                break;
+            }
+            assert!(chip8.stack_pointer < chip8.stack.len());
+            // The debugger's view of the code around the program counter has
+            // to work no matter where it is
+            for relative in -2..=2 {
+                chip8.get_relative_instruction(relative);
+            }
+        }
+    }
+});
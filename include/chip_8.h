@@ -0,0 +1,56 @@
+/*
+ * The C API of the Chip-8 interpreter, which is built with
+ * `cargo build --release --features ffi` and linked in as `libchip_8`.
+ *
+ * The interpreter is an opaque pointer that comes from `chip8_create`, and has
+ * to be given back to `chip8_destroy`. The functions that return an `int`
+ * return 0 when they worked and -1 when they didn't, see `src/ffi.rs` for more
+ * about each of them.
+ */
+
+#ifndef CHIP_8_H
+#define CHIP_8_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct Chip8 Chip8;
+
+/* Creates an interpreter, with a random seed or the one passed in */
+Chip8 *chip8_create(void);
+Chip8 *chip8_create_with_seed(uint64_t seed);
+/* Frees the interpreter, null does nothing */
+void chip8_destroy(Chip8 *chip8);
+
+/* Resets the interpreter and loads the rom into it */
+int chip8_load_rom(Chip8 *chip8, const uint8_t *rom, size_t length);
+/* Runs one instruction */
+int chip8_step(Chip8 *chip8);
+/* Ticks the delay and sound timers, this has to be called 60 times a second */
+void chip8_tick_timers(Chip8 *chip8);
+/* Returns 1 while the tone should be playing */
+int chip8_sound_playing(const Chip8 *chip8);
+
+/* Presses and lets go of the keys 0x0 through 0xf */
+void chip8_key_down(Chip8 *chip8, uint8_t key);
+void chip8_key_up(Chip8 *chip8, uint8_t key);
+
+/*
+ * Returns the screen's pixels, 8 to a byte with the leftmost one in the most
+ * significant bit, row by row, and writes the size of the screen in pixels to
+ * `width` and `height` if they aren't null. The pointer is only good until the
+ * interpreter runs again.
+ */
+const uint8_t *chip8_framebuffer(const Chip8 *chip8, size_t *width, size_t *height);
+/* Returns 1 if the screen has changed since the last time this was called */
+int chip8_take_redraw(Chip8 *chip8);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
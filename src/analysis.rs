@@ -0,0 +1,207 @@
+//! This module contains the analysis of a rom before it runs, which follows
+//! the code through it to sort every byte into code, sprites, or something
+//! else, so that a disassembler doesn't turn the sprites into instructions that
+//! don't mean anything.
+//!
+//! It is only a guess, like `LoadInfo::data_start`, since where `bnnn` jumps
+//! to can't be known without running the rom, and anything that `annn` points
+//! at is taken to be a sprite as long as the rom draws anything at all.
+//!
+//! ```rust
+//! use chip_8::{analysis::ByteKind, Chip8};
+//!
+//! let mut chip8 = Chip8::with_seed(0);
+//! // `a206` points at the sprite after the code, `d015` draws 5 rows of it,
+//! // and `1204` jumps back to itself
+//! chip8
+//!     .load(&[0xa2, 0x06, 0xd0, 0x15, 0x12, 0x04, 0xf0, 0x90, 0x90, 0x90, 0xf0])
+//!     .unwrap();
+//! let analysis = chip8.analyze(0x200, 0x20b);
+//! assert_eq!(analysis.kind(0x204), ByteKind::Code);
+//! assert_eq!(analysis.kind(0x206), ByteKind::Sprite);
+//! ```
+
+use crate::chip8::Chip8;
+use alloc::{collections::BTreeSet, vec, vec::Vec};
+use core::ops::Range;
+
+/// What a byte of the rom looks like it is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteKind {
+    /// Part of an instruction that can be reached from the start
+    Code,
+    /// Part of a sprite that the index gets pointed at
+    Sprite,
+    /// Anything else, which could be data that isn't drawn, or code that only
+    /// gets reached in a way that can't be followed
+    Unknown,
+}
+
+/// Which subroutines call which, found by following the code from where the
+/// rom starts, the same way as `Analysis` does. Subroutines that only get
+/// called in ways that can't be followed aren't in it, but the calls that were
+/// seen while the rom ran can be added with `add_call`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallGraph {
+    /// Where the code started being followed from, which isn't a subroutine
+    /// but calls into them like one
+    entry: usize,
+    /// Where each subroutine starts, with the entry too
+    subroutines: BTreeSet<usize>,
+    /// Each call, as where the subroutine that calls starts and where the one
+    /// that gets called starts
+    calls: BTreeSet<(usize, usize)>,
+}
+
+impl CallGraph {
+    /// Returns where the code started being followed from
+    pub fn entry(&self) -> usize {
+        self.entry
+    }
+
+    /// Returns where every subroutine starts, in order, with the entry too
+    pub fn subroutines(&self) -> impl Iterator<Item = usize> + '_ {
+        self.subroutines.iter().copied()
+    }
+
+    /// Returns every call, as the subroutine that calls and the subroutine
+    /// that gets called, in order
+    pub fn calls(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.calls.iter().copied()
+    }
+
+    /// Adds a call from one subroutine to another, and the subroutines if they
+    /// weren't already there
+    pub fn add_call(&mut self, caller: usize, callee: usize) {
+        self.subroutines.insert(caller);
+        self.subroutines.insert(callee);
+        self.calls.insert((caller, callee));
+    }
+}
+
+/// What every byte between two addresses looks like it is used for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    start: usize,
+    kinds: Vec<ByteKind>,
+    /// Where each of the sprites starts, in order
+    sprite_starts: Vec<usize>,
+}
+
+impl Analysis {
+    /// Returns what the byte at the address looks like it is used for,
+    /// anything outside of what was looked at is unknown
+    pub fn kind(&self, address: usize) -> ByteKind {
+        address
+            .checked_sub(self.start)
+            .and_then(|offset| self.kinds.get(offset))
+            .copied()
+            .unwrap_or(ByteKind::Unknown)
+    }
+
+    /// Returns the address of the first byte that was looked at
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the address right after the last byte that was looked at
+    pub fn end(&self) -> usize {
+        self.start + self.kinds.len()
+    }
+
+    /// Returns where every sprite is, in order. Each one goes until the next
+    /// one starts, or until the bytes stop looking like a sprite.
+    pub fn sprites(&self) -> Vec<Range<usize>> {
+        self.sprite_starts
+            .iter()
+            .enumerate()
+            .map(|(i, start)| {
+                let next = self.sprite_starts.get(i + 1).copied().unwrap_or(self.end());
+                let end = (*start..next)
+                    .find(|address| self.kind(*address) != ByteKind::Sprite)
+                    .unwrap_or(next);
+                *start..end
+            })
+            .collect()
+    }
+}
+
+impl Chip8 {
+    /// Sorts the bytes of memory from `start` up to `end` into code, sprites,
+    /// and everything else, by following the code from `start`
+    pub fn analyze(&self, start: usize, end: usize) -> Analysis {
+        let end = end.min(self.memory.len());
+        let mut kinds = vec![ByteKind::Unknown; end.saturating_sub(start)];
+
+        let mut pointers = Vec::new();
+        // How many bytes the tallest sprite that gets drawn takes up
+        let mut sprite_size = 0;
+        for address in self.reachable(start, end) {
+            let opcode = self.opcode_at(address);
+            let (mnemonic, _) = self.parse_opcode(&opcode);
+            // The 2 bytes after `ldhi` are the address that it loads
+            let length = if mnemonic == "ldhi" { 4 } else { 2 };
+            for offset in address..(address + length).min(end) {
+                kinds[offset - start] = ByteKind::Code;
+            }
+            match mnemonic {
+                "ldi" => pointers.push(opcode.nnn as usize),
+                // A height of 0 draws SUPER-CHIP's 16x16 sprites, which are 2
+                // bytes a row
+                "drw" if opcode.n == 0 => sprite_size = 32,
+                "drw" => sprite_size = sprite_size.max(opcode.n as usize),
+                _ => {}
+            }
+        }
+
+        // A sprite goes until the code starts again, or until it is as tall as
+        // the tallest one that gets drawn
+        pointers.sort_unstable();
+        pointers.dedup();
+        let mut sprite_starts = Vec::new();
+        for pointer in pointers {
+            for address in pointer..(pointer + sprite_size).min(end) {
+                match address.checked_sub(start).map(|offset| &mut kinds[offset]) {
+                    Some(ByteKind::Code) => break,
+                    Some(kind) => *kind = ByteKind::Sprite,
+                    None => {}
+                }
+            }
+            if pointer >= start && pointer < end && kinds[pointer - start] == ByteKind::Sprite {
+                sprite_starts.push(pointer);
+            }
+        }
+        Analysis {
+            start,
+            kinds,
+            sprite_starts,
+        }
+    }
+
+    /// Finds which subroutines call which, by following the code from `start`
+    /// without going outside of `start` up to `end`, and then following each
+    /// subroutine that it calls in the same way
+    pub fn call_graph(&self, start: usize, end: usize) -> CallGraph {
+        let end = end.min(self.memory.len());
+        let mut graph = CallGraph {
+            entry: start,
+            subroutines: BTreeSet::new(),
+            calls: BTreeSet::new(),
+        };
+        let mut next = vec![start];
+        while let Some(subroutine) = next.pop() {
+            if !graph.subroutines.insert(subroutine) {
+                continue;
+            }
+            // Only the code of this subroutine gets followed, the ones it
+            // calls get their own turn
+            let mut callees = Vec::new();
+            self.trace(subroutine, start..end, Some(&mut callees));
+            for callee in callees {
+                graph.calls.insert((subroutine, callee));
+                next.push(callee);
+            }
+        }
+        graph
+    }
+}
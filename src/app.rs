@@ -1,237 +1,1077 @@
 //! This module contains all of the application relevant code that interacts
 //! with the chip8 interpreter
 
-use crate::chip8::Chip8;
-use crossterm::{cursor, input, terminal, AlternateScreen, InputEvent, KeyEvent};
-use std::{
-    fs::File,
-    io::{stdout, Error, Read, Write},
-    time::{Duration, SystemTime},
+use crate::{
+    browser::RomBrowser,
+    commands::Commands,
+    config::Config,
+    debugger::{self, Debugger},
+    history::History,
+    keymap::Keymap,
+    keypad::{self, Keypad},
+    movie::pressed_keys,
+    palette::Palette,
+    phosphor::Phosphor,
+    renderer::{self, Renderer, Scale},
+    rom,
+    rotation::Rotation,
+    screenshot,
+    status::StatusBar,
+    threads::{self, FrameSender, KeyReceiver},
 };
+use chip_8::{
+    detect::{self, Suggestion},
+    frontend::{Audio, Display, Event, Input},
+    symbols::Symbols,
+    Chip8, Chip8Error, Quirks,
+};
+use crossterm::{
+    cursor::{Hide, Show},
+    event::{
+        DisableMouseCapture, EnableMouseCapture, Event as TerminalEvent, KeyCode, KeyEventKind,
+        KeyModifiers, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
+    },
+    execute, queue,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen, SetSize},
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    widgets::{Block, BorderType, Borders, Widget},
+};
+use std::io::{stdout, Error, Write};
+use std::{panic, path::PathBuf, thread};
 
-/// Just an enum to check for events that the application needs to take care of
-enum Event {
-    Quit,
-}
+/// How much of the terminal the screen's pane takes up across, and how much
+/// the row that it is in takes up down, as a percentage, when the debugger is
+/// open
+const SCREEN_SHARE: u16 = 50;
+const TOP_SHARE: u16 = 60;
+/// How much the panes get resized by for each press, and how small and big
+/// they can get, as a percentage
+const SHARE_STEP: u16 = 5;
+const MIN_SHARE: u16 = 20;
+const MAX_SHARE: u16 = 80;
+
+/// How many times faster the rom runs while it is fast forwarded, and how
+/// much slower it runs in slow motion
+const FAST_FORWARD_SPEED: f64 = 8.0;
+const SLOW_MOTION_SPEED: f64 = 0.25;
 
-/// A struct that contains application-wide state
+/// A struct that contains application-wide state, and acts as the terminal
+/// frontend for the interpreter.
+///
+/// The interpreter runs on it's own thread, while the keys are read on another
+/// one, and the frames are written to the terminal on the thread that started
+/// the app, see the `threads` module.
 pub struct App {
-    chip8: Chip8,
+    /// How the screen is turned into characters
+    renderer: Renderer,
+    /// How much bigger the screen gets drawn when the terminal has room
+    scale: Scale,
+    /// The colors that the screen is drawn with
+    palette: Palette,
+    /// Fades the pixels out instead of turning them off straight away, when
+    /// it is on
+    phosphor: Phosphor,
+    /// How far the screen is turned, for the roms that were made for a
+    /// display on its side
+    rotation: Rotation,
+    /// Which characters press which keys on the keypad
+    keymap: Keymap,
+    /// Set when the renderer changes, so the old characters get cleared away
+    needs_clear: bool,
+    /// What is already on the terminal, so only the cells that are different
+    /// get written
+    previous: Buffer,
+    /// The size of the terminal the last time it was checked, so it can be
+    /// noticed when it gets resized
+    terminal_size: (u16, u16),
+    /// The panes that show what is going on inside of the interpreter
+    debugger: Debugger,
+    /// How much of the terminal the screen's pane takes up across, and how
+    /// much the row with the screen in it takes up down, as a percentage
+    screen_share: u16,
+    top_share: u16,
+    /// Runs what is typed into the command line under the panes
+    commands: Commands,
+    /// What has been typed so far, while a command is being typed in
+    command: Option<String>,
+    /// What the last command answered with
+    answer: String,
+    /// The line under the screen that shows how the rom is running
+    status: StatusBar,
+    /// The keypad next to the screen, which can be clicked on
+    keypad: Keypad,
+    /// Whether the overlay that shows which character presses each key is
+    /// drawn over the screen
+    key_overlay: bool,
+    /// The keys that were down at the end of the last frame, for the keypad
+    /// and the overlay, since the keys might have been let go of since
+    pressed_keys: u16,
+    /// Whether the terminal has been asked to send the mouse's clicks, which
+    /// it is while the keypad is shown, since it stops text from being
+    /// selected
+    mouse_captured: bool,
+    /// The quirks that the rom looks like it needs, which are shown under the
+    /// screen until the user says whether to use them
+    prompt: Option<Suggestion>,
+    /// How many pixels of the image each pixel of the screen is in a screenshot
+    screenshot_scale: u32,
+    /// Whether the rom is being fast forwarded, which is while the key is held
+    /// down, or until it is pressed again in terminals that can't tell when
+    /// it is let go of
+    fast_forward: bool,
+    /// Whether the rom is running in slow motion, when it isn't being fast
+    /// forwarded
+    slow_motion: bool,
+    /// Set while the rom is being played a frame at a time, so the debugger
+    /// doesn't get opened for it being paused
+    frame_advance: bool,
+    /// Whether the tone is playing, there might not be any speakers, so the
+    /// border gets drawn thicker while it is
+    sound_playing: bool,
+    /// Whether the terminal's bell gets rung when the tone starts
+    bell: bool,
+    /// The queue of key events, from the thread that reads them
+    keys: KeyReceiver,
+    /// Set once the terminal has said that a key was let go of, after which
+    /// the keys stay held down until it says so
+    key_releases: bool,
+    /// Where the frames go to get written to the terminal, this is only open
+    /// while the roms are running
+    output: FrameSender,
 }
 
 impl App {
-    /// Creates a default App struct
-    pub fn new() -> Self {
+    /// Creates an App struct with the options that were passed in
+    pub fn new(config: &Config) -> Self {
         App {
-            chip8: Chip8::new(),
+            renderer: config.renderer,
+            scale: config.scale,
+            palette: config.palette,
+            phosphor: Phosphor::new(config.filter),
+            rotation: config.rotation.unwrap_or_default(),
+            keymap: config.keymap,
+            // The first frame needs to fill in the whole screen
+            needs_clear: true,
+            previous: Buffer::empty(Rect::default()),
+            terminal_size: terminal::size().unwrap_or_default(),
+            debugger: Debugger::new(Symbols::new()),
+            screen_share: SCREEN_SHARE,
+            top_share: TOP_SHARE,
+            commands: Commands::new(config),
+            command: None,
+            answer: String::new(),
+            status: StatusBar::new(config.status),
+            keypad: Keypad::new(config.keypad),
+            key_overlay: false,
+            pressed_keys: 0,
+            mouse_captured: false,
+            prompt: None,
+            screenshot_scale: config.screenshot_scale,
+            fast_forward: false,
+            slow_motion: false,
+            frame_advance: false,
+            sound_playing: false,
+            bell: config.bell,
+            keys: KeyReceiver::spawn(),
+            key_releases: false,
+            output: FrameSender::closed(),
+        }
+    }
+
+    /// Picks how much bigger the screen can be drawn than the `columns` and
+    /// `rows` that it needs so that it fits in the `room`, and whether there
+    /// is room for a border around it too, if it can have one. Anything that
+    /// doesn't fit falls back to the screen as it is.
+    fn fit(
+        &self,
+        columns: u16,
+        rows: u16,
+        room: (u16, u16),
+        border: bool,
+    ) -> ((usize, usize), bool) {
+        // Anything too big to count in a u16 won't fit anyways
+        let scaled = |(scale_columns, scale_rows): (usize, usize), border: usize| {
+            (
+                (columns as usize * scale_columns + border).min(u16::MAX as usize) as u16,
+                (rows as usize * scale_rows + border).min(u16::MAX as usize) as u16,
+            )
+        };
+        let mut candidates = Vec::new();
+        match self.scale {
+            Scale::Fixed(scale_columns, scale_rows) => {
+                candidates.push((scale_columns, scale_rows));
+            }
+            Scale::Auto => {
+                // From the biggest that could fit across down to the smallest
+                // that keeps the pixels square
+                let (aspect_columns, aspect_rows) = self.renderer.aspect();
+                let most = room.0 as usize / (columns as usize * aspect_columns).max(1);
+                for times in (1..=most).rev() {
+                    candidates.push((aspect_columns * times, aspect_rows * times));
+                }
+            }
+        }
+        candidates.push((1, 1));
+
+        // The border takes up a column and a row on each side
+        let borders: &[(usize, bool)] = if border {
+            &[(2, true), (0, false)]
+        } else {
+            &[(0, false)]
+        };
+        for scale in candidates.iter() {
+            for (border, has_border) in borders.iter() {
+                let (width, height) = scaled(*scale, *border);
+                if width <= room.0 && height <= room.1 {
+                    return (*scale, *has_border);
+                }
+            }
         }
+        ((1, 1), false)
     }
 
-    /// Sets up the initial state for the app and calls the event loop
-    pub fn run(&mut self) -> Result<(), Error> {
+    /// Sets up the terminal for the app and runs roms in it until the user
+    /// quits, starting with `first_rom` if there is one
+    pub fn run(
+        &mut self,
+        config: &Config,
+        first_rom: Option<PathBuf>,
+        history: &mut History,
+    ) -> Result<(), Error> {
         // Get the current terminal's size, so that it can be restored when the application quits.
-        let (terminal_starting_width, terminal_starting_height) = terminal().terminal_size();
-
-        // Sets the terminal to the chip8 specification's size
-        terminal().set_size(64, 32)?;
-        // Creates an alternate screen, so that the contents of the terminal aren't
-        // overridden
-        let _screen = AlternateScreen::to_alternate(true);
-        // hides the cursor
-        // Note: doesn't work on Windows with using AlternateScreen
-        cursor().hide()?;
-
-        // Opens the rom file
-        // Todo: This is hard coded, needs to be an option that is passed in
-        let mut rom_file = File::open("roms/test_opcode.ch8")?;
-        // Creates a buffer to store the file
-        let mut rom: Vec<u8> = Vec::new();
-        // Writes to the buffer
-        rom_file.read_to_end(&mut rom)?;
-        // Loads the rom into the interpreter's memory
-        self.chip8.load(rom);
-
-        // Runs the event loop, and stores the value in case if it throws an error
-        let event_loop_result = self.event_loop();
+        let (terminal_starting_width, terminal_starting_height) = terminal::size()?;
+
+        // The terminal only gets resized if it is too small for the renderer, it
+        // gets left alone otherwise and the screen gets centered in it instead.
+        // Every rom starts out with the same size screen.
+        let (width, height) = self.rotation.size(Chip8::new().screen().size());
+        let (columns, rows) = self.renderer.size(width, height);
+        let needs_resize = terminal_starting_width < columns || terminal_starting_height < rows;
+        if needs_resize {
+            execute!(
+                stdout(),
+                SetSize(
+                    columns.max(terminal_starting_width),
+                    rows.max(terminal_starting_height)
+                )
+            )?;
+            self.terminal_size = terminal::size()?;
+        }
+        // Gets the terminal ready for drawing, it is put back once this is
+        // dropped
+        let _screen = TerminalMode::enter()?;
+
+        // Runs the roms on their own thread, and stores the value in case if it
+        // throws an error. This thread writes what they draw to the terminal,
+        // so a write that gets stuck doesn't hold up the interpreter.
+        let (output, frames) = threads::frame_channel();
+        self.output = output;
+        let session_result = thread::scope(|scope| {
+            let core = scope.spawn(|| {
+                let result = self.session(config, first_rom, history);
+                // Nothing else gets drawn, which lets the frames finish
+                self.output.close();
+                result
+            });
+            let written = threads::write_frames(frames);
+            // A panic on the interpreter's thread gets passed on as if it
+            // happened here
+            let result = core
+                .join()
+                .unwrap_or_else(|panic| panic::resume_unwind(panic));
+            result.and(written)
+        });
 
         // Restore's the terminal's size to what it was before the application started
-        terminal().set_size(
-            terminal_starting_width as i16,
-            terminal_starting_height as i16,
-        )?;
-
-        // Returns the result that was return from the event loop
-        event_loop_result
-    }
-
-    /// This runs the chip8 interpreter, keeping track of the two different clocks
-    /// that the interpreter needs
-    fn event_loop(&mut self) -> Result<(), Error> {
-        // It is hard to find the speed that the interpreter runs, but according
-        // to a document I had read, it said that the computer that it was based
-        // off of had a clock speed of 1KHz
-        let clock_duration = Duration::new(0, 1000000);
-        // The delays for the interpreter are ticked down at a rate of 60Hz
-        let delay_duration = Duration::new(0, 16666667);
-
-        // Sets the initial system time for the timers
-        let mut last_clock_time = SystemTime::now();
-        let mut last_delay_time = last_clock_time;
-
-        // And now to the loop
+        if needs_resize {
+            execute!(
+                stdout(),
+                SetSize(terminal_starting_width, terminal_starting_height)
+            )?;
+        }
+
+        // Returns the result that was return from the roms
+        session_result
+    }
+
+    /// Runs the first rom, or the one picked from the menu, and goes back to
+    /// the menu every time the user unloads a rom
+    fn session(
+        &mut self,
+        config: &Config,
+        first_rom: Option<PathBuf>,
+        history: &mut History,
+    ) -> Result<(), Error> {
+        let mut next_rom = first_rom;
         loop {
-            // handle_input returns an Option<Event> so that if the user decides
-            // to quit the application, they can
-            match self.handle_input() {
-                Some(event) => match event {
-                    Event::Quit => break,
+            // A rom that exits goes back to the menu it was picked from, or
+            // closes the app if it was passed in
+            let (path, from_menu) = match next_rom.take() {
+                Some(path) => (path, false),
+                None => match RomBrowser::new(&config.roms_dir, history.roms())
+                    .choose(&self.keys, &self.output)?
+                {
+                    Some(path) => (path, true),
+                    // The user left the menu without picking anything
+                    None => return Ok(()),
                 },
-                None => {}
-            }
+            };
+            // Not being able to save the history isn't worth stopping the rom over
+            let _ = history.record(&path);
 
-            // The duration since the last clock cycle
-            let mut duration = App::calculate_duration(last_clock_time);
-            // Keep running until the interpreter catches up it's clock cycles
-            while duration >= clock_duration {
-                // runs the current instruction
-                self.chip8.clock();
-
-                // adds the clock duration of the interpreter
-                last_clock_time += clock_duration;
-                // recalculate the duration to be re-checked
-                duration = App::calculate_duration(last_clock_time);
+            // The menu is still on the terminal
+            self.needs_clear = true;
+            // Nothing about the last rom carries over to this one, the
+            // breakpoints, the pane's place in memory, the names from it's
+            // symbol file, and the keys held down by commands were for it
+            self.debugger = Debugger::new(rom::symbols(&path, config)?);
+            self.keypad = Keypad::new(self.keypad.is_open);
+            self.commands = Commands::new(config);
+            self.command = None;
+            self.answer.clear();
+            // The status bar shows the title for roms that are in the database
+            let entry = config.database.find_file(&path);
+            let name = match entry {
+                Some(entry) => entry.name(),
+                None => path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+            self.status.start(&name);
+            self.phosphor = Phosphor::for_rom(config, &path);
+            let (rotation, turn_keys) = Rotation::for_rom(config, &path);
+            self.rotation = rotation;
+            self.keymap = if turn_keys {
+                rotation.turn_keymap(config.keymap)
+            } else {
+                config.keymap
+            };
+            // The rom gets looked through for the quirks it needs when
+            // neither the options nor the database said which ones
+            self.prompt = match (
+                config
+                    .quirks
+                    .or(config.profile.map(|profile| profile.quirks)),
+                entry,
+            ) {
+                (None, None) => rom::read(&path)
+                    .ok()
+                    .and_then(|rom| detect::suggest(&rom, config.load_address)),
+                _ => None,
+            };
+
+            // Runs the event loop with the app as the frontend, a rom that
+            // does something the interpreter can't handle gets the error shown
+            // over it, instead of the app closing before it can be seen
+            let event = match rom::run(self, &path, config) {
+                Ok(event) => event,
+                Err(error) => {
+                    let chip8_error = error
+                        .get_ref()
+                        .and_then(|inner| inner.downcast_ref::<Chip8Error>());
+                    match chip8_error {
+                        Some(chip8_error) => self.show_error(chip8_error)?,
+                        None => return Err(error),
+                    }
+                    // The error still gets printed when there is no menu to
+                    // go back to
+                    if !from_menu {
+                        return Err(error);
+                    }
+                    continue;
+                }
+            };
+            match event {
+                Event::Quit => return Ok(()),
+                Event::Exit if !from_menu => return Ok(()),
+                Event::Unload | Event::Exit => {}
             }
+        }
+    }
 
-            // The duration since the last delay cycle
-            let mut duration = App::calculate_duration(last_delay_time);
-            // Keep running until the interpreter catches up the delay/sound timers
-            while duration >= delay_duration {
-                // The delay and sound timers tick down one every 1/60th of a second
-                // until they hit 0
-                self.chip8.delay_timer = self.chip8.delay_timer.saturating_sub(1);
-                self.chip8.sound = self.chip8.sound.saturating_sub(1);
-                // Sets all of the keys to be unpressed
-                for key in self.chip8.keys.iter_mut() {
-                    *key = false;
-                }
-                // Draws the interpreter's buffer, I believe that the screen that
-                // the telemac updated at was 1/60th of a second, even if it is not,
-                // it seems like a reasonable speed to update the screen
-                self.draw()?;
-
-                // basically the same thing as the clock duration/delay
-                last_delay_time += delay_duration;
-                duration = App::calculate_duration(last_delay_time);
+    /// Writes the error on the bottom row of the terminal, under what the rom
+    /// had drawn, and waits for a key to be pressed
+    fn show_error(&mut self, error: &Chip8Error) -> Result<(), Error> {
+        let (columns, rows) = self.terminal_size;
+        let message = format!("The rom stopped, press any key: {}", error);
+        let message: String = message.chars().take(columns as usize).collect();
+        self.output
+            .send(format!("\x1b[{};1H\x1b[2K\x1b[7m{}\x1b[27m", rows, message).into_bytes())?;
+        while let Some(event) = self.keys.wait() {
+            if let TerminalEvent::Key(key) = event {
+                if key.kind != KeyEventKind::Release {
+                    break;
+                }
             }
         }
-        // Yay, nothing broke
         Ok(())
     }
+}
 
+impl Input for App {
     /// Sets the keys that are pressed, and handles sending the quit event
-    fn handle_input(&mut self) -> Option<Event> {
-        // Gets stdin, so that the key events can be checked
-        let mut stdin = input().read_sync();
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        // The events that came in since the last time, they have to be taken
+        // off of the queue first, since handling them changes the app
+        let events: Vec<TerminalEvent> = self.keys.pending().collect();
+
+        // Anything that resumes the rom, like the remote control, ends frame
+        // advance
+        if !chip8.is_paused() {
+            self.frame_advance = false;
+        }
+
+        // The rom only pauses itself when it runs into something that isn't
+        // an instruction, or the program counter halts out of bounds, which
+        // are shown in the pane
+        if chip8.is_paused() && !self.debugger.is_open && !self.frame_advance {
+            self.debugger.is_open = true;
+            self.needs_clear = true;
+            chip8.request_redraw();
+        }
+
+        // The run loop picks up the speed from the interpreter, which starts
+        // over at normal speed for every rom
+        chip8.set_speed(if self.fast_forward {
+            FAST_FORWARD_SPEED
+        } else if self.slow_motion {
+            SLOW_MOTION_SPEED
+        } else {
+            1.0
+        });
+
+        // The instructions are kept to step back through while the pane is
+        // open, this also starts them over for a rom that was just loaded.
+        // When the rom can pause itself they are always kept, so that it can
+        // be seen how it got there.
+        if (self.debugger.is_open || chip8.breaks_on_unknown()) && !chip8.is_journal_on() {
+            chip8.enable_journal(debugger::JOURNAL_LENGTH);
+        }
 
         // Iterates over every event that has passed
-        while let Some(key_event) = stdin.next() {
-            match key_event {
-                InputEvent::Keyboard(event) => match event {
-                    // There is no specific instruction for chip8 to quit the
-                    // the program, so it has to be implemented in the interpreter
-                    KeyEvent::Esc => return Some(Event::Quit),
-                    KeyEvent::Char(c) => match c {
-                        // The chip8 virtual computer was originally made for a
-                        // computer that had a keypad using hexadecimal digits
-                        // which is usually mapped in this way:
-                        /*
-                        123c    1234
-                        456d    qwer
-                        789e    asdf
-                        a0bf    zxcv
-                        */
-                        '1' => self.chip8.keys[0x1] = true,
-                        '2' => self.chip8.keys[0x2] = true,
-                        '3' => self.chip8.keys[0x3] = true,
-                        '4' => self.chip8.keys[0xc] = true,
-                        'q' => self.chip8.keys[0x4] = true,
-                        'w' => self.chip8.keys[0x5] = true,
-                        'e' => self.chip8.keys[0x6] = true,
-                        'r' => self.chip8.keys[0xd] = true,
-                        'a' => self.chip8.keys[0x7] = true,
-                        's' => self.chip8.keys[0x8] = true,
-                        'd' => self.chip8.keys[0x9] = true,
-                        'f' => self.chip8.keys[0xe] = true,
-                        'z' => self.chip8.keys[0xa] = true,
-                        'x' => self.chip8.keys[0x0] = true,
-                        'c' => self.chip8.keys[0xb] = true,
-                        'v' => self.chip8.keys[0xf] = true,
-                        _ => {}
-                    },
+        for event in events {
+            let key = match event {
+                TerminalEvent::Key(key) => key,
+                // Everything gets drawn again in the middle of the new size
+                TerminalEvent::Resize(columns, rows) => {
+                    self.terminal_size = (columns, rows);
+                    self.needs_clear = true;
+                    chip8.request_redraw();
+                    continue;
+                }
+                TerminalEvent::Mouse(mouse) => {
+                    if self.keypad.is_open && self.keypad.handle_mouse(&mouse, chip8) {
+                        chip8.request_redraw();
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            // Only some terminals say when a key is let go of, the rest only
+            // ever send presses
+            if key.kind == KeyEventKind::Release {
+                self.key_releases = true;
+                if key.code == KeyCode::Tab {
+                    self.fast_forward = false;
+                } else if let KeyCode::Char(c) = key.code {
+                    if let Some(key) = self.keymap.key(c) {
+                        chip8.release_key(key);
+                    } else if let Some(key) = self.keymap.second_key(c) {
+                        chip8.release_second_key(key);
+                    }
+                }
+                continue;
+            }
+
+            // The prompt gets answered before anything else gets the keys, and
+            // goes away either way
+            if let Some(suggestion) = self.prompt.take() {
+                match key.code {
+                    KeyCode::Enter => {
+                        chip8.quirks = Quirks {
+                            wrap: chip8.quirks.wrap,
+                            index_carry: chip8.quirks.index_carry,
+                            ..suggestion.quirks
+                        }
+                    }
+                    KeyCode::Backspace => {}
+                    _ => self.prompt = Some(suggestion),
+                }
+                if self.prompt.is_none() {
+                    self.needs_clear = true;
+                    chip8.request_redraw();
+                    continue;
+                }
+            }
+
+            // While a command is being typed, every key goes to it
+            if let Some(typed) = &mut self.command {
+                match key.code {
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        typed.push(c)
+                    }
+                    KeyCode::Backspace => {
+                        typed.pop();
+                    }
+                    KeyCode::Enter => {
+                        let line = typed.trim().to_string();
+                        self.command = None;
+                        if !line.is_empty() {
+                            self.answer = self.commands.run(chip8, &line);
+                        }
+                        if self.commands.should_quit() {
+                            return Some(Event::Quit);
+                        }
+                    }
+                    KeyCode::Esc => self.command = None,
                     _ => {}
-                },
+                }
+                chip8.request_redraw();
+                continue;
+            }
+
+            // Ctrl and the arrows resize the panes, before the debugger gets
+            // the arrows for moving around
+            if self.debugger.is_open && key.modifiers.contains(KeyModifiers::CONTROL) {
+                let resized = match key.code {
+                    KeyCode::Left => Some((&mut self.screen_share, false)),
+                    KeyCode::Right => Some((&mut self.screen_share, true)),
+                    KeyCode::Up => Some((&mut self.top_share, false)),
+                    KeyCode::Down => Some((&mut self.top_share, true)),
+                    _ => None,
+                };
+                if let Some((share, grow)) = resized {
+                    *share = if grow {
+                        (*share + SHARE_STEP).min(MAX_SHARE)
+                    } else {
+                        share.saturating_sub(SHARE_STEP).max(MIN_SHARE)
+                    };
+                    chip8.request_redraw();
+                    continue;
+                }
+            }
+
+            // The debugger gets first pick of the keys while it is open
+            if self.debugger.is_open && self.debugger.handle_key(&key.code, chip8) {
+                chip8.request_redraw();
+                continue;
+            }
+            match key.code {
+                // There is no specific instruction for chip8 to quit the
+                // the program, so it has to be implemented in the interpreter
+                KeyCode::Esc => return Some(Event::Quit),
+                // The terminal is in raw mode, so ctrl-c doesn't stop the app
+                // by itself
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Some(Event::Quit)
+                }
+                // Stops the rom and goes back to the menu to pick another one
+                KeyCode::F(1) => return Some(Event::Unload),
+                // Switches to the next way of drawing the screen, which
+                // needs the whole screen to be drawn again
+                KeyCode::F(2) => {
+                    self.renderer = self.renderer.next();
+                    self.needs_clear = true;
+                    chip8.request_redraw();
+                }
+                // Switches to the next color theme
+                KeyCode::F(3) => {
+                    self.palette = self.palette.next();
+                    chip8.request_redraw();
+                }
+                // Opens or closes the debugger's panes, which moves the screen
+                KeyCode::F(4) => {
+                    self.debugger.is_open = !self.debugger.is_open;
+                    self.command = None;
+                    // The rom can't be stepped through or resumed without the
+                    // pane, so it goes back to running normally
+                    if !self.debugger.is_open {
+                        if !chip8.breaks_on_unknown() {
+                            chip8.disable_journal();
+                        }
+                        chip8.set_paused(false);
+                    }
+                    self.needs_clear = true;
+                    chip8.request_redraw();
+                }
+                // Turns the ghosting on or off
+                KeyCode::F(8) => {
+                    self.phosphor.toggle();
+                    chip8.request_redraw();
+                }
+                // Shows or hides the status bar, which moves the screen
+                KeyCode::F(7) => {
+                    self.status.is_open = !self.status.is_open;
+                    self.needs_clear = true;
+                    chip8.request_redraw();
+                }
+                // Saves the screen to an image in the current directory,
+                // there is nowhere to show it if that fails, and it isn't
+                // worth stopping the rom over
+                KeyCode::F(6) => {
+                    let path = screenshot::file_name();
+                    let _ = screenshot::save(chip8, &path, self.screenshot_scale, self.palette);
+                }
+                // Pauses the rom at the end of the frame, and then runs one
+                // more frame for every press after that
+                KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if self.frame_advance {
+                        chip8.step_frame();
+                    } else {
+                        self.frame_advance = true;
+                        chip8.set_paused(true);
+                        chip8.request_redraw();
+                    }
+                }
+                // Goes back to running the rom normally after frame advance
+                KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.frame_advance = false;
+                    chip8.set_paused(false);
+                }
+                // Shows or hides the keypad, which moves the screen
+                KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.keypad.is_open = !self.keypad.is_open;
+                    self.needs_clear = true;
+                    chip8.request_redraw();
+                }
+                // Fast forwards while it is held down, terminals that only say
+                // when it is pressed turn it on and off with every press
+                // instead
+                KeyCode::Tab if key.kind == KeyEventKind::Press => {
+                    self.fast_forward = if self.key_releases {
+                        true
+                    } else {
+                        !self.fast_forward
+                    };
+                }
+                // Switches slow motion on or off
+                KeyCode::BackTab => self.slow_motion = !self.slow_motion,
+                // Shows or hides the overlay with the keys over the screen
+                KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.key_overlay = !self.key_overlay;
+                    chip8.request_redraw();
+                }
+                // Starts typing a command into the line under the panes
+                KeyCode::Char(':') if self.debugger.is_open => {
+                    self.command = Some(String::new());
+                    chip8.request_redraw();
+                }
+                // The chip8 virtual computer was originally made for a
+                // computer that had a keypad using hexadecimal digits,
+                // which the keymap maps to the keyboard
+                KeyCode::Char(c) => {
+                    if let Some(key) = self.keymap.key(c) {
+                        chip8.press_key(key);
+                    } else if let Some(key) = self.keymap.second_key(c) {
+                        chip8.press_second_key(key);
+                    }
+                }
                 _ => {}
             }
         }
         None
     }
 
-    /// Prints out the chip8 interpreter's draw buffer to the terminal
-    fn draw(&mut self) -> Result<(), Error> {
-        let mut stdout = stdout();
-
-        // this ensures that we don't draw to the terminal unless if the chip8
-        // interpreter has drawn or cleared.
-        if self.chip8.has_drawn && !self.chip8.has_handled_draw {
-            self.chip8.has_handled_draw = true;
-
-            // Iterate over each y coordinate by values of one
-            for y in 0..self.chip8.screen_size.1 {
-                // set the cursor to the left most column on the corresponding y coordinate
-                cursor().goto(0, y as u16).unwrap();
-                // create a buffer for each line that will be outputted to the terminal
-                let mut line_buffer = String::new();
-
-                // Iterate over each x coordinate by a factor of 1/8 because
-                // of the amount of bits in use
-                for x in 0..self.chip8.screen_size.0 / 8 {
-                    // Get the u8 block of pixels to be drawn
-                    let pixel_block =
-                        self.chip8.screen[(x + y * (self.chip8.screen_size.0 / 8)) as usize];
-
-                    // Iterate over each bit
-                    for i in 0..8 {
-                        // Move the corresponding pixel bit to the left most column,
-                        // and check to see if it is on
-                        if (pixel_block << i) & 0b10000000 != 0 {
-                            // If the pixel is on, then push a fill block character
-                            // (which is 3 bytes long apparently) to the line buffer
-                            line_buffer.push('█');
-                        } else {
-                            // If it is off, push an empty block (space) to the line buffer
-                            line_buffer.push(' ');
-                        }
-                    }
-                }
-                // Write the line to the terminal
-                write!(stdout, "{}", line_buffer)?;
+    /// Presses the keys that commands and the mouse are holding down
+    fn start_frame(&mut self, chip8: &mut Chip8) {
+        self.commands.hold_keys(chip8);
+        self.keypad.hold_key(chip8);
+    }
+
+    /// Pauses the rom when a breakpoint is hit, or when it is done stepping
+    /// over or out of a subroutine, otherwise counts the instruction for the
+    /// status bar
+    fn before_instruction(&mut self, chip8: &mut Chip8) {
+        // The rom is only stopped while the pane is there to resume it
+        if self.debugger.is_open && self.debugger.should_pause(chip8) {
+            chip8.set_paused(true);
+            chip8.request_redraw();
+            return;
+        }
+        self.status.count_instruction();
+    }
+
+    /// Sets all of the keys to be unpressed, if the terminal can't tell us
+    /// when a key has been let go of
+    fn tick(&mut self, chip8: &mut Chip8) {
+        self.status.count_frame();
+
+        // The keys are shown the way they were for the frame, before they
+        // get let go of
+        self.pressed_keys = pressed_keys(chip8);
+        if !self.key_releases {
+            chip8.release_all_keys();
+        }
+
+        // The border has to be drawn again when the tone starts or stops,
+        // and the screen has to be while pixels are fading out
+        let fading = self.phosphor.update(chip8);
+        if chip8.is_sound_playing() != self.sound_playing || fading {
+            chip8.request_redraw();
+        }
+
+        // The memory can change without the screen changing, so the pane gets
+        // drawn every frame while it is open, and the same goes for the
+        // program counter and the timers in the status bar, and the keys on
+        // the keypad
+        if self.debugger.is_open || self.status.is_open || self.keypad.is_open || self.key_overlay {
+            chip8.request_redraw();
+        }
+    }
+}
+
+impl App {
+    /// Works out what has to be written to the terminal to show the chip8
+    /// interpreter's draw buffer, which is only the cells that are different
+    /// from the last frame
+    fn render_frame(&mut self, chip8: &Chip8) -> Result<Vec<u8>, Error> {
+        let mut output = Vec::new();
+        let (terminal_width, terminal_height) = self.terminal_size;
+        let area = Rect::new(0, 0, terminal_width, terminal_height);
+
+        if self.needs_clear {
+            // Gets rid of whatever the last renderer or the menu left behind,
+            // so everything has to be written again
+            write!(output, "\x1b[2J")?;
+            self.previous = Buffer::empty(area);
+            self.needs_clear = false;
+        }
+        if self.keypad.is_open != self.mouse_captured {
+            if self.keypad.is_open {
+                queue!(output, EnableMouseCapture)?;
+            } else {
+                queue!(output, DisableMouseCapture)?;
             }
-            // Flush the content that has been written to the terminal
-            stdout.flush()?;
+            self.mouse_captured = self.keypad.is_open;
+        }
+
+        let mut buffer = Buffer::empty(area);
+        if self.debugger.is_open {
+            self.draw_panes(chip8, area, &mut buffer);
+        } else {
+            self.draw_screen(chip8, area, &mut buffer);
+        }
+        let changes = self.previous.diff(&buffer);
+        if !changes.is_empty() {
+            CrosstermBackend::new(&mut output).draw(changes.into_iter())?;
+        }
+        self.previous = buffer;
+        Ok(output)
+    }
+
+    /// Returns the lines of the screen, drawn as big as it fits in the `room`,
+    /// with a border around it if it can have one and there is room for it.
+    /// While the ghosting is on, it also returns how bright each character of
+    /// the lines is, which is empty while it is off.
+    fn render_screen(
+        &self,
+        chip8: &Chip8,
+        room: (u16, u16),
+        border: bool,
+    ) -> (Vec<String>, Vec<Vec<f32>>) {
+        let (width, height) = self.rotation.size(chip8.screen().size());
+        let (columns, rows) = self.renderer.size(width, height);
+        let (scale, has_border) = self.fit(columns, rows, room, border);
+        let screen = self.renderer.render(chip8, &self.phosphor, self.rotation);
+        let lines = renderer::enlarge(screen, scale);
+        let mut shades = Vec::new();
+        if self.phosphor.is_on() {
+            let unscaled = self.renderer.shades(chip8, &self.phosphor, self.rotation);
+            shades = renderer::enlarge_shades(unscaled, scale);
+        }
+        if has_border {
+            // The border is always as bright as it gets
+            if !shades.is_empty() {
+                let width = shades[0].len() + 2;
+                shades = std::iter::once(vec![1.0; width])
+                    .chain(shades.into_iter().map(|row| {
+                        std::iter::once(1.0)
+                            .chain(row)
+                            .chain(std::iter::once(1.0))
+                            .collect()
+                    }))
+                    .chain(std::iter::once(vec![1.0; width]))
+                    .collect();
+            }
+            (renderer::add_border(lines, self.sound_playing), shades)
+        } else {
+            (lines, shades)
         }
-        // If we got here, then everything worked as intended
-        Ok(())
     }
 
-    // This is just a helper function, going into the semantic compression theory
-    // being, if you use it more than once, make it into a function
-    fn calculate_duration(time_from: SystemTime) -> Duration {
-        // Get the current time
-        let now = SystemTime::now();
-        // Get the duration, and check to see if it makes sense/throws an error
-        match now.duration_since(time_from) {
-            Ok(duration) => duration,      // The duration is reasonable
-            Err(_) => Duration::new(0, 0), // The duration is negative
+    /// Draws the screen in the middle of the terminal, with the status bar
+    /// and the prompt under it, and the keypad next to it
+    fn draw_screen(&mut self, chip8: &Chip8, area: Rect, buffer: &mut Buffer) {
+        let (width, height) = self.rotation.size(chip8.screen().size());
+        let (columns, rows) = self.renderer.size(width, height);
+
+        // Instead of drawing a garbled screen, say what size is needed
+        if area.width < columns || area.height < rows {
+            let message = format!(
+                "Terminal too small: need {}x{}, have {}x{}",
+                columns, rows, area.width, area.height
+            );
+            buffer.set_stringn(0, 0, message, area.width as usize, Style::default());
+            return;
+        }
+
+        // The status bar and the prompt take up a row each under the screen,
+        // if there is still a row left for them
+        let status_rows = self.status.is_open as u16 + self.prompt.is_some() as u16;
+        // The keypad goes to the right of the screen, with a column of space
+        // in between them, as long as there is room for it next to the
+        // smallest screen
+        let keypad_columns = if self.keypad.is_open && area.width > columns + keypad::COLUMNS {
+            keypad::COLUMNS + 1
+        } else {
+            0
+        };
+        let room = (
+            area.width - keypad_columns,
+            area.height.saturating_sub(status_rows).max(rows),
+        );
+        let (mut lines, shades) = self.render_screen(chip8, room, true);
+        let width = lines.first().map(|line| line.chars().count()).unwrap_or(0);
+        let screen_rows = lines.len() as u16;
+        if self.status.is_open && lines.len() < area.height as usize {
+            lines.push(self.status.render(chip8, width));
+        }
+        if let Some(suggestion) = &self.prompt {
+            if lines.len() < area.height as usize {
+                lines.push(render_prompt(suggestion, width));
+            }
+        }
+
+        // The screen and the keypad get centered together
+        let x = area.width.saturating_sub(width as u16 + keypad_columns) / 2;
+        let screen = Rect::new(x, area.y, width as u16, area.height);
+        let origin = draw_centered(&lines, screen, self.palette.style(), buffer);
+        draw_shades(&shades, origin, screen, self.palette, buffer);
+        let y = area.height.saturating_sub(lines.len() as u16) / 2;
+        if self.key_overlay {
+            let screen = Rect::new(x, y, width as u16, screen_rows);
+            keypad::draw_overlay(&self.keymap, self.pressed_keys, screen, buffer);
         }
+        if keypad_columns > 0 {
+            let keypad = Rect::new(
+                screen.right() + 1,
+                y,
+                keypad::COLUMNS,
+                keypad::ROWS.min(area.height - y),
+            );
+            self.keypad.draw(chip8, self.pressed_keys, keypad, buffer);
+        }
+    }
+
+    /// Draws the screen in a pane with the debugger's panes around it, and
+    /// the status bar, the prompt, and the command line under them
+    fn draw_panes(&mut self, chip8: &Chip8, area: Rect, buffer: &mut Buffer) {
+        let rows = Layout::vertical([
+            Constraint::Percentage(self.top_share),
+            Constraint::Min(0),
+            Constraint::Length(self.status.is_open as u16),
+            Constraint::Length(self.prompt.is_some() as u16),
+            Constraint::Length(1),
+        ])
+        .split(area);
+        let keypad_columns = if self.keypad.is_open {
+            keypad::COLUMNS
+        } else {
+            0
+        };
+        let top = Layout::horizontal([
+            Constraint::Percentage(self.screen_share),
+            Constraint::Length(keypad_columns),
+            Constraint::Min(0),
+        ])
+        .split(rows[0]);
+
+        // The pane's border is the screen's border, which gets thicker while
+        // the tone plays the same as the screen's own
+        let pane = Block::default()
+            .borders(Borders::ALL)
+            .border_type(if self.sound_playing {
+                BorderType::Thick
+            } else {
+                BorderType::Plain
+            })
+            .title("screen");
+        let inner = pane.inner(top[0]);
+        pane.render(top[0], buffer);
+        buffer.set_style(inner, self.palette.style());
+        let (lines, shades) = self.render_screen(chip8, (inner.width, inner.height), false);
+        let origin = draw_centered(&lines, inner, self.palette.style(), buffer);
+        draw_shades(&shades, origin, inner, self.palette, buffer);
+        if self.key_overlay {
+            keypad::draw_overlay(&self.keymap, self.pressed_keys, inner, buffer);
+        }
+
+        if self.keypad.is_open {
+            let keypad = Rect {
+                height: top[1].height.min(keypad::ROWS),
+                ..top[1]
+            };
+            self.keypad.draw(chip8, self.pressed_keys, keypad, buffer);
+        }
+        self.debugger.draw(chip8, top[2], rows[1], buffer);
+
+        if self.status.is_open {
+            let status = rows[2];
+            buffer.set_stringn(
+                status.x,
+                status.y,
+                self.status.render(chip8, status.width as usize),
+                status.width as usize,
+                Style::default(),
+            );
+        }
+        if let Some(suggestion) = &self.prompt {
+            let prompt = rows[3];
+            buffer.set_stringn(
+                prompt.x,
+                prompt.y,
+                render_prompt(suggestion, prompt.width as usize),
+                prompt.width as usize,
+                Style::default(),
+            );
+        }
+        let command = match &self.command {
+            Some(typed) => format!(":{}_", typed),
+            None if !self.answer.is_empty() => self.answer.clone(),
+            None => String::from("Press : to type a command, like peek 200 8"),
+        };
+        let line = rows[4];
+        buffer.set_stringn(
+            line.x,
+            line.y,
+            command,
+            line.width as usize,
+            Style::default(),
+        );
+    }
+}
+
+/// Draws the lines in the middle of the area, cutting off anything that goes
+/// past the edges of it
+fn draw_centered(lines: &[String], area: Rect, style: Style, buffer: &mut Buffer) -> (u16, u16) {
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0) as u16;
+    let x = area.x + area.width.saturating_sub(width) / 2;
+    let y = area.y + area.height.saturating_sub(lines.len() as u16) / 2;
+    for (row, line) in lines.iter().take(area.height as usize).enumerate() {
+        buffer.set_stringn(x, y + row as u16, line, area.width as usize, style);
+    }
+    (x, y)
+}
+
+/// Dims the characters that only have pixels in them that are fading out, the
+/// shades line up with the lines that were drawn from `origin`
+fn draw_shades(
+    shades: &[Vec<f32>],
+    (x, y): (u16, u16),
+    area: Rect,
+    palette: Palette,
+    buffer: &mut Buffer,
+) {
+    for (row, shades) in shades.iter().enumerate() {
+        for (column, shade) in shades.iter().enumerate() {
+            let (x, y) = (x + column as u16, y + row as u16);
+            if *shade >= 1.0 || x >= area.right() || y >= area.bottom() {
+                continue;
+            }
+            let [red, green, blue] = palette.blend(*shade);
+            buffer.get_mut(x, y).set_fg(Color::Rgb(red, green, blue));
+        }
+    }
+}
+
+/// Returns the line that asks whether to use the suggested quirks, cut off or
+/// padded out with spaces so that it is exactly `width` characters wide
+fn render_prompt(suggestion: &Suggestion, width: usize) -> String {
+    let line = format!(
+        "Made for {}? Enter uses its quirks, Backspace doesn't ({})",
+        suggestion.name,
+        suggestion.reasons.join(", ")
+    );
+    line.chars()
+        .chain(std::iter::repeat(' '))
+        .take(width)
+        .collect()
+}
+
+impl Display for App {
+    /// Prints out the chip8 interpreter's draw buffer to the terminal, by
+    /// handing it to the thread that writes to it
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error> {
+        let frame = self.render_frame(chip8)?;
+        if frame.is_empty() {
+            return Ok(());
+        }
+        self.output.send(frame)
+    }
+}
+
+/// Puts the terminal into the mode that the app draws in, and puts it back
+/// the way it was when this is dropped, even if something went wrong
+struct TerminalMode {
+    /// Whether the terminal was asked to say when keys are let go of
+    key_releases: bool,
+}
+
+impl TerminalMode {
+    fn enter() -> Result<Self, Error> {
+        // Raw mode passes every key straight through, without waiting for enter
+        terminal::enable_raw_mode()?;
+        // Creates an alternate screen, so that the contents of the terminal
+        // aren't overridden, and hides the cursor
+        execute!(stdout(), EnterAlternateScreen, Hide)?;
+        // Only some terminals can say when a key is let go of, and only once
+        // they have been asked to
+        let key_releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if key_releases {
+            execute!(
+                stdout(),
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+        }
+        Ok(TerminalMode { key_releases })
+    }
+}
+
+impl Drop for TerminalMode {
+    /// There is nothing to be done if the terminal can't be put back
+    fn drop(&mut self) {
+        if self.key_releases {
+            let _ = execute!(stdout(), PopKeyboardEnhancementFlags);
+        }
+        // The keypad might have asked for the mouse's clicks
+        let _ = execute!(stdout(), DisableMouseCapture);
+        let _ = execute!(stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl Audio for App {
+    /// The terminal doesn't have a way to play a tone, so instead the border
+    /// gets thicker while it plays, and the bell rings when it starts if it
+    /// was asked for
+    fn set_playing(&mut self, playing: bool) -> Result<(), Error> {
+        self.sound_playing = playing;
+        if playing && self.bell {
+            self.output.send(b"\x07".to_vec())?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,31 @@
+//! This module contains the `assemble` command, which builds Octo source into
+//! a rom that other interpreters can run
+
+use chip_8::octo;
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+};
+
+/// Runs `chip_8 assemble <source> <rom>`, which assembles the source and saves
+/// the rom. Running the source doesn't need this, `chip_8 game.8o` assembles
+/// it as it is loaded.
+pub fn command(args: Vec<String>) -> Result<(), Error> {
+    let (source, rom) = match args.as_slice() {
+        [source, rom] => (source, rom),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "assemble needs the source and where to save the rom, like `chip_8 assemble game.8o game.ch8`",
+            ))
+        }
+    };
+    let text = fs::read_to_string(source)?;
+    let bytes = octo::assemble(&text).map_err(|error| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{} can't be assembled, {}", source, error),
+        )
+    })?;
+    fs::write(rom, bytes)
+}
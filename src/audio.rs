@@ -0,0 +1,262 @@
+//! This module contains the buzzer, which is the tone that plays while the
+//! sound timer is running, and XO-CHIP's audio, which lets a rom choose what
+//! the buzzer sounds like instead of it always being the same tone.
+//!
+//! The buzzer's waveform, frequency, and volume are up to the user, and
+//! `Buzzer` turns them into samples the same way `PatternPlayer` does for the
+//! pattern, so every frontend that plays samples sounds the same.
+//!
+//! The sound is a pattern of 128 bits that loops for as long as the sound timer
+//! is running, with each bit being the speaker either pushed out or pulled in.
+//! The instructions for it are:
+//! - `f002` loads the 16 bytes at the index into the pattern
+//! - `fx3a` sets the pitch to register x, which is how fast the pattern plays
+//!
+//! The pattern plays at 4000 bits a second at the starting pitch of 64, and
+//! every 48 higher doubles that. A rom that never loads a pattern gets the
+//! normal tone, so the frontends only have to play the pattern once there is
+//! one, and `PatternPlayer` turns it into samples for whatever rate the audio
+//! device plays at.
+//!
+//! Turning the tone and the pattern into samples needs the floating point math
+//! from the standard library, so that is left out without the `std` feature.
+
+use crate::{
+    chip8::{Chip8, Opcode},
+    error::Chip8Error,
+};
+
+/// How many bytes are in the pattern
+pub const PATTERN_SIZE: usize = 16;
+/// How many bits are in the pattern, which is how many it takes to loop
+#[cfg(feature = "std")]
+const PATTERN_BITS: f64 = (PATTERN_SIZE * 8) as f64;
+
+/// The pitch that the rom starts out with, which plays the pattern at
+/// `BASE_RATE`
+const BASE_PITCH: u8 = 64;
+/// How many bits a second the pattern plays at, at the starting pitch
+#[cfg(feature = "std")]
+const BASE_RATE: f64 = 4000.0;
+/// How much higher the pitch has to go to play the pattern twice as fast
+#[cfg(feature = "std")]
+const PITCH_OCTAVE: f64 = 48.0;
+
+/// The frequency of the tone, unless the user picked another one
+pub const DEFAULT_FREQUENCY: f32 = 440.0;
+/// How loud the tone is, unless the user picked another volume, where 1 is as
+/// loud as the device goes
+pub const DEFAULT_VOLUME: f32 = 0.25;
+/// The lowest and highest frequencies that the tone can be, anything past them
+/// can't be heard anyways
+pub const MIN_FREQUENCY: f32 = 20.0;
+pub const MAX_FREQUENCY: f32 = 20000.0;
+
+/// The shapes that the buzzer's tone can have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    /// The harsh beep that most interpreters play
+    Square,
+    /// A soft, pure tone
+    Sine,
+    /// Somewhere in between the other two
+    Triangle,
+}
+
+impl Waveform {
+    /// Gets the waveform with the name that is used for it in the options
+    pub fn from_name(name: &str) -> Option<Waveform> {
+        match name {
+            "square" => Some(Waveform::Square),
+            "sine" => Some(Waveform::Sine),
+            "triangle" => Some(Waveform::Triangle),
+            _ => None,
+        }
+    }
+
+    /// The name that `from_name` takes
+    pub fn name(self) -> &'static str {
+        match self {
+            Waveform::Square => "square",
+            Waveform::Sine => "sine",
+            Waveform::Triangle => "triangle",
+        }
+    }
+
+    /// The waveform after this one, for going through them with a key
+    pub fn next(self) -> Waveform {
+        match self {
+            Waveform::Square => Waveform::Sine,
+            Waveform::Sine => Waveform::Triangle,
+            Waveform::Triangle => Waveform::Square,
+        }
+    }
+
+    /// Where the speaker is, from -1 to 1, at a point in a cycle of the tone
+    /// from 0 to 1
+    #[cfg(feature = "std")]
+    fn level(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square if phase <= 0.5 => 1.0,
+            Waveform::Square => -1.0,
+            Waveform::Sine => (phase * std::f32::consts::TAU).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+        }
+    }
+}
+
+/// The tone that plays while the sound timer is running, for the roms that
+/// haven't loaded a pattern. The frontend can change any of it while it is
+/// playing.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct Buzzer {
+    pub waveform: Waveform,
+    /// In hertz, from `MIN_FREQUENCY` to `MAX_FREQUENCY`
+    pub frequency: f32,
+    /// From 0 to 1
+    pub volume: f32,
+    /// How far through a cycle of the tone it is, from 0 to 1
+    phase: f32,
+}
+
+#[cfg(feature = "std")]
+impl Default for Buzzer {
+    fn default() -> Self {
+        Buzzer::new(Waveform::Square, DEFAULT_FREQUENCY, DEFAULT_VOLUME)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Buzzer {
+    /// Creates the tone, with the frequency and volume kept to what they can be
+    pub fn new(waveform: Waveform, frequency: f32, volume: f32) -> Self {
+        Buzzer {
+            waveform,
+            frequency: frequency.clamp(MIN_FREQUENCY, MAX_FREQUENCY),
+            volume: volume.clamp(0.0, 1.0),
+            phase: 0.0,
+        }
+    }
+
+    /// Fills the samples with the tone, on a device that plays `device_rate`
+    /// samples a second
+    pub fn fill(&mut self, device_rate: f32, samples: &mut [f32]) {
+        let step = self.frequency / device_rate;
+        for sample in samples.iter_mut() {
+            *sample = self.volume * self.waveform.level(self.phase);
+            self.phase = (self.phase + step) % 1.0;
+        }
+    }
+}
+
+/// The pattern that the rom loaded, and the pitch it set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct AudioState {
+    pattern: Option<[u8; PATTERN_SIZE]>,
+    pitch: u8,
+}
+
+impl Default for AudioState {
+    fn default() -> Self {
+        AudioState {
+            pattern: None,
+            pitch: BASE_PITCH,
+        }
+    }
+}
+
+impl Chip8 {
+    /// Returns the pattern that the rom loaded with `f002`, or `None` if it
+    /// hasn't loaded one, and the normal tone should be played
+    pub fn audio_pattern(&self) -> Option<[u8; PATTERN_SIZE]> {
+        self.audio.pattern
+    }
+
+    /// Returns the pitch that the rom set with `fx3a`
+    pub fn pitch(&self) -> u8 {
+        self.audio.pitch
+    }
+
+    /// Returns how many bits of the pattern play in a second at the pitch
+    #[cfg(feature = "std")]
+    pub fn playback_rate(&self) -> f64 {
+        BASE_RATE * 2f64.powf((self.audio.pitch as f64 - BASE_PITCH as f64) / PITCH_OCTAVE)
+    }
+
+    /// Opcode: `f002`
+    ///
+    /// Explanation: Loads the 16 bytes at the index into the audio pattern.
+    pub(crate) fn audio(&mut self, _opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.check_index(PATTERN_SIZE)?;
+        let mut pattern = [0; PATTERN_SIZE];
+        for (i, byte) in pattern.iter_mut().enumerate() {
+            *byte = self.read_memory(self.index_address(i));
+        }
+        self.audio.pattern = Some(pattern);
+        Ok(())
+    }
+
+    /// Opcode: `fx3a`
+    ///
+    /// Explanation: Sets the pitch of the audio pattern to the value of
+    /// register x.
+    pub(crate) fn ldpitch(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.audio.pitch = self.registers[opcode.x as usize];
+        Ok(())
+    }
+}
+
+/// Turns the pattern into samples for an audio device, keeping track of where
+/// it is in the pattern so that it carries on smoothly from one buffer to the
+/// next
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct PatternPlayer {
+    /// How far into the pattern it is, in bits
+    position: f64,
+}
+
+#[cfg(feature = "std")]
+impl PatternPlayer {
+    pub fn new() -> Self {
+        PatternPlayer::default()
+    }
+
+    /// Fills the samples with the pattern playing at `rate` bits a second, on
+    /// a device that plays `device_rate` samples a second. Each sample is the
+    /// average of the bits that it covers, so the pattern doesn't get harsher
+    /// when it plays faster than the device does.
+    pub fn fill(
+        &mut self,
+        pattern: &[u8; PATTERN_SIZE],
+        rate: f64,
+        device_rate: f64,
+        volume: f32,
+        samples: &mut [f32],
+    ) {
+        let step = rate / device_rate;
+        if !(step > 0.0 && step.is_finite()) {
+            samples.iter_mut().for_each(|sample| *sample = 0.0);
+            return;
+        }
+        for sample in samples.iter_mut() {
+            let end = self.position + step;
+            let mut start = self.position;
+            let mut on = 0.0;
+            while start < end {
+                let bit_end = (start.floor() + 1.0).min(end);
+                let bit = start as usize % (PATTERN_SIZE * 8);
+                if pattern[bit / 8] & (0x80 >> (bit % 8)) != 0 {
+                    on += bit_end - start;
+                }
+                start = bit_end;
+            }
+            // All of the bits being on pushes the speaker all the way out,
+            // and all of them being off pulls it all the way in
+            *sample = volume * (2.0 * (on / step) as f32 - 1.0);
+            self.position = end % PATTERN_BITS;
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! This module contains what happens when the program counter goes somewhere
+//! that a rom's code can't be, which is past the end of memory, or below
+//! `0x200` where the interpreter and the fonts are. A rom that gets there has
+//! almost always gone wrong, like by returning with a stack that was messed
+//! up, so it can be stopped right there instead of running whatever it finds.
+//!
+//! It also contains what happens when the index plus how far an instruction
+//! reads or writes from it goes past the end of memory, which `dxyn`, `fx33`,
+//! `fx55`, and `fx65` can all do with a bad index, and what happens when `fx1e`
+//! adds the index past the end, or `fx55` and `fx65` leave it there with the
+//! memory increment quirk.
+//!
+//! ```rust
+//! use chip_8::{bounds::ProgramCounterPolicy, Chip8, Chip8Error};
+//!
+//! let mut chip8 = Chip8::with_seed(0);
+//! // `1100` jumps into the fonts
+//! chip8.load(&[0x11, 0x00]).unwrap();
+//! chip8.clock().unwrap();
+//! assert_eq!(
+//!     chip8.clock(),
+//!     Err(Chip8Error::ProgramCounterOutOfBounds { pc: 0x100 })
+//! );
+//!
+//! // The COSMAC VIP would have kept on going, and wrapped around at the end
+//! chip8.set_program_counter_policy(ProgramCounterPolicy::Wrap);
+//! chip8.clock().unwrap();
+//! assert_eq!(chip8.program_counter, 0x102);
+//! ```
+//!
+//! ```rust
+//! use chip_8::{bounds::IndexPolicy, Chip8};
+//!
+//! let mut chip8 = Chip8::with_seed(0);
+//! chip8.set_index_policy(IndexPolicy::Wrap);
+//! // `6007` puts 7 in register 0, `affe` points the index at the second to
+//! // last byte, and `f033` stores 0, 0, 7 there, with the 7 going at the start
+//! // of memory
+//! chip8.load(&[0x60, 0x07, 0xaf, 0xfe, 0xf0, 0x33]).unwrap();
+//! for _ in 0..3 {
+//!     chip8.clock().unwrap();
+//! }
+//! assert_eq!(chip8.memory[0xfff], 0);
+//! assert_eq!(chip8.memory[0], 7);
+//! ```
+
+#[cfg(feature = "megachip")]
+use crate::megachip;
+use crate::{chip8::Chip8, error::Chip8Error, protection::PROTECTED_END};
+
+/// What happens when the program counter goes out of bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProgramCounterPolicy {
+    /// The instruction doesn't run, and stops with
+    /// `Chip8Error::ProgramCounterOutOfBounds`
+    #[default]
+    Trap,
+    /// Anything goes below `0x200`, and going past the end of memory wraps
+    /// around to the start, like the 12 bit addresses of the COSMAC VIP did
+    Wrap,
+    /// The rom is paused with the program counter left where it is, so a
+    /// debugger can have a look at how it got there
+    Halt,
+}
+
+impl ProgramCounterPolicy {
+    /// Gets the policy that goes by the name passed in
+    pub fn from_name(name: &str) -> Option<ProgramCounterPolicy> {
+        match name {
+            "trap" => Some(ProgramCounterPolicy::Trap),
+            "wrap" => Some(ProgramCounterPolicy::Wrap),
+            "halt" => Some(ProgramCounterPolicy::Halt),
+            _ => None,
+        }
+    }
+}
+
+/// What happens when an instruction reads or writes past the end of memory
+/// from the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IndexPolicy {
+    /// The instruction doesn't run, and stops with
+    /// `Chip8Error::IndexOutOfBounds`
+    #[default]
+    Trap,
+    /// The addresses past the end wrap around to the start of memory, like
+    /// the 12 bit addresses of the COSMAC VIP did
+    Wrap,
+    /// The addresses past the end all go to the last byte of memory
+    Clamp,
+}
+
+impl IndexPolicy {
+    /// Gets the policy that goes by the name passed in
+    pub fn from_name(name: &str) -> Option<IndexPolicy> {
+        match name {
+            "trap" => Some(IndexPolicy::Trap),
+            "wrap" => Some(IndexPolicy::Wrap),
+            "clamp" => Some(IndexPolicy::Clamp),
+            _ => None,
+        }
+    }
+}
+
+impl Chip8 {
+    /// Sets what happens when the program counter goes out of bounds
+    pub fn set_program_counter_policy(&mut self, policy: ProgramCounterPolicy) {
+        self.program_counter_policy = policy;
+    }
+
+    /// Returns what happens when the program counter goes out of bounds
+    pub fn program_counter_policy(&self) -> ProgramCounterPolicy {
+        self.program_counter_policy
+    }
+
+    /// Returns whether the program counter is somewhere that code can be, with
+    /// both bytes of the instruction inside of memory. With the wrap policy it
+    /// always is, since it gets wrapped back around.
+    pub fn program_counter_in_bounds(&self) -> bool {
+        self.program_counter_policy == ProgramCounterPolicy::Wrap
+            || (self.program_counter >= PROTECTED_END
+                && self.program_counter < self.memory.len().saturating_sub(1))
+    }
+
+    /// Sets what happens when an instruction goes past the end of memory from
+    /// the index
+    pub fn set_index_policy(&mut self, policy: IndexPolicy) {
+        self.index_policy = policy;
+    }
+
+    /// Returns what happens when an instruction goes past the end of memory
+    /// from the index
+    pub fn index_policy(&self) -> IndexPolicy {
+        self.index_policy
+    }
+
+    /// Makes sure that `length` bytes starting at the index are inside of
+    /// memory, which they always are unless going past the end is trapped
+    pub(crate) fn check_index(&self, length: usize) -> Result<(), Chip8Error> {
+        if self.index_policy == IndexPolicy::Trap && self.index + length > self.memory.len() {
+            return Err(Chip8Error::IndexOutOfBounds {
+                pc: self.program_counter,
+                index: self.index,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns how far the index can go, which is the end of memory, except
+    /// with MEGA-CHIP's big screen on, where the index is 24 bits and points
+    /// into it's own much bigger memory
+    pub(crate) fn index_space(&self) -> usize {
+        #[cfg(feature = "megachip")]
+        if self.is_megachip_on() {
+            return megachip::MEMORY_SIZE;
+        }
+        self.memory.len()
+    }
+
+    /// Brings a new value for the index back inside of memory by the policy,
+    /// for `fx1e`, and `fx55` and `fx65` with the memory increment quirk,
+    /// which can all move the index past the end. When it is trapped the
+    /// index is left the way it was.
+    pub(crate) fn index_in_bounds(&self, index: usize) -> Result<usize, Chip8Error> {
+        let size = self.index_space();
+        if index < size {
+            return Ok(index);
+        }
+        match self.index_policy {
+            IndexPolicy::Trap => Err(Chip8Error::IndexOutOfBounds {
+                pc: self.program_counter,
+                index,
+            }),
+            // With the 4K of memory that there normally is, this keeps the
+            // index to 12 bits
+            IndexPolicy::Wrap => Ok(index % size),
+            IndexPolicy::Clamp => Ok(size - 1),
+        }
+    }
+
+    /// Returns the address that is `offset` bytes from the index, brought back
+    /// inside of memory by the policy. When it is trapped, `check_index` has
+    /// already made sure that it is inside.
+    pub(crate) fn index_address(&self, offset: usize) -> usize {
+        let address = self.index + offset;
+        match self.index_policy {
+            IndexPolicy::Trap => address,
+            IndexPolicy::Wrap => address % self.memory.len(),
+            IndexPolicy::Clamp => address.min(self.memory.len() - 1),
+        }
+    }
+}
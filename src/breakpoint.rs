@@ -0,0 +1,418 @@
+//! This module contains breakpoints, which stop the rom as soon as a condition
+//! about it's state becomes true, like `v3 == 0x1f && i > 0x300`.
+//!
+//! A condition can look at the registers `v0` through `vf`, the index `i`, the
+//! program counter `pc`, the stack pointer `sp`, the timers `dt` and `st`, and
+//! a byte of memory with the address in square brackets, like `[i + 1]`. Those
+//! can be compared with `==`, `!=`, `<`, `<=`, `>`, and `>=`, and the
+//! comparisons put together with `&&`, `||`, `!`, and brackets. Numbers are in
+//! decimal, or in hex with a `0x` in front of them. Anything that isn't
+//! compared is true when it isn't 0, so `vf` alone is the same as `vf != 0`.
+//!
+//! With the names from a symbol file, see `Condition::with_symbols`, a name
+//! is the address it stands for, like `i == sprites + 5`, and a condition
+//! that is only a name stops the rom when it gets there, so `game_loop` is
+//! the same as `pc == game_loop`. Only names made of letters, digits, and `_`
+//! can be used, and `i`, `pc`, and the rest mean what they always do.
+//!
+//! ```rust
+//! use chip_8::{breakpoint::Condition, Chip8};
+//!
+//! let mut chip8 = Chip8::new();
+//! chip8.registers[3] = 0x1f;
+//! chip8.index = 0x320;
+//! let condition: Condition = "v3 == 0x1F && i > 0x300".parse().unwrap();
+//! assert!(condition.is_true(&chip8));
+//! ```
+
+use crate::{chip8::Chip8, error::ConditionError, symbols::Symbols};
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, str::FromStr};
+
+/// The ways that two values can be compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// A condition after it has been parsed, which can be worked out for any state
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expression {
+    Number(usize),
+    Register(usize),
+    Index,
+    ProgramCounter,
+    StackPointer,
+    Delay,
+    Sound,
+    /// The byte of memory at the address, anything outside of memory is 0
+    Memory(Box<Expression>),
+    Add(Box<Expression>, Box<Expression>),
+    Subtract(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Compare(Box<Expression>, Comparison, Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Works out the value of the expression, comparisons are 1 when they are
+    /// true and 0 when they aren't
+    fn evaluate(&self, chip8: &Chip8) -> usize {
+        match self {
+            Expression::Number(number) => *number,
+            Expression::Register(register) => chip8.registers[*register] as usize,
+            Expression::Index => chip8.index,
+            Expression::ProgramCounter => chip8.program_counter,
+            Expression::StackPointer => chip8.stack_pointer,
+            Expression::Delay => chip8.delay as usize,
+            Expression::Sound => chip8.sound as usize,
+            Expression::Memory(address) => chip8
+                .memory
+                .get(address.evaluate(chip8))
+                .map(|byte| *byte as usize)
+                .unwrap_or(0),
+            Expression::Add(left, right) => {
+                left.evaluate(chip8).wrapping_add(right.evaluate(chip8))
+            }
+            Expression::Subtract(left, right) => {
+                left.evaluate(chip8).wrapping_sub(right.evaluate(chip8))
+            }
+            Expression::Not(value) => (value.evaluate(chip8) == 0) as usize,
+            Expression::Compare(left, comparison, right) => {
+                let (left, right) = (left.evaluate(chip8), right.evaluate(chip8));
+                let is_true = match comparison {
+                    Comparison::Equal => left == right,
+                    Comparison::NotEqual => left != right,
+                    Comparison::Less => left < right,
+                    Comparison::LessOrEqual => left <= right,
+                    Comparison::Greater => left > right,
+                    Comparison::GreaterOrEqual => left >= right,
+                };
+                is_true as usize
+            }
+            // Like in most languages, the right side isn't looked at when the
+            // left side already decides it
+            Expression::And(left, right) => {
+                (left.evaluate(chip8) != 0 && right.evaluate(chip8) != 0) as usize
+            }
+            Expression::Or(left, right) => {
+                (left.evaluate(chip8) != 0 || right.evaluate(chip8) != 0) as usize
+            }
+        }
+    }
+}
+
+/// One piece of a condition
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(usize),
+    Name(String),
+    /// Any of the operators and brackets
+    Symbol(&'static str),
+}
+
+/// Every operator and bracket, the ones that start with the same character as
+/// another one come first so that they are found first
+const SYMBOLS: [&str; 15] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "(", ")", "[", "]", "+", "-",
+];
+
+/// Splits the condition up into it's pieces, along with where each one starts
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, ConditionError> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while position < text.len() {
+        let rest = &text[position..];
+        let c = rest.chars().next().unwrap_or_default();
+        if c.is_whitespace() {
+            position += c.len_utf8();
+            continue;
+        }
+        if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+            tokens.push((position, Token::Symbol(symbol)));
+            position += symbol.len();
+            continue;
+        }
+        if !c.is_ascii_alphanumeric() && c != '_' {
+            return Err(ConditionError::Unexpected {
+                position,
+                found: c.to_string(),
+            });
+        }
+
+        // Numbers and names both go until the next thing that isn't a letter,
+        // digit, or `_`. Names keep their case, since the ones from a symbol
+        // file could differ by it.
+        let length = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+        let word = &rest[..length];
+        let token = if c.is_ascii_digit() {
+            let word = word.to_ascii_lowercase();
+            let number = match word.strip_prefix("0x") {
+                Some(digits) => usize::from_str_radix(digits, 16),
+                None => word.parse(),
+            };
+            Token::Number(number.map_err(|_| ConditionError::Unexpected {
+                position,
+                found: word.clone(),
+            })?)
+        } else {
+            Token::Name(word.to_string())
+        };
+        tokens.push((position, token));
+        position += length;
+    }
+    Ok(tokens)
+}
+
+/// Turns the pieces of a condition into an expression, from the loosest
+/// operator (`||`) down to the tightest (`!` and the brackets)
+struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    /// The names that stand for addresses, on top of the registers
+    symbols: Option<&'a Symbols>,
+}
+
+impl Parser<'_> {
+    /// Looks at the next token without using it up
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    /// Uses up the next token if it is the symbol
+    fn eat(&mut self, symbol: &str) -> bool {
+        match self.peek() {
+            Some(Token::Symbol(next)) if *next == symbol => {
+                self.next += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Uses up the next token, which has to be the symbol
+    fn expect(&mut self, symbol: &str) -> Result<(), ConditionError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// The error for the next token not being something that can go there
+    fn unexpected(&self) -> ConditionError {
+        match self.tokens.get(self.next) {
+            Some((position, token)) => ConditionError::Unexpected {
+                position: *position,
+                found: match token {
+                    Token::Number(number) => number.to_string(),
+                    Token::Name(name) => name.clone(),
+                    Token::Symbol(symbol) => symbol.to_string(),
+                },
+            },
+            None => ConditionError::UnexpectedEnd,
+        }
+    }
+
+    /// Returns the address that the name stands for, if there is one
+    fn symbol(&self, name: &str) -> Option<usize> {
+        self.symbols.and_then(|symbols| symbols.address(name))
+    }
+
+    fn or(&mut self) -> Result<Expression, ConditionError> {
+        let mut left = self.and()?;
+        while self.eat("||") {
+            left = Expression::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expression, ConditionError> {
+        let mut left = self.comparison()?;
+        while self.eat("&&") {
+            left = Expression::And(Box::new(left), Box::new(self.comparison()?));
+        }
+        Ok(left)
+    }
+
+    /// Only one comparison can be made at a time, `a < b < c` needs brackets
+    fn comparison(&mut self) -> Result<Expression, ConditionError> {
+        let left = self.sum()?;
+        let comparisons = [
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ];
+        for (symbol, comparison) in comparisons.iter() {
+            if self.eat(symbol) {
+                let right = self.sum()?;
+                return Ok(Expression::Compare(
+                    Box::new(left),
+                    *comparison,
+                    Box::new(right),
+                ));
+            }
+        }
+        Ok(left)
+    }
+
+    fn sum(&mut self) -> Result<Expression, ConditionError> {
+        let mut left = self.value()?;
+        loop {
+            if self.eat("+") {
+                left = Expression::Add(Box::new(left), Box::new(self.value()?));
+            } else if self.eat("-") {
+                left = Expression::Subtract(Box::new(left), Box::new(self.value()?));
+            } else {
+                return Ok(left);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Expression, ConditionError> {
+        if self.eat("!") {
+            return Ok(Expression::Not(Box::new(self.value()?)));
+        }
+        if self.eat("(") {
+            let inside = self.or()?;
+            self.expect(")")?;
+            return Ok(inside);
+        }
+        if self.eat("[") {
+            let address = self.or()?;
+            self.expect("]")?;
+            return Ok(Expression::Memory(Box::new(address)));
+        }
+        let value = match self.peek() {
+            Some(Token::Number(number)) => Expression::Number(*number),
+            Some(Token::Name(name)) => match name.to_ascii_lowercase().as_str() {
+                "i" => Expression::Index,
+                "pc" => Expression::ProgramCounter,
+                "sp" => Expression::StackPointer,
+                "dt" => Expression::Delay,
+                "st" => Expression::Sound,
+                lowercase => match (lowercase.strip_prefix('v'), self.symbol(name)) {
+                    (Some(digit), _) if digit.len() == 1 => {
+                        match usize::from_str_radix(digit, 16) {
+                            Ok(register) => Expression::Register(register),
+                            Err(_) => return Err(self.unexpected()),
+                        }
+                    }
+                    (_, Some(address)) => Expression::Number(address),
+                    _ => return Err(self.unexpected()),
+                },
+            },
+            _ => return Err(self.unexpected()),
+        };
+        self.next += 1;
+        Ok(value)
+    }
+}
+
+/// Something about the state of the interpreter that can be true or not
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    /// What was typed in, so it can be shown the same way
+    text: String,
+    expression: Expression,
+}
+
+impl Condition {
+    /// Parses a condition that can use the names of addresses in the symbols,
+    /// a condition that is only a name is true when the program counter gets
+    /// to it
+    pub fn with_symbols(text: &str, symbols: &Symbols) -> Result<Self, ConditionError> {
+        Condition::parse(text, Some(symbols))
+    }
+
+    fn parse(text: &str, symbols: Option<&Symbols>) -> Result<Self, ConditionError> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            next: 0,
+            symbols,
+        };
+        let mut expression = parser.or()?;
+        // Everything has to be part of the condition
+        if parser.peek().is_some() {
+            return Err(parser.unexpected());
+        }
+        // A name alone would always be true, since it is only an address,
+        // so it stops the rom at the address instead
+        if let (1, Expression::Number(address)) = (parser.tokens.len(), &expression) {
+            if let Some((_, Token::Name(_))) = parser.tokens.first() {
+                expression = Expression::Compare(
+                    Box::new(Expression::ProgramCounter),
+                    Comparison::Equal,
+                    Box::new(Expression::Number(*address)),
+                );
+            }
+        }
+        Ok(Condition {
+            text: text.trim().to_string(),
+            expression,
+        })
+    }
+
+    /// Returns whether the condition is true right now
+    pub fn is_true(&self, chip8: &Chip8) -> bool {
+        self.expression.evaluate(chip8) != 0
+    }
+}
+
+impl FromStr for Condition {
+    type Err = ConditionError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Condition::parse(text, None)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// A condition that stops the rom, only at the moment that it becomes true,
+/// so that a rom that is resumed while it is still true keeps going until the
+/// next time
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub condition: Condition,
+    was_true: bool,
+}
+
+impl Breakpoint {
+    /// Creates a breakpoint for the condition, if it is already true then it
+    /// has to become false before it stops anything
+    pub fn new(condition: Condition, chip8: &Chip8) -> Self {
+        Breakpoint {
+            was_true: condition.is_true(chip8),
+            condition,
+        }
+    }
+
+    /// Returns whether the condition just became true, which is when the rom
+    /// should be stopped
+    pub fn hit(&mut self, chip8: &Chip8) -> bool {
+        let is_true = self.condition.is_true(chip8);
+        let hit = is_true && !self.was_true;
+        self.was_true = is_true;
+        hit
+    }
+}
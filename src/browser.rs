@@ -0,0 +1,201 @@
+//! This module contains the menu that lets the user pick a rom out of a
+//! directory, for when the app is started without one, or when a rom gets
+//! unloaded to switch to another. A rom somewhere else can be opened by typing
+//! in its path.
+
+use crate::threads::{FrameSender, KeyReceiver};
+use crossterm::{
+    event::{Event, KeyCode, KeyEventKind},
+    terminal,
+};
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+/// A scrollable list of the roms in a directory
+pub struct RomBrowser {
+    /// The directory the roms are in, so it can be shown at the top
+    directory: PathBuf,
+    /// The roms that were played recently, followed by every other file in the
+    /// directory, sorted by name
+    roms: Vec<PathBuf>,
+    /// How many of the roms at the top of the list were played recently
+    recent: usize,
+    /// The position of the highlighted rom in the list
+    selected: usize,
+    /// The position of the rom at the top of the visible part of the list
+    scroll: usize,
+    /// The path that has been typed so far, while one is being typed in
+    typed_path: Option<String>,
+    /// Why the last path that was typed in couldn't be opened
+    path_error: Option<String>,
+}
+
+impl RomBrowser {
+    /// Reads the list of roms out of the directory, a directory that doesn't
+    /// exist just has no roms in it. The `recent` roms go at the top of the
+    /// list, as long as they are still there.
+    pub fn new(directory: &Path, recent: &[PathBuf]) -> Self {
+        let recent: Vec<PathBuf> = recent.iter().filter(|rom| rom.is_file()).cloned().collect();
+        let mut roms: Vec<PathBuf> = fs::read_dir(directory)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_file())
+                    .collect()
+            })
+            .unwrap_or_default();
+        roms.sort();
+        // The recent roms are saved with their full path, so the directory's
+        // roms have to be too in order to tell that they are the same
+        roms.retain(|rom| {
+            let rom = rom.canonicalize().unwrap_or_else(|_| rom.clone());
+            !recent.contains(&rom)
+        });
+
+        RomBrowser {
+            directory: directory.to_path_buf(),
+            recent: recent.len(),
+            roms: recent.into_iter().chain(roms).collect(),
+            selected: 0,
+            scroll: 0,
+            typed_path: None,
+            path_error: None,
+        }
+    }
+
+    /// Shows the menu until the user picks a rom, or returns `None` if they
+    /// pressed escape instead
+    pub fn choose(
+        &mut self,
+        keys: &KeyReceiver,
+        output: &FrameSender,
+    ) -> Result<Option<PathBuf>, Error> {
+        self.draw(output)?;
+
+        // Waits for each key press, and draws the menu again after it
+        while let Some(event) = keys.wait() {
+            let key = match event {
+                // Letting go of a key doesn't do anything in the menu
+                Event::Key(key) if key.kind != KeyEventKind::Release => key.code,
+                // The list gets drawn again to fit the new size
+                Event::Resize(..) => {
+                    self.draw(output)?;
+                    continue;
+                }
+                _ => continue,
+            };
+            // While a path is being typed, every key goes to it
+            if let Some(typed) = &mut self.typed_path {
+                match key {
+                    KeyCode::Char(c) => typed.push(c),
+                    KeyCode::Backspace => {
+                        typed.pop();
+                    }
+                    KeyCode::Esc => self.typed_path = None,
+                    KeyCode::Enter => {
+                        let path = PathBuf::from(typed.trim());
+                        if path.is_file() {
+                            return Ok(Some(path));
+                        }
+                        self.path_error = Some(format!("{} isn't a file", path.display()));
+                        self.typed_path = None;
+                    }
+                    _ => continue,
+                }
+                self.draw(output)?;
+                continue;
+            }
+            self.path_error = None;
+
+            // Leaves room for the title
+            let page = (terminal::size()?.1 as usize).saturating_sub(2).max(1);
+            match key {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Enter => {
+                    if let Some(rom) = self.roms.get(self.selected) {
+                        return Ok(Some(rom.clone()));
+                    }
+                }
+                KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Down => self.selected += 1,
+                KeyCode::PageUp => self.selected = self.selected.saturating_sub(page),
+                KeyCode::PageDown => self.selected += page,
+                KeyCode::Home => self.selected = 0,
+                KeyCode::End => self.selected = self.roms.len(),
+                KeyCode::Tab => self.typed_path = Some(String::new()),
+                _ => continue,
+            }
+            self.selected = self.selected.min(self.roms.len().saturating_sub(1));
+            self.draw(output)?;
+        }
+        Ok(None)
+    }
+
+    /// Draws the title, and as much of the list as fits, scrolled so that the
+    /// highlighted rom can be seen
+    fn draw(&mut self, output: &FrameSender) -> Result<(), Error> {
+        let (width, height) = terminal::size()?;
+        let visible = (height as usize).saturating_sub(2).max(1);
+
+        // Keeps the highlighted rom inside of the visible part of the list
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + visible {
+            self.scroll = self.selected + 1 - visible;
+        }
+
+        // Clears the terminal first
+        let mut menu = format!(
+            "\x1b[2J\x1b[1;1HPick a rom from {} (* played recently, arrows to move, enter to start, tab to type a path, esc to quit)",
+            self.directory.display()
+        );
+        if self.roms.is_empty() {
+            menu.push_str("\x1b[3;1HThere aren't any roms in here");
+        }
+        for (row, rom) in self.roms.iter().enumerate().skip(self.scroll).take(visible) {
+            let name = rom
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            // Recent roms are marked, since they might not be in the directory
+            let marker = if row < self.recent { "* " } else { "  " };
+            let name: String = marker
+                .chars()
+                .chain(name.chars())
+                .take(width as usize)
+                .collect();
+            // The highlighted rom is drawn with the colors swapped
+            let (start, end) = if row == self.selected {
+                ("\x1b[7m", "\x1b[0m")
+            } else {
+                ("", "")
+            };
+            menu.push_str(&format!(
+                "\x1b[{};1H{}{}{}",
+                row - self.scroll + 3,
+                start,
+                name,
+                end
+            ));
+        }
+        // The path being typed, or why the last one didn't work, goes over
+        // the bottom row
+        let bottom = match (&self.typed_path, &self.path_error) {
+            (Some(typed), _) => Some(format!("Open: {}", typed)),
+            (None, Some(error)) => Some(error.clone()),
+            (None, None) => None,
+        };
+        if let Some(bottom) = bottom {
+            let bottom: String = bottom.chars().take(width as usize).collect();
+            menu.push_str(&format!(
+                "\x1b[{};1H\x1b[2K\x1b[7m{}\x1b[27m",
+                height, bottom
+            ));
+        }
+        output.send(menu.into_bytes())
+    }
+}
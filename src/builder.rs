@@ -0,0 +1,129 @@
+//! This module contains the builder for the interpreter, for when the choices
+//! that `Chip8::new` makes aren't the right ones.
+//!
+//! ```rust
+//! use chip_8::{chip8::FONT, Chip8, Quirks};
+//!
+//! let chip8 = Chip8::builder()
+//!     .seed(0)
+//!     .quirks(Quirks::VIP)
+//!     .font(FONT)
+//!     .load_address(0x200)
+//!     .build();
+//! assert_eq!(chip8.memory.len(), 4096);
+//! ```
+//!
+//! With the `alloc` feature memory can be a different size too, using
+//! `memory_size`. Without it memory is always `MEMORY_SIZE` bytes.
+
+use crate::{
+    chip8::{Chip8, BIG_FONT, BIG_FONT_ADDRESS, FONT},
+    quirks::Quirks,
+};
+
+/// The choices that the interpreter gets made with, every one of them starts
+/// out as what `Chip8::new` uses
+#[derive(Debug, Clone)]
+pub struct Chip8Builder {
+    seed: Option<u64>,
+    random_source: Option<fn() -> u8>,
+    quirks: Quirks,
+    font: [[u8; 5]; 16],
+    big_font: [[u8; 10]; 16],
+    load_address: usize,
+    #[cfg(feature = "alloc")]
+    memory_size: usize,
+}
+
+impl Chip8 {
+    /// Starts building an interpreter, see `Chip8Builder`
+    pub fn builder() -> Chip8Builder {
+        Chip8Builder {
+            seed: None,
+            random_source: None,
+            quirks: Quirks::default(),
+            font: FONT,
+            big_font: BIG_FONT,
+            load_address: 0x200,
+            #[cfg(feature = "alloc")]
+            memory_size: crate::chip8::MEMORY_SIZE,
+        }
+    }
+}
+
+impl Chip8Builder {
+    /// Makes the `rnd` instruction always give the same numbers for the same
+    /// seed, without one the seed is random. Without the `std` feature there
+    /// is nothing to get a random seed from, so it is 0 instead.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Makes the `rnd` instruction get its numbers from the function instead
+    /// of the seed, like a microcontroller's hardware random number generator
+    pub fn random_source(mut self, source: fn() -> u8) -> Self {
+        self.random_source = Some(source);
+        self
+    }
+
+    /// Sets which quirks the instructions run with
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Sets the 4x5 font that `fx29` points at, one sprite for each hex digit
+    pub fn font(mut self, font: [[u8; 5]; 16]) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the 8x10 font that `fx30` points at, one sprite for each hex digit
+    pub fn big_font(mut self, big_font: [[u8; 10]; 16]) -> Self {
+        self.big_font = big_font;
+        self
+    }
+
+    /// Sets where roms get loaded into memory, and start running from
+    pub fn load_address(mut self, load_address: usize) -> Self {
+        self.load_address = load_address;
+        self
+    }
+
+    /// Sets how many bytes of memory there are, it always has room for the
+    /// fonts at the start
+    #[cfg(feature = "alloc")]
+    pub fn memory_size(mut self, memory_size: usize) -> Self {
+        self.memory_size = memory_size;
+        self
+    }
+
+    /// Creates the interpreter, with the fonts already in memory
+    pub fn build(self) -> Chip8 {
+        #[cfg(feature = "std")]
+        let seed = self.seed.unwrap_or_else(rand::random);
+        #[cfg(not(feature = "std"))]
+        let seed = self.seed.unwrap_or(0);
+        let mut chip8 = Chip8::empty(seed, self.load_address);
+        #[cfg(feature = "alloc")]
+        {
+            let big_font_end = BIG_FONT_ADDRESS + self.big_font.len() * self.big_font[0].len();
+            chip8.memory.resize(self.memory_size.max(big_font_end), 0);
+        }
+        chip8.quirks = self.quirks;
+        chip8.rng.set_source(self.random_source);
+
+        // The small font starts at the very beginning of memory, and the big
+        // font goes right after it
+        for (letter, sprite) in self.font.iter().enumerate() {
+            let offset = letter * sprite.len();
+            chip8.memory[offset..offset + sprite.len()].copy_from_slice(sprite);
+        }
+        for (letter, sprite) in self.big_font.iter().enumerate() {
+            let offset = BIG_FONT_ADDRESS + letter * sprite.len();
+            chip8.memory[offset..offset + sprite.len()].copy_from_slice(sprite);
+        }
+        chip8
+    }
+}
@@ -0,0 +1,120 @@
+//! This module contains the `callgraph` command, which prints which
+//! subroutines of a rom call which as a graph in Graphviz's DOT language, so it
+//! can be drawn with something like `chip_8 callgraph game.ch8 | dot -Tsvg`
+
+use crate::{config::Config, logging, rom, screenshot::Headless};
+use chip_8::{analysis::CallGraph, symbols::Symbols};
+use std::{
+    collections::BTreeSet,
+    io::{stdout, Error, ErrorKind, Write},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Runs `chip_8 callgraph [options] [--frames <number>] [--symbols <file>]
+/// <rom>`, which follows the code from where the rom starts to find the calls,
+/// and then runs it without showing it for a number of frames, 0 if it isn't
+/// given, to find the calls that can't be followed without running it. The
+/// subroutines are named from the symbol file, and every other option works
+/// the same as it does normally.
+pub fn command(mut args: Vec<String>) -> Result<(), Error> {
+    let mut frames = 0;
+    if let Some(position) = args.iter().position(|arg| arg == "--frames") {
+        args.remove(position);
+        if position >= args.len() {
+            return Err(invalid("--frames needs a value after it"));
+        }
+        let value = args.remove(position);
+        frames = value
+            .parse()
+            .map_err(|_| invalid(&format!("{} isn't a number of frames", value)))?;
+    }
+
+    let config = Config::from_args(args)?;
+    logging::init(&config)?;
+    let path = config
+        .rom
+        .clone()
+        .ok_or_else(|| invalid("callgraph needs a rom, like `chip_8 callgraph roms/pong.ch8`"))?;
+    let symbols = rom::symbols(&path, &config)?;
+    let (mut chip8, mut scheduler) = rom::start(&path, config.seed, &config)?;
+    let entry = chip8.load_address();
+    let end = entry + rom::read(&path)?.len();
+    let mut graph = chip8.call_graph(entry, end);
+    let followed: BTreeSet<(usize, usize)> = graph.calls().collect();
+
+    if frames > 0 {
+        // Every call that runs is written down, along with the subroutine that
+        // it was called from, which is the one under it on the stack
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let hook_seen = Arc::clone(&seen);
+        chip8.set_post_hook(Box::new(move |chip8, step| {
+            if step.mnemonic != "call" {
+                return;
+            }
+            let stack = chip8.call_stack();
+            let caller = stack.get(1).map_or(entry, |frame| frame.subroutine);
+            if let (Some(frame), Ok(mut seen)) = (stack.first(), hook_seen.lock()) {
+                seen.push((caller, frame.subroutine));
+            }
+        }));
+        scheduler.advance(
+            &mut chip8,
+            &mut Headless,
+            Duration::from_nanos(16_666_667 * frames),
+        )?;
+        let seen = seen
+            .lock()
+            .map_err(|_| Error::other("the calls got lost"))?;
+        for (caller, callee) in seen.iter() {
+            graph.add_call(*caller, *callee);
+        }
+    }
+
+    stdout().write_all(dot(&graph, &followed, &symbols).as_bytes())
+}
+
+/// Returns the graph in the DOT language, with each subroutine labelled with
+/// it's address and name. The entry is drawn in bold, and the calls that were
+/// only seen while the rom ran are dashed, since they couldn't be followed.
+fn dot(graph: &CallGraph, followed: &BTreeSet<(usize, usize)>, symbols: &Symbols) -> String {
+    let mut dot = String::from("digraph calls {\n    node [shape=box, fontname=\"monospace\"];\n");
+    for subroutine in graph.subroutines() {
+        let label = match symbols.name(subroutine) {
+            Some(name) => format!("{:#05x}\\n{}", subroutine, escape(name)),
+            None => format!("{:#05x}", subroutine),
+        };
+        let style = if subroutine == graph.entry() {
+            ", style=bold"
+        } else {
+            ""
+        };
+        dot.push_str(&format!(
+            "    \"{:03x}\" [label=\"{}\"{}];\n",
+            subroutine, label, style
+        ));
+    }
+    for (caller, callee) in graph.calls() {
+        let style = if followed.contains(&(caller, callee)) {
+            ""
+        } else {
+            " [style=dashed]"
+        };
+        dot.push_str(&format!(
+            "    \"{:03x}\" -> \"{:03x}\"{};\n",
+            caller, callee, style
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Escapes the quotes and backslashes in a name, so it can go in a label
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Creates the error for a `callgraph` command that doesn't make sense
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
@@ -0,0 +1,212 @@
+//! This module contains the cheats that are saved for each rom, and the cheat
+//! list that the debugger shows them in.
+//!
+//! They get saved next to the rom, in a file with the same name ending in
+//! `.cheats`, with a cheat on each line like `on freeze 2f0 03 lives`, see the
+//! `cheats` module of the interpreter for how they are written. The file is
+//! written whenever they change, and read back the next time the rom is
+//! started, so they don't have to be found again.
+
+use chip_8::{
+    cheats::{self, Cheat},
+    frontend::{Audio, Display, Event, Input},
+    Chip8,
+};
+#[cfg(feature = "terminal")]
+use crossterm::event::KeyCode;
+#[cfg(feature = "terminal")]
+use ratatui::{style::Modifier, text::Line};
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// How long the line that a cheat is being typed into can get
+#[cfg(feature = "terminal")]
+const MAX_TYPED: usize = 48;
+
+/// Wraps a frontend, and saves the cheats whenever they change
+pub struct CheatsFrontend<'a, F> {
+    frontend: &'a mut F,
+    /// Where the cheats get saved to
+    path: PathBuf,
+    /// The cheats as they were last saved, so the file is only written when
+    /// they change
+    saved: Vec<Cheat>,
+}
+
+impl<'a, F> CheatsFrontend<'a, F> {
+    /// Reads the cheats that were saved for the rom into the interpreter, the
+    /// ones that are on get written into memory straight away
+    pub fn load(frontend: &'a mut F, rom: &Path, chip8: &mut Chip8) -> Result<Self, Error> {
+        let path = rom.with_extension("cheats");
+        let saved = match fs::read_to_string(&path) {
+            Ok(text) => cheats::parse(&text).map_err(|error| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} can't be read, {}", path.display(), error),
+                )
+            })?,
+            Err(error) if error.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error),
+        };
+        chip8.set_cheats(saved.clone());
+        Ok(CheatsFrontend {
+            frontend,
+            path,
+            saved,
+        })
+    }
+}
+
+impl<'a, F: Input> Input for CheatsFrontend<'a, F> {
+    /// Saves the cheats after the frontend has had the input, which is when
+    /// they get changed, and which still happens while the rom is paused
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        let event = self.frontend.handle_input(chip8);
+        if chip8.cheats() != self.saved.as_slice() {
+            // There is nowhere to show an error, so it is tried again the
+            // next time instead
+            if fs::write(&self.path, cheats::to_text(chip8.cheats())).is_ok() {
+                self.saved = chip8.cheats().to_vec();
+            }
+        }
+        event
+    }
+
+    fn start_frame(&mut self, chip8: &mut Chip8) {
+        self.frontend.start_frame(chip8);
+    }
+
+    fn before_instruction(&mut self, chip8: &mut Chip8) {
+        self.frontend.before_instruction(chip8);
+    }
+
+    fn tick(&mut self, chip8: &mut Chip8) {
+        self.frontend.tick(chip8);
+    }
+}
+
+impl<'a, F: Display> Display for CheatsFrontend<'a, F> {
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error> {
+        self.frontend.draw(chip8)
+    }
+}
+
+impl<'a, F: Audio> Audio for CheatsFrontend<'a, F> {
+    fn set_playing(&mut self, playing: bool) -> Result<(), Error> {
+        self.frontend.set_playing(playing)
+    }
+}
+
+/// The cheats shown in the debugger's memory pane, where they can be turned
+/// on and off, added, and taken away
+#[cfg(feature = "terminal")]
+pub struct CheatList {
+    selected: usize,
+    /// The cheat that has been typed so far, while one is being added
+    typing: Option<String>,
+    /// Why the last cheat that was typed in couldn't be added
+    error: Option<String>,
+}
+
+#[cfg(feature = "terminal")]
+impl CheatList {
+    /// Creates the list, with the first cheat picked
+    pub fn new() -> Self {
+        CheatList {
+            selected: 0,
+            typing: None,
+            error: None,
+        }
+    }
+
+    /// Handles a key, and returns whether it was used. `+` starts typing a
+    /// cheat in, which then gets every key until `Enter` adds it or `Esc`
+    /// stops, `Enter` or `Space` turns the one that is picked on or off, and
+    /// `Delete` takes it away.
+    pub fn handle_key(&mut self, key: &KeyCode, chip8: &mut Chip8) -> bool {
+        if let Some(typing) = &mut self.typing {
+            match key {
+                KeyCode::Char(c) if typing.len() < MAX_TYPED => typing.push(*c),
+                KeyCode::Backspace => {
+                    typing.pop();
+                }
+                KeyCode::Enter => {
+                    match typing.parse::<Cheat>() {
+                        Ok(cheat) => {
+                            chip8.add_cheat(cheat);
+                            self.selected = chip8.cheats().len() - 1;
+                            self.error = None;
+                        }
+                        Err(error) => self.error = Some(error),
+                    }
+                    self.typing = None;
+                }
+                KeyCode::Esc => self.typing = None,
+                _ => {}
+            }
+            return true;
+        }
+
+        let last = chip8.cheats().len().saturating_sub(1);
+        match key {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::Char('+') => self.typing = Some(String::new()),
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(cheat) = chip8.cheats().get(self.selected) {
+                    let enabled = !cheat.enabled;
+                    chip8.set_cheat_enabled(self.selected, enabled);
+                }
+            }
+            KeyCode::Delete => {
+                chip8.remove_cheat(self.selected);
+                self.selected = self.selected.min(chip8.cheats().len().saturating_sub(1));
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Returns the title of the pane, which says how many cheats are on
+    pub fn title(&self, chip8: &Chip8) -> String {
+        let on = chip8.cheats().iter().filter(|cheat| cheat.enabled).count();
+        format!("cheats  {} of {} on", on, chip8.cheats().len())
+    }
+
+    /// Returns the lines of the pane, with the line that a cheat is typed
+    /// into first, and then as many of the cheats around the one that is
+    /// picked as there is room for in `rows`
+    pub fn render(&self, chip8: &Chip8, rows: usize) -> Vec<Line<'static>> {
+        let first_line = match (&self.typing, &self.error) {
+            (Some(typing), _) => format!("add: {}_", typing),
+            (None, Some(error)) => format!("can't add: {}", error),
+            (None, None) if chip8.cheats().is_empty() => {
+                String::from("+ adds one, like `freeze 2f0 03 lives`")
+            }
+            (None, None) => String::from("+ adds  Enter turns on or off  Delete takes away"),
+        };
+        let mut lines = vec![Line::from(first_line)];
+
+        // The list scrolls along with the one that is picked
+        let first = (self.selected + 1).saturating_sub(rows.max(1));
+        for (index, cheat) in chip8.cheats().iter().enumerate().skip(first).take(rows) {
+            let line = format!(
+                "[{}] {:<6} {:03x} = {:02x}  {}",
+                if cheat.enabled { 'x' } else { ' ' },
+                cheat.kind.name(),
+                cheat.address,
+                cheat.value,
+                cheat.name
+            );
+            if index == self.selected {
+                lines.push(Line::styled(line, Modifier::REVERSED));
+            } else {
+                lines.push(Line::from(line));
+            }
+        }
+        lines
+    }
+}
@@ -0,0 +1,201 @@
+//! This module contains cheats, which write a byte into memory to change
+//! something about a game, like how many lives are left. A cheat either
+//! freezes the byte, which writes it again every frame so the game can't
+//! change it, or patches it, which writes it once when the cheat is turned on.
+//!
+//! Each cheat can be written as a line, which is how they are saved:
+//!
+//! ```text
+//! on freeze 2f0 03 lives never run out
+//! off patch 31a 00
+//! ```
+//!
+//! Which is whether it is on, if that's left out then it is, whether it
+//! freezes or patches, the address and the byte in hex, and a name for it,
+//! which can be left out too. Anything after a `#` is a comment.
+//!
+//! ```rust
+//! use chip_8::{cheats::Cheat, Chip8};
+//!
+//! let mut chip8 = Chip8::with_seed(0);
+//! chip8.add_cheat("freeze 2f0 03 lives".parse::<Cheat>().unwrap());
+//! assert_eq!(chip8.memory[0x2f0], 3);
+//! // The rom loses a life, but it comes back on the next frame
+//! chip8.memory[0x2f0] = 2;
+//! chip8.tick_timers();
+//! assert_eq!(chip8.memory[0x2f0], 3);
+//! ```
+
+use crate::{chip8::Chip8, error::CheatError};
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt, str::FromStr};
+
+/// How a cheat writes it's byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatKind {
+    /// Writes the byte again every frame, while the cheat is on
+    Freeze,
+    /// Writes the byte once, whenever the cheat gets turned on
+    Patch,
+}
+
+impl CheatKind {
+    /// Returns the kind of cheat with the name, `freeze` or `patch`
+    pub fn from_name(name: &str) -> Option<CheatKind> {
+        match name {
+            "freeze" => Some(CheatKind::Freeze),
+            "patch" => Some(CheatKind::Patch),
+            _ => None,
+        }
+    }
+
+    /// Returns the name that `from_name` takes
+    pub fn name(self) -> &'static str {
+        match self {
+            CheatKind::Freeze => "freeze",
+            CheatKind::Patch => "patch",
+        }
+    }
+}
+
+/// A byte that gets written into memory, to change something about a game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cheat {
+    pub address: usize,
+    pub value: u8,
+    pub kind: CheatKind,
+    /// Only the cheats that are on get written
+    pub enabled: bool,
+    /// What the cheat does, which can be empty
+    pub name: String,
+}
+
+impl Cheat {
+    /// Writes the byte into memory, anything outside of memory is left alone
+    fn write(&self, memory: &mut [u8]) {
+        if let Some(byte) = memory.get_mut(self.address) {
+            *byte = self.value;
+        }
+    }
+}
+
+impl FromStr for Cheat {
+    type Err = String;
+
+    /// Reads a cheat from a line, see the module for how it is written
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut words = line.split_whitespace().peekable();
+        let enabled = words
+            .next_if(|word| *word == "on" || *word == "off")
+            .is_none_or(|word| word == "on");
+        let kind = words
+            .next()
+            .and_then(CheatKind::from_name)
+            .ok_or_else(|| format!("{} should start with freeze or patch", line))?;
+        let address = words.next().unwrap_or_default();
+        let address = usize::from_str_radix(address.trim_start_matches("0x"), 16)
+            .map_err(|_| format!("{} isn't a hex address", address))?;
+        let value = words.next().unwrap_or_default();
+        let value = u8::from_str_radix(value.trim_start_matches("0x"), 16)
+            .map_err(|_| format!("{} isn't a hex byte", value))?;
+        // Whatever is left is the name
+        let name = words.collect::<Vec<&str>>().join(" ");
+        Ok(Cheat {
+            address,
+            value,
+            kind,
+            enabled,
+            name,
+        })
+    }
+}
+
+impl fmt::Display for Cheat {
+    /// Writes the cheat as a line that `from_str` reads back
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let enabled = if self.enabled { "on" } else { "off" };
+        write!(
+            f,
+            "{} {} {:03x} {:02x}",
+            enabled,
+            self.kind.name(),
+            self.address,
+            self.value
+        )?;
+        if !self.name.is_empty() {
+            write!(f, " {}", self.name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads every cheat from the text of a cheats file, a cheat on each line,
+/// blank lines and comments are left out
+pub fn parse(text: &str) -> Result<Vec<Cheat>, CheatError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.split('#').next().unwrap_or_default().trim().is_empty())
+        .map(|(number, line)| {
+            line.parse().map_err(|message| CheatError {
+                line: number + 1,
+                message,
+            })
+        })
+        .collect()
+}
+
+/// Writes the cheats as the text of a cheats file, that `parse` reads back
+pub fn to_text(cheats: &[Cheat]) -> String {
+    cheats.iter().map(|cheat| format!("{}\n", cheat)).collect()
+}
+
+impl Chip8 {
+    /// Returns the cheats, in the order they were added
+    pub fn cheats(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    /// Swaps the cheats for others, the ones that are on get written straight
+    /// away
+    pub fn set_cheats(&mut self, cheats: Vec<Cheat>) {
+        for cheat in cheats.iter().filter(|cheat| cheat.enabled) {
+            cheat.write(&mut self.memory);
+        }
+        self.cheats = cheats;
+    }
+
+    /// Adds a cheat, which gets written straight away if it's on
+    pub fn add_cheat(&mut self, cheat: Cheat) {
+        if cheat.enabled {
+            cheat.write(&mut self.memory);
+        }
+        self.cheats.push(cheat);
+    }
+
+    /// Takes the cheat away, the byte that it wrote stays the way it is
+    pub fn remove_cheat(&mut self, index: usize) -> Option<Cheat> {
+        (index < self.cheats.len()).then(|| self.cheats.remove(index))
+    }
+
+    /// Turns the cheat on or off, one that gets turned on is written straight
+    /// away, so a patch is written again every time it's turned on
+    pub fn set_cheat_enabled(&mut self, index: usize, enabled: bool) {
+        if let Some(cheat) = self.cheats.get_mut(index) {
+            if enabled && !cheat.enabled {
+                cheat.write(&mut self.memory);
+            }
+            cheat.enabled = enabled;
+        }
+    }
+
+    /// Writes the cheats that freeze their byte again, which is done once a
+    /// frame when the timers tick
+    pub(crate) fn apply_cheats(&mut self) {
+        for cheat in &self.cheats {
+            if cheat.enabled && cheat.kind == CheatKind::Freeze {
+                cheat.write(&mut self.memory);
+            }
+        }
+    }
+}
@@ -4,7 +4,7 @@
 //! So the Chip-8 virtual machine was designed by Joseph Weisbecker for the
 //! COSMAC VIP and Telmac 1800 computers back in the 1970's.
 //! Since then there has been an extension made to it, called Super Chip-8,
-//! which isn't implemented in this project. There is also a discrepancy in how
+//! which is only partly implemented in this project. There is also a discrepancy in how
 //! a couple of the opcodes were used in some implementations, as a result some
 //! roms may not work as intended.
 //!
@@ -12,7 +12,9 @@
 //! So the memory of Chip-8 is 4k bytes where the program starts at address `0x200`
 //! before that address the hexadecimal characters are usually stored, in newer
 //! interpreters, however in older interpreters, the entire interpreter would be
-//! there.
+//! there. The ETI-660 started programs at `0x600` instead, so where the program
+//! gets loaded can be changed with `Chip8::with_load_address`, or with
+//! `Chip8::builder` along with the fonts and the size of memory.
 //!
 //! ## Registers
 //! So there are 16 8-bit registers where register VF is used to flag certain
@@ -27,11 +29,12 @@
 //! There are two timers, which both run at a frequency of 60Hz. The way they
 //! work is by ticking down until they reach zero. One timer is used for delay
 //! events for the games, and the sound timer plays a sound until it hits 0.
+//! XO-CHIP roms can choose what that sound is, see the `audio` module.
 //!
 //! ## Input
 //! The input for Chip-8 is based on a hex keypad which contains only hexadecimal
 //! characters (0-9A-F) arranged in a 4x4 grid. In modern interpreters they get mapped as follows
-//! ```
+//! ```text
 //! |1|2|3|c|    |1|2|3|4|
 //! |4|5|6|d|    |q|w|e|r|
 //! |7|8|9|e|    |a|s|d|f|
@@ -40,24 +43,281 @@
 //!
 //! ## Graphics
 //! The display resolution is 64x32 pixels, which are drawn to the screen with
-//! sprites that are xor'ed to the screen buffer.
+//! sprites that are xor'ed to the screen buffer. Hi-res Chip-8 has 64x64
+//! pixels instead, see the `hires` module.
+
+#[cfg(feature = "megachip")]
+use crate::megachip::MegaChip;
+use crate::{
+    audio::AudioState,
+    bounds::{IndexPolicy, ProgramCounterPolicy},
+    error::Chip8Error,
+    protection::WriteProtection,
+    quirks::Quirks,
+    rng::Rng,
+    screen::Screen,
+};
+#[cfg(feature = "alloc")]
+use crate::{cheats::Cheat, coverage::Coverage, devices::MappedDevice, journal::Journal};
+#[cfg(feature = "alloc")]
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+#[cfg(feature = "alloc")]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+use tracing::{trace, warn};
+
+/// Where the big font that SUPER-CHIP added starts in memory, right after the
+/// small one, which starts at 0
+pub(crate) const BIG_FONT_ADDRESS: usize = 0x50;
+
+/// How many bytes of memory there are, unless `Chip8Builder::memory_size`
+/// changes it, which is the 4K that the COSMAC VIP could address
+pub const MEMORY_SIZE: usize = 0x1000;
+
+/// What memory is kept in. With the `alloc` feature it can be any size, and
+/// without it, it is always `MEMORY_SIZE` bytes, so it doesn't need a heap.
+/// Either way it can be indexed and sliced the same.
+#[cfg(feature = "alloc")]
+pub type Memory = Vec<u8>;
+#[cfg(not(feature = "alloc"))]
+pub type Memory = [u8; MEMORY_SIZE];
+
+/// The 4x5 font, for the hex digits from 0 to f, look at the hex value to
+/// know which character it represents. Only the left 4 bits of each row are
+/// drawn.
+pub const FONT: [[u8; 5]; 16] = [
+    [0b11110000, 0b10010000, 0b10010000, 0b10010000, 0b11110000], // 0
+    [0b00100000, 0b01100000, 0b00100000, 0b00100000, 0b01110000], // 1
+    [0b11110000, 0b00010000, 0b11110000, 0b10000000, 0b11110000], // 2
+    [0b11110000, 0b00010000, 0b11110000, 0b00010000, 0b11110000], // 3
+    [0b10010000, 0b10010000, 0b11110000, 0b00010000, 0b00010000], // 4
+    [0b11110000, 0b10000000, 0b11110000, 0b00010000, 0b11110000], // 5
+    [0b11110000, 0b10000000, 0b11110000, 0b10010000, 0b11110000], // 6
+    [0b11110000, 0b00010000, 0b00100000, 0b01000000, 0b01000000], // 7
+    [0b11110000, 0b10010000, 0b11110000, 0b10010000, 0b11110000], // 8
+    [0b11110000, 0b10010000, 0b11110000, 0b00010000, 0b11110000], // 9
+    [0b11110000, 0b10010000, 0b11110000, 0b10010000, 0b10010000], // a
+    [0b11100000, 0b10010000, 0b11100000, 0b10010000, 0b11100000], // b
+    [0b11110000, 0b10000000, 0b10000000, 0b10000000, 0b11110000], // c
+    [0b11100000, 0b10010000, 0b10010000, 0b10010000, 0b11100000], // d
+    [0b11110000, 0b10000000, 0b11110000, 0b10000000, 0b11110000], // e
+    [0b11110000, 0b10000000, 0b11110000, 0b10000000, 0b10000000], // f
+];
+
+/// The mnemonics of the instructions that always go somewhere else
+const JUMPS: [&str; 4] = ["jp", "jp0", "call", "ret"];
+
+/// The mnemonics of the instructions that can skip the next instruction
+const SKIPS: [&str; 8] = ["se", "sne", "sey", "sney", "skp", "skpn", "skp2", "skpn2"];
+
+/// The fastest that the run loop can be asked to run a rom, past this it
+/// couldn't keep up anyways
+const MAX_SPEED: f64 = 100.0;
+
+/// The big 8x10 font, for the hex digits from 0 to f. SUPER-CHIP only had 0
+/// through 9, the letters are the ones that Octo uses.
+pub const BIG_FONT: [[u8; 10]; 16] = [
+    [0xff, 0xff, 0xc3, 0xc3, 0xc3, 0xc3, 0xc3, 0xc3, 0xff, 0xff], // 0
+    [0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xff, 0xff], // 1
+    [0xff, 0xff, 0x03, 0x03, 0xff, 0xff, 0xc0, 0xc0, 0xff, 0xff], // 2
+    [0xff, 0xff, 0x03, 0x03, 0xff, 0xff, 0x03, 0x03, 0xff, 0xff], // 3
+    [0xc3, 0xc3, 0xc3, 0xc3, 0xff, 0xff, 0x03, 0x03, 0x03, 0x03], // 4
+    [0xff, 0xff, 0xc0, 0xc0, 0xff, 0xff, 0x03, 0x03, 0xff, 0xff], // 5
+    [0xff, 0xff, 0xc0, 0xc0, 0xff, 0xff, 0xc3, 0xc3, 0xff, 0xff], // 6
+    [0xff, 0xff, 0x03, 0x03, 0x06, 0x0c, 0x18, 0x18, 0x18, 0x18], // 7
+    [0xff, 0xff, 0xc3, 0xc3, 0xff, 0xff, 0xc3, 0xc3, 0xff, 0xff], // 8
+    [0xff, 0xff, 0xc3, 0xc3, 0xff, 0xff, 0x03, 0x03, 0xff, 0xff], // 9
+    [0x7e, 0xff, 0xc3, 0xc3, 0xc3, 0xff, 0xff, 0xc3, 0xc3, 0xc3], // a
+    [0xfc, 0xfc, 0xc3, 0xc3, 0xfc, 0xfc, 0xc3, 0xc3, 0xfc, 0xfc], // b
+    [0x3c, 0xff, 0xc3, 0xc0, 0xc0, 0xc0, 0xc0, 0xc3, 0xff, 0x3c], // c
+    [0xfc, 0xfe, 0xc3, 0xc3, 0xc3, 0xc3, 0xc3, 0xc3, 0xfe, 0xfc], // d
+    [0xff, 0xff, 0xc0, 0xc0, 0xff, 0xff, 0xc0, 0xc0, 0xff, 0xff], // e
+    [0xff, 0xff, 0xc0, 0xc0, 0xff, 0xff, 0xc0, 0xc0, 0xc0, 0xc0], // f
+];
+
+/// Everything that changes which instruction an opcode decodes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DecodeMode {
+    shift: bool,
+    megachip: bool,
+    chip8x: bool,
+    hires: bool,
+}
+
+#[cfg(feature = "std")]
+impl DecodeMode {
+    /// How many different modes there are
+    const COUNT: usize = 16;
+
+    /// A number for the mode from 0 up to `COUNT`, to find it's table with
+    fn index(self) -> usize {
+        self.shift as usize
+            | (self.megachip as usize) << 1
+            | (self.chip8x as usize) << 2
+            | (self.hires as usize) << 3
+    }
+}
+
+/// Every opcode decoded ahead of time for one mode, so that running an
+/// instruction doesn't need to go through the matches in `decode` again
+#[cfg(feature = "std")]
+struct DispatchTable {
+    /// Which of the instructions every opcode is, by the opcode
+    opcodes: Box<[u8]>,
+    /// Every instruction that an opcode can decode to in the mode, there are
+    /// only a few dozen so they fit in a byte
+    instructions: Vec<(&'static str, Instruction)>,
+}
+
+/// The tables for every mode, each one is only built the first time it is used
+#[cfg(feature = "std")]
+static DISPATCH_TABLES: [OnceLock<DispatchTable>; DecodeMode::COUNT] =
+    [const { OnceLock::new() }; DecodeMode::COUNT];
+
+#[cfg(feature = "std")]
+impl DispatchTable {
+    /// Returns the table for the mode, building it if this is the first time
+    fn get(mode: DecodeMode) -> &'static DispatchTable {
+        DISPATCH_TABLES[mode.index()].get_or_init(|| DispatchTable::build(mode))
+    }
+
+    /// Decodes all 65536 opcodes in the mode
+    fn build(mode: DecodeMode) -> DispatchTable {
+        let mut instructions: Vec<(&'static str, Instruction)> = Vec::new();
+        let opcodes = (0..=u16::MAX)
+            .map(|code| {
+                let decoded = Chip8::decode(&Opcode::new(code), mode);
+                // Every mnemonic is a different function, so the mnemonic is
+                // enough to tell whether it has been seen already
+                let position = instructions
+                    .iter()
+                    .position(|(mnemonic, _)| *mnemonic == decoded.0)
+                    .unwrap_or_else(|| {
+                        instructions.push(decoded);
+                        instructions.len() - 1
+                    });
+                position as u8
+            })
+            .collect();
+        DispatchTable {
+            opcodes,
+            instructions,
+        }
+    }
+
+    /// Returns the function and mnemonic for the opcode
+    fn lookup(&self, opcode: &Opcode) -> (&'static str, Instruction) {
+        self.instructions[self.opcodes[opcode.code as usize] as usize]
+    }
+}
+
+/// An instruction that has already been decoded, along with the opcode it was
+/// decoded from
+#[derive(Clone, Copy)]
+struct Decoded {
+    opcode: Opcode,
+    mnemonic: &'static str,
+    instruction: Instruction,
+}
+
+/// The instructions at every address in memory, decoded the first time they
+/// run. An entry is only used while the opcode in memory is still the same, so
+/// roms that change their own code (or a parent program writing to memory)
+/// just get the new instruction decoded again.
+#[cfg(feature = "alloc")]
+struct DecodeCache {
+    /// The mode that everything was decoded in, changing it throws all of it away
+    mode: DecodeMode,
+    entries: Vec<Option<Decoded>>,
+}
+
+/// The decoded instructions are function pointers, which can't be saved, and
+/// they are only a cache anyways, so only how many entries there are is saved
+#[cfg(feature = "serde")]
+impl serde::Serialize for DecodeCache {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.entries.len() as u64)
+    }
+}
+
+/// Loads the cache with nothing decoded yet, it fills back up as the
+/// instructions run again. The saved length isn't trusted, the entries get
+/// made for however big memory is the first time an instruction is fetched.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DecodeCache {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <u64 as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(DecodeCache {
+            mode: DecodeMode {
+                shift: false,
+                megachip: false,
+                chip8x: false,
+                hires: false,
+            },
+            entries: Vec::new(),
+        })
+    }
+}
+
+/// Reads the stack pointer of a save state, which can't be past the top of
+/// the stack, or `ret` would read outside of it
+#[cfg(feature = "serde")]
+fn deserialize_stack_pointer<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<usize, D::Error> {
+    let stack_pointer = <usize as serde::Deserialize>::deserialize(deserializer)?;
+    if stack_pointer > 16 {
+        return Err(serde::de::Error::custom(format!(
+            "the stack pointer is {}, but the stack only has 16 levels",
+            stack_pointer
+        )));
+    }
+    Ok(stack_pointer)
+}
+
+/// Reads the memory of a save state, which has to have room for the fonts
+/// like `Chip8Builder` always makes it, and can't be empty, since the
+/// policies wrap addresses around it
+#[cfg(feature = "serde")]
+fn deserialize_memory<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Memory, D::Error> {
+    let memory = <Memory as serde::Deserialize>::deserialize(deserializer)?;
+    let big_font_end = BIG_FONT_ADDRESS + BIG_FONT.len() * BIG_FONT[0].len();
+    if memory.len() < big_font_end {
+        return Err(serde::de::Error::custom(format!(
+            "memory is {} bytes, but it needs at least {} for the fonts",
+            memory.len(),
+            big_font_end
+        )));
+    }
+    Ok(memory)
+}
 
 /// This is a helper struct, so that the opcodes can be parsed, and used more
 /// easily
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Opcode {
-    code: u16,
-    n: u8,
-    nn: u8,
-    nnn: u16,
-    x: u8,
-    y: u8,
+    pub(crate) code: u16,
+    pub(crate) n: u8,
+    pub(crate) nn: u8,
+    pub(crate) nnn: u16,
+    pub(crate) x: u8,
+    pub(crate) y: u8,
 }
 
 impl Opcode {
     /// Parses the opcode from the 16-bit integer
     pub fn new(code: u16) -> Opcode {
         Opcode {
-            code: code,
+            code,
             n: (code & 0xf) as u8,
             nn: (code & 0xff) as u8,
             nnn: code & 0xfff,
@@ -68,6 +328,7 @@ impl Opcode {
 }
 
 /// This is my rendition of the interpreter
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Chip8 {
     /// This is `V`
     pub registers: [u8; 16],
@@ -80,199 +341,810 @@ pub struct Chip8 {
     /// This is `PC`
     pub program_counter: usize,
     /// This is `SP`
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "deserialize_stack_pointer")
+    )]
     pub stack_pointer: usize,
     pub stack: [usize; 16],
-    pub memory: [u8; 0xfff],
-    pub screen_size: (u8, u8),
-    pub screen: Vec<u8>,
-    /// This is to control which version of the instruction it should execute
+    /// The address of the subroutine that each level of the stack called, kept
+    /// alongside the stack so a debugger can show where the calls went
+    pub(crate) subroutines: [usize; 16],
+    /// This is 0x1000 bytes, unless `Chip8Builder::memory_size` changed it
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_memory"))]
+    pub memory: Memory,
+    /// The RPL user flags of the HP-48 that SUPER-CHIP ran on, which roms use
+    /// to keep things like high scores, the parent program can save them.
+    /// SUPER-CHIP only had 8 of them, XO-CHIP has all 16.
+    pub flags: [u8; 16],
+    pub(crate) screen: Screen,
+    /// This is to control which version of the instructions it should execute
     /// since there is a discrepancy in the documentation that people have been
     /// able to get their hands on, not being exactly the same
-    pub other_mode: bool,
+    pub quirks: Quirks,
     /// This keeps track of which of the keys are down
-    pub keys: [bool; 16],
-    /// This keeps track if the interpreter has executed a draw command, and should
-    /// not be updated outside of the interpreter
-    pub has_drawn: bool,
-    /// This keeps track if the parent program of the interpreter has handled it's draw
-    pub has_handled_draw: bool,
+    keys: [bool; 16],
+    /// Which keys the rom has checked with `ex9e` or `exa1`, as a mask where
+    /// bit `n` is key `n`. This is for showing which keys the rom uses, so it
+    /// isn't saved with the rest of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    checked_keys: u16,
+    /// This keeps track if the interpreter has executed a draw command since the
+    /// parent program last asked for it
+    pub(crate) has_drawn: bool,
+    /// This is set when the timers tick, which is the vertical blank that draw
+    /// instructions wait for with the display wait quirk
+    pub(crate) vblank: bool,
+    /// Where the `rnd` instruction gets it's numbers from
+    pub(crate) rng: Rng,
+    /// Where the rom gets loaded into memory, and where it starts running from
+    load_address: usize,
+    /// Set once the rom has stopped itself with `exit`
+    pub(crate) has_exited: bool,
+    /// Set while the run loop should leave the rom where it is, see `set_paused`
+    paused: bool,
+    /// How many frames the run loop still has to run while the rom is paused,
+    /// see `step_frame`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    frame_steps: u32,
+    /// How much faster or slower than normal the run loop runs the rom, see
+    /// `set_speed`. It is up to the parent program, so it isn't saved with the
+    /// rest of the state.
+    #[cfg_attr(feature = "serde", serde(skip, default = "normal_speed"))]
+    speed: f64,
+    /// Pauses the rom on anything that isn't an instruction, instead of
+    /// skipping over it, see `set_break_on_unknown`
+    break_on_unknown: bool,
+    /// What happens when the rom writes over the fonts, see the `protection`
+    /// module
+    pub(crate) write_protection: WriteProtection,
+    /// What happens when the program counter goes out of bounds, see the
+    /// `bounds` module
+    pub(crate) program_counter_policy: ProgramCounterPolicy,
+    /// What happens when the index goes past the end of memory, see the
+    /// `bounds` module
+    pub(crate) index_policy: IndexPolicy,
+    /// Only there once it has been turned on with `enable_decode_cache`
+    #[cfg(feature = "alloc")]
+    decode_cache: Option<DecodeCache>,
+    /// The extra state for MEGA-CHIP, only there once it has been turned on
+    #[cfg(feature = "megachip")]
+    pub(crate) megachip: Option<Box<MegaChip>>,
+    /// The second keypad of CHIP-8X, only there once it has been turned on
+    pub(crate) second_keys: Option<[bool; 16]>,
+    /// Set once hi-res Chip-8 has been turned on, see the `hires` module
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) hires: bool,
+    /// Where the 4x5 font starts in memory, which only hi-res Chip-8 moves.
+    /// States that were saved before it could be moved have it at the start.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) font_address: usize,
+    /// XO-CHIP's audio pattern and pitch, see the `audio` module. States that
+    /// were saved before it was added get the normal tone.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) audio: AudioState,
+    /// What gets called right before and right after every instruction, see
+    /// `set_pre_hook` and `set_post_hook`, these belong to the parent program
+    /// so they aren't saved with the rest of the state
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pre_hook: Option<Hook>,
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    post_hook: Option<Hook>,
+    /// The devices that are mapped over memory, see the `devices` module
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) devices: Vec<MappedDevice>,
+    /// What the last instructions changed, so they can be undone, see the
+    /// `journal` module. It is only there once it has been turned on, and it
+    /// is for debugging the rom, so it isn't saved with the rest of the state.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) journal: Option<Box<Journal>>,
+    /// How many times each instruction has ran, see the `coverage` module. It
+    /// is only there once it has been turned on, and isn't saved either.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) coverage: Option<Coverage>,
+    /// The bytes that get written into memory to change the game, see the
+    /// `cheats` module. They belong to the player rather than the rom, so
+    /// they aren't saved either.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) cheats: Vec<Cheat>,
 }
 
 /// This is to create a type for all of the instruction functions so that
 /// a debugger can be attached to it, and be provided mnemonics
-type Instruction = fn(&mut Chip8, &Opcode);
+pub type Instruction = fn(&mut Chip8, &Opcode) -> Result<(), Chip8Error>;
+
+/// Something that gets told about the instructions as they run, so that
+/// tracers, profilers, and cheats can be added on without changing the
+/// interpreter, see `Chip8::set_pre_hook` and `Chip8::set_post_hook`
+#[cfg(feature = "alloc")]
+pub type Hook = Box<dyn FnMut(&Chip8, &StepInfo) + Send>;
+
+/// One level of the stack, for showing where the rom has called into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallFrame {
+    /// The address of the call instruction, which is where `ret` goes back to
+    pub call_site: usize,
+    /// The address of the subroutine that was called
+    pub subroutine: usize,
+}
+
+/// An instruction in memory, taken apart for showing to the user
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisassembledInstruction {
+    /// The address the instruction is at
+    pub address: usize,
+    /// The raw 16-bit opcode
+    pub opcode: u16,
+    /// The mnemonic for the instruction, `nai` if it isn't one
+    pub mnemonic: &'static str,
+    /// What the instruction works on, like `v1, v2, 5`, which is empty for the
+    /// instructions that don't take anything, and the raw opcode for `nai`
+    pub operands: String,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for DisassembledInstruction {
+    /// Writes the instruction as text like `drw v1, v2, 5`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.operands.is_empty() {
+            write!(f, "{}", self.mnemonic)
+        } else {
+            write!(f, "{} {}", self.mnemonic, self.operands)
+        }
+    }
+}
+
+/// Information about the instruction that was just executed, returned from
+/// every clock cycle so the parent program can see what is going on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepInfo {
+    /// The address the instruction was read from
+    pub pc: usize,
+    /// The raw 16-bit opcode
+    pub opcode: u16,
+    /// The mnemonic for the instruction
+    pub mnemonic: &'static str,
+    /// Whether the instruction drew to or cleared the screen
+    pub drew: bool,
+    /// Whether the instruction was a jump, call, or return, which always go
+    /// somewhere else
+    pub jumped: bool,
+    /// Whether the instruction was a skip, and it skipped the next instruction
+    pub skipped: bool,
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Chip8::new()
+    }
+}
 
 impl Chip8 {
-    /// Creates a default Chip8 instance
+    /// Creates a default Chip8 instance, with a random seed for the `rnd`
+    /// instruction
     pub fn new() -> Chip8 {
-        let mut chip8 = Chip8 {
+        Chip8::builder().build()
+    }
+
+    /// Creates a Chip8 instance where the `rnd` instruction always gives the
+    /// same numbers for the same seed, so that a run can be repeated exactly
+    pub fn with_seed(seed: u64) -> Chip8 {
+        Chip8::builder().seed(seed).build()
+    }
+
+    /// Creates a Chip8 instance like `with_seed`, that loads roms at the
+    /// address and starts running them from there, like `0x600` for roms made
+    /// for the ETI-660
+    pub fn with_load_address(seed: u64, load_address: usize) -> Chip8 {
+        Chip8::builder()
+            .seed(seed)
+            .load_address(load_address)
+            .build()
+    }
+
+    /// Creates a Chip8 instance with nothing in memory, not even the fonts,
+    /// which `Chip8Builder` puts there
+    pub(crate) fn empty(seed: u64, load_address: usize) -> Chip8 {
+        Chip8 {
             registers: [0; 16],
             index: 0,
             delay: 0,
             sound: 0,
-            program_counter: 0x200,
+            program_counter: load_address,
             stack_pointer: 0,
             stack: [0; 16],
-            memory: [0; 0xfff],
-            screen_size: (64, 32),
-            screen: Vec::new(),
-            other_mode: false,
+            subroutines: [0; 16],
+            #[cfg(feature = "alloc")]
+            memory: vec![0; MEMORY_SIZE],
+            #[cfg(not(feature = "alloc"))]
+            memory: [0; MEMORY_SIZE],
+            flags: [0; 16],
+            // The screen is 64x32 pixels
+            screen: Screen::new(64, 32).expect("every screen has room for 64x32"),
+            quirks: Quirks::default(),
             keys: [false; 16],
+            checked_keys: 0,
             has_drawn: false,
-            has_handled_draw: false,
-        };
-        // resizes the screen to be 64x32 pixels wide
-        chip8.screen.resize((64 / 8) * 32, 0);
-
-        // The following mess is to add the letters that can be printed to the
-        // screen, look at the hex value to know which character it represents
-        chip8.add_letter(
-            0x0,
-            &[0b11110000, 0b10010000, 0b10010000, 0b10010000, 0b11110000],
-        );
-        chip8.add_letter(
-            0x1,
-            &[0b00100000, 0b01100000, 0b00100000, 0b00100000, 0b01110000],
-        );
-        chip8.add_letter(
-            0x2,
-            &[0b11110000, 0b00010000, 0b11110000, 0b10000000, 0b11110000],
-        );
-        chip8.add_letter(
-            0x3,
-            &[0b11110000, 0b00010000, 0b11110000, 0b00010000, 0b11110000],
-        );
-        chip8.add_letter(
-            0x4,
-            &[0b10010000, 0b10010000, 0b11110000, 0b00010000, 0b00010000],
-        );
-        chip8.add_letter(
-            0x5,
-            &[0b11110000, 0b10000000, 0b11110000, 0b00010000, 0b11110000],
-        );
-        chip8.add_letter(
-            0x6,
-            &[0b11110000, 0b10000000, 0b11110000, 0b10010000, 0b11110000],
-        );
-        chip8.add_letter(
-            0x7,
-            &[0b11110000, 0b00010000, 0b00100000, 0b01000000, 0b01000000],
-        );
-        chip8.add_letter(
-            0x8,
-            &[0b11110000, 0b10010000, 0b11110000, 0b10010000, 0b11110000],
-        );
-        chip8.add_letter(
-            0x9,
-            &[0b11110000, 0b10010000, 0b11110000, 0b00010000, 0b11110000],
-        );
-        chip8.add_letter(
-            0xa,
-            &[0b11110000, 0b10010000, 0b11110000, 0b10010000, 0b10010000],
-        );
-        chip8.add_letter(
-            0xb,
-            &[0b11100000, 0b10010000, 0b11100000, 0b10010000, 0b11100000],
-        );
-        chip8.add_letter(
-            0xc,
-            &[0b11110000, 0b10000000, 0b10000000, 0b10000000, 0b11110000],
-        );
-        chip8.add_letter(
-            0xd,
-            &[0b11100000, 0b10010000, 0b10010000, 0b10010000, 0b11100000],
-        );
-        chip8.add_letter(
-            0xe,
-            &[0b11110000, 0b10000000, 0b11110000, 0b10000000, 0b11110000],
-        );
-        chip8.add_letter(
-            0xf,
-            &[0b11110000, 0b10000000, 0b11110000, 0b10000000, 0b10000000],
+            vblank: false,
+            rng: Rng::new(seed),
+            load_address,
+            has_exited: false,
+            paused: false,
+            frame_steps: 0,
+            speed: 1.0,
+            break_on_unknown: false,
+            write_protection: WriteProtection::Off,
+            program_counter_policy: ProgramCounterPolicy::Trap,
+            index_policy: IndexPolicy::Trap,
+            #[cfg(feature = "alloc")]
+            decode_cache: None,
+            #[cfg(feature = "megachip")]
+            megachip: None,
+            second_keys: None,
+            hires: false,
+            font_address: 0,
+            audio: AudioState::default(),
+            #[cfg(feature = "alloc")]
+            pre_hook: None,
+            #[cfg(feature = "alloc")]
+            post_hook: None,
+            #[cfg(feature = "alloc")]
+            devices: Vec::new(),
+            #[cfg(feature = "alloc")]
+            journal: None,
+            #[cfg(feature = "alloc")]
+            coverage: None,
+            #[cfg(feature = "alloc")]
+            cheats: Vec::new(),
+        }
+    }
+
+    /// This is where the interpreter runs all of the code it needs to, it
+    /// steps exactly one instruction
+    pub fn clock(&mut self) -> Result<StepInfo, Chip8Error> {
+        match self.program_counter_policy {
+            ProgramCounterPolicy::Wrap => self.program_counter %= self.memory.len(),
+            // Nothing runs, and it stays paused there until the program
+            // counter is moved, since there isn't an instruction to read
+            ProgramCounterPolicy::Halt if !self.program_counter_in_bounds() => {
+                warn!(
+                    pc = format_args!("{:03x}", self.program_counter),
+                    "paused with the program counter out of bounds"
+                );
+                self.paused = true;
+                return Ok(StepInfo {
+                    pc: self.program_counter,
+                    opcode: 0,
+                    mnemonic: "nai",
+                    drew: false,
+                    jumped: false,
+                    skipped: false,
+                });
+            }
+            _ => {}
+        }
+        let pc = self.program_counter;
+        // Gets and parses the current opcode that needs to be ran
+        let Decoded {
+            opcode,
+            mnemonic,
+            instruction,
+        } = self.fetch()?;
+        trace!(
+            pc = format_args!("{:03x}", pc),
+            opcode = format_args!("{:04x}", opcode.code),
+            mnemonic,
+            "clock"
         );
 
-        chip8
+        // A rom that has gone off into it's data would otherwise keep on
+        // running it, so it is stopped right on the opcode that gave it away
+        if self.break_on_unknown && mnemonic == "nai" {
+            warn!(
+                pc = format_args!("{:03x}", pc),
+                opcode = format_args!("{:04x}", opcode.code),
+                "paused on something that isn't an instruction"
+            );
+            self.paused = true;
+            return Ok(StepInfo {
+                pc,
+                opcode: opcode.code,
+                mnemonic,
+                drew: false,
+                jumped: false,
+                skipped: false,
+            });
+        }
+
+        // The hook can't be called while it is still inside of the
+        // interpreter that it gets to look at, so it is taken out until then
+        #[cfg(feature = "alloc")]
+        if let Some(mut hook) = self.pre_hook.take() {
+            let step = StepInfo {
+                pc,
+                opcode: opcode.code,
+                mnemonic,
+                drew: false,
+                jumped: false,
+                skipped: false,
+            };
+            hook(self, &step);
+            self.pre_hook = Some(hook);
+        }
+
+        // Runs the associated function for the opcode, with whether anything
+        // was drawn before it put aside, so it can be seen whether this drew
+        let had_drawn = self.has_drawn;
+        self.has_drawn = false;
+        #[cfg(feature = "alloc")]
+        {
+            self.record_coverage(pc);
+            self.begin_journal_entry();
+        }
+        let result = instruction(self, &opcode);
+        #[cfg(feature = "alloc")]
+        self.end_journal_entry();
+        let drew = self.has_drawn;
+        self.has_drawn |= had_drawn;
+        result?;
+
+        // Increments the program counter by one instruction or 2 bytes, the
+        // jumps take 2 off of where they go to make up for this, which wraps
+        // around for addresses under 2 and gets wrapped back here
+        self.program_counter = self.program_counter.wrapping_add(2);
+
+        let step = StepInfo {
+            pc,
+            opcode: opcode.code,
+            mnemonic,
+            drew,
+            jumped: JUMPS.contains(&mnemonic),
+            // The skips add another 2 on top of the usual 2 when they skip
+            skipped: SKIPS.contains(&mnemonic) && self.program_counter != pc.wrapping_add(2),
+        };
+        #[cfg(feature = "alloc")]
+        if let Some(mut hook) = self.post_hook.take() {
+            hook(self, &step);
+            self.post_hook = Some(hook);
+        }
+        Ok(step)
+    }
+
+    /// Sets what gets called right before every instruction runs, with what
+    /// is about to run. Only the address, opcode, and mnemonic are filled in,
+    /// since it hasn't done anything yet.
+    #[cfg(feature = "alloc")]
+    pub fn set_pre_hook(&mut self, hook: Hook) {
+        self.pre_hook = Some(hook);
     }
 
-    /// A helper function that is used to add a letter to the beginning of the
-    /// interpreter
-    fn add_letter(&mut self, letter: usize, sprite: &[u8; 5]) {
-        // Sets up the offset in memory for the letter to be placed in
-        let offset: usize = letter * 5;
-        // Loops through the sprite's size
-        for i in 0 as usize..5 {
-            // Places it in memory
-            self.memory[offset + i] = sprite[i];
+    /// Sets what gets called right after every instruction runs, with the
+    /// same `StepInfo` that `clock` returns. Instructions that stop with an
+    /// error don't get passed to it.
+    #[cfg(feature = "alloc")]
+    pub fn set_post_hook(&mut self, hook: Hook) {
+        self.post_hook = Some(hook);
+    }
+
+    /// Takes away both of the hooks
+    #[cfg(feature = "alloc")]
+    pub fn clear_hooks(&mut self) {
+        self.pre_hook = None;
+        self.post_hook = None;
+    }
+
+    /// Returns whether either of the hooks is set
+    #[cfg(feature = "alloc")]
+    pub fn has_hooks(&self) -> bool {
+        self.pre_hook.is_some() || self.post_hook.is_some()
+    }
+
+    /// Runs `cycles` instructions and then ticks the timers once, which is
+    /// everything that happens in one 60th of a second
+    pub fn frame(&mut self, cycles: usize) -> Result<(), Chip8Error> {
+        for _ in 0..cycles {
+            self.clock()?;
         }
+        self.tick_timers();
+        Ok(())
     }
 
-    /// This is where the interpreter runs all of the code it needs to
-    pub fn clock(&mut self) {
-        // Gets and parses the current opcode that needs to be ran
-        let opcode = self.get_current_opcode();
+    /// Ticks the delay and sound timers down by one until they hit 0, this
+    /// needs to be called 60 times a second by the parent program
+    pub fn tick_timers(&mut self) {
+        self.delay = self.delay.saturating_sub(1);
+        self.sound = self.sound.saturating_sub(1);
+        self.vblank = true;
+        #[cfg(feature = "alloc")]
+        self.apply_cheats();
+    }
+
+    /// Returns whether the sound timer is still running, and a tone should be played
+    pub fn is_sound_playing(&self) -> bool {
+        self.sound > 0
+    }
+
+    /// Returns whether the rom has stopped itself, after which it doesn't do
+    /// anything else, so the parent program can stop running it
+    pub fn has_exited(&self) -> bool {
+        self.has_exited
+    }
+
+    /// Pauses or resumes the rom, while it is paused the run loop doesn't run
+    /// any instructions or tick the timers, but still draws the screen, so a
+    /// debugger can step through it by hand
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Returns whether the rom is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Asks the run loop to run one more frame of the paused rom, the next
+    /// time it gets to one, which is a whole frame's instructions and a tick
+    /// of the timers, and to leave it paused after that. This is frame
+    /// advance, for playing a rom one frame at a time.
+    pub fn step_frame(&mut self) {
+        self.frame_steps += 1;
+    }
 
-        // If the parent application has handled the draw instruction set `has_drawn`
-        // and `had_handled_draw` to false
-        if self.has_handled_draw {
-            self.has_drawn = false;
-            self.has_handled_draw = false;
+    /// Takes one of the frames that `step_frame` asked for, and returns
+    /// whether there was one, for the run loop
+    pub fn take_frame_step(&mut self) -> bool {
+        if self.frame_steps == 0 {
+            return false;
         }
+        self.frame_steps -= 1;
+        true
+    }
+
+    /// Speeds up or slows down the rom, the run loop runs `speed` times as
+    /// many frames in the same amount of time, so 8 runs it 8 times as fast,
+    /// and 0.25 runs it at a quarter of the speed. Every frame still runs the
+    /// same instructions, so the rom can't tell the difference.
+    pub fn set_speed(&mut self, speed: f64) {
+        // Anything that isn't a number stops it, the same as 0
+        self.speed = if speed.is_nan() {
+            0.0
+        } else {
+            speed.clamp(0.0, MAX_SPEED)
+        };
+    }
+
+    /// Returns how much faster or slower than normal the rom is running
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
 
-        // Gets the associated function for the opcode, and runs the it
-        self.get_instruction(&opcode)(self, &opcode);
+    /// Makes the `rnd` instruction get its numbers from the function instead
+    /// of the seed, or from the seed again with `None`, see
+    /// `Chip8Builder::random_source`
+    pub fn set_random_source(&mut self, source: Option<fn() -> u8>) {
+        self.rng.set_source(source);
+    }
+
+    /// Makes running anything that isn't an instruction pause the rom with
+    /// the program counter still on it, instead of going on to the next
+    /// opcode like nothing happened. The `clock` that finds it returns it as
+    /// `nai`, without it having done anything.
+    pub fn set_break_on_unknown(&mut self, break_on_unknown: bool) {
+        self.break_on_unknown = break_on_unknown;
+    }
+
+    /// Returns whether the rom gets paused on anything that isn't an instruction
+    pub fn breaks_on_unknown(&self) -> bool {
+        self.break_on_unknown
+    }
 
-        // Increments the program counter by one instruction or 2 bytes
-        self.program_counter += 2;
+    /// Returns the opcode that the rom was paused on for not being an
+    /// instruction, if that is why it is paused
+    #[cfg(feature = "alloc")]
+    pub fn unknown_opcode(&self) -> Option<DisassembledInstruction> {
+        if !self.paused || !self.break_on_unknown || !self.program_counter_in_bounds() {
+            return None;
+        }
+        self.get_relative_instruction(0)
+            .filter(|instruction| instruction.mnemonic == "nai")
     }
 
-    /// Returns the parsed version of the opcode that needs to be ran
-    fn get_current_opcode(&self) -> Opcode {
-        let code = (self.memory[self.program_counter] as u16) << 8
-            | self.memory[self.program_counter + 1] as u16;
-        Opcode::new(code)
+    /// Returns the address that roms get loaded at
+    pub fn load_address(&self) -> usize {
+        self.load_address
     }
 
-    /// Returns the function for the opcode provided
-    fn get_instruction(&self, opcode: &Opcode) -> Instruction {
-        self.parse_opcode(&opcode).1
+    /// Returns the screen, which can be asked which pixels are on
+    pub fn screen(&self) -> &Screen {
+        &self.screen
     }
 
-    /// Gets the instruction relative to the current one, used for
-    /// when the parent application wants to see which instruction is running.
+    /// Returns the screen to change the pixels of, for setting up what is on it
+    /// before running something, and marks it to be drawn again
+    pub fn screen_mut(&mut self) -> &mut Screen {
+        self.has_drawn = true;
+        &mut self.screen
+    }
+
+    /// Returns true if the interpreter has drawn or cleared the screen since the
+    /// last time this was called, so the parent program only redraws when needed
+    pub fn take_redraw(&mut self) -> bool {
+        let has_drawn = self.has_drawn;
+        self.has_drawn = false;
+        has_drawn
+    }
+
+    /// Makes the next call to `take_redraw` return true, and marks the whole
+    /// screen as changed, for when the parent program needs to draw the screen
+    /// again even though it hasn't changed
+    pub fn request_redraw(&mut self) {
+        self.has_drawn = true;
+        self.screen.mark_dirty();
+    }
+
+    /// Marks every byte of the screen as unchanged, this should be called once
+    /// the parent program has drawn the changes
+    pub fn clear_dirty(&mut self) {
+        self.screen.clear_dirty();
+    }
+
+    /// Marks the key (`0x0` through `0xf`) as being held down
+    pub fn press_key(&mut self, key: u8) {
+        self.keys[(key & 0xf) as usize] = true;
+    }
+
+    /// Marks the key (`0x0` through `0xf`) as being let go of
+    pub fn release_key(&mut self, key: u8) {
+        self.keys[(key & 0xf) as usize] = false;
+    }
+
+    /// Marks every key as being let go of, useful for frontends that can't
+    /// tell when a key has been released, this includes the second keypad
+    pub fn release_all_keys(&mut self) {
+        for key in self.keys.iter_mut() {
+            *key = false;
+        }
+        if let Some(keys) = &mut self.second_keys {
+            *keys = [false; 16];
+        }
+    }
+
+    /// Returns whether the key (`0x0` through `0xf`) is being held down
+    pub fn is_key_pressed(&self, key: u8) -> bool {
+        self.keys[(key & 0xf) as usize]
+    }
+
+    /// Returns which keys the rom has checked with `ex9e` or `exa1` since it
+    /// started, as a mask where bit `n` is key `n`, which are most likely the
+    /// keys that it uses
+    pub fn checked_keys(&self) -> u16 {
+        self.checked_keys
+    }
+
+    /// Turns on the decode cache, which keeps the instruction that was decoded
+    /// at every address, so running it again skips straight to it. This is
+    /// only worth it when a lot of instructions are ran every frame.
+    #[cfg(feature = "alloc")]
+    pub fn enable_decode_cache(&mut self) {
+        self.decode_cache = Some(DecodeCache {
+            mode: self.decode_mode(),
+            entries: vec![None; self.memory.len()],
+        });
+    }
+
+    /// Reads and decodes the instruction that needs to be ran, from the decode
+    /// cache if it is on and already has it
+    fn fetch(&mut self) -> Result<Decoded, Chip8Error> {
+        let pc = self.program_counter;
+        // Both bytes of the opcode have to be inside of memory, unless the
+        // second one wraps around to the start
+        if !self.program_counter_in_bounds() {
+            return Err(Chip8Error::ProgramCounterOutOfBounds { pc });
+        }
+        let code = (self.memory[pc] as u16) << 8 | self.memory[(pc + 1) % self.memory.len()] as u16;
+        let mode = self.decode_mode();
+
+        #[cfg(not(feature = "alloc"))]
+        return Ok(Self::decode_with_table(code, mode));
+        #[cfg(feature = "alloc")]
+        let cache = match &mut self.decode_cache {
+            Some(cache) => cache,
+            // Every opcode was already decoded into the table, so this is the
+            // only work that is done for it
+            None => return Ok(Self::decode_with_table(code, mode)),
+        };
+        #[cfg(feature = "alloc")]
+        if cache.mode != mode {
+            cache.mode = mode;
+            cache.entries.iter_mut().for_each(|entry| *entry = None);
+        }
+        // A cache that was loaded from a save state is empty, and memory can
+        // be made bigger by the parent program after the cache was, so it is
+        // made the size of memory whenever it isn't
+        #[cfg(feature = "alloc")]
+        if cache.entries.len() != self.memory.len() {
+            cache.entries = vec![None; self.memory.len()];
+        }
+        #[cfg(feature = "alloc")]
+        match cache.entries[pc] {
+            Some(decoded) if decoded.opcode.code == code => Ok(decoded),
+            _ => {
+                let decoded = Self::decode_with_table(code, mode);
+                cache.entries[pc] = Some(decoded);
+                Ok(decoded)
+            }
+        }
+    }
+
+    /// Decodes the opcode with the dispatch table for the mode
+    fn decode_with_table(code: u16, mode: DecodeMode) -> Decoded {
+        let opcode = Opcode::new(code);
+        #[cfg(feature = "std")]
+        let (mnemonic, instruction) = DispatchTable::get(mode).lookup(&opcode);
+        // Without the standard library there is nothing to build the tables
+        // just once with, and they would take up 64K each, so the opcode is
+        // decoded as it comes
+        #[cfg(not(feature = "std"))]
+        let (mnemonic, instruction) = Self::decode(&opcode, mode);
+        Decoded {
+            opcode,
+            mnemonic,
+            instruction,
+        }
+    }
+
+    /// Makes sure that the key stored in register x is actually on the keypad,
+    /// and remembers that the rom checked it
+    fn check_key(&mut self, opcode: &Opcode) -> Result<usize, Chip8Error> {
+        let key = self.registers[opcode.x as usize];
+        if key > 0xf {
+            return Err(Chip8Error::InvalidKey {
+                pc: self.program_counter,
+                key,
+            });
+        }
+        self.checked_keys |= 1 << key;
+        Ok(key as usize)
+    }
+
+    /// Gets the instruction relative to the current one, counted in
+    /// instructions, used for when the parent application wants to show the
+    /// code around the one that is running. Anything that would be outside of
+    /// memory is `None`, no matter how far away it is.
     /// Used like so:
     /// ```rust
+    /// # use chip_8::Chip8;
     /// fn do_stuff(chip8: &Chip8) {
-    ///     chip8.get_relative_instruction(-2);
-    ///     chip8.get_relative_instruction(-1);
-    ///     chip8.get_relative_instruction(0);
-    ///     chip8.get_relative_instruction(1);
-    ///     chip8.get_relative_instruction(2);
+    ///     for relative in -2..=2 {
+    ///         if let Some(instruction) = chip8.get_relative_instruction(relative) {
+    ///             println!("{:03x}  {}", instruction.address, instruction);
+    ///         }
+    ///     }
     /// }
+    /// # do_stuff(&Chip8::new());
     /// ```
-    pub fn get_relative_instruction(&self, relative: i32) -> &'static str {
-        // gets the absolute value of the relative address
-        let absolute = if relative < 0 { -relative } else { relative } as usize * 2;
-        // adds or subtracts the relative address depending on whether it was negative or not
-        let relative_address = if relative < 0 {
-            self.program_counter - absolute
+    #[cfg(feature = "alloc")]
+    pub fn get_relative_instruction(&self, relative: i32) -> Option<DisassembledInstruction> {
+        // Every instruction is 2 bytes, and anything that would go past
+        // either end of an address can't be in memory anyways
+        let offset = (relative.unsigned_abs() as usize).checked_mul(2)?;
+        let address = if relative < 0 {
+            self.program_counter.checked_sub(offset)?
         } else {
-            self.program_counter + absolute
+            self.program_counter.checked_add(offset)?
         };
+        self.disassemble_at(address)
+    }
 
-        // gets the opcode stored at that address
-        let code =
-            (self.memory[relative_address] as u16) << 8 | self.memory[relative_address + 1] as u16;
-        // parse the opcode
-        let opcode = Opcode::new(code);
-        // return the mnemonic
-        self.parse_opcode(&opcode).0
+    /// Returns the subroutines that are currently being ran, with the most
+    /// recent call first
+    #[cfg(feature = "alloc")]
+    pub fn call_stack(&self) -> Vec<CallFrame> {
+        // The stack pointer is how many levels are in use, and a state that
+        // was changed by hand could have it past the end
+        let depth = self.stack_pointer.min(self.stack.len());
+        (0..depth)
+            .rev()
+            .map(|level| CallFrame {
+                call_site: self.stack[level],
+                subroutine: self.subroutines[level],
+            })
+            .collect()
+    }
+
+    /// Turns the instruction at the address into text like `drw v1, v2, 5`,
+    /// or `nai` if it isn't inside of memory
+    #[cfg(feature = "alloc")]
+    pub fn disassemble(&self, address: usize) -> String {
+        match self.disassemble_at(address) {
+            Some(instruction) => instruction.to_string(),
+            None => String::from("nai"),
+        }
+    }
+
+    /// Takes apart the instruction at the address, which is `None` if either
+    /// of its bytes are outside of memory
+    #[cfg(feature = "alloc")]
+    pub fn disassemble_at(&self, address: usize) -> Option<DisassembledInstruction> {
+        let high = *self.memory.get(address)?;
+        let low = *self.memory.get(address.checked_add(1)?)?;
+        let opcode = Opcode::new((high as u16) << 8 | low as u16);
+        let (mnemonic, _) = self.parse_opcode(&opcode);
+
+        // The operands are worked out from the shape of the opcode
+        let operands = match opcode.code >> 12 {
+            _ if mnemonic == "nai" => format!("{:04x}", opcode.code),
+            0x0 if mnemonic == "scd" => format!("{}", opcode.n),
+            0x0 => String::new(),
+            0xf if mnemonic == "audio" => String::new(),
+            // CHIP-8X's `col` takes the same operands as `drw`
+            0xb if mnemonic == "col" => {
+                format!("v{:x}, v{:x}, {}", opcode.x, opcode.y, opcode.n)
+            }
+            0x1 | 0x2 | 0xa | 0xb => format!("{:#05x}", opcode.nnn),
+            0x3 | 0x4 | 0x6 | 0x7 | 0xc => format!("v{:x}, {:#04x}", opcode.x, opcode.nn),
+            0x5 | 0x8 | 0x9 => format!("v{:x}, v{:x}", opcode.x, opcode.y),
+            0xd => format!("v{:x}, v{:x}, {}", opcode.x, opcode.y, opcode.n),
+            _ => format!("v{:x}", opcode.x),
+        };
+        Some(DisassembledInstruction {
+            address,
+            opcode: opcode.code,
+            mnemonic,
+            operands,
+        })
     }
 
     /// Parses the opcode and returns the corresponding function and mnemonic
     pub fn parse_opcode(&self, opcode: &Opcode) -> (&'static str, Instruction) {
+        Self::decode(opcode, self.decode_mode())
+    }
+
+    /// Works out which instructions the opcodes decode to right now, which
+    /// depends on the quirks and which extensions are on
+    fn decode_mode(&self) -> DecodeMode {
+        DecodeMode {
+            shift: self.quirks.shift,
+            #[cfg(feature = "megachip")]
+            megachip: self.megachip.is_some(),
+            #[cfg(not(feature = "megachip"))]
+            megachip: false,
+            chip8x: self.is_chip8x(),
+            hires: self.hires,
+        }
+    }
+
+    /// Gets the function and mnemonic for the opcode in the mode, this is the
+    /// only place that knows which opcode is which instruction
+    fn decode(opcode: &Opcode, mode: DecodeMode) -> (&'static str, Instruction) {
+        // CHIP-8X only adds or changes a few of the instructions, everything
+        // else is the same
+        if mode.chip8x {
+            if let Some(parsed) = Self::parse_chip8x_opcode(opcode) {
+                return parsed;
+            }
+        }
         match opcode.code {
             0x00e0 => ("cls", Self::cls),
+            // Hi-res Chip-8 clears its bigger screen with a call to its own
+            // machine code
+            0x0230 if mode.hires => ("cls", Self::cls),
             0x00ee => ("ret", Self::ret),
+            0x00fb => ("scr", Self::scr),
+            0x00fc => ("scl", Self::scl),
+            0x00fd => ("exit", Self::exit),
+            code if code & 0xfff0 == 0x00c0 => ("scd", Self::scd),
+            // MEGA-CHIP's instructions are all in the range that used to call
+            // machine code, which nothing here runs anyways
+            #[cfg(feature = "megachip")]
+            code if code >> 12 == 0 && mode.megachip => Self::parse_megachip_opcode(opcode),
             _ => match opcode.code >> 12 {
                 0x1 => ("jp", Self::jp),
                 0x2 => ("call", Self::call),
@@ -292,7 +1164,7 @@ impl Chip8 {
                     0x4 => ("addy", Self::addy),
                     0x5 => ("sub", Self::sub),
                     0x6 => {
-                        if self.other_mode {
+                        if mode.shift {
                             ("shr", Self::shr)
                         } else {
                             ("shry", Self::shry)
@@ -300,7 +1172,7 @@ impl Chip8 {
                     }
                     0x7 => ("subn", Self::subn),
                     0xe => {
-                        if self.other_mode {
+                        if mode.shift {
                             ("shl", Self::shl)
                         } else {
                             ("shly", Self::shly)
@@ -322,15 +1194,20 @@ impl Chip8 {
                     _ => ("nai", Self::nai),
                 },
                 0xf => match opcode.code & 0xff {
+                    0x02 if opcode.x == 0 => ("audio", Self::audio),
                     0x07 => ("ldxdt", Self::ldxdt),
                     0x0a => ("ldk", Self::ldk),
                     0x15 => ("lddt", Self::lddt),
                     0x18 => ("ldst", Self::ldst),
                     0x1e => ("addi", Self::addi),
                     0x29 => ("ldf", Self::ldf),
+                    0x30 => ("ldhf", Self::ldhf),
                     0x33 => ("ldb", Self::ldb),
+                    0x3a => ("ldpitch", Self::ldpitch),
                     0x55 => ("ldix", Self::ldix),
                     0x65 => ("ldxi", Self::ldxi),
+                    0x75 => ("ldrx", Self::ldrx),
+                    0x85 => ("ldxr", Self::ldxr),
                     _ => ("nai", Self::nai),
                 },
                 _ => ("nai", Self::nai),
@@ -341,289 +1218,408 @@ impl Chip8 {
     /// Not an instruction, used to provide a mnemonic for when the interpreter
     /// tries to give a mnemonic for a piece of memory that is not actually
     /// an instruction.
-    pub fn nai(&mut self, _opcode: &Opcode) {}
+    pub fn nai(&mut self, _opcode: &Opcode) -> Result<(), Chip8Error> {
+        Ok(())
+    }
 
     /// Opcode: `00e0`
     ///
     /// Explanation: Clears the screen.
-    fn cls(&mut self, _opcode: &Opcode) {
+    fn cls(&mut self, _opcode: &Opcode) -> Result<(), Chip8Error> {
         self.has_drawn = true;
-        for pixel in self.screen.iter_mut() {
-            *pixel = 0;
-        }
+        self.screen.clear();
+        #[cfg(feature = "megachip")]
+        self.cls_megachip();
+        Ok(())
     }
 
     /// Opcode: `00ee`
     ///
     /// Explanation: Returns from a subroutine.
-    fn ret(&mut self, _opcode: &Opcode) {
-        self.program_counter = self.stack[self.stack_pointer];
+    fn ret(&mut self, _opcode: &Opcode) -> Result<(), Chip8Error> {
+        if self.stack_pointer == 0 {
+            return Err(Chip8Error::StackUnderflow {
+                pc: self.program_counter,
+            });
+        }
         self.stack_pointer -= 1;
+        self.program_counter = self.stack[self.stack_pointer];
+        Ok(())
+    }
+
+    /// Opcode: `00cn`
+    ///
+    /// Explanation: Scrolls the screen down by n pixels.
+    fn scd(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.has_drawn = true;
+        self.screen.scroll_down(opcode.n as usize);
+        Ok(())
+    }
+
+    /// Opcode: `00fb`
+    ///
+    /// Explanation: Scrolls the screen right by 4 pixels.
+    fn scr(&mut self, _opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.has_drawn = true;
+        self.screen.scroll_right(4);
+        Ok(())
+    }
+
+    /// Opcode: `00fc`
+    ///
+    /// Explanation: Scrolls the screen left by 4 pixels.
+    fn scl(&mut self, _opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.has_drawn = true;
+        self.screen.scroll_left(4);
+        Ok(())
+    }
+
+    /// Opcode: `00fd`
+    ///
+    /// Explanation: Stops the rom. The program counter stays on this
+    /// instruction, so running it again doesn't do anything else.
+    fn exit(&mut self, _opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.has_exited = true;
+        self.program_counter = self.program_counter.wrapping_sub(2);
+        Ok(())
     }
 
     /// Opcode: `1nnn`
     ///
     /// Explanation: Jumps to address nnn.
-    fn jp(&mut self, opcode: &Opcode) {
-        self.program_counter = opcode.nnn as usize - 2;
+    fn jp(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.program_counter = (opcode.nnn as usize).wrapping_sub(2);
+        Ok(())
     }
 
     /// Opcode: `2nnn`
     ///
     /// Explanation: Calls subroutine at nnn.
-    fn call(&mut self, opcode: &Opcode) {
-        self.stack_pointer += 1;
+    fn call(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        // Every level of the stack gets used, so 16 calls can be nested
+        if self.stack_pointer >= self.stack.len() {
+            return Err(Chip8Error::StackOverflow {
+                pc: self.program_counter,
+                #[cfg(feature = "alloc")]
+                calls: self.call_stack(),
+            });
+        }
         self.stack[self.stack_pointer] = self.program_counter;
-        self.program_counter = opcode.nnn as usize - 2;
+        self.subroutines[self.stack_pointer] = opcode.nnn as usize;
+        self.stack_pointer += 1;
+        self.program_counter = (opcode.nnn as usize).wrapping_sub(2);
+        Ok(())
     }
 
     /// Opcode: `3xnn`
     ///
     /// Explanation: Skips the next instruction if register x equals nn.
-    fn se(&mut self, opcode: &Opcode) {
+    fn se(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         if self.registers[opcode.x as usize] == opcode.nn {
             self.program_counter += 2;
         }
+        Ok(())
     }
 
     /// Opcode: `4xnn`
     ///
     /// Explanation: Skips the next instruction if register x doesn't equal nn.
-    fn sne(&mut self, opcode: &Opcode) {
+    fn sne(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         if self.registers[opcode.x as usize] != opcode.nn {
             self.program_counter += 2;
         }
+        Ok(())
     }
 
     /// Opcode: `5xy0`
     ///
     /// Explanation: Skips the next instruction if register x equals register y.
-    fn sey(&mut self, opcode: &Opcode) {
+    fn sey(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         if self.registers[opcode.x as usize] == self.registers[opcode.y as usize] {
             self.program_counter += 2;
         }
+        Ok(())
     }
 
     /// Opcode: `6xnn`
     ///
     /// Explanation: Sets register x to nn.
-    fn ld(&mut self, opcode: &Opcode) {
+    fn ld(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         self.registers[opcode.x as usize] = opcode.nn;
+        Ok(())
     }
 
     /// Opcode: `7xnn`
     ///
     /// Explanation: Adds nn to register x without changing the carry flag.
-    fn add(&mut self, opcode: &Opcode) {
+    fn add(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         let x = &mut self.registers[opcode.x as usize];
         *x = x.wrapping_add(opcode.nn);
+        Ok(())
     }
 
     /// Opcode: `8xy0`
     ///
     /// Explanation: Sets register x to the value of register y.
-    fn ldy(&mut self, opcode: &Opcode) {
+    fn ldy(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         self.registers[opcode.x as usize] = self.registers[opcode.y as usize];
+        Ok(())
     }
 
     /// Opcode: `8xy1`
     ///
     /// Explanation: Sets register x to the value of the bitwise *or* of register x and register y.
-    fn or(&mut self, opcode: &Opcode) {
+    fn or(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         self.registers[opcode.x as usize] |= self.registers[opcode.y as usize];
+        if self.quirks.vf_reset {
+            self.registers[0xf] = 0;
+        }
+        Ok(())
     }
 
     /// Opcode: `8xy2`
     ///
     /// Explanation: Sets register x to the value of the bitwise *and* of register x and register y.
-    fn and(&mut self, opcode: &Opcode) {
+    fn and(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         self.registers[opcode.x as usize] &= self.registers[opcode.y as usize];
+        if self.quirks.vf_reset {
+            self.registers[0xf] = 0;
+        }
+        Ok(())
     }
 
     /// Opcode: `8xy3`
     ///
     /// Explanation: Sets register x to the value of the bitwise *xor* of register x and y.
-    fn xor(&mut self, opcode: &Opcode) {
+    fn xor(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         self.registers[opcode.x as usize] ^= self.registers[opcode.y as usize];
+        if self.quirks.vf_reset {
+            self.registers[0xf] = 0;
+        }
+        Ok(())
     }
 
     /// Opcode: `8xy4`
     ///
     /// Explanation: Adds register y to register x, and sets register f to 1 if there is an overflow, and 0 if there isn't.
-    fn addy(&mut self, opcode: &Opcode) {
-        self.registers[0xf] = 0;
-        let result =
+    ///
+    /// Note: Register f is set after register x, so the flag is what is left in it when x is register f.
+    fn addy(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let (result, overflowed) =
             self.registers[opcode.x as usize].overflowing_add(self.registers[opcode.y as usize]);
-        self.registers[opcode.x as usize] = result.0;
-        if result.1 {
-            self.registers[0xf] = 1;
-        }
+        self.registers[opcode.x as usize] = result;
+        self.registers[0xf] = overflowed as u8;
+        Ok(())
     }
 
     /// Opcode: `8xy5`
     ///
-    /// Explanation: Subtracts register y from register x and sets register f to 1 if there is an under flow, and 0 if there isn't.
-    fn sub(&mut self, opcode: &Opcode) {
-        self.registers[0xf] = 0;
-        let result =
+    /// Explanation: Subtracts register y from register x and sets register f to 0 if there is an under flow, and 1 if there isn't.
+    ///
+    /// Note: Register f is set after register x, so the flag is what is left in it when x is register f.
+    fn sub(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let (result, underflowed) =
             self.registers[opcode.x as usize].overflowing_sub(self.registers[opcode.y as usize]);
-        self.registers[opcode.x as usize] = result.0;
-        if result.1 {
-            self.registers[0xf] = 1;
-        }
+        self.registers[opcode.x as usize] = result;
+        self.registers[0xf] = !underflowed as u8;
+        Ok(())
     }
 
     /// Opcode: `8x06`
     ///
     /// Explanation: Stores the least significant bit of register x into register f and shifts register x by 1.
     ///
-    /// Note: This is one of the functions whose definition has changed over the years. This is the default.
-    fn shr(&mut self, opcode: &Opcode) {
-        self.registers[0xf] = 0;
-        if self.registers[opcode.x as usize] & 0b1 == 1 {
-            self.registers[0xf] = 1;
-        }
-        self.registers[opcode.x as usize] = self.registers[opcode.x as usize] >> 1;
+    /// Note: This is one of the functions whose definition has changed over the years. This is used if the shift quirk is on.
+    fn shr(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let value = self.registers[opcode.x as usize];
+        self.registers[opcode.x as usize] = value >> 1;
+        self.registers[0xf] = value & 0b1;
+        Ok(())
     }
 
     /// Opcode: `8xy6`
     ///
-    /// Explanation: Stores the least significant bit of register x into register f and shifts register x by the value of register y.
+    /// Explanation: Stores the least significant bit of register y into register f and sets register x to register y shifted by 1.
     ///
-    /// Note: This is one of the functions whose definition has changed over the years. This is used if other_mode is set to true.
-    fn shry(&mut self, opcode: &Opcode) {
-        self.registers[0xf] = 0;
-        if self.registers[opcode.y as usize] & 0b1 == 1 {
-            self.registers[0xf] = 1;
-        }
-        self.registers[opcode.x as usize] = self.registers[opcode.y as usize] >> 1;
+    /// Note: This is one of the functions whose definition has changed over the years. This is the default.
+    fn shry(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let value = self.registers[opcode.y as usize];
+        self.registers[opcode.x as usize] = value >> 1;
+        self.registers[0xf] = value & 0b1;
+        Ok(())
     }
 
     /// Opcode: `8xy7`
     ///
-    /// Explanation: Sets register x to register y minus register x, setting register f to 1 if there is an underflow, and 0 if there isn't.
-    fn subn(&mut self, opcode: &Opcode) {
-        self.registers[0xf] = 0;
-        let result =
+    /// Explanation: Sets register x to register y minus register x, setting register f to 0 if there is an underflow, and 1 if there isn't.
+    ///
+    /// Note: Register f is set after register x, so the flag is what is left in it when x is register f.
+    fn subn(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let (result, underflowed) =
             self.registers[opcode.y as usize].overflowing_sub(self.registers[opcode.x as usize]);
-        self.registers[opcode.x as usize] = result.0;
-        if result.1 {
-            self.registers[0xf] = 1;
-        }
+        self.registers[opcode.x as usize] = result;
+        self.registers[0xf] = !underflowed as u8;
+        Ok(())
     }
 
     /// Opcode: `8x0e`
     ///
     /// Explanation: Stores the most significant bit of register x into register f then shifts register x by 1.
     ///
-    /// Note: This is one of the functions whose definition has changed over the years. This is the default.
-    fn shl(&mut self, opcode: &Opcode) {
-        self.registers[0xf] = 0;
-        if self.registers[opcode.x as usize] & 0b10000000 != 0 {
-            self.registers[0xf] = 1;
-        }
-        self.registers[opcode.x as usize] = self.registers[opcode.x as usize] << 1;
+    /// Note: This is one of the functions whose definition has changed over the years. This is used if the shift quirk is on.
+    fn shl(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let value = self.registers[opcode.x as usize];
+        self.registers[opcode.x as usize] = value << 1;
+        self.registers[0xf] = value >> 7;
+        Ok(())
     }
 
     /// Opcode: `8xye`
     ///
-    /// Explanation: Stores the most significant bit of register x into register f then shifts register x by the value in register y.
+    /// Explanation: Stores the most significant bit of register y into register f and sets register x to register y shifted by 1.
     ///
-    /// Note: This is one of the functions whose definition has changed over the years. This is used if other_mode is set to true.
-    fn shly(&mut self, opcode: &Opcode) {
-        self.registers[0xf] = 0;
-        if self.registers[opcode.y as usize] & 0b10000000 != 0 {
-            self.registers[0xf] = 1;
-        }
-        self.registers[opcode.x as usize] = self.registers[opcode.y as usize] << 1;
+    /// Note: This is one of the functions whose definition has changed over the years. This is the default.
+    fn shly(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let value = self.registers[opcode.y as usize];
+        self.registers[opcode.x as usize] = value << 1;
+        self.registers[0xf] = value >> 7;
+        Ok(())
     }
 
     /// Opcode: `9xy0`
     ///
     /// Explanation: skips the next instruction if register x doesn't equal register y.
-    fn sney(&mut self, opcode: &Opcode) {
+    fn sney(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         if self.registers[opcode.x as usize] != self.registers[opcode.y as usize] {
             self.program_counter += 2;
         }
+        Ok(())
     }
 
     /// Opcode: `annn`
     ///
     /// Explanation: Sets the index to address nnn.
-    fn ldi(&mut self, opcode: &Opcode) {
+    fn ldi(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         self.index = opcode.nnn as usize;
+        Ok(())
     }
 
     /// Opcode: `bnnn`
     ///
     /// Explanation: Jumps to address nnn plus the value of register 0.
-    fn jp0(&mut self, opcode: &Opcode) {
-        self.program_counter = opcode.nnn as usize + self.registers[0] as usize - 2;
+    ///
+    /// Note: This is one of the functions whose definition has changed over the years. If the jump quirk is on, register x is used instead of register 0.
+    fn jp0(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let register = if self.quirks.jump { opcode.x } else { 0 };
+        self.program_counter =
+            (opcode.nnn as usize + self.registers[register as usize] as usize).wrapping_sub(2);
+        Ok(())
     }
 
     /// Opcode: `cxnn`
     ///
     /// Explanation: Sets register x to the bitwise and of a random number and nn.
-    fn rnd(&mut self, opcode: &Opcode) {
-        self.registers[opcode.x as usize] = rand::random::<u8>() & opcode.nn;
+    fn rnd(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.registers[opcode.x as usize] = self.rng.next_u8() & opcode.nn;
+        Ok(())
     }
 
     /// Opcode: `dxyn`
     ///
     /// Explanation: Draws a sprite at coordinates located in registers x and y with a width of 8 pixels and a height of n pixels.
     /// The sprite it reads is the one pointed to by index and if any pixels are changed from 1 to 0, sets register f to 1, otherwise 0.
-    fn drw(&mut self, opcode: &Opcode) {
-        self.has_drawn = true;
-        self.registers[0xf] = 0;
-        for i in 0..opcode.n {
-            let y = self.registers[opcode.y as usize] + i;
-            let sprite = self.memory[self.index + i as usize];
-            let x = self.registers[opcode.x as usize];
-            let x_byte = (x / 8) % 8;
-            let y_offset = y % 32;
-
-            let pixel_location = (x_byte + (y_offset * 8)) as usize;
-            let shift_amount = x % 8;
-            if self.screen[pixel_location] & (sprite >> shift_amount) != 0 {
-                self.registers[0xf] = 1;
+    /// The coordinates wrap around the screen, but the parts of the sprite that go past the right or bottom edge are cut off.
+    ///
+    /// Note: This is one of the functions whose definition has changed over the years. If the display wait quirk is on, it waits for the next frame before drawing.
+    /// If the wrap quirk is on, the parts of the sprite past the edges wrap around to the other side instead of being cut off.
+    fn drw(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        // The big screen draws sprites completely differently
+        #[cfg(feature = "megachip")]
+        if self.is_megachip_on() {
+            return self.drw_megachip(opcode);
+        }
+        self.check_index(opcode.n as usize)?;
+        if self.quirks.display_wait {
+            // Runs this same instruction again until the timers have ticked,
+            // which stalls the rom until the start of the next frame
+            if !self.vblank {
+                self.program_counter = self.program_counter.wrapping_sub(2);
+                return Ok(());
             }
-            self.screen[pixel_location] ^= sprite >> shift_amount;
+            self.vblank = false;
+        }
+        self.has_drawn = true;
 
-            let pixel_location = (((x_byte + 1) % 8) + (y_offset * 8)) as usize;
-            let shift_amount = 8 - shift_amount;
-            if shift_amount == 8 {
-                continue;
+        let (width, height) = self.screen.size();
+        // Where the sprite starts always wraps, no matter the quirk. The
+        // coordinates are read before register f is cleared, since either of
+        // them can be register f
+        let x = self.registers[opcode.x as usize] as usize % width;
+        let y = self.registers[opcode.y as usize] as usize % height;
+        self.registers[0xf] = 0;
+        for row in 0..opcode.n as usize {
+            let mut pixel_y = y + row;
+            if pixel_y >= height {
+                if !self.quirks.wrap {
+                    break;
+                }
+                pixel_y %= height;
             }
-            if self.screen[pixel_location] & (sprite << shift_amount) != 0 {
-                self.registers[0xf] = 1;
+            let sprite = self.memory[self.index_address(row)];
+            for column in 0..8 {
+                // The left most pixel of the sprite is the most significant bit
+                if sprite & (0b10000000 >> column) == 0 {
+                    continue;
+                }
+                let mut pixel_x = x + column;
+                if pixel_x >= width {
+                    if !self.quirks.wrap {
+                        break;
+                    }
+                    pixel_x %= width;
+                }
+                if self.screen.toggle_pixel(pixel_x, pixel_y) {
+                    self.registers[0xf] = 1;
+                }
             }
-            self.screen[pixel_location] ^= sprite << shift_amount;
         }
+        trace!(
+            x,
+            y,
+            rows = opcode.n,
+            index = format_args!("{:03x}", self.index),
+            collided = self.registers[0xf] == 1,
+            "drew a sprite"
+        );
+        Ok(())
     }
 
     /// Opcode: `ex9e`
     ///
     /// Explanation: Skips the next instruction if the key stored in register x is pressed.
-    fn skp(&mut self, opcode: &Opcode) {
-        if self.keys[self.registers[opcode.x as usize] as usize] {
+    fn skp(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        if self.keys[self.check_key(opcode)?] {
             self.program_counter += 2;
         }
+        Ok(())
     }
 
     /// Opcode: `exa1`
     ///
     /// Explanation: Skips the next instruction if the key stored in register x is not pressed.
-    fn skpn(&mut self, opcode: &Opcode) {
-        if !self.keys[self.registers[opcode.x as usize] as usize] {
+    fn skpn(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        if !self.keys[self.check_key(opcode)?] {
             self.program_counter += 2;
         }
+        Ok(())
     }
 
     /// Opcode: `fx07`
     ///
     /// Explanation: Sets register x to the value of the delay timer.
-    fn ldxdt(&mut self, opcode: &Opcode) {
+    fn ldxdt(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         self.registers[opcode.x as usize] = self.delay;
+        Ok(())
     }
 
     /// Opcode: `fx0a`
@@ -631,7 +1627,7 @@ impl Chip8 {
     /// Explanation: Waits for a key to be pressed, then stores that value into register x.
     ///
     /// Note: This operation blocks all other execution.
-    fn ldk(&mut self, opcode: &Opcode) {
+    fn ldk(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         let mut wait = true;
 
         for i in 0..=0xf {
@@ -643,29 +1639,45 @@ impl Chip8 {
         }
 
         if wait {
-            self.program_counter -= 2;
+            self.program_counter = self.program_counter.wrapping_sub(2);
         }
+        Ok(())
     }
 
     /// Opcode: `fx15`
     ///
     /// Explanation: Sets the delay timer to the value of register x.
-    fn lddt(&mut self, opcode: &Opcode) {
+    fn lddt(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         self.delay = self.registers[opcode.x as usize];
+        Ok(())
     }
 
     /// Opcode: `fx18`
     ///
     /// Explanation: Sets the sound timer to the value of register x.
-    fn ldst(&mut self, opcode: &Opcode) {
+    fn ldst(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
         self.sound = self.registers[opcode.x as usize];
+        Ok(())
     }
 
     /// Opcode: `fx1e`
     ///
     /// Explanation: Adds the value of register x to the index.
-    fn addi(&mut self, opcode: &Opcode) {
-        self.index += self.registers[opcode.x as usize] as usize;
+    ///
+    /// Note: If the index carry quirk is on, register f is set to 1 when the index goes past the end of memory, otherwise 0,
+    /// and the index wraps around to the start like it did on the Amiga interpreter that the quirk comes from.
+    /// Otherwise an index that goes past the end of memory is trapped, wrapped, or clamped
+    /// by the index policy, see the `bounds` module.
+    fn addi(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let index = self.index + self.registers[opcode.x as usize] as usize;
+        if self.quirks.index_carry {
+            let size = self.index_space();
+            self.registers[0xf] = (index >= size) as u8;
+            self.index = index % size;
+        } else {
+            self.index = self.index_in_bounds(index)?;
+        }
+        Ok(())
     }
 
     /// Opcode: `fx29`
@@ -673,8 +1685,21 @@ impl Chip8 {
     /// Explanation: Sets the index to the location for the character stored in register x.
     ///
     /// Note: This is represented by a 4x5 pixel font.
-    fn ldf(&mut self, opcode: &Opcode) {
-        self.index = self.registers[opcode.x as usize] as usize * 5;
+    fn ldf(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.index = self.font_address + self.registers[opcode.x as usize] as usize * 5;
+        Ok(())
+    }
+
+    /// Opcode: `fx30`
+    ///
+    /// Explanation: Sets the index to the location for the big character stored
+    /// in register x.
+    ///
+    /// Note: This is represented by an 8x10 pixel font, so it needs `dxya` to draw.
+    fn ldhf(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let letter = (self.registers[opcode.x as usize] & 0xf) as usize;
+        self.index = BIG_FONT_ADDRESS + letter * BIG_FONT[0].len();
+        Ok(())
     }
 
     /// Opcode: `fx33`
@@ -682,36 +1707,82 @@ impl Chip8 {
     /// Explanation: Stores the binary coded decimal representation of the value
     /// in register x with the most significant number stored at the index, and
     /// the least significant number stored at the index + 2.
-    fn ldb(&mut self, opcode: &Opcode) {
-        self.memory[self.index] = self.registers[opcode.x as usize] / 100;
-        self.memory[self.index + 1] = (self.registers[opcode.x as usize] / 10) % 10;
-        self.memory[self.index + 2] = self.registers[opcode.x as usize] % 10;
+    fn ldb(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.check_index(3)?;
+        self.check_protected(3)?;
+        let value = self.registers[opcode.x as usize];
+        self.write_memory(self.index_address(0), value / 100);
+        self.write_memory(self.index_address(1), (value / 10) % 10);
+        self.write_memory(self.index_address(2), value % 10);
+        Ok(())
     }
 
     /// Opcode: `fx55`
     ///
     /// Explanation: Stores register 0 through register x into memory starting at
     /// the index, without modifying the index.
-    fn ldix(&mut self, opcode: &Opcode) {
+    ///
+    /// Note: This is one of the functions whose definition has changed over the years. If the memory increment quirk is on, the index is left after the last register.
+    fn ldix(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.check_index(opcode.x as usize + 1)?;
+        self.check_protected(opcode.x as usize + 1)?;
+        let next_index = self.next_index(opcode)?;
         for i in 0..=opcode.x {
-            self.memory[self.index + i as usize] = self.registers[i as usize];
+            self.write_memory(self.index_address(i as usize), self.registers[i as usize]);
         }
+        self.index = next_index;
+        Ok(())
     }
 
     /// Opcode: `fx65`
     ///
     /// Explanation: Loads register 0 through register x with values from memory
     /// starting at the index, without modifying the index.
-    fn ldxi(&mut self, opcode: &Opcode) {
+    ///
+    /// Note: This is one of the functions whose definition has changed over the years. If the memory increment quirk is on, the index is left after the last register.
+    fn ldxi(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.check_index(opcode.x as usize + 1)?;
+        let next_index = self.next_index(opcode)?;
         for i in 0..=opcode.x {
-            self.registers[i as usize] = self.memory[self.index + i as usize];
+            self.registers[i as usize] = self.read_memory(self.index_address(i as usize));
         }
+        self.index = next_index;
+        Ok(())
     }
 
-    /// Loads the bytes of the rom into the memory starting at location `0x200`.
-    pub fn load(&mut self, rom: Vec<u8>) {
-        for i in 0..rom.len() {
-            self.memory[0x200 + i] = rom[i];
+    /// Returns where the index is left after `fx55` or `fx65`, which is after
+    /// the last register with the memory increment quirk, brought back inside
+    /// of memory by the index policy. It is worked out before anything is
+    /// read or written, so a trapped index doesn't leave it half done.
+    fn next_index(&self, opcode: &Opcode) -> Result<usize, Chip8Error> {
+        if self.quirks.memory_increment {
+            self.index_in_bounds(self.index + opcode.x as usize + 1)
+        } else {
+            Ok(self.index)
         }
     }
+
+    /// Opcode: `fx75`
+    ///
+    /// Explanation: Stores register 0 through register x into the flags.
+    fn ldrx(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let count = opcode.x as usize + 1;
+        self.flags[..count].copy_from_slice(&self.registers[..count]);
+        Ok(())
+    }
+
+    /// Opcode: `fx85`
+    ///
+    /// Explanation: Loads register 0 through register x with the flags.
+    fn ldxr(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let count = opcode.x as usize + 1;
+        self.registers[..count].copy_from_slice(&self.flags[..count]);
+        Ok(())
+    }
+}
+
+/// The speed that a rom that was saved starts out at
+#[cfg(feature = "serde")]
+fn normal_speed() -> f64 {
+    1.0
 }
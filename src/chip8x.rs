@@ -0,0 +1,153 @@
+//! This module contains CHIP-8X, the version of Chip-8 for the VIP with the
+//! color board and a second keypad.
+//!
+//! Like MEGA-CHIP, it is only there once it is turned on with
+//! `Chip8::enable_chip8x`, which gives the screen it's colors. The instructions
+//! that are added, or that replace the normal ones, are:
+//! - `02a0` steps the background through it's 4 colors
+//! - `5xy1` adds each hex digit of register y to register x, wrapping at 8
+//! - `bxy0` sets the foreground color of an area of 8x4 pixel zones
+//! - `bxyn` sets the foreground color of n rows of 8 pixels
+//! - `exf2` and `exf5` skip if the key on the second keypad is, or isn't, pressed
+//!
+//! The color for `bxy0` and `bxyn` is in the register after x, the colors are
+//! the numbers in `screen::COLORS`.
+
+use crate::{
+    chip8::{Chip8, Instruction, Opcode},
+    error::Chip8Error,
+};
+
+/// How many pixels wide each zone that `bxy0` colors is
+const ZONE_WIDTH: usize = 8;
+/// How many pixels tall each zone that `bxy0` colors is
+const ZONE_HEIGHT: usize = 4;
+
+impl Chip8 {
+    /// Turns on CHIP-8X, which adds the colors to the screen and the second
+    /// keypad, along with it's instructions
+    pub fn enable_chip8x(&mut self) {
+        self.second_keys = Some([false; 16]);
+        self.screen.enable_colors();
+    }
+
+    /// Returns whether CHIP-8X has been turned on
+    pub fn is_chip8x(&self) -> bool {
+        self.second_keys.is_some()
+    }
+
+    /// Marks the key (`0x0` through `0xf`) on the second keypad as being held
+    /// down, this does nothing unless CHIP-8X is on
+    pub fn press_second_key(&mut self, key: u8) {
+        if let Some(keys) = &mut self.second_keys {
+            keys[(key & 0xf) as usize] = true;
+        }
+    }
+
+    /// Marks the key (`0x0` through `0xf`) on the second keypad as being let go of
+    pub fn release_second_key(&mut self, key: u8) {
+        if let Some(keys) = &mut self.second_keys {
+            keys[(key & 0xf) as usize] = false;
+        }
+    }
+
+    /// Returns whether the key (`0x0` through `0xf`) on the second keypad is
+    /// being held down
+    pub fn is_second_key_pressed(&self, key: u8) -> bool {
+        self.second_keys
+            .map(|keys| keys[(key & 0xf) as usize])
+            .unwrap_or(false)
+    }
+
+    /// Gets the CHIP-8X instruction for the opcode, if it is one of the ones
+    /// that it adds or changes
+    pub(crate) fn parse_chip8x_opcode(opcode: &Opcode) -> Option<(&'static str, Instruction)> {
+        let parsed: (&'static str, Instruction) = match opcode.code >> 12 {
+            0x0 if opcode.code == 0x02a0 => ("bgcol", Self::bgcol),
+            0x5 if opcode.n == 0x1 => ("addn", Self::addn),
+            0xb => ("col", Self::col),
+            0xe if opcode.nn == 0xf2 => ("skp2", Self::skp2),
+            0xe if opcode.nn == 0xf5 => ("skpn2", Self::skpn2),
+            _ => return None,
+        };
+        Some(parsed)
+    }
+
+    /// Opcode: `02a0`
+    ///
+    /// Explanation: Changes the background to the next color, which goes dark
+    /// blue, black, dark green, dark red, and back to dark blue.
+    fn bgcol(&mut self, _opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.has_drawn = true;
+        self.screen.next_background();
+        Ok(())
+    }
+
+    /// Opcode: `5xy1`
+    ///
+    /// Explanation: Adds each hex digit of register y to the same digit of
+    /// register x, each one wrapping around at 8.
+    fn addn(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let x = self.registers[opcode.x as usize];
+        let y = self.registers[opcode.y as usize];
+        let high = ((x >> 4) + (y >> 4)) & 0x7;
+        let low = ((x & 0xf) + (y & 0xf)) & 0x7;
+        self.registers[opcode.x as usize] = high << 4 | low;
+        Ok(())
+    }
+
+    /// Opcode: `bxyn`
+    ///
+    /// Explanation: Sets the foreground color to the one in the register after
+    /// x. When n is 0, the area is made of 8x4 pixel zones, with the low digit
+    /// of register x being the left zone and the high digit how many more to
+    /// the right, and register y the same for the top zone and how many more
+    /// below. Otherwise it is the n rows of 8 pixels starting at the
+    /// coordinates in registers x and y.
+    fn col(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.has_drawn = true;
+        let x = self.registers[opcode.x as usize] as usize;
+        let y = self.registers[opcode.y as usize] as usize;
+        let color = self.registers[(opcode.x as usize + 1) & 0xf];
+
+        let (columns, rows) = if opcode.n == 0 {
+            let left = (x & 0xf) * ZONE_WIDTH;
+            let top = (y & 0xf) * ZONE_HEIGHT;
+            let width = ((x >> 4) + 1) * ZONE_WIDTH;
+            let height = ((y >> 4) + 1) * ZONE_HEIGHT;
+            (left..left + width, top..top + height)
+        } else {
+            (x..x + 1, y..y + opcode.n as usize)
+        };
+        // Every pixel in a byte shares it's color, so setting it once for every
+        // byte is enough
+        for row in rows {
+            for column in columns.clone().step_by(ZONE_WIDTH) {
+                self.screen.set_foreground(column, row, color);
+            }
+        }
+        Ok(())
+    }
+
+    /// Opcode: `exf2`
+    ///
+    /// Explanation: Skips the next instruction if the key stored in register
+    /// x is pressed on the second keypad.
+    fn skp2(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        if self.is_second_key_pressed(self.registers[opcode.x as usize]) {
+            self.program_counter += 2;
+        }
+        Ok(())
+    }
+
+    /// Opcode: `exf5`
+    ///
+    /// Explanation: Skips the next instruction if the key stored in register
+    /// x isn't pressed on the second keypad.
+    fn skpn2(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        if !self.is_second_key_pressed(self.registers[opcode.x as usize]) {
+            self.program_counter += 2;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+//! This module contains the commands that other programs can drive the rom
+//! with through the remote control, which the debugger's command line runs
+//! too. Each command is a line, and numbers are in hex, with or without a
+//! `0x`:
+//!
+//! ```text
+//! pause                    stops running the rom
+//! resume                   starts running it again
+//! step [count]             pauses, and runs that many instructions, 1 if
+//!                          there isn't a count, answering with where it's up to
+//! frame [count]            pauses, and runs that many whole frames, 1 if
+//!                          there isn't a count, once the run loop gets to them
+//! registers                answers with the program counter, the index, the
+//!                          timers, and the registers
+//! peek <address> [length]  answers with the bytes of memory there, 1 if there
+//!                          isn't a length
+//! poke <address> <byte>..  writes the bytes into memory there
+//! press <key>              holds a key down until it's released
+//! release <key>            lets go of a key
+//! screenshot <file>        saves the screen, the same as the screenshot key
+//! coverage <file>          saves the report of which instructions have ran,
+//!                          the same as `--coverage` does once the rom stops
+//! cheat <cheat>            adds a cheat, like `cheat freeze 2f0 03 lives`,
+//!                          which gets saved with the rom's other cheats
+//! state <file>             saves the whole state of the interpreter, which
+//!                          `chip_8 diff` compares, with `--features serde`
+//! quit                     closes the app
+//! ```
+//!
+//! Each one answers with a line starting with `ok` and whatever it asked for,
+//! or with `error` and why it couldn't be done:
+//!
+//! ```text
+//! > peek 200 4
+//! < ok 00 e0 a2 2a
+//! > step
+//! < ok 202
+//! ```
+
+use crate::{
+    config::Config,
+    movie::{pressed_keys, set_keys},
+    palette::Palette,
+    rom, screenshot, states,
+};
+use chip_8::{cheats::Cheat, Chip8};
+use std::{convert::TryFrom, path::Path};
+
+/// Runs the commands, and keeps what they leave behind between them
+pub struct Commands {
+    /// The keys that were pressed with `press`, as a mask where bit `n` is key
+    /// `n`, these stay down until they are released even when the frontend
+    /// lets go of every key
+    held_keys: u16,
+    /// Set by `quit`, so the app closes
+    quit: bool,
+    screenshot_scale: u32,
+    palette: Palette,
+}
+
+impl Commands {
+    /// Creates the commands, with screenshots saved the way the options say
+    pub fn new(config: &Config) -> Self {
+        Commands {
+            held_keys: 0,
+            quit: false,
+            screenshot_scale: config.screenshot_scale,
+            palette: config.palette,
+        }
+    }
+
+    /// Runs a command, and returns what it answers with, which starts with
+    /// `ok` and whatever it asked for, or `error` and why it couldn't be done
+    pub fn run(&mut self, chip8: &mut Chip8, line: &str) -> String {
+        match self.command(chip8, line) {
+            Ok(answer) if answer.is_empty() => String::from("ok"),
+            Ok(answer) => format!("ok {}", answer),
+            Err(error) => format!("error {}", error),
+        }
+    }
+
+    /// Whether `quit` has been ran, so the app should close
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Presses the keys that are held down with `press` again, for the start
+    /// of every frame, in case the frontend let go of them
+    pub fn hold_keys(&self, chip8: &mut Chip8) {
+        if self.held_keys != 0 {
+            set_keys(chip8, pressed_keys(chip8) | self.held_keys);
+        }
+    }
+
+    /// Runs a command, and returns what it answers with, without the `ok`
+    fn command(&mut self, chip8: &mut Chip8, line: &str) -> Result<String, String> {
+        let mut parts = line.split_whitespace();
+        let name = parts.next().unwrap_or_default();
+        let arguments: Vec<&str> = parts.collect();
+        match (name, arguments.as_slice()) {
+            ("pause", []) => {
+                chip8.set_paused(true);
+                chip8.request_redraw();
+                Ok(String::new())
+            }
+            ("resume", []) => {
+                chip8.set_paused(false);
+                Ok(String::new())
+            }
+            ("step", arguments) if arguments.len() <= 1 => {
+                let count = match arguments.first() {
+                    Some(count) => parse_number(count)?,
+                    None => 1,
+                };
+                chip8.set_paused(true);
+                chip8.request_redraw();
+                for _ in 0..count {
+                    chip8.clock().map_err(|error| error.to_string())?;
+                }
+                Ok(format!("{:03x}", chip8.program_counter))
+            }
+            ("frame", arguments) if arguments.len() <= 1 => {
+                let count = match arguments.first() {
+                    Some(count) => parse_number(count)?,
+                    None => 1,
+                };
+                chip8.set_paused(true);
+                for _ in 0..count {
+                    chip8.step_frame();
+                }
+                Ok(String::new())
+            }
+            ("registers", []) => {
+                let registers: Vec<String> = chip8
+                    .registers
+                    .iter()
+                    .map(|register| format!("{:02x}", register))
+                    .collect();
+                Ok(format!(
+                    "pc {:03x} i {:03x} dt {:02x} st {:02x} v {}",
+                    chip8.program_counter,
+                    chip8.index,
+                    chip8.delay,
+                    chip8.sound,
+                    registers.join(" ")
+                ))
+            }
+            ("peek", [address]) => peek(chip8, address, "1"),
+            ("peek", [address, length]) => peek(chip8, address, length),
+            ("poke", [address, bytes @ ..]) if !bytes.is_empty() => {
+                let address = parse_number(address)?;
+                let bytes = bytes
+                    .iter()
+                    .map(|byte| {
+                        parse_number(byte).and_then(|byte| {
+                            u8::try_from(byte).map_err(|_| format!("{:x} isn't a byte", byte))
+                        })
+                    })
+                    .collect::<Result<Vec<u8>, String>>()?;
+                let memory = chip8
+                    .memory
+                    .get_mut(address..address + bytes.len())
+                    .ok_or_else(|| String::from("that goes past the end of memory"))?;
+                memory.copy_from_slice(&bytes);
+                Ok(String::new())
+            }
+            ("press", [key]) => {
+                let key = parse_key(key)?;
+                self.held_keys |= 1 << key;
+                chip8.press_key(key);
+                Ok(String::new())
+            }
+            ("release", [key]) => {
+                let key = parse_key(key)?;
+                self.held_keys &= !(1 << key);
+                chip8.release_key(key);
+                Ok(String::new())
+            }
+            ("screenshot", [path]) => {
+                screenshot::save(chip8, Path::new(path), self.screenshot_scale, self.palette)
+                    .map_err(|error| error.to_string())?;
+                Ok(String::new())
+            }
+            ("coverage", [path]) => {
+                rom::save_coverage(chip8, Path::new(path)).map_err(|error| error.to_string())?;
+                Ok(String::new())
+            }
+            ("cheat", arguments) if !arguments.is_empty() => {
+                chip8.add_cheat(arguments.join(" ").parse::<Cheat>()?);
+                Ok(String::new())
+            }
+            ("state", [path]) => {
+                states::save(chip8, Path::new(path)).map_err(|error| error.to_string())?;
+                Ok(String::new())
+            }
+            ("quit", []) => {
+                self.quit = true;
+                Ok(String::new())
+            }
+            _ => Err(format!("{} isn't a command", line)),
+        }
+    }
+}
+
+/// Answers with the bytes of memory at the address
+fn peek(chip8: &Chip8, address: &str, length: &str) -> Result<String, String> {
+    let address = parse_number(address)?;
+    let length = parse_number(length)?;
+    let bytes = chip8
+        .memory
+        .get(address..address + length)
+        .ok_or_else(|| String::from("that goes past the end of memory"))?;
+    let bytes: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(bytes.join(" "))
+}
+
+/// Parses a number written in hex, with or without a `0x` in front of it
+fn parse_number(number: &str) -> Result<usize, String> {
+    let digits = number.strip_prefix("0x").unwrap_or(number);
+    usize::from_str_radix(digits, 16).map_err(|_| format!("{} isn't a hex number", number))
+}
+
+/// Parses a key on the hex keypad
+fn parse_key(key: &str) -> Result<u8, String> {
+    parse_number(key)
+        .ok()
+        .filter(|key| *key < 16)
+        .map(|key| key as u8)
+        .ok_or_else(|| format!("{} isn't a key from 0 to f", key))
+}
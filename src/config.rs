@@ -0,0 +1,468 @@
+//! This module contains the options that the app can be started with, which are
+//! read from the command line arguments
+
+#[cfg(feature = "terminal")]
+use crate::renderer::{Renderer, Scale};
+use crate::{
+    database::Database,
+    keymap::Keymap,
+    logging,
+    movie::Branch,
+    palette::Palette,
+    phosphor::{Filter, DEFAULT_FRAMES},
+    rotation::Rotation,
+};
+use chip_8::{
+    audio::{Buzzer, Waveform, MAX_FREQUENCY, MIN_FREQUENCY},
+    bounds::{IndexPolicy, ProgramCounterPolicy},
+    profile::Profile,
+    protection::WriteProtection,
+    Quirks,
+};
+use std::{
+    io::{Error, ErrorKind},
+    path::PathBuf,
+};
+use tracing::Level;
+
+/// The extensions of Chip-8 that can be turned on for roms that need them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extension {
+    /// MEGA-CHIP, with it's 256x192 screen in color
+    MegaChip,
+    /// CHIP-8X, with it's colors and second keypad
+    Chip8X,
+    /// Hi-res Chip-8, with it's 64x64 screen, which roms that start with
+    /// `1260` get without asking for it
+    HiRes,
+}
+
+/// The different frontends that the interpreter can be ran in
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FrontendKind {
+    /// The terminal, which is the default, needs the `terminal` feature
+    Terminal,
+    /// A window drawn with SDL2, needs the `sdl` feature
+    Sdl,
+    /// A window drawn with the GPU, needs the `gpu` feature
+    Gpu,
+}
+
+impl Default for FrontendKind {
+    /// The terminal, or the window that was built in if the terminal wasn't
+    fn default() -> Self {
+        if cfg!(feature = "terminal") {
+            FrontendKind::Terminal
+        } else if cfg!(feature = "sdl") {
+            FrontendKind::Sdl
+        } else if cfg!(feature = "gpu") {
+            FrontendKind::Gpu
+        } else {
+            FrontendKind::Terminal
+        }
+    }
+}
+
+/// All of the options that the app was started with
+pub struct Config {
+    /// Which frontend to run the interpreter in
+    pub frontend: FrontendKind,
+    /// How the terminal draws the screen, this can also be changed while running
+    #[cfg(feature = "terminal")]
+    pub renderer: Renderer,
+    /// How much bigger the terminal draws the screen when it has room to
+    #[cfg(feature = "terminal")]
+    pub scale: Scale,
+    /// The colors that the screen is drawn with, this can also be changed while running
+    pub palette: Palette,
+    /// Which keys on the keyboard press the keys on the hex keypad
+    pub keymap: Keymap,
+    /// The rom to run, if there isn't one the terminal shows a menu to pick one
+    pub rom: Option<PathBuf>,
+    /// The directory that the menu lists the roms from
+    pub roms_dir: PathBuf,
+    /// Starts the rom that was played last, instead of showing the menu
+    pub resume: bool,
+    /// The seed for the `rnd` instruction, so that a run can be repeated
+    /// exactly, a random one gets picked if there isn't one
+    pub seed: Option<u64>,
+    /// The file to save a movie of the keys that get pressed to
+    pub record: Option<PathBuf>,
+    /// The movie file to play the keys back from, instead of the user's keys
+    pub playback: Option<PathBuf>,
+    /// When to stop playing the movie back and let the user take over, with
+    /// `record` saving what they do after the part that was played
+    pub branch: Option<Branch>,
+    /// The address to wait for a peer on, and send the session to
+    pub host: Option<String>,
+    /// The address of the host to mirror the session of
+    pub join: Option<String>,
+    /// The TCP address, or `unix:` and the path of a Unix socket, that other
+    /// programs can control the rom through
+    pub remote: Option<String>,
+    /// The rhai script to hook into the interpreter, needs the `scripting` feature
+    pub script: Option<PathBuf>,
+    /// Where to write the report of which instructions ran once the rom stops
+    pub coverage: Option<PathBuf>,
+    /// The file with the names of the addresses in the rom, otherwise the
+    /// `.sym` file next to the rom is used if there is one
+    pub symbols: Option<PathBuf>,
+    /// The file that says which gamepad buttons press which keys, needs the
+    /// `gamepad` feature
+    pub gamepad: Option<PathBuf>,
+    /// Which version of the instructions that have changed over the years to
+    /// run, if there aren't any then the ones from the database are used
+    pub quirks: Option<Quirks>,
+    /// The quirks and speed of an interpreter, which `quirks`, `speed`, and
+    /// `vip_timing` go on top of
+    pub profile: Option<Profile>,
+    /// Wraps sprites around the edges of the screen, on top of whichever
+    /// quirks get used
+    pub wrap: bool,
+    /// Sets register f when `fx1e` takes the index past `0xfff`, on top of
+    /// whichever quirks get used
+    pub index_carry: bool,
+    /// How many instructions run in a second, if this isn't set then the
+    /// speed from the database is used
+    pub speed: Option<u64>,
+    /// Runs the instructions as fast as they ran on the COSMAC VIP, instead of
+    /// at a speed
+    pub vip_timing: bool,
+    /// The roms that are known about, with the options they need
+    pub database: Database,
+    /// Pauses the rom when it runs into something that isn't an instruction,
+    /// and shows it in the debugger pane
+    pub break_on_unknown: bool,
+    /// What happens when the rom writes over the fonts below `0x200`
+    pub write_protection: WriteProtection,
+    /// What happens when the program counter goes past the end of memory, or
+    /// below `0x200`
+    pub program_counter_policy: ProgramCounterPolicy,
+    /// What happens when an instruction goes past the end of memory from the
+    /// index
+    pub index_policy: IndexPolicy,
+    /// Turns on the MEGA-CHIP or CHIP-8X instructions, for roms that are
+    /// written for them
+    pub extension: Option<Extension>,
+    /// Where roms get loaded into memory, and start running from
+    pub load_address: usize,
+    /// How many pixels of the image each pixel of the screen is in a screenshot
+    pub screenshot_scale: u32,
+    /// Rings the terminal's bell whenever the tone starts, on top of the
+    /// border getting thicker while it plays
+    pub bell: bool,
+    /// What hides the flickering of the screen, if anything, which goes
+    /// before the database's
+    pub filter: Option<Filter>,
+    /// How far the screen is turned, which goes before the database's
+    pub rotation: Option<Rotation>,
+    /// Turns the keys for up, down, left, and right along with the screen
+    pub rotate_keys: bool,
+    /// What the tone sounds like in the frontends that can play one
+    pub buzzer: Buzzer,
+    /// Starts out with the status bar shown under the screen
+    pub status: bool,
+    /// Starts out with the keypad shown next to the screen
+    pub keypad: bool,
+    /// A file with another font in it, see `chip_8::font` for what goes in it
+    pub font: Option<PathBuf>,
+    /// How much gets written to the log file, nothing gets logged without it
+    pub log_level: Option<Level>,
+    /// The file that the log gets written to
+    pub log_file: PathBuf,
+}
+
+impl Config {
+    /// Reads the options out of the arguments, not including the program's name
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Config, Error> {
+        let mut config = Config {
+            frontend: FrontendKind::default(),
+            #[cfg(feature = "terminal")]
+            renderer: Renderer::Block,
+            #[cfg(feature = "terminal")]
+            scale: Scale::Auto,
+            palette: Palette::default(),
+            keymap: Keymap::default(),
+            rom: None,
+            roms_dir: PathBuf::from("roms"),
+            resume: false,
+            seed: None,
+            record: None,
+            playback: None,
+            branch: None,
+            host: None,
+            join: None,
+            remote: None,
+            script: None,
+            coverage: None,
+            symbols: None,
+            gamepad: None,
+            quirks: None,
+            profile: None,
+            wrap: false,
+            index_carry: false,
+            speed: None,
+            vip_timing: false,
+            database: Database::default(),
+            break_on_unknown: false,
+            write_protection: WriteProtection::Off,
+            program_counter_policy: ProgramCounterPolicy::Trap,
+            index_policy: IndexPolicy::Trap,
+            extension: None,
+            load_address: 0x200,
+            screenshot_scale: 8,
+            bell: false,
+            buzzer: Buzzer::default(),
+            filter: None,
+            rotation: None,
+            rotate_keys: false,
+            status: false,
+            keypad: false,
+            font: None,
+            log_level: None,
+            log_file: PathBuf::from("chip_8.log"),
+        };
+
+        // The database is read once all of the options have been, since it can
+        // be in the roms directory
+        let mut database = None;
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--sdl" => config.frontend = FrontendKind::Sdl,
+                "--gpu" => config.frontend = FrontendKind::Gpu,
+                #[cfg(feature = "terminal")]
+                "--renderer" => {
+                    let name = value(&arg, args.next())?;
+                    config.renderer = Renderer::from_name(&name)
+                        .ok_or_else(|| invalid(format!("there is no renderer called {}", name)))?;
+                }
+                #[cfg(feature = "terminal")]
+                "--scale" => {
+                    let name = value(&arg, args.next())?;
+                    config.scale = Scale::from_name(&name).ok_or_else(|| {
+                        invalid(format!("{} isn't a scale like auto or 2x1", name))
+                    })?;
+                }
+                "--theme" => {
+                    let name = value(&arg, args.next())?;
+                    config.palette = Palette::from_theme(&name)
+                        .ok_or_else(|| invalid(format!("there is no theme called {}", name)))?;
+                }
+                // Picking a color by hand means the palette isn't one of the
+                // themes anymore
+                "--fg" => {
+                    config.palette.on = parse_color(&value(&arg, args.next())?)?;
+                    config.palette.name = "custom";
+                }
+                "--bg" => {
+                    config.palette.off = parse_color(&value(&arg, args.next())?)?;
+                    config.palette.name = "custom";
+                }
+                "--keys" => config.keymap = Keymap::from_name(&value(&arg, args.next())?)?,
+                "--seed" => {
+                    let seed = value(&arg, args.next())?;
+                    config.seed = Some(
+                        seed.parse()
+                            .map_err(|_| invalid(format!("{} isn't a seed like 1234", seed)))?,
+                    );
+                }
+                "--record" => config.record = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--playback" => config.playback = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--branch-at" => {
+                    let branch = value(&arg, args.next())?;
+                    config.branch = Some(Branch::from_name(&branch).ok_or_else(|| {
+                        invalid(format!("{} isn't a frame like 600, or key", branch))
+                    })?);
+                }
+                "--host" => config.host = Some(value(&arg, args.next())?),
+                "--join" => config.join = Some(value(&arg, args.next())?),
+                "--remote" => config.remote = Some(value(&arg, args.next())?),
+                "--script" => config.script = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--coverage" => config.coverage = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--symbols" => config.symbols = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--gamepad" => config.gamepad = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--log-level" => {
+                    let name = value(&arg, args.next())?;
+                    config.log_level = Some(logging::level_from_name(&name).ok_or_else(|| {
+                        invalid(format!("{} isn't a level like info or trace", name))
+                    })?);
+                }
+                "--log-file" => config.log_file = PathBuf::from(value(&arg, args.next())?),
+                "--font" => config.font = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--quirks" => {
+                    let name = value(&arg, args.next())?;
+                    config.quirks =
+                        Some(Quirks::from_name(&name).ok_or_else(|| {
+                            invalid(format!("there are no quirks called {}", name))
+                        })?);
+                }
+                "--speed" => {
+                    let speed = value(&arg, args.next())?;
+                    config.speed = Some(
+                        speed
+                            .parse()
+                            .ok()
+                            .filter(|speed| *speed > 0)
+                            .ok_or_else(|| invalid(format!("{} isn't a speed like 1000", speed)))?,
+                    );
+                }
+                "--database" => database = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--screenshot-scale" => {
+                    let scale = value(&arg, args.next())?;
+                    config.screenshot_scale = scale
+                        .parse()
+                        .ok()
+                        .filter(|scale| *scale > 0)
+                        .ok_or_else(|| invalid(format!("{} isn't a scale like 8", scale)))?;
+                }
+                "--profile" => {
+                    let name = value(&arg, args.next())?;
+                    config.profile = Some(Profile::from_name(&name).ok_or_else(|| {
+                        invalid(format!("{} isn't vip, chip48, schip, or xochip", name))
+                    })?);
+                }
+                "--vip-timing" => config.vip_timing = true,
+                "--wrap" => config.wrap = true,
+                "--index-carry" => config.index_carry = true,
+                "--break-on-unknown" => config.break_on_unknown = true,
+                "--pc-bounds" => {
+                    let name = value(&arg, args.next())?;
+                    config.program_counter_policy = ProgramCounterPolicy::from_name(&name)
+                        .ok_or_else(|| invalid(format!("{} isn't trap, wrap, or halt", name)))?;
+                }
+                "--index-bounds" => {
+                    let name = value(&arg, args.next())?;
+                    config.index_policy = IndexPolicy::from_name(&name)
+                        .ok_or_else(|| invalid(format!("{} isn't trap, wrap, or clamp", name)))?;
+                }
+                "--write-protect" => {
+                    let name = value(&arg, args.next())?;
+                    config.write_protection = WriteProtection::from_name(&name)
+                        .ok_or_else(|| invalid(format!("{} isn't off, ignore, or trap", name)))?;
+                }
+                "--bell" => config.bell = true,
+                // The number of frames is optional
+                "--ghosting" => {
+                    let frames = match args.peek().and_then(|frames| frames.parse().ok()) {
+                        Some(frames) => {
+                            args.next();
+                            frames
+                        }
+                        None => DEFAULT_FRAMES,
+                    };
+                    // No frames to fade out over is the same as not fading
+                    config.filter = (frames > 0).then_some(Filter::Ghosting(frames));
+                }
+                "--blend-frames" => config.filter = Some(Filter::Blend),
+                "--rotate" => {
+                    let degrees = value(&arg, args.next())?;
+                    config.rotation =
+                        Some(Rotation::from_name(&degrees).ok_or_else(|| {
+                            invalid(format!("{} isn't 0, 90, 180, or 270", degrees))
+                        })?);
+                }
+                "--rotate-keys" => config.rotate_keys = true,
+                "--waveform" => {
+                    let name = value(&arg, args.next())?;
+                    config.buzzer.waveform = Waveform::from_name(&name).ok_or_else(|| {
+                        invalid(format!("{} isn't square, sine, or triangle", name))
+                    })?;
+                }
+                "--tone" => {
+                    let tone = value(&arg, args.next())?;
+                    config.buzzer.frequency = tone
+                        .parse()
+                        .ok()
+                        .filter(|tone| (MIN_FREQUENCY..=MAX_FREQUENCY).contains(tone))
+                        .ok_or_else(|| invalid(format!("{} isn't a frequency like 440", tone)))?;
+                }
+                "--volume" => {
+                    let volume = value(&arg, args.next())?;
+                    config.buzzer.volume = volume
+                        .parse()
+                        .ok()
+                        .filter(|volume| (0.0..=100.0).contains(volume))
+                        .map(|volume: f32| volume / 100.0)
+                        .ok_or_else(|| {
+                            invalid(format!("{} isn't a volume from 0 to 100", volume))
+                        })?;
+                }
+                "--status" => config.status = true,
+                "--keypad" => config.keypad = true,
+                "--megachip" => config.extension = Some(Extension::MegaChip),
+                "--chip8x" => config.extension = Some(Extension::Chip8X),
+                "--hires" => config.extension = Some(Extension::HiRes),
+                "--load-address" => {
+                    config.load_address = parse_address(&value(&arg, args.next())?)?
+                }
+                "--resume" => config.resume = true,
+                "--roms-dir" => config.roms_dir = PathBuf::from(value(&arg, args.next())?),
+                // Anything that isn't an option is the rom to run
+                _ if !arg.starts_with("--") && config.rom.is_none() => {
+                    config.rom = Some(PathBuf::from(arg))
+                }
+                _ => return Err(invalid(format!("{} isn't an option", arg))),
+            }
+        }
+
+        config.database = Database::load(database.as_deref(), &config.roms_dir)?;
+
+        if config.branch.is_some() && config.playback.is_none() {
+            return Err(invalid(String::from(
+                "--branch-at needs a movie to branch from with --playback",
+            )));
+        }
+
+        // Each of these needs to be the only thing deciding which keys are
+        // pressed, other than recording a movie that branches off of another
+        let sessions = [
+            config.record.is_some() && config.playback.is_none(),
+            config.playback.is_some(),
+            config.host.is_some(),
+            config.join.is_some(),
+        ];
+        if sessions.iter().filter(|used| **used).count() > 1 {
+            return Err(invalid(String::from(
+                "only one of --record, --playback, --host, and --join can be used at once",
+            )));
+        }
+        Ok(config)
+    }
+}
+
+/// Makes sure that an option that needs a value was given one
+fn value(arg: &str, value: Option<String>) -> Result<String, Error> {
+    value.ok_or_else(|| invalid(format!("{} needs a value after it", arg)))
+}
+
+/// Parses an address in memory written in hex, with or without a `0x` in front
+/// of it, or `eti` for where the ETI-660 loaded programs
+fn parse_address(address: &str) -> Result<usize, Error> {
+    if address == "eti" {
+        return Ok(0x600);
+    }
+    let digits = address.strip_prefix("0x").unwrap_or(address);
+    usize::from_str_radix(digits, 16)
+        .ok()
+        // The rom needs at least one instruction worth of memory after it
+        .filter(|address| *address < 0xffe)
+        .ok_or_else(|| invalid(format!("{} isn't an address like 0x600", address)))
+}
+
+/// Parses a color written as `rrggbb`, with or without a `#` in front of it
+pub fn parse_color(hex: &str) -> Result<[u8; 3], Error> {
+    let digits = hex.trim_start_matches('#');
+    let value = if digits.len() == 6 {
+        u32::from_str_radix(digits, 16).ok()
+    } else {
+        None
+    };
+    let value = value.ok_or_else(|| invalid(format!("{} isn't a color like #ff8800", hex)))?;
+    Ok([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+/// Creates the error for an option that doesn't make sense
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
@@ -0,0 +1,178 @@
+//! This module keeps track of which addresses have been ran as instructions,
+//! and how many times each one has, for finding the parts of a rom that are
+//! code when taking it apart, and for checking that a test rom gets to all of
+//! the code it has.
+//!
+//! Only the address that each instruction starts at gets counted, so
+//! `covers` is the one to ask whether a byte was part of an instruction that
+//! ran. The instructions that the JIT compiles run without the interpreter, so
+//! they don't get counted.
+//!
+//! ```rust
+//! use chip_8::Chip8;
+//!
+//! let mut chip8 = Chip8::with_seed(0);
+//! // `6005` sets register 0 to 5, `1202` then jumps back to itself forever
+//! chip8.load(&[0x60, 0x05, 0x12, 0x02]).unwrap();
+//! chip8.enable_coverage();
+//! chip8.frame(4).unwrap();
+//! let coverage = chip8.coverage().unwrap();
+//! assert_eq!(coverage.count(0x200), 1);
+//! assert_eq!(coverage.count(0x202), 3);
+//! assert!(coverage.covers(0x203));
+//! assert_eq!(coverage.ranges(), vec![0x200..0x204]);
+//! ```
+
+use crate::{analysis::ByteKind, chip8::Chip8};
+use alloc::{string::String, vec, vec::Vec};
+use core::{fmt::Write, ops::Range};
+
+/// How many levels `Coverage::heat` has, not counting 0 for nothing having ran
+pub const HEAT_LEVELS: u8 = 4;
+
+/// How many times the instruction at each address of memory has ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    counts: Vec<u32>,
+}
+
+impl Coverage {
+    /// Returns how many times the instruction at the address has ran, which
+    /// is 0 for anything outside of memory
+    pub fn count(&self, address: usize) -> u32 {
+        self.counts.get(address).copied().unwrap_or(0)
+    }
+
+    /// Returns whether the byte was part of an instruction that ran, which is
+    /// either the first or the second byte of it
+    pub fn covers(&self, address: usize) -> bool {
+        self.count(address) > 0
+            || address
+                .checked_sub(1)
+                .is_some_and(|before| self.count(before) > 0)
+    }
+
+    /// Returns every address that an instruction has ran from, in order
+    pub fn addresses(&self) -> impl Iterator<Item = usize> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(address, _)| address)
+    }
+
+    /// Returns how many times the instruction that ran the most has ran
+    pub fn hottest(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Returns the bytes of every instruction that ran, with the instructions
+    /// that are next to each other joined together
+    pub fn ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for address in self.addresses() {
+            match ranges.last_mut() {
+                Some(range) if address <= range.end => range.end = range.end.max(address + 2),
+                _ => ranges.push(address..address + 2),
+            }
+        }
+        ranges
+    }
+
+    /// Returns how hot the addresses are, from 0 when nothing in them ran, up
+    /// to `HEAT_LEVELS` for the instruction that ran the most. It goes by how
+    /// many digits the count has compared to the hottest, since a rom's main
+    /// loop runs so many more times than everything else.
+    pub fn heat(&self, addresses: Range<usize>) -> u8 {
+        let count = addresses
+            .map(|address| self.count(address))
+            .max()
+            .unwrap_or(0);
+        if count == 0 {
+            return 0;
+        }
+        // The number of binary digits, rounded up to the next level
+        let digits = |count: u32| 32 - count.leading_zeros();
+        let hottest = digits(self.hottest());
+        let level = (digits(count) * HEAT_LEVELS as u32).div_ceil(hottest);
+        level.clamp(1, HEAT_LEVELS as u32) as u8
+    }
+
+    /// Returns the report of what ran, with the ranges of addresses, how many
+    /// times each instruction ran, and the code that the analysis found
+    /// between `start` and `end` that hasn't ran, as text
+    pub fn report(&self, chip8: &Chip8, start: usize, end: usize) -> String {
+        let mut report = String::new();
+        let ranges = self.ranges();
+        let bytes: usize = ranges.iter().map(|range| range.len()).sum();
+        let _ = writeln!(
+            report,
+            "# {} instructions ran, {} bytes",
+            self.addresses().count(),
+            bytes
+        );
+
+        let _ = writeln!(report, "\n# The addresses that ran");
+        for range in &ranges {
+            let _ = writeln!(report, "{:03x}-{:03x}", range.start, range.end - 1);
+        }
+
+        // Code that the analysis can get to from the start, but that hasn't
+        // ran, is what a test rom hasn't tested yet
+        let analysis = chip8.analyze(start, end);
+        let missed: Vec<usize> = (start..analysis.end())
+            .step_by(2)
+            .filter(|address| analysis.kind(*address) == ByteKind::Code && !self.covers(*address))
+            .collect();
+        let _ = writeln!(report, "\n# The code that hasn't ran");
+        for address in missed {
+            let _ = writeln!(report, "{:03x}  {}", address, chip8.disassemble(address));
+        }
+
+        let _ = writeln!(report, "\n# How many times each instruction ran");
+        for address in self.addresses() {
+            let _ = writeln!(
+                report,
+                "{:03x}  {:<10}  {}",
+                address,
+                self.count(address),
+                chip8.disassemble(address)
+            );
+        }
+        report
+    }
+
+    /// Counts the instruction at the address
+    fn record(&mut self, address: usize) {
+        if let Some(count) = self.counts.get_mut(address) {
+            *count = count.saturating_add(1);
+        }
+    }
+}
+
+impl Chip8 {
+    /// Starts counting the instructions as they run, anything that was counted
+    /// before is forgotten
+    pub fn enable_coverage(&mut self) {
+        self.coverage = Some(Coverage {
+            counts: vec![0; self.memory.len()],
+        });
+    }
+
+    /// Stops counting the instructions, and forgets the ones that were counted
+    pub fn disable_coverage(&mut self) {
+        self.coverage = None;
+    }
+
+    /// Returns what has ran, if it is being counted
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Counts the instruction that is about to run at the address
+    pub(crate) fn record_coverage(&mut self, address: usize) {
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record(address);
+        }
+    }
+}
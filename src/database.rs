@@ -0,0 +1,192 @@
+//! This module contains the database of known roms, which says what each one
+//! is called, who made it, and how it needs to be ran, so that a rom that was
+//! made for the COSMAC VIP or SUPER-CHIP runs right without having to pick the
+//! options by hand.
+//!
+//! The roms are looked up by the SHA-1 hash of the file, which is what other
+//! Chip-8 databases use, so their hashes can be copied over. The database is
+//! read from `--database`, or from `database.txt` in the roms directory if it
+//! is there. Every line is a rom, with the hash, title, author, quirks,
+//! instructions a second, profile, what hides its flickering (`ghosting` or
+//! `blend`, see the `phosphor` module), and how many degrees the screen is
+//! turned (with `keys` after it to turn the keys too, see the `rotation`
+//! module) split up by `|`, anything after a `#` is ignored, and the fields
+//! on the end can be left off or empty. The quirks and the speed go on top of
+//! the profile's:
+//! ```text
+//! # hash                                   | title    | author | quirks | speed | profile | filter | rotation
+//! 0123456789abcdef0123456789abcdef01234567 | Some Rom | Person | schip  | 2000  |         |        |
+//! 89abcdef0123456789abcdef0123456789abcdef | Old Rom  |        |        |       | vip     | blend  |
+//! 456789abcdef0123456789abcdef0123456789ab | Tall Rom |        |        |       |         |        | 90 keys
+//! ```
+
+use crate::{phosphor::Filter, rotation::Rotation};
+use chip_8::{profile::Profile, Quirks};
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+/// What the database knows about a rom
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// The SHA-1 hash of the rom, in lowercase hex
+    pub hash: String,
+    pub title: String,
+    pub author: Option<String>,
+    /// The quirks the rom was written for
+    pub quirks: Option<Quirks>,
+    /// How many instructions a second the rom was meant to run at
+    pub speed: Option<u64>,
+    /// The interpreter the rom was written for
+    pub profile: Option<Profile>,
+    /// What hides the rom's flickering
+    pub filter: Option<Filter>,
+    /// How far the rom's screen is turned, and whether the keys are turned
+    /// with it
+    pub rotation: Option<(Rotation, bool)>,
+}
+
+impl Entry {
+    /// The title and author, for showing in the frontend
+    pub fn name(&self) -> String {
+        match &self.author {
+            Some(author) => format!("{} by {}", self.title, author),
+            None => self.title.clone(),
+        }
+    }
+}
+
+/// Every rom that is known about, which is empty without a database file
+#[derive(Debug, Clone, Default)]
+pub struct Database {
+    entries: Vec<Entry>,
+}
+
+impl Database {
+    /// Reads the database from the file if there is one, otherwise from
+    /// `database.txt` in the roms directory, or is empty if neither is there
+    pub fn load(file: Option<&Path>, roms_dir: &Path) -> Result<Database, Error> {
+        let path = match file {
+            Some(path) => path.to_path_buf(),
+            None => roms_dir.join("database.txt"),
+        };
+        if file.is_none() && !path.exists() {
+            return Ok(Database::default());
+        }
+        Database::parse(&fs::read_to_string(&path)?)
+    }
+
+    /// Reads the roms out of the lines of a database file
+    pub fn parse(text: &str) -> Result<Database, Error> {
+        let mut entries = Vec::new();
+        for line in text.lines() {
+            // Everything after a `#` is a comment
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields = line.split('|').map(str::trim);
+            // There is always at least one field, even in a line without a `|`
+            let hash = fields.next().unwrap_or_default().to_lowercase();
+            if hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid(format!("{} isn't a SHA-1 hash", hash)));
+            }
+            let title = match fields.next() {
+                Some(title) if !title.is_empty() => title.to_string(),
+                _ => return Err(invalid(format!("{} doesn't have a title", hash))),
+            };
+            let author = fields
+                .next()
+                .filter(|author| !author.is_empty())
+                .map(String::from);
+            let quirks = match fields.next().filter(|name| !name.is_empty()) {
+                Some(name) => Some(
+                    Quirks::from_name(name)
+                        .ok_or_else(|| invalid(format!("there are no quirks called {}", name)))?,
+                ),
+                None => None,
+            };
+            let speed = match fields.next().filter(|speed| !speed.is_empty()) {
+                Some(speed) => Some(
+                    speed
+                        .parse()
+                        .ok()
+                        .filter(|speed| *speed > 0)
+                        .ok_or_else(|| invalid(format!("{} isn't a speed like 1000", speed)))?,
+                ),
+                None => None,
+            };
+            let profile = match fields.next().filter(|name| !name.is_empty()) {
+                Some(name) => Some(
+                    Profile::from_name(name)
+                        .ok_or_else(|| invalid(format!("there is no profile called {}", name)))?,
+                ),
+                None => None,
+            };
+            let filter = match fields.next().filter(|name| !name.is_empty()) {
+                Some(name) => Some(
+                    Filter::from_name(name)
+                        .ok_or_else(|| invalid(format!("{} isn't ghosting or blend", name)))?,
+                ),
+                None => None,
+            };
+            let rotation = match fields.next().filter(|rotation| !rotation.is_empty()) {
+                Some(rotation) => {
+                    let mut words = rotation.split_whitespace();
+                    let degrees = words.next().and_then(Rotation::from_name);
+                    let keys = match words.next() {
+                        Some("keys") => Some(true),
+                        Some(_) => None,
+                        None => Some(false),
+                    };
+                    match (degrees, keys, words.next()) {
+                        (Some(degrees), Some(keys), None) => Some((degrees, keys)),
+                        _ => {
+                            return Err(invalid(format!(
+                                "{} isn't 0, 90, 180, or 270, with keys after it or not",
+                                rotation
+                            )))
+                        }
+                    }
+                }
+                None => None,
+            };
+            if fields.next().is_some() {
+                return Err(invalid(format!("{} has too many fields", hash)));
+            }
+            entries.push(Entry {
+                hash,
+                title,
+                author,
+                quirks,
+                speed,
+                profile,
+                filter,
+                rotation,
+            });
+        }
+        Ok(Database { entries })
+    }
+
+    /// Looks up the rom by it's hash
+    pub fn find(&self, rom: &[u8]) -> Option<&Entry> {
+        let hash = sha1_smol::Sha1::from(rom).digest().to_string();
+        self.entries.iter().find(|entry| entry.hash == hash)
+    }
+
+    /// Looks up the rom in the file, a file that can't be read isn't in the
+    /// database
+    pub fn find_file(&self, path: &Path) -> Option<&Entry> {
+        // Nothing needs to be read when there is nothing to find
+        if self.entries.is_empty() {
+            return None;
+        }
+        self.find(&fs::read(path).ok()?)
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,576 @@
+//! This module contains the debugger's panes, which are drawn around the screen
+//! in the terminal to show what is going on inside of the interpreter while a
+//! rom runs
+
+use crate::{cheat_list::CheatList, memory_search::SearchView, sprites::SpriteViewer};
+use chip_8::{
+    breakpoint::{Breakpoint, Condition},
+    chip8::DisassembledInstruction,
+    coverage::HEAT_LEVELS,
+    symbols::Symbols,
+    Chip8,
+};
+use crossterm::event::KeyCode;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+/// How many bytes are shown on each row of the memory view
+const BYTES_PER_ROW: usize = 16;
+/// How many columns the memory view takes up, the 3 digits of the address,
+/// then a space and 2 digits for every byte
+const MEMORY_COLUMNS: usize = 3 + BYTES_PER_ROW * 3;
+/// How many bytes each cell of the coverage view stands for
+const BYTES_PER_CELL: usize = 8;
+/// How many cells are on each row of the coverage view, which makes each row
+/// `0x100` bytes, so all of the usual 4K of memory fits in 16 rows
+const CELLS_PER_ROW: usize = 32;
+/// What the cells of the coverage view are drawn with, from where nothing has
+/// ran up to where the instructions that ran the most are
+const HEAT: [char; HEAT_LEVELS as usize + 1] = ['·', '░', '▒', '▓', '█'];
+/// How many columns the registers view takes up, which goes between the screen
+/// and the code view
+const REGISTERS_COLUMNS: u16 = 14;
+/// How many instructions before the program counter the code view starts at
+const CODE_BEFORE: i32 = 4;
+/// How many instructions can be stepped back through, which are kept for as
+/// long as the panes are open
+pub const JOURNAL_LENGTH: usize = 1000;
+
+/// What the debugger's panes are showing, and where
+pub struct Debugger {
+    /// Whether the panes are shown at all
+    pub is_open: bool,
+    /// The address of the first byte in the memory view, always the start of a row
+    memory_start: usize,
+    /// How many rows of memory there was room for the last time it was drawn
+    memory_rows: usize,
+    /// The hex digits that have been typed so far, while an address to jump to
+    /// is being typed in
+    goto: Option<String>,
+    /// The rom gets paused when any of these are hit
+    breakpoints: Vec<Breakpoint>,
+    /// The condition that has been typed so far, while a breakpoint is being
+    /// typed in
+    typing_breakpoint: Option<String>,
+    /// Why the last condition that was typed in couldn't be used
+    breakpoint_error: Option<String>,
+    /// While stepping over a call or out of a subroutine, the rom runs until
+    /// it gets back down to this many calls deep
+    run_to_depth: Option<usize>,
+    /// Shows the sprites in the rom in place of the memory view, while it is
+    /// there
+    sprites: Option<SpriteViewer>,
+    /// Shows which instructions have ran, and how often, in place of the
+    /// memory view, while it is true
+    coverage: bool,
+    /// Shows the cheats in place of the memory view, while it is there
+    cheats: Option<CheatList>,
+    /// The memory search, which is kept when it isn't shown so the rom can be
+    /// played between searches
+    search: Option<SearchView>,
+    /// Shows the memory search in place of the memory view, while it is true
+    searching: bool,
+    /// The names of the addresses in the rom, which the code and the stack
+    /// are shown with, and which the breakpoints can use
+    symbols: Symbols,
+}
+
+impl Debugger {
+    /// Creates a closed debugger, looking at where roms are loaded, with the
+    /// names of the addresses in the rom
+    pub fn new(symbols: Symbols) -> Self {
+        Debugger {
+            is_open: false,
+            memory_start: 0x200,
+            memory_rows: 16,
+            goto: None,
+            breakpoints: Vec::new(),
+            typing_breakpoint: None,
+            breakpoint_error: None,
+            run_to_depth: None,
+            sprites: None,
+            coverage: false,
+            cheats: None,
+            search: None,
+            searching: false,
+            symbols,
+        }
+    }
+
+    /// Handles a key while the panes are open, and returns whether it was used,
+    /// so that it doesn't also get passed on to the keypad
+    pub fn handle_key(&mut self, key: &KeyCode, chip8: &mut Chip8) -> bool {
+        let memory_size = chip8.memory.len();
+        let last_row = (memory_size - 1) / BYTES_PER_ROW * BYTES_PER_ROW;
+
+        // While a breakpoint is being typed, every key goes to it
+        if let Some(typing) = &mut self.typing_breakpoint {
+            match key {
+                KeyCode::Char(c) if typing.len() < MEMORY_COLUMNS => typing.push(*c),
+                KeyCode::Backspace => {
+                    typing.pop();
+                }
+                // Nothing typed in takes all of the breakpoints away
+                KeyCode::Enter if typing.trim().is_empty() => {
+                    self.breakpoints.clear();
+                    self.breakpoint_error = None;
+                    self.typing_breakpoint = None;
+                }
+                KeyCode::Enter => {
+                    match Condition::with_symbols(typing, &self.symbols) {
+                        Ok(condition) => {
+                            self.breakpoints.push(Breakpoint::new(condition, chip8));
+                            self.breakpoint_error = None;
+                        }
+                        Err(error) => self.breakpoint_error = Some(error.to_string()),
+                    }
+                    self.typing_breakpoint = None;
+                }
+                KeyCode::Esc => self.typing_breakpoint = None,
+                _ => {}
+            }
+            return true;
+        }
+
+        // While an address is being typed, every key goes to it
+        if let Some(goto) = &mut self.goto {
+            match key {
+                KeyCode::Char(c) if c.is_ascii_hexdigit() && goto.len() < 3 => goto.push(*c),
+                KeyCode::Backspace => {
+                    goto.pop();
+                }
+                // The sprite viewer goes to the first sprite from there on
+                KeyCode::Enter => {
+                    if let Ok(address) = usize::from_str_radix(goto, 16) {
+                        match &mut self.sprites {
+                            Some(sprites) => sprites.select_address(address),
+                            None => {
+                                self.memory_start =
+                                    address.min(memory_size - 1) / BYTES_PER_ROW * BYTES_PER_ROW
+                            }
+                        }
+                    }
+                    self.goto = None;
+                }
+                KeyCode::Esc => self.goto = None,
+                _ => {}
+            }
+            return true;
+        }
+
+        // The sprite viewer gets the keys that move around before the memory
+        // view does, and Enter shows the sprite that is picked in memory
+        if let Some(sprites) = &mut self.sprites {
+            match key {
+                KeyCode::Enter => {
+                    if let Some(address) = sprites.selected() {
+                        self.memory_start = address / BYTES_PER_ROW * BYTES_PER_ROW;
+                    }
+                    self.sprites = None;
+                    return true;
+                }
+                // Tab goes on to the coverage view
+                KeyCode::Tab => {
+                    self.sprites = None;
+                    self.coverage = true;
+                    return true;
+                }
+                KeyCode::Esc => {
+                    self.sprites = None;
+                    return true;
+                }
+                _ if sprites.handle_key(key) => return true,
+                _ => {}
+            }
+        }
+
+        // Tab goes on from the coverage view to the cheats
+        if self.coverage && matches!(key, KeyCode::Tab | KeyCode::Esc) {
+            self.coverage = false;
+            if *key == KeyCode::Tab {
+                self.cheats = Some(CheatList::new());
+            }
+            return true;
+        }
+
+        // Then from the cheats to the memory search, once the cheat list has
+        // had it's pick of the keys
+        if let Some(cheats) = &mut self.cheats {
+            if cheats.handle_key(key, chip8) {
+                chip8.request_redraw();
+                return true;
+            }
+            if matches!(key, KeyCode::Tab | KeyCode::Esc) {
+                self.cheats = None;
+                if *key == KeyCode::Tab {
+                    self.search.get_or_insert_with(|| SearchView::new(chip8));
+                    self.searching = true;
+                }
+                return true;
+            }
+        }
+
+        // And from the memory search back to memory
+        if let (true, Some(search)) = (self.searching, &mut self.search) {
+            if search.handle_key(key, chip8) {
+                chip8.request_redraw();
+                return true;
+            }
+            if matches!(key, KeyCode::Tab | KeyCode::Esc) {
+                self.searching = false;
+                return true;
+            }
+        }
+
+        // The coverage view has a lot more bytes on each row
+        let row_bytes = if self.coverage {
+            BYTES_PER_CELL * CELLS_PER_ROW
+        } else {
+            BYTES_PER_ROW
+        };
+        let page = row_bytes * self.memory_rows;
+        match key {
+            // The sprites are looked for again every time, since the rom
+            // could have been changed
+            KeyCode::Tab => self.sprites = Some(SpriteViewer::new(chip8)),
+            KeyCode::Up => self.memory_start = self.memory_start.saturating_sub(row_bytes),
+            KeyCode::Down => self.memory_start = (self.memory_start + row_bytes).min(last_row),
+            KeyCode::PageUp => self.memory_start = self.memory_start.saturating_sub(page),
+            KeyCode::PageDown => self.memory_start = (self.memory_start + page).min(last_row),
+            // Jumps to wherever the index is pointing
+            KeyCode::Home => {
+                self.memory_start = chip8.index.min(memory_size - 1) / BYTES_PER_ROW * BYTES_PER_ROW
+            }
+            KeyCode::F(5) => self.goto = Some(String::new()),
+            KeyCode::F(11) => self.typing_breakpoint = Some(String::new()),
+            KeyCode::F(8) => {
+                self.run_to_depth = None;
+                chip8.set_paused(!chip8.is_paused());
+            }
+            // Stepping pauses the rom first, so it stays on the instruction
+            KeyCode::F(9) => {
+                self.run_to_depth = None;
+                chip8.set_paused(true);
+                chip8.step_back();
+            }
+            // An instruction that can't run is left for the run loop to
+            // show once the rom is resumed
+            KeyCode::F(10) => {
+                self.run_to_depth = None;
+                chip8.set_paused(true);
+                let _ = chip8.clock();
+            }
+            // Steps over a call by running the whole subroutine, anything
+            // else is stepped over the same as F10
+            KeyCode::F(12) => {
+                self.run_to_depth = None;
+                chip8.set_paused(true);
+                // The stack pointer goes up by one for every call that hasn't
+                // returned yet, so it is how deep the rom is
+                let depth = chip8.stack_pointer;
+                let _ = chip8.clock();
+                if chip8.stack_pointer > depth {
+                    self.run_to(depth, chip8);
+                }
+            }
+            // Steps out of the subroutine by running it until it returns
+            KeyCode::End if chip8.stack_pointer > 0 => self.run_to(chip8.stack_pointer - 1, chip8),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Resumes the rom until it returns back down to the depth
+    fn run_to(&mut self, depth: usize, chip8: &mut Chip8) {
+        self.run_to_depth = Some(depth);
+        chip8.set_paused(false);
+    }
+
+    /// Checks the breakpoints right before an instruction runs, and whether
+    /// the rom has gotten to where it was being run to, and returns whether
+    /// it should be paused for either of them
+    pub fn should_pause(&mut self, chip8: &Chip8) -> bool {
+        // Every one of them has to be checked, so they all know whether their
+        // condition was true for the next time
+        let mut hit = false;
+        for breakpoint in self.breakpoints.iter_mut() {
+            hit |= breakpoint.hit(chip8);
+        }
+        // A return that goes past the depth, like from a rom that jumps out of
+        // it's subroutines, still stops it
+        let returned = matches!(self.run_to_depth, Some(depth) if chip8.stack_pointer <= depth);
+        if hit || returned {
+            self.run_to_depth = None;
+        }
+        hit || returned
+    }
+
+    /// Draws the panes into the areas that the app laid out for them, the
+    /// registers and the code go next to the screen in `top`, and the memory
+    /// and the stack go under it in `bottom`
+    pub fn draw(&mut self, chip8: &Chip8, top: Rect, bottom: Rect, buffer: &mut Buffer) {
+        let top = Layout::horizontal([
+            Constraint::Length(REGISTERS_COLUMNS + 2),
+            Constraint::Min(0),
+        ])
+        .split(top);
+        let bottom = Layout::horizontal([
+            Constraint::Length(MEMORY_COLUMNS as u16 + 2),
+            Constraint::Min(0),
+        ])
+        .split(bottom);
+
+        // Paging through memory goes by however many rows there was room for,
+        // which is all of them but the one with the breakpoints
+        self.memory_rows = (bottom[0].height.saturating_sub(3) as usize).max(1);
+
+        Paragraph::new(render_registers(chip8))
+            .block(pane(String::from("registers")))
+            .render(top[0], buffer);
+        let code_rows = top[1].height.saturating_sub(3) as usize;
+        Paragraph::new(render_code(chip8, code_rows, &self.symbols))
+            .block(pane(code_title(chip8)))
+            .render(top[1], buffer);
+        let (title, memory) = self.render_memory(chip8);
+        Paragraph::new(memory)
+            .block(pane(title))
+            .render(bottom[0], buffer);
+        Paragraph::new(render_stack(chip8, &self.symbols))
+            .block(pane(format!("stack  SP = {}", chip8.stack_pointer)))
+            .render(bottom[1], buffer);
+    }
+
+    /// Returns the title and the lines of the memory view, with the byte that
+    /// the index points at drawn with it's colors swapped
+    fn render_memory(&self, chip8: &Chip8) -> (String, Vec<Line<'static>>) {
+        if let (Some(sprites), None) = (&self.sprites, &self.goto) {
+            return (sprites.title(), sprites.render(chip8));
+        }
+        if self.coverage && self.goto.is_none() {
+            return self.render_coverage(chip8);
+        }
+        if let (Some(cheats), None) = (&self.cheats, &self.goto) {
+            return (cheats.title(chip8), cheats.render(chip8, self.memory_rows));
+        }
+        if let (true, Some(search), None) = (self.searching, &self.search, &self.goto) {
+            return (search.title(), search.render(chip8, self.memory_rows));
+        }
+
+        let title = match &self.goto {
+            Some(goto) => format!("go to: {}_", goto),
+            None if chip8.is_journal_on() => format!(
+                "memory  I = {:#05x}  {} steps back",
+                chip8.index,
+                chip8.journal_len()
+            ),
+            None => format!("memory  I = {:#05x}", chip8.index),
+        };
+
+        // The first row shows the breakpoints, it gets cut off at the edge of
+        // the pane
+        let breakpoints = match (&self.typing_breakpoint, &self.breakpoint_error) {
+            (Some(typing), _) => format!("break when: {}_", typing),
+            (None, Some(error)) => format!("can't break: {}", error),
+            (None, None) => match self.breakpoints.as_slice() {
+                [] => String::new(),
+                [breakpoint] => format!("break when: {}", breakpoint.condition),
+                [breakpoint, rest @ ..] => format!(
+                    "break when: {} (and {} more)",
+                    breakpoint.condition,
+                    rest.len()
+                ),
+            },
+        };
+        let mut lines = vec![Line::from(breakpoints)];
+
+        for row in 0..self.memory_rows {
+            let address = self.memory_start + row * BYTES_PER_ROW;
+            if address >= chip8.memory.len() {
+                break;
+            }
+            let mut spans = vec![Span::raw(format!("{:03x}", address))];
+            for (offset, byte) in chip8.memory[address..]
+                .iter()
+                .take(BYTES_PER_ROW)
+                .enumerate()
+            {
+                spans.push(Span::raw(" "));
+                // The bytes of the instructions that have ran are underlined
+                let mut modifier = Modifier::empty();
+                if address + offset == chip8.index {
+                    modifier |= Modifier::REVERSED;
+                }
+                if chip8
+                    .coverage()
+                    .is_some_and(|coverage| coverage.covers(address + offset))
+                {
+                    modifier |= Modifier::UNDERLINED;
+                }
+                spans.push(Span::styled(format!("{:02x}", byte), modifier));
+            }
+            lines.push(Line::from(spans));
+        }
+        (title, lines)
+    }
+
+    /// Returns the title and the lines of the coverage view, where each cell
+    /// is `BYTES_PER_CELL` bytes of memory, drawn hotter the more times the
+    /// instructions in it have ran, with the cell that the program counter is
+    /// in drawn with it's colors swapped
+    fn render_coverage(&self, chip8: &Chip8) -> (String, Vec<Line<'static>>) {
+        let coverage = match chip8.coverage() {
+            Some(coverage) => coverage,
+            None => return (String::from("coverage isn't being counted"), Vec::new()),
+        };
+        let title = format!(
+            "coverage  {} instructions ran",
+            coverage.addresses().count()
+        );
+        let legend = format!(
+            "{} never ran  {} up to {} times",
+            HEAT[0],
+            HEAT[1..].iter().collect::<String>(),
+            coverage.hottest()
+        );
+        let mut lines = vec![Line::from(legend)];
+
+        let row_bytes = BYTES_PER_CELL * CELLS_PER_ROW;
+        let first_row = self.memory_start / row_bytes;
+        for row in first_row..first_row + self.memory_rows {
+            let address = row * row_bytes;
+            if address >= chip8.memory.len() {
+                break;
+            }
+            let mut spans = vec![Span::raw(format!("{:03x} ", address))];
+            for cell in (address..address + row_bytes).step_by(BYTES_PER_CELL) {
+                let cells = cell..cell + BYTES_PER_CELL;
+                let heat = HEAT[coverage.heat(cells.clone()) as usize].to_string();
+                if cells.contains(&chip8.program_counter) {
+                    spans.push(Span::styled(heat, Modifier::REVERSED));
+                } else {
+                    spans.push(Span::raw(heat));
+                }
+            }
+            lines.push(Line::from(spans));
+        }
+        (title, lines)
+    }
+}
+
+/// Creates the box that a pane is drawn in, with its title on the top edge
+fn pane(title: String) -> Block<'static> {
+    Block::default().borders(Borders::ALL).title(title)
+}
+
+/// Returns the lines of the registers view, with the registers in two columns,
+/// and the index, the program counter, the stack pointer, and the timers under
+/// them
+fn render_registers(chip8: &Chip8) -> Vec<Line<'static>> {
+    let mut lines: Vec<Line> = (0..8)
+        .map(|register| {
+            Line::from(format!(
+                "v{:x} {:02x}   v{:x} {:02x}",
+                register,
+                chip8.registers[register],
+                register + 8,
+                chip8.registers[register + 8]
+            ))
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(format!("I  {:03x}", chip8.index)));
+    lines.push(Line::from(format!("PC {:03x}", chip8.program_counter)));
+    lines.push(Line::from(format!("SP {}", chip8.stack_pointer)));
+    lines.push(Line::from(format!("DT {:02x}", chip8.delay)));
+    lines.push(Line::from(format!("ST {:02x}", chip8.sound)));
+    lines
+}
+
+/// Returns the title of the code view, which says whether the rom is paused
+fn code_title(chip8: &Chip8) -> String {
+    let paused = if chip8.is_paused() { "  paused" } else { "" };
+    format!("code  PC = {:#05x}{}", chip8.program_counter, paused)
+}
+
+/// Returns the lines of the code view, with `rows` instructions under the first
+/// line, and the instruction that runs next drawn with it's colors swapped.
+/// Anything past the edges of memory is left blank, and the addresses that
+/// have names get a line with the name above them, like in Octo source.
+fn render_code(chip8: &Chip8, rows: usize, symbols: &Symbols) -> Vec<Line<'static>> {
+    // The first row says when the rom was paused for running into something
+    // that isn't an instruction, or for going out of bounds
+    let unknown = match chip8.unknown_opcode() {
+        Some(instruction) => format!(
+            "can't run {:04x} at {:03x}",
+            instruction.opcode, instruction.address
+        ),
+        None if chip8.is_paused() && !chip8.program_counter_in_bounds() => {
+            String::from("PC is out of bounds")
+        }
+        None => String::new(),
+    };
+    let mut lines = vec![Line::from(unknown)];
+    for relative in -CODE_BEFORE..rows as i32 - CODE_BEFORE {
+        let instruction = chip8.get_relative_instruction(relative);
+        if let Some(name) = instruction
+            .as_ref()
+            .and_then(|instruction| symbols.name(instruction.address))
+        {
+            lines.push(Line::from(format!(": {}", name)));
+        }
+        let line = match instruction {
+            Some(instruction) if relative == 0 => Line::styled(
+                format!(
+                    "{:03x}  {}",
+                    instruction.address,
+                    named(&instruction, symbols)
+                ),
+                Modifier::REVERSED,
+            ),
+            Some(instruction) => Line::from(format!(
+                "{:03x}  {}",
+                instruction.address,
+                named(&instruction, symbols)
+            )),
+            None => Line::from(""),
+        };
+        lines.push(line);
+    }
+    // The names take up rows too, so the ones at the bottom don't fit
+    lines.truncate(rows + 1);
+    lines
+}
+
+/// Returns the instruction as text, with the address that it jumps to, calls,
+/// or points the index at swapped for it's name if it has one
+fn named(instruction: &DisassembledInstruction, symbols: &Symbols) -> String {
+    // Those are the only instructions that take nothing but an address
+    let name = instruction
+        .operands
+        .strip_prefix("0x")
+        .and_then(|digits| usize::from_str_radix(digits, 16).ok())
+        .and_then(|address| symbols.name(address));
+    match name {
+        Some(name) => format!("{} {}", instruction.mnemonic, name),
+        None => instruction.to_string(),
+    }
+}
+
+/// Returns the lines of the stack view, with the most recent call at the top,
+/// and what called it next to each return address
+fn render_stack(chip8: &Chip8, symbols: &Symbols) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from("")];
+    for frame in chip8.call_stack().iter() {
+        let call = match chip8.disassemble_at(frame.call_site) {
+            Some(instruction) => named(&instruction, symbols),
+            None => String::from("nai"),
+        };
+        lines.push(Line::from(format!("{:03x}  {}", frame.call_site, call)));
+    }
+    lines
+}
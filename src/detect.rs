@@ -0,0 +1,86 @@
+//! This module contains the guessing of which interpreter a rom was made for,
+//! for when it isn't in a database that says so. Some instructions only exist
+//! on one of them, and some ways of using the others only make sense on one of
+//! them, so the code of the rom is looked through for those.
+//!
+//! ```rust
+//! use chip_8::{detect, Quirks};
+//!
+//! // `f275` saves registers 0 to 2 to the flags, which only SUPER-CHIP has
+//! let suggestion = detect::suggest(&[0xf2, 0x75, 0x12, 0x02], 0x200).unwrap();
+//! assert_eq!(suggestion.quirks, Quirks::SCHIP);
+//! ```
+
+use crate::{chip8::Chip8, quirks::Quirks};
+use alloc::vec::Vec;
+
+/// The quirks that a rom looks like it needs, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// The interpreter that the rom looks like it was made for
+    pub name: &'static str,
+    pub quirks: Quirks,
+    /// What was found in the rom that gave it away, each one only once
+    pub reasons: Vec<&'static str>,
+}
+
+/// Looks through the code of the rom for the instructions that give away
+/// which interpreter it was made for, there is no suggestion when nothing
+/// gave it away. Only the code that can be reached from the load address is
+/// looked at, so sprites and other data don't get mistaken for instructions.
+pub fn suggest(rom: &[u8], load_address: usize) -> Option<Suggestion> {
+    // The rom only gets loaded to follow the code through it, it never runs
+    let mut chip8 = Chip8::builder().seed(0).load_address(load_address).build();
+    let info = chip8.load(rom).ok()?;
+    let end = (load_address + info.size).min(chip8.memory.len());
+
+    let mut schip = Vec::new();
+    let mut vip = Vec::new();
+    let mut addresses = chip8.reachable(load_address, end);
+    // They are looked at in order, so the reasons come out the same every time
+    addresses.sort_unstable();
+    for address in addresses {
+        let code = (chip8.memory[address] as u16) << 8 | chip8.memory[address + 1] as u16;
+        let (x, y) = ((code >> 8) & 0xf, (code >> 4) & 0xf);
+        let (found, reason) = match code {
+            0x00fe | 0x00ff => (&mut schip, "00fe and 00ff switch the screen size"),
+            0x00fb | 0x00fc => (&mut schip, "00fb and 00fc scroll the screen"),
+            code if code & 0xfff0 == 0x00c0 => (&mut schip, "00cn scrolls the screen"),
+            // Nothing is there to call at 0, it is most likely padding
+            0x0000 | 0x00e0 | 0x00ee | 0x00fd => continue,
+            code if code >> 12 == 0 => (&mut vip, "0nnn calls machine code"),
+            code if code & 0xf00f == 0xd000 => (&mut schip, "dxy0 draws 16x16 sprites"),
+            code if code & 0xf0ff == 0xf030 => (&mut schip, "fx30 uses the big font"),
+            code if code & 0xf0ff == 0xf075 || code & 0xf0ff == 0xf085 => {
+                (&mut schip, "fx75 and fx85 use the flags")
+            }
+            // SUPER-CHIP shifts register x and ignores register y, so a rom
+            // that shifts one register into another expects the VIP
+            code if (code & 0xf00f == 0x8006 || code & 0xf00f == 0x800e) && x != y => {
+                (&mut vip, "8xy6 and 8xye shift register y into register x")
+            }
+            _ => continue,
+        };
+        if !found.contains(&reason) {
+            found.push(reason);
+        }
+    }
+
+    // SUPER-CHIP can run most of what was made for the VIP, but not the other
+    // way around, so anything that only SUPER-CHIP has wins
+    if !schip.is_empty() {
+        Some(Suggestion {
+            name: "SUPER-CHIP",
+            quirks: Quirks::SCHIP,
+            reasons: schip,
+        })
+    } else if !vip.is_empty() {
+        Some(Suggestion {
+            name: "COSMAC VIP",
+            quirks: Quirks::VIP,
+            reasons: vip,
+        })
+    } else {
+        None
+    }
+}
@@ -0,0 +1,126 @@
+//! This module contains the memory-mapped devices, which let the parent
+//! program add virtual peripherals to the interpreter without adding any new
+//! instructions.
+//!
+//! A device is mapped over a range of memory with `Chip8::map_device`, after
+//! which the instructions that load and store registers through the index
+//! (`fx33`, `fx55`, and `fx65`) read from and write to the device instead of
+//! the memory underneath it. Fetching instructions and drawing sprites still
+//! use the memory as it is, so code and sprites can't be put in a device.
+//!
+//! ```rust
+//! # #[cfg(feature = "alloc")]
+//! # fn main() {
+//! use chip_8::{devices::Device, Chip8};
+//! use std::sync::mpsc::{self, Sender};
+//!
+//! /// Sends every byte that gets written to it to the parent program
+//! struct Serial(Sender<u8>);
+//!
+//! impl Device for Serial {
+//!     fn read(&mut self, _offset: usize) -> u8 {
+//!         0
+//!     }
+//!
+//!     fn write(&mut self, _offset: usize, value: u8) {
+//!         let _ = self.0.send(value);
+//!     }
+//! }
+//!
+//! let (sender, receiver) = mpsc::channel();
+//! let mut chip8 = Chip8::with_seed(0);
+//! chip8.map_device(0xff0..0xff1, Box::new(Serial(sender)));
+//! // `6041` sets register 0 to `A`, `aff0` points the index at the device,
+//! // and `f055` stores register 0 there
+//! chip8.load(&[0x60, 0x41, 0xaf, 0xf0, 0xf0, 0x55]).unwrap();
+//! chip8.frame(3).unwrap();
+//! assert_eq!(receiver.try_recv(), Ok(b'A'));
+//! # }
+//! # #[cfg(not(feature = "alloc"))]
+//! # fn main() {}
+//! ```
+//!
+//! Devices are kept in a `Box`, so they need the `alloc` feature. Without it
+//! the instructions just use memory.
+
+use crate::chip8::Chip8;
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use core::ops::Range;
+
+/// Something that can be mapped over memory, to be read and written to by the
+/// rom like any other memory
+pub trait Device: Send {
+    /// Returns the byte that the rom reads at `offset` bytes into the range
+    /// that the device is mapped over
+    fn read(&mut self, offset: usize) -> u8;
+
+    /// Gets the byte that the rom writes at `offset` bytes into the range that
+    /// the device is mapped over
+    fn write(&mut self, offset: usize, value: u8);
+}
+
+/// A device, along with where it is in memory
+#[cfg(feature = "alloc")]
+pub(crate) struct MappedDevice {
+    range: Range<usize>,
+    device: Box<dyn Device>,
+}
+
+impl Chip8 {
+    /// Maps the device over the range of memory. Where the ranges of devices
+    /// overlap, the one that was mapped first gets the reads and writes.
+    #[cfg(feature = "alloc")]
+    pub fn map_device(&mut self, range: Range<usize>, device: Box<dyn Device>) {
+        self.devices.push(MappedDevice { range, device });
+    }
+
+    /// Takes away all of the devices, which leaves the memory under them as it
+    /// was before they were mapped
+    #[cfg(feature = "alloc")]
+    pub fn unmap_devices(&mut self) {
+        self.devices.clear();
+    }
+
+    /// Reads the byte at the address, from the device that is mapped over it
+    /// if there is one. The address has to be inside of memory.
+    pub(crate) fn read_memory(&mut self, address: usize) -> u8 {
+        #[cfg(not(feature = "alloc"))]
+        return self.memory[address];
+        #[cfg(feature = "alloc")]
+        match self.device_at(address) {
+            Some(mapped) => mapped.device.read(address - mapped.range.start),
+            None => self.memory[address],
+        }
+    }
+
+    /// Writes the byte at the address, to the device that is mapped over it if
+    /// there is one. The address has to be inside of memory. Nothing gets
+    /// written when the address is protected, not even to a device.
+    pub(crate) fn write_memory(&mut self, address: usize, value: u8) {
+        if self.is_protected(address) {
+            return;
+        }
+        #[cfg(not(feature = "alloc"))]
+        {
+            self.memory[address] = value
+        }
+        #[cfg(feature = "alloc")]
+        match self.device_at(address) {
+            Some(mapped) => mapped.device.write(address - mapped.range.start, value),
+            None => {
+                self.journal_write(address);
+                self.memory[address] = value
+            }
+        }
+    }
+
+    /// Returns the device that is mapped over the address
+    #[cfg(feature = "alloc")]
+    fn device_at(&mut self, address: usize) -> Option<&mut MappedDevice> {
+        self.devices
+            .iter_mut()
+            .find(|mapped| mapped.range.contains(&address))
+    }
+}
@@ -0,0 +1,296 @@
+//! This module compares two states of the interpreter, for seeing what a
+//! stretch of a game actually changed, like which byte went down when a life
+//! was lost. It finds the registers that are different, the ranges of memory
+//! that are, and the parts of the screen that are.
+//!
+//! ```rust
+//! use chip_8::Chip8;
+//!
+//! let before = Chip8::with_seed(0);
+//! let mut after = Chip8::with_seed(0);
+//! after.registers[3] = 5;
+//! after.memory[0x300] = 2;
+//! after.memory[0x301] = 7;
+//! let diff = before.diff(&after);
+//! assert_eq!(diff.registers()[0].name, "v3");
+//! assert_eq!(diff.memory()[0].start, 0x300);
+//! assert_eq!(diff.memory()[0].after, [2, 7]);
+//! ```
+
+use crate::chip8::Chip8;
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+/// How many bytes of a range of memory get written out, the rest of it is
+/// left as `..` so one big change doesn't bury the others
+const SHOWN_BYTES: usize = 8;
+
+/// A register that is different between the two states
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterChange {
+    /// Like `v3`, `i`, `pc`, `dt`, or `stack 2`
+    pub name: String,
+    pub before: usize,
+    pub after: usize,
+}
+
+/// A run of bytes of memory that are all different between the two states
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryChange {
+    pub start: usize,
+    pub before: Vec<u8>,
+    pub after: Vec<u8>,
+}
+
+impl MemoryChange {
+    /// Returns the address right after the last byte that changed
+    pub fn end(&self) -> usize {
+        self.start + self.after.len().max(self.before.len())
+    }
+}
+
+/// A box around the pixels that are different between the two states, on the
+/// rows from `top` to `bottom`, and the columns from `left` to `right`, both
+/// including the last one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenChange {
+    pub left: usize,
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+    /// How many of the pixels in the box changed
+    pub pixels: usize,
+}
+
+/// Everything that is different between two states
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateDiff {
+    registers: Vec<RegisterChange>,
+    memory: Vec<MemoryChange>,
+    screen: Vec<ScreenChange>,
+    /// The sizes of the two screens, when they aren't the same, in which case
+    /// there aren't any pixels to compare
+    screen_sizes: Option<((usize, usize), (usize, usize))>,
+}
+
+impl StateDiff {
+    /// Returns the registers that are different, `V` first, then the rest
+    pub fn registers(&self) -> &[RegisterChange] {
+        &self.registers
+    }
+
+    /// Returns the runs of memory that are different, in order
+    pub fn memory(&self) -> &[MemoryChange] {
+        &self.memory
+    }
+
+    /// Returns the parts of the screen that are different, from the top down
+    pub fn screen(&self) -> &[ScreenChange] {
+        &self.screen
+    }
+
+    /// Returns the sizes of the screens before and after, if they aren't the
+    /// same, like when hi-res or SUPER-CHIP's big screen was turned on
+    pub fn screen_sizes(&self) -> Option<((usize, usize), (usize, usize))> {
+        self.screen_sizes
+    }
+
+    /// Returns whether the two states are the same
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty()
+            && self.memory.is_empty()
+            && self.screen.is_empty()
+            && self.screen_sizes.is_none()
+    }
+}
+
+impl Chip8 {
+    /// Compares this state to one from later on, and returns what is different
+    pub fn diff(&self, after: &Chip8) -> StateDiff {
+        StateDiff {
+            registers: diff_registers(self, after),
+            memory: diff_memory(&self.memory, &after.memory),
+            screen: diff_screen(self, after),
+            screen_sizes: (self.screen.size() != after.screen.size())
+                .then(|| (self.screen.size(), after.screen.size())),
+        }
+    }
+}
+
+/// Compares `V`, then `I`, the program counter, the timers, and the stack
+fn diff_registers(before: &Chip8, after: &Chip8) -> Vec<RegisterChange> {
+    let mut pairs: Vec<(String, usize, usize)> = before
+        .registers
+        .iter()
+        .zip(after.registers.iter())
+        .enumerate()
+        .map(|(n, (b, a))| (format!("v{:x}", n), *b as usize, *a as usize))
+        .collect();
+    pairs.push((String::from("i"), before.index, after.index));
+    pairs.push((
+        String::from("pc"),
+        before.program_counter,
+        after.program_counter,
+    ));
+    pairs.push((
+        String::from("sp"),
+        before.stack_pointer,
+        after.stack_pointer,
+    ));
+    pairs.push((
+        String::from("dt"),
+        before.delay as usize,
+        after.delay as usize,
+    ));
+    pairs.push((
+        String::from("st"),
+        before.sound as usize,
+        after.sound as usize,
+    ));
+    for (level, (b, a)) in before.stack.iter().zip(after.stack.iter()).enumerate() {
+        pairs.push((format!("stack {:x}", level), *b, *a));
+    }
+    pairs
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(name, before, after)| RegisterChange {
+            name,
+            before,
+            after,
+        })
+        .collect()
+}
+
+/// Finds the runs of bytes that are different. When one memory is bigger than
+/// the other, the bytes past the end of the smaller one count as different.
+fn diff_memory(before: &[u8], after: &[u8]) -> Vec<MemoryChange> {
+    let mut changes: Vec<MemoryChange> = Vec::new();
+    for address in 0..before.len().max(after.len()) {
+        let (b, a) = (before.get(address), after.get(address));
+        if b == a {
+            continue;
+        }
+        // A byte right after the last run carries it on
+        match changes.last_mut() {
+            Some(change) if change.end() == address => {
+                change.before.extend(b);
+                change.after.extend(a);
+            }
+            _ => changes.push(MemoryChange {
+                start: address,
+                before: b.copied().into_iter().collect(),
+                after: a.copied().into_iter().collect(),
+            }),
+        }
+    }
+    changes
+}
+
+/// Finds the parts of the screen that are different, each run of rows with a
+/// pixel that changed becomes one box around every pixel that did on them
+fn diff_screen(before: &Chip8, after: &Chip8) -> Vec<ScreenChange> {
+    let (width, height) = before.screen.size();
+    if after.screen.size() != (width, height) {
+        return Vec::new();
+    }
+    let mut changes: Vec<ScreenChange> = Vec::new();
+    for y in 0..height {
+        let changed: Vec<usize> = (0..width)
+            .filter(|x| before.screen.get_pixel(*x, y) != after.screen.get_pixel(*x, y))
+            .collect();
+        let (left, right) = match (changed.first(), changed.last()) {
+            (Some(left), Some(right)) => (*left, *right),
+            _ => continue,
+        };
+        match changes.last_mut() {
+            Some(change) if change.bottom + 1 == y => {
+                change.left = change.left.min(left);
+                change.right = change.right.max(right);
+                change.bottom = y;
+                change.pixels += changed.len();
+            }
+            _ => changes.push(ScreenChange {
+                left,
+                top: y,
+                right,
+                bottom: y,
+                pixels: changed.len(),
+            }),
+        }
+    }
+    changes
+}
+
+/// Writes the bytes in hex, only the first few of them if there are a lot
+fn hex_bytes(bytes: &[u8]) -> String {
+    let mut text: Vec<String> = bytes
+        .iter()
+        .take(SHOWN_BYTES)
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    if bytes.len() > SHOWN_BYTES {
+        text.push(String::from(".."));
+    }
+    text.join(" ")
+}
+
+impl fmt::Display for StateDiff {
+    /// Writes what is different, a section for the registers, memory, and the
+    /// screen, leaving out the ones that are the same
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "the states are the same");
+        }
+        if !self.registers.is_empty() {
+            writeln!(f, "registers")?;
+            for change in &self.registers {
+                writeln!(
+                    f,
+                    "  {:<8} {:02x} -> {:02x}",
+                    change.name, change.before, change.after
+                )?;
+            }
+        }
+        if !self.memory.is_empty() {
+            writeln!(f, "memory")?;
+            for change in &self.memory {
+                // A single byte is only written with it's own address
+                let range = if change.end() - change.start == 1 {
+                    format!("{:03x}", change.start)
+                } else {
+                    format!("{:03x}-{:03x}", change.start, change.end() - 1)
+                };
+                writeln!(
+                    f,
+                    "  {:<8} {} -> {}",
+                    range,
+                    hex_bytes(&change.before),
+                    hex_bytes(&change.after)
+                )?;
+            }
+        }
+        if let Some(((bw, bh), (aw, ah))) = self.screen_sizes {
+            writeln!(f, "screen")?;
+            writeln!(f, "  was {}x{}, is {}x{}", bw, bh, aw, ah)?;
+        } else if !self.screen.is_empty() {
+            writeln!(f, "screen")?;
+            for change in &self.screen {
+                writeln!(
+                    f,
+                    "  x {}-{}, y {}-{}  {} {}",
+                    change.left,
+                    change.right,
+                    change.top,
+                    change.bottom,
+                    change.pixels,
+                    if change.pixels == 1 {
+                        "pixel"
+                    } else {
+                        "pixels"
+                    }
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+//! This module contains the `disassemble` command, which prints a rom as
+//! instructions, with the sprites drawn out and the rest of the data left as
+//! bytes, so the data doesn't show up as instructions that don't mean anything
+
+use crate::{config::Config, rom};
+use chip_8::{analysis::ByteKind, Chip8};
+use std::io::{stdout, BufWriter, Error, ErrorKind, Write};
+
+/// How many bytes of data that isn't a sprite go on each line
+const BYTES_PER_LINE: usize = 8;
+
+/// Runs `chip_8 disassemble [options] <rom>`, which prints every line of the
+/// rom to the terminal. The options that change which instructions the
+/// opcodes are, like `--quirks` and `--megachip`, work the same as they do
+/// normally.
+pub fn command(args: Vec<String>) -> Result<(), Error> {
+    let config = Config::from_args(args)?;
+    let path = config.rom.clone().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "disassemble needs a rom, like `chip_8 disassemble roms/pong.ch8`",
+        )
+    })?;
+    let (chip8, _) = rom::start(&path, config.seed, &config)?;
+    let end = chip8.load_address() + rom::read(&path)?.len();
+
+    let stdout = stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    for line in lines(&chip8, chip8.load_address(), end) {
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()
+}
+
+/// Returns the lines for the memory from `start` up to `end`, an instruction
+/// on each line of code, a row on each line of a sprite, and a few bytes on
+/// each line of anything else
+fn lines(chip8: &Chip8, start: usize, end: usize) -> Vec<String> {
+    let analysis = chip8.analyze(start, end);
+    let end = analysis.end();
+    let mut lines = Vec::new();
+    let mut address = start;
+    while address < end {
+        match analysis.kind(address) {
+            // Code always starts an instruction, it is only ever marked from
+            // the start of one
+            ByteKind::Code => {
+                let instruction = chip8.disassemble_at(address);
+                match instruction {
+                    // The address that `ldhi` loads is the next 2 bytes
+                    Some(instruction) if instruction.mnemonic == "ldhi" && address + 3 < end => {
+                        let long = (chip8.memory[address + 2] as u16) << 8
+                            | chip8.memory[address + 3] as u16;
+                        lines.push(format!(
+                            "{:03x}  {:04x}  ldhi {:#06x}",
+                            address, instruction.opcode, long
+                        ));
+                        address += 4;
+                    }
+                    Some(instruction) => {
+                        lines.push(format!(
+                            "{:03x}  {:04x}  {}",
+                            address, instruction.opcode, instruction
+                        ));
+                        address += 2;
+                    }
+                    None => {
+                        lines.push(format!("{:03x}  {:02x}", address, chip8.memory[address]));
+                        address += 1;
+                    }
+                }
+            }
+            // The pixels of the row are drawn with a `#` for every one that
+            // is on
+            ByteKind::Sprite => {
+                let byte = chip8.memory[address];
+                let pixels: String = (0..8)
+                    .map(|bit| if byte << bit & 0x80 != 0 { '#' } else { '.' })
+                    .collect();
+                lines.push(format!("{:03x}  {:02x}    {}", address, byte, pixels));
+                address += 1;
+            }
+            ByteKind::Unknown => {
+                let length = (address..end)
+                    .take(BYTES_PER_LINE)
+                    .take_while(|address| analysis.kind(*address) == ByteKind::Unknown)
+                    .count();
+                let bytes: Vec<String> = chip8.memory[address..address + length]
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect();
+                lines.push(format!("{:03x}  {}", address, bytes.join(" ")));
+                address += length;
+            }
+        }
+    }
+    lines
+}
@@ -0,0 +1,245 @@
+//! This module contains the errors that the interpreter can run into when a rom
+//! does something that the Chip-8 can't actually do, or can't be loaded at all.
+
+#[cfg(feature = "alloc")]
+use crate::chip8::CallFrame;
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+/// Everything that can go wrong while the interpreter is running a rom, each
+/// one keeps track of the program counter of the instruction that caused it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chip8Error {
+    /// The program counter points outside of memory, or below `0x200` where
+    /// the interpreter is, see the `bounds` module
+    ProgramCounterOutOfBounds { pc: usize },
+    /// A `ret` was executed without there being a subroutine to return from,
+    /// so there are never any calls that led up to it
+    StackUnderflow { pc: usize },
+    /// A `call` was executed when the stack was already full, along with the
+    /// calls that filled it up, with the most recent one first. The calls are
+    /// only kept with the `alloc` feature.
+    StackOverflow {
+        pc: usize,
+        #[cfg(feature = "alloc")]
+        calls: Vec<CallFrame>,
+    },
+    /// An instruction tried to read or write memory past the end using the index
+    IndexOutOfBounds { pc: usize, index: usize },
+    /// An instruction tried to check a key that isn't on the keypad
+    InvalidKey { pc: usize, key: u8 },
+    /// An instruction tried to write below `0x200` while it is protected, see
+    /// the `protection` module
+    ProtectedWrite { pc: usize, address: usize },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Chip8Error::ProgramCounterOutOfBounds { pc } => {
+                write!(f, "the program counter went out of bounds at {:#05x}", pc)
+            }
+            Chip8Error::StackUnderflow { pc } => write!(
+                f,
+                "tried to return from a subroutine at {:#05x}, but the stack is empty",
+                pc
+            ),
+            #[cfg(not(feature = "alloc"))]
+            Chip8Error::StackOverflow { pc } => write!(
+                f,
+                "tried to call a subroutine at {:#05x}, but the stack is full",
+                pc
+            ),
+            #[cfg(feature = "alloc")]
+            Chip8Error::StackOverflow { pc, calls } => {
+                write!(
+                    f,
+                    "tried to call a subroutine at {:#05x}, but the stack is full",
+                    pc
+                )?;
+                // The calls are written in the order they were made, so it
+                // reads like the path the rom took to get there
+                for (i, call) in calls.iter().rev().enumerate() {
+                    let separator = if i == 0 { ", after calls at" } else { "," };
+                    write!(f, "{} {:#05x}", separator, call.call_site)?;
+                }
+                Ok(())
+            }
+            Chip8Error::IndexOutOfBounds { pc, index } => write!(
+                f,
+                "the instruction at {:#05x} tried to access memory past the end with the index {:#05x}",
+                pc, index
+            ),
+            Chip8Error::InvalidKey { pc, key } => write!(
+                f,
+                "the instruction at {:#05x} checked for key {:#04x}, which isn't on the keypad",
+                pc, key
+            ),
+            Chip8Error::ProtectedWrite { pc, address } => write!(
+                f,
+                "the instruction at {:#05x} tried to write to {:#05x}, which is protected",
+                pc, address
+            ),
+        }
+    }
+}
+
+impl core::error::Error for Chip8Error {}
+
+/// Everything that can be wrong with a rom that stops it from being loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// The rom doesn't have anything in it
+    Empty,
+    /// The rom is bigger than the memory after the load address
+    TooBig {
+        size: usize,
+        room: usize,
+        load_address: usize,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Empty => write!(f, "the rom is empty"),
+            LoadError::TooBig {
+                size,
+                room,
+                load_address,
+            } => write!(
+                f,
+                "the rom is {} bytes, but there is only room for {} bytes at {:#05x}",
+                size, room, load_address
+            ),
+        }
+    }
+}
+
+impl core::error::Error for LoadError {}
+
+/// Everything that can be wrong with the condition of a breakpoint
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConditionError {
+    /// Something was found where it can't go, at the position in the text
+    Unexpected { position: usize, found: String },
+    /// The condition stopped before it was finished
+    UnexpectedEnd,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConditionError::Unexpected { position, found } => {
+                write!(f, "didn't expect {} at {}", found, position)
+            }
+            ConditionError::UnexpectedEnd => write!(f, "the condition isn't finished"),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::error::Error for ConditionError {}
+
+/// Something in Octo source that can't be assembled, see the `octo` module
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    /// The line it is on, starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::error::Error for AssembleError {}
+
+/// A line of a symbol file that can't be read, see the `symbols` module
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolError {
+    /// The line it is on, starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::error::Error for SymbolError {}
+
+/// A line of a cheats file that can't be read, see the `cheats` module
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheatError {
+    /// The line it is on, starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for CheatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::error::Error for CheatError {}
+
+/// Lets a `Chip8Error` be passed up through anything that returns an `io::Error`,
+/// like the frontends do
+#[cfg(feature = "std")]
+impl From<Chip8Error> for io::Error {
+    fn from(error: Chip8Error) -> Self {
+        io::Error::other(error)
+    }
+}
+
+/// A rom that can't be loaded is bad data, as far as the frontends are concerned
+#[cfg(feature = "std")]
+impl From<LoadError> for io::Error {
+    fn from(error: LoadError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// Source that can't be assembled is bad data too
+#[cfg(feature = "std")]
+impl From<AssembleError> for io::Error {
+    fn from(error: AssembleError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// And so is a symbol file that can't be read
+#[cfg(feature = "std")]
+impl From<SymbolError> for io::Error {
+    fn from(error: SymbolError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// And so is a cheats file that can't be read
+#[cfg(feature = "std")]
+impl From<CheatError> for io::Error {
+    fn from(error: CheatError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
@@ -0,0 +1,171 @@
+//! This module contains the C API, for running the interpreter from a frontend
+//! or a game engine that is written in C or C++.
+//!
+//! It is only built when the `ffi` feature is turned on, and the library gets
+//! linked in as `libchip_8` from `cargo build --release --features ffi`, with
+//! the functions declared in `include/chip_8.h`. The interpreter is handed out
+//! as a pointer that C holds onto, and that has to be given back to
+//! `chip8_destroy` when it is done with. Like the browser frontend, C owns the
+//! loop, so it calls `chip8_step` as often as it wants instructions to run,
+//! and `chip8_tick_timers` 60 times a second.
+//!
+//! The functions that can go wrong return `0` when they worked, and `-1` when
+//! they didn't, which includes being passed a null pointer.
+
+use crate::chip8::Chip8;
+use std::{os::raw::c_int, ptr, slice};
+
+/// What the functions return when they worked
+const OK: c_int = 0;
+/// What the functions return when something went wrong
+const FAILED: c_int = -1;
+
+/// Creates an interpreter with nothing loaded into it, with a random seed for
+/// the `rnd` instruction. It has to be given to `chip8_destroy` to be freed.
+#[no_mangle]
+pub extern "C" fn chip8_create() -> *mut Chip8 {
+    Box::into_raw(Box::new(Chip8::new()))
+}
+
+/// Creates an interpreter like `chip8_create`, where the `rnd` instruction
+/// always gives the same numbers for the same seed
+#[no_mangle]
+pub extern "C" fn chip8_create_with_seed(seed: u64) -> *mut Chip8 {
+    Box::into_raw(Box::new(Chip8::with_seed(seed)))
+}
+
+/// Frees the interpreter, passing in null does nothing
+///
+/// # Safety
+/// `chip8` has to have come from `chip8_create`, and can't be used again after
+#[no_mangle]
+pub unsafe extern "C" fn chip8_destroy(chip8: *mut Chip8) {
+    if !chip8.is_null() {
+        drop(Box::from_raw(chip8));
+    }
+}
+
+/// Resets the interpreter and loads the `length` bytes of the rom into it. A
+/// rom that can't be loaded, because it is too big, leaves the one that was
+/// running alone.
+///
+/// # Safety
+/// `chip8` has to be null or have come from `chip8_create`, and `rom` has to
+/// point at `length` bytes
+#[no_mangle]
+pub unsafe extern "C" fn chip8_load_rom(chip8: *mut Chip8, rom: *const u8, length: usize) -> c_int {
+    let chip8 = match chip8.as_mut() {
+        Some(chip8) if !rom.is_null() => chip8,
+        _ => return FAILED,
+    };
+    // Loading it into a new interpreter is what resets it, the same as the
+    // browser does, and it's seed comes from the old one so that an
+    // interpreter created with a seed still runs the same every time
+    let mut fresh = Chip8::with_load_address(chip8.rng.next_u64(), chip8.load_address());
+    match fresh.load(slice::from_raw_parts(rom, length)) {
+        Ok(_) => {
+            *chip8 = fresh;
+            OK
+        }
+        Err(_) => FAILED,
+    }
+}
+
+/// Runs exactly one instruction, this fails when the rom runs into something
+/// that the interpreter can't run, like an instruction that doesn't exist
+///
+/// # Safety
+/// `chip8` has to be null or have come from `chip8_create`
+#[no_mangle]
+pub unsafe extern "C" fn chip8_step(chip8: *mut Chip8) -> c_int {
+    match chip8.as_mut().map(Chip8::clock) {
+        Some(Ok(_)) => OK,
+        _ => FAILED,
+    }
+}
+
+/// Ticks the delay and sound timers, this has to be called 60 times a second
+///
+/// # Safety
+/// `chip8` has to be null or have come from `chip8_create`
+#[no_mangle]
+pub unsafe extern "C" fn chip8_tick_timers(chip8: *mut Chip8) {
+    if let Some(chip8) = chip8.as_mut() {
+        chip8.tick_timers();
+    }
+}
+
+/// Returns `1` while the tone should be playing, and `0` when it shouldn't
+///
+/// # Safety
+/// `chip8` has to be null or have come from `chip8_create`
+#[no_mangle]
+pub unsafe extern "C" fn chip8_sound_playing(chip8: *const Chip8) -> c_int {
+    chip8
+        .as_ref()
+        .map_or(0, |chip8| chip8.is_sound_playing() as c_int)
+}
+
+/// Marks the key (`0x0` through `0xf`) as being held down
+///
+/// # Safety
+/// `chip8` has to be null or have come from `chip8_create`
+#[no_mangle]
+pub unsafe extern "C" fn chip8_key_down(chip8: *mut Chip8, key: u8) {
+    if let Some(chip8) = chip8.as_mut() {
+        chip8.press_key(key);
+    }
+}
+
+/// Marks the key (`0x0` through `0xf`) as being let go of
+///
+/// # Safety
+/// `chip8` has to be null or have come from `chip8_create`
+#[no_mangle]
+pub unsafe extern "C" fn chip8_key_up(chip8: *mut Chip8, key: u8) {
+    if let Some(chip8) = chip8.as_mut() {
+        chip8.release_key(key);
+    }
+}
+
+/// Returns the screen's pixels, and writes how many pixels wide and tall it is
+/// to `width` and `height` if they aren't null. Each byte is 8 pixels next to
+/// each other, with the most significant bit being the one on the left, and
+/// the rows come one after another, so a row is `width / 8` bytes. The pointer
+/// is only good until the interpreter runs again, since the screen can change
+/// size when the rom turns on SUPER-CHIP's or MEGA-CHIP's bigger screens.
+/// Returns null if `chip8` is null.
+///
+/// # Safety
+/// `chip8` has to be null or have come from `chip8_create`, and `width` and
+/// `height` have to be null or point at somewhere they can be written to
+#[no_mangle]
+pub unsafe extern "C" fn chip8_framebuffer(
+    chip8: *const Chip8,
+    width: *mut usize,
+    height: *mut usize,
+) -> *const u8 {
+    let screen = match chip8.as_ref() {
+        Some(chip8) => chip8.screen(),
+        None => return ptr::null(),
+    };
+    if let Some(width) = width.as_mut() {
+        *width = screen.width();
+    }
+    if let Some(height) = height.as_mut() {
+        *height = screen.height();
+    }
+    screen.as_bytes().as_ptr()
+}
+
+/// Returns `1` if the screen has changed since the last time this was called,
+/// so it only has to be drawn again when it has, and `0` if it hasn't
+///
+/// # Safety
+/// `chip8` has to be null or have come from `chip8_create`
+#[no_mangle]
+pub unsafe extern "C" fn chip8_take_redraw(chip8: *mut Chip8) -> c_int {
+    chip8
+        .as_mut()
+        .map_or(0, |chip8| chip8.take_redraw() as c_int)
+}
@@ -0,0 +1,86 @@
+//! This module contains the saved RPL flags, which SUPER-CHIP roms use to keep
+//! things like high scores.
+//!
+//! They get saved next to the rom, in a file with the same name ending in
+//! `.flags`, which is just the 16 flags one byte after another. They are read
+//! back the next time the rom is started, so the high scores are still there.
+
+use chip_8::{
+    frontend::{Audio, Display, Event, Input},
+    Chip8,
+};
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+/// Wraps a frontend, and saves the flags whenever the rom changes them
+pub struct FlagsFrontend<'a, F> {
+    frontend: &'a mut F,
+    /// Where the flags get saved to
+    path: PathBuf,
+    /// The flags as they were last saved, so the file is only written when they
+    /// change
+    saved: [u8; 16],
+}
+
+impl<'a, F> FlagsFrontend<'a, F> {
+    /// Reads the flags that were saved for the rom into the interpreter, if
+    /// there isn't a file for it yet then they all start out as 0
+    pub fn load(frontend: &'a mut F, rom: &Path, chip8: &mut Chip8) -> Self {
+        let path = rom.with_extension("flags");
+        let mut saved = [0; 16];
+        if let Ok(bytes) = fs::read(&path) {
+            // A file that is too short only has some of the flags in it
+            let count = bytes.len().min(saved.len());
+            saved[..count].copy_from_slice(&bytes[..count]);
+        }
+        chip8.flags = saved;
+        FlagsFrontend {
+            frontend,
+            path,
+            saved,
+        }
+    }
+}
+
+impl<'a, F: Input> Input for FlagsFrontend<'a, F> {
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        self.frontend.handle_input(chip8)
+    }
+
+    fn start_frame(&mut self, chip8: &mut Chip8) {
+        self.frontend.start_frame(chip8);
+    }
+
+    fn before_instruction(&mut self, chip8: &mut Chip8) {
+        self.frontend.before_instruction(chip8);
+    }
+
+    /// Saves the flags once a frame if they have changed, roms only change them
+    /// when there is something to keep, so this hardly ever writes anything
+    fn tick(&mut self, chip8: &mut Chip8) {
+        self.frontend.tick(chip8);
+        if chip8.flags == self.saved {
+            return;
+        }
+        // There is nowhere to show an error, so it is tried again on the next
+        // tick instead
+        if fs::write(&self.path, chip8.flags).is_ok() {
+            self.saved = chip8.flags;
+        }
+    }
+}
+
+impl<'a, F: Display> Display for FlagsFrontend<'a, F> {
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error> {
+        self.frontend.draw(chip8)
+    }
+}
+
+impl<'a, F: Audio> Audio for FlagsFrontend<'a, F> {
+    fn set_playing(&mut self, playing: bool) -> Result<(), Error> {
+        self.frontend.set_playing(playing)
+    }
+}
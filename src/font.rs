@@ -0,0 +1,85 @@
+//! This module contains the loading of other fonts, since some roms look better
+//! with slightly different digits, or were made for an interpreter that drew
+//! them differently.
+//!
+//! A font is given as bytes, either the 80 bytes of the 4x5 font (5 rows for
+//! each of the 16 digits), or those followed by the 160 bytes of the 8x10 font
+//! (10 rows for each digit). This is the same layout that the fonts have in
+//! memory, so a font can be cut straight out of another interpreter's memory.
+
+#[cfg(feature = "std")]
+use crate::{
+    builder::Chip8Builder,
+    chip8::{Chip8, BIG_FONT_ADDRESS},
+};
+#[cfg(feature = "std")]
+use std::io::{Error, ErrorKind};
+
+/// How many bytes the 4x5 font takes up
+pub const FONT_SIZE: usize = 16 * 5;
+/// How many bytes the 8x10 font takes up
+pub const BIG_FONT_SIZE: usize = 16 * 10;
+
+/// The 4x5 font, along with the 8x10 font if there was one
+pub type Fonts = ([[u8; 5]; 16], Option<[[u8; 10]; 16]>);
+
+/// Splits the bytes up into the sprites of the fonts, it is an error for there
+/// to be any other amount of bytes than one or both of the fonts
+#[cfg(feature = "std")]
+pub fn parse(bytes: &[u8]) -> Result<Fonts, Error> {
+    if bytes.len() != FONT_SIZE && bytes.len() != FONT_SIZE + BIG_FONT_SIZE {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "a font is {} bytes, or {} with the big font, not {}",
+                FONT_SIZE,
+                FONT_SIZE + BIG_FONT_SIZE,
+                bytes.len()
+            ),
+        ));
+    }
+    let mut font = [[0; 5]; 16];
+    for (sprite, rows) in font.iter_mut().zip(bytes.chunks(5)) {
+        sprite.copy_from_slice(rows);
+    }
+    let big_font = if bytes.len() > FONT_SIZE {
+        let mut big_font = [[0; 10]; 16];
+        for (sprite, rows) in big_font.iter_mut().zip(bytes[FONT_SIZE..].chunks(10)) {
+            sprite.copy_from_slice(rows);
+        }
+        Some(big_font)
+    } else {
+        None
+    };
+    Ok((font, big_font))
+}
+
+#[cfg(feature = "std")]
+impl Chip8 {
+    /// Replaces the fonts in memory with the ones in the bytes, the big font is
+    /// left alone unless it is in them too
+    pub fn load_font(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        parse(bytes)?;
+        let start = self.font_address;
+        self.memory[start..start + FONT_SIZE].copy_from_slice(&bytes[..FONT_SIZE]);
+        if bytes.len() > FONT_SIZE {
+            self.memory[BIG_FONT_ADDRESS..BIG_FONT_ADDRESS + BIG_FONT_SIZE]
+                .copy_from_slice(&bytes[FONT_SIZE..]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Chip8Builder {
+    /// Sets the fonts from bytes, like the ones read from a font file, the big
+    /// font is left as it is unless it is in them too
+    pub fn font_bytes(self, bytes: &[u8]) -> Result<Self, Error> {
+        let (font, big_font) = parse(bytes)?;
+        let builder = self.font(font);
+        Ok(match big_font {
+            Some(big_font) => builder.big_font(big_font),
+            None => builder,
+        })
+    }
+}
@@ -0,0 +1,345 @@
+//! This module contains the traits that a frontend needs to implement in order
+//! to be driven by the shared run loop, so that the interpreter doesn't need to
+//! know anything about terminals, windows, or speakers.
+
+use crate::chip8::Chip8;
+use std::{
+    io::Error,
+    thread,
+    time::{Duration, SystemTime},
+};
+use tracing::{debug, debug_span, error, info, trace};
+
+/// Just an enum to check for events that the frontend needs the run loop to take care of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// Stops running the interpreter and closes the app
+    Quit,
+    /// Stops running the current rom, so that the frontend can pick another one
+    Unload,
+    /// The rom stopped itself with `00fd`
+    Exit,
+}
+
+/// Something that can show the interpreter's screen
+pub trait Display {
+    /// Draws the interpreter's screen, this only gets called when the screen
+    /// has actually changed, and `Chip8::dirty` says which parts of it did
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error>;
+}
+
+/// Something that can pass key presses on to the interpreter
+pub trait Input {
+    /// Sets the keys that are pressed, and returns an event if the frontend
+    /// wants the run loop to do something
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event>;
+
+    /// Gets called at the start of every frame, before any of it's instructions
+    /// run, the keys that are pressed now are the ones the whole frame sees
+    fn start_frame(&mut self, _chip8: &mut Chip8) {}
+
+    /// Gets called right before every instruction runs, for frontends that
+    /// need to look at or change the interpreter in the middle of a frame
+    fn before_instruction(&mut self, _chip8: &mut Chip8) {}
+
+    /// Gets called every 60th of a second, for frontends that need to do
+    /// something with the keys on a regular basis
+    fn tick(&mut self, _chip8: &mut Chip8) {}
+}
+
+/// Something that can play the interpreter's tone
+pub trait Audio {
+    /// Starts or stops the tone, this only gets called when the sound timer
+    /// starts or stops running
+    fn set_playing(&mut self, playing: bool) -> Result<(), Error>;
+}
+
+/// Everything that the run loop needs from a frontend
+pub trait Frontend: Display + Input + Audio {}
+
+impl<T: Display + Input + Audio> Frontend for T {}
+
+/// How many instructions the interpreter runs in a second, unless it is told
+/// otherwise. It is hard to find the speed that the interpreter runs, but
+/// according to a document I had read, it said that the computer that it was
+/// based off of had a clock speed of 1KHz
+pub const CLOCK_SPEED: u64 = 1000;
+
+/// The most frames of time that get caught up on at once. After a stall, like
+/// the process being suspended or a window being dragged, the time past this
+/// is dropped, instead of running every frame that was missed back to back
+/// and freezing the frontend while it does.
+pub const MAX_BACKLOG_FRAMES: u32 = 10;
+
+/// This keeps track of the time that the interpreter needs, without caring
+/// where the time comes from, so that frontends that can't read the system
+/// clock (like the browser) can pass in how much time has passed.
+///
+/// The time is split up into frames of a 60th of a second, and every frame runs
+/// the same number of instructions no matter how the time was passed in, so
+/// that a run with the same keys and the same seed always ends up the same.
+///
+/// With the VIP timing, every frame runs instructions until they have taken a
+/// 60th of a second on the COSMAC VIP instead, see the `timing` module, which
+/// only depends on the instructions so it always ends up the same too.
+pub struct Scheduler {
+    /// How many instructions run in a second
+    clock_speed: u64,
+    /// Counts how long each instruction took on the VIP, instead of running
+    /// `clock_speed` of them a second
+    vip_timing: bool,
+    /// How far the last frame's instructions went past the end of it with the
+    /// VIP timing, which the next frame has to make up for
+    overrun: Duration,
+    /// How long it takes the delay and sound timers to tick down once
+    frame_duration: Duration,
+    /// How much time has built up that hasn't been used by a frame yet
+    frame_time: Duration,
+    /// How many frames have been ran
+    frame: u64,
+    /// Keeps track of whether the tone was playing, so the frontend only hears
+    /// about it when it changes
+    was_playing: bool,
+}
+
+impl Scheduler {
+    /// Creates a scheduler that runs at the interpreter's normal speed
+    pub fn new() -> Self {
+        Scheduler::with_clock_speed(CLOCK_SPEED)
+    }
+
+    /// Creates a scheduler that runs however many instructions a second, for
+    /// roms that were made for faster or slower interpreters
+    pub fn with_clock_speed(clock_speed: u64) -> Self {
+        Scheduler {
+            clock_speed,
+            vip_timing: false,
+            overrun: Duration::new(0, 0),
+            // The delays for the interpreter are ticked down at a rate of 60Hz
+            frame_duration: Duration::new(0, 16666667),
+            frame_time: Duration::new(0, 0),
+            frame: 0,
+            was_playing: false,
+        }
+    }
+
+    /// Creates a scheduler that runs the instructions as fast as they ran on
+    /// the COSMAC VIP, so `dxyn` takes a lot longer than `6xnn`
+    pub fn with_vip_timing() -> Self {
+        Scheduler {
+            vip_timing: true,
+            ..Scheduler::new()
+        }
+    }
+
+    /// Runs every frame that should have happened in the time that has
+    /// `elapsed` since the last time this was called
+    pub fn advance<F: Frontend>(
+        &mut self,
+        chip8: &mut Chip8,
+        frontend: &mut F,
+        elapsed: Duration,
+    ) -> Result<(), Error> {
+        // Only so much time gets caught up on, the rest of it was a stall
+        let elapsed = elapsed.min(self.frame_duration * MAX_BACKLOG_FRAMES);
+        // Fast forwarding and slow motion only change how much time the frames
+        // get, so every frame still runs the same
+        self.frame_time += elapsed.mul_f64(chip8.speed());
+
+        // Keep running until the interpreter catches up it's frames
+        while self.frame_time >= self.frame_duration {
+            // Everything that gets logged while the frame runs says which
+            // frame it was
+            let _span = debug_span!("frame", frame = self.frame).entered();
+
+            // A paused rom doesn't run or make any noise, but whatever the
+            // frontend changes about it still gets drawn, unless it was asked
+            // to run one frame, which runs the same as any other
+            let stepping = chip8.is_paused() && chip8.take_frame_step();
+            if chip8.is_paused() && !stepping {
+                self.frame_time -= self.frame_duration;
+                self.stop(frontend)?;
+                if chip8.take_redraw() {
+                    frontend.draw(chip8)?;
+                    chip8.clear_dirty();
+                }
+                continue;
+            }
+            frontend.start_frame(chip8);
+
+            // A second doesn't split into 60 frames evenly, so the number of
+            // instructions in each frame is worked out from the total that
+            // should have ran by the end of it, which only depends on the frame
+            let cycles = self.cycles_before(self.frame + 1) - self.cycles_before(self.frame);
+            // With the VIP timing, the frame starts with however long the last
+            // one went over
+            let mut spent = self.overrun;
+            let mut ran = 0;
+            while if self.vip_timing {
+                spent < self.frame_duration
+            } else {
+                ran < cycles
+            } {
+                frontend.before_instruction(chip8);
+                // The frontend can pause the rom right before an instruction,
+                // like when a breakpoint is hit, then the rest of the frame's
+                // instructions don't run, unless it is a frame that is being
+                // stepped through
+                if chip8.is_paused() && !stepping {
+                    break;
+                }
+                // runs the current instruction, if the rom does something that
+                // the interpreter can't handle, stop running so the frontend
+                // can show the error
+                let step = match chip8.clock() {
+                    Ok(step) => step,
+                    Err(chip8_error) => {
+                        error!(
+                            pc = format_args!("{:03x}", chip8.program_counter),
+                            "the rom stopped: {}", chip8_error
+                        );
+                        return Err(chip8_error.into());
+                    }
+                };
+                ran += 1;
+                spent += step.vip_time();
+                // An instruction that left the program counter where it was
+                // is waiting, for a key or for the next frame, and the VIP
+                // didn't do anything else until it got it
+                if chip8.program_counter == step.pc {
+                    spent = spent.max(self.frame_duration);
+                }
+            }
+            if self.vip_timing {
+                self.overrun = spent.saturating_sub(self.frame_duration);
+            }
+            // The delay and sound timers tick down one every 1/60th of a
+            // second until they hit 0
+            chip8.tick_timers();
+            self.frame += 1;
+            frontend.tick(chip8);
+
+            // Only bother the frontend about the tone when it changes
+            let is_playing = chip8.is_sound_playing();
+            if is_playing != self.was_playing {
+                debug!(is_playing, "the tone changed");
+                frontend.set_playing(is_playing)?;
+                self.was_playing = is_playing;
+            }
+
+            // Draws the interpreter's buffer, I believe that the screen that
+            // the telemac updated at was 1/60th of a second, even if it is not,
+            // it seems like a reasonable speed to update the screen. While
+            // fast forwarding, only the last of the frames that are being
+            // caught up on gets drawn, since nobody could see the rest.
+            let is_last = self.frame_time < self.frame_duration * 2;
+            if (is_last || chip8.speed() <= 1.0) && chip8.take_redraw() {
+                trace!("drawing the screen");
+                frontend.draw(chip8)?;
+                chip8.clear_dirty();
+            }
+
+            self.frame_time -= self.frame_duration;
+        }
+        Ok(())
+    }
+
+    /// Returns how long it is until there is enough time built up for the next
+    /// frame, which is how long the run loop can sleep for
+    pub fn until_next_frame(&self) -> Duration {
+        self.frame_duration
+            .checked_sub(self.frame_time)
+            .unwrap_or_default()
+    }
+
+    /// Returns how many instructions have ran before the frame starts
+    fn cycles_before(&self, frame: u64) -> u64 {
+        frame * self.clock_speed / 60
+    }
+
+    /// Makes sure the tone doesn't keep going after the interpreter stops running
+    pub fn stop<F: Frontend>(&mut self, frontend: &mut F) -> Result<(), Error> {
+        if self.was_playing {
+            frontend.set_playing(false)?;
+            self.was_playing = false;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler::new()
+    }
+}
+
+/// This runs the chip8 interpreter using the system clock, until the frontend
+/// asks to stop or the rom exits, and returns the event that stopped it
+pub fn run<F: Frontend>(chip8: &mut Chip8, frontend: &mut F) -> Result<Event, Error> {
+    run_at(chip8, frontend, CLOCK_SPEED)
+}
+
+/// The same as `run`, but running however many instructions a second
+pub fn run_at<F: Frontend>(
+    chip8: &mut Chip8,
+    frontend: &mut F,
+    clock_speed: u64,
+) -> Result<Event, Error> {
+    info!(clock_speed, "the run loop started");
+    run_with(chip8, frontend, Scheduler::with_clock_speed(clock_speed))
+}
+
+/// The same as `run`, but with the frames worked out by the scheduler, like
+/// one that uses the VIP timing
+pub fn run_with<F: Frontend>(
+    chip8: &mut Chip8,
+    frontend: &mut F,
+    mut scheduler: Scheduler,
+) -> Result<Event, Error> {
+    // Sets the initial system time for the timers
+    let mut last_time = SystemTime::now();
+
+    // And now to the loop
+    let event = loop {
+        // handle_input returns an Option<Event> so that if the user decides
+        // to quit the application, or go back to pick another rom, they can
+        if let Some(event) = frontend.handle_input(chip8) {
+            info!(?event, "the frontend stopped the run loop");
+            break event;
+        }
+
+        // Only the time that has passed gets handed to the scheduler, so the
+        // time that is spent running instructions isn't lost
+        let now = SystemTime::now();
+        scheduler.advance(chip8, frontend, calculate_duration(last_time, now))?;
+        last_time = now;
+
+        // There is nothing left to run once the rom has exited
+        if chip8.has_exited() {
+            info!("the rom exited");
+            break Event::Exit;
+        }
+
+        // Nothing happens until the next frame is due, so instead of spinning
+        // around the loop until then, the thread sleeps. Sleeping tends to go
+        // a little long, but that time isn't lost, it just gets handed to the
+        // scheduler along with the rest, so the frames still come out right on
+        // average. The time that the frames took to run is already part of
+        // the wait.
+        let spent = calculate_duration(last_time, SystemTime::now());
+        thread::sleep(scheduler.until_next_frame().saturating_sub(spent));
+    };
+    scheduler.stop(frontend)?;
+    // Yay, nothing broke
+    Ok(event)
+}
+
+// This is just a helper function, going into the semantic compression theory
+// being, if you use it more than once, make it into a function
+fn calculate_duration(time_from: SystemTime, now: SystemTime) -> Duration {
+    // Get the duration, and check to see if it makes sense/throws an error
+    match now.duration_since(time_from) {
+        Ok(duration) => duration,      // The duration is reasonable
+        Err(_) => Duration::new(0, 0), // The duration is negative
+    }
+}
@@ -0,0 +1,224 @@
+//! This module contains gamepad support, which lets the buttons on a controller
+//! press the keys on the hex keypad, on top of whatever the frontend does.
+//!
+//! Which button presses which key is read from a mapping file, from `--gamepad`
+//! or from a file next to the rom with the same name ending in `.pad`, so each
+//! rom can have its own. Every line is a button and the hex key it presses, and
+//! anything after a `#` is ignored:
+//! ```text
+//! # pong only uses 1 and 4 to move the paddle
+//! dpad-up 1
+//! dpad-down 4
+//! ```
+
+use crate::config::Config;
+use chip_8::{
+    frontend::{Audio, Display, Event, Input},
+    Chip8,
+};
+use gilrs::{Button, EventType, Gilrs};
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+/// The name that each button goes by in a mapping file
+const BUTTONS: [(&str, Button); 17] = [
+    ("dpad-up", Button::DPadUp),
+    ("dpad-down", Button::DPadDown),
+    ("dpad-left", Button::DPadLeft),
+    ("dpad-right", Button::DPadRight),
+    ("south", Button::South),
+    ("east", Button::East),
+    ("north", Button::North),
+    ("west", Button::West),
+    ("left-trigger", Button::LeftTrigger),
+    ("left-trigger-2", Button::LeftTrigger2),
+    ("right-trigger", Button::RightTrigger),
+    ("right-trigger-2", Button::RightTrigger2),
+    ("select", Button::Select),
+    ("start", Button::Start),
+    ("mode", Button::Mode),
+    ("left-thumb", Button::LeftThumb),
+    ("right-thumb", Button::RightThumb),
+];
+
+/// The mapping used when there isn't a file for it, the d-pad presses the keys
+/// that most roms move with, which are laid out the same way on the keypad
+const DEFAULT_MAPPING: [(Button, u8); 10] = [
+    (Button::DPadUp, 0x2),
+    (Button::DPadDown, 0x8),
+    (Button::DPadLeft, 0x4),
+    (Button::DPadRight, 0x6),
+    (Button::South, 0x5),
+    (Button::East, 0x6),
+    (Button::West, 0x4),
+    (Button::North, 0x0),
+    (Button::Start, 0xf),
+    (Button::Select, 0xe),
+];
+
+/// Which buttons press which keys on the keypad, a button can press more than
+/// one key
+pub struct Mapping {
+    buttons: Vec<(Button, u8)>,
+}
+
+impl Mapping {
+    /// Reads the mapping from `--gamepad`, or the `.pad` file next to the rom,
+    /// or uses the default one if neither is there
+    pub fn load(rom: &Path, config: &Config) -> Result<Mapping, Error> {
+        let path = match &config.gamepad {
+            Some(path) => path.clone(),
+            None => rom.with_extension("pad"),
+        };
+        if config.gamepad.is_none() && !path.exists() {
+            return Ok(Mapping::default());
+        }
+        Mapping::parse(&fs::read_to_string(&path)?)
+    }
+
+    /// Reads the mapping out of the lines of a mapping file
+    pub fn parse(text: &str) -> Result<Mapping, Error> {
+        let mut buttons = Vec::new();
+        for line in text.lines() {
+            // Everything after a `#` is a comment
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let (name, key) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(name), Some(key), None) => (name, key),
+                _ => return Err(invalid(format!("{} isn't a button and a key", line))),
+            };
+            let button = BUTTONS
+                .iter()
+                .find(|(button_name, _)| *button_name == name)
+                .map(|(_, button)| *button)
+                .ok_or_else(|| invalid(format!("there is no button called {}", name)))?;
+            let key = u8::from_str_radix(key, 16)
+                .ok()
+                .filter(|key| *key < 16)
+                .ok_or_else(|| invalid(format!("{} isn't a key from 0 to f", key)))?;
+            buttons.push((button, key));
+        }
+        Ok(Mapping { buttons })
+    }
+
+    /// Returns every key that the button presses
+    fn keys<'a>(&'a self, button: Button) -> impl Iterator<Item = u8> + 'a {
+        self.buttons
+            .iter()
+            .filter(move |(mapped, _)| *mapped == button)
+            .map(|(_, key)| *key)
+    }
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Mapping {
+            buttons: DEFAULT_MAPPING.to_vec(),
+        }
+    }
+}
+
+/// Wraps a frontend and presses the keys for the buttons that are held down on
+/// any connected gamepad
+pub struct GamepadFrontend<'a, F> {
+    frontend: &'a mut F,
+    /// There isn't one if gamepads can't be read on this system, then this
+    /// does nothing
+    gilrs: Option<Gilrs>,
+    mapping: Mapping,
+    /// Which keys are held down on the gamepad
+    held: [bool; 16],
+}
+
+impl<'a, F> GamepadFrontend<'a, F> {
+    /// Starts listening for gamepads, with the mapping for the rom
+    pub fn new(frontend: &'a mut F, mapping: Mapping) -> Self {
+        GamepadFrontend {
+            frontend,
+            // The keyboard still works without a gamepad, so not being able to
+            // read them isn't worth stopping over
+            gilrs: Gilrs::new().ok(),
+            mapping,
+            held: [false; 16],
+        }
+    }
+}
+
+impl<'a, F: Input> Input for GamepadFrontend<'a, F> {
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        let event = self.frontend.handle_input(chip8);
+        let gilrs = match &mut self.gilrs {
+            Some(gilrs) => gilrs,
+            None => return event,
+        };
+        while let Some(gamepad_event) = gilrs.next_event() {
+            match gamepad_event.event {
+                EventType::ButtonPressed(button, _) => {
+                    for key in self.mapping.keys(button) {
+                        self.held[key as usize] = true;
+                        chip8.press_key(key);
+                    }
+                }
+                EventType::ButtonReleased(button, _) => {
+                    for key in self.mapping.keys(button) {
+                        self.held[key as usize] = false;
+                        chip8.release_key(key);
+                    }
+                }
+                // Nothing can be let go of on a gamepad that isn't there anymore
+                EventType::Disconnected => {
+                    for (key, is_held) in self.held.iter_mut().enumerate() {
+                        if *is_held {
+                            *is_held = false;
+                            chip8.release_key(key as u8);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        event
+    }
+
+    fn start_frame(&mut self, chip8: &mut Chip8) {
+        self.frontend.start_frame(chip8);
+    }
+
+    fn before_instruction(&mut self, chip8: &mut Chip8) {
+        self.frontend.before_instruction(chip8);
+    }
+
+    fn tick(&mut self, chip8: &mut Chip8) {
+        self.frontend.tick(chip8);
+        // The terminal lets go of every key each frame, since it can't tell
+        // when they are let go of, but the gamepad can
+        for (key, is_held) in self.held.iter().enumerate() {
+            if *is_held {
+                chip8.press_key(key as u8);
+            }
+        }
+    }
+}
+
+impl<'a, F: Display> Display for GamepadFrontend<'a, F> {
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error> {
+        self.frontend.draw(chip8)
+    }
+}
+
+impl<'a, F: Audio> Audio for GamepadFrontend<'a, F> {
+    fn set_playing(&mut self, playing: bool) -> Result<(), Error> {
+        self.frontend.set_playing(playing)
+    }
+}
+
+/// Creates the error for a mapping file that doesn't make sense
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
@@ -0,0 +1,254 @@
+//! This module contains the winit + pixels frontend, which draws the
+//! interpreter's screen with the GPU so that the pixels stay crisp at any
+//! integer scale, without needing any system libraries like SDL.
+//!
+//! It is only built when the `gpu` feature is turned on.
+
+use crate::{
+    config::Config, keymap::Keymap, palette::Palette, phosphor::Phosphor, rom, rotation::Rotation,
+};
+use chip_8::{
+    frontend::{Audio, Display, Event, Input},
+    Chip8,
+};
+use pixels::{Pixels, PixelsBuilder, SurfaceTexture};
+use std::{io::Error, path::Path};
+use winit::{
+    dpi::LogicalSize,
+    event::{ElementState, Event as WinitEvent, KeyboardInput, VirtualKeyCode, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::{Window, WindowBuilder},
+};
+
+/// How many times bigger than the interpreter's screen the window starts out as
+const STARTING_SCALE: f64 = 10.0;
+
+/// The state that is needed for the window and the GPU surface
+pub struct GpuApp {
+    event_loop: EventLoop<()>,
+    // The window has to stay alive for as long as the surface that draws to
+    // it, and gets the title of the rom
+    window: Window,
+    pixels: Pixels,
+    palette: Palette,
+    /// Fades the pixels out instead of turning them off straight away, when
+    /// it is on
+    phosphor: Phosphor,
+    /// How far the screen is turned
+    rotation: Rotation,
+    keymap: Keymap,
+    /// The size of the screen that the texture is made for, before it is
+    /// turned
+    size: (usize, usize),
+}
+
+impl GpuApp {
+    /// Opens the window, and sets up the surface to draw to
+    pub fn new(palette: Palette) -> Result<Self, Error> {
+        let event_loop = EventLoop::new();
+        let window = WindowBuilder::new()
+            .with_title("Chip-8")
+            .with_inner_size(LogicalSize::new(
+                64.0 * STARTING_SCALE,
+                32.0 * STARTING_SCALE,
+            ))
+            .with_min_inner_size(LogicalSize::new(64.0, 32.0))
+            .build(&event_loop)
+            .map_err(gpu_error)?;
+
+        let size = window.inner_size();
+        let surface_texture = SurfaceTexture::new(size.width, size.height, &window);
+        // pixels only ever scales the screen by whole numbers, and puts a border
+        // around whatever space is left over, so every pixel is the same size
+        let pixels = PixelsBuilder::new(64, 32, surface_texture)
+            .enable_vsync(true)
+            .build()
+            .map_err(gpu_error)?;
+
+        Ok(GpuApp {
+            event_loop,
+            window,
+            pixels,
+            palette,
+            phosphor: Phosphor::new(None),
+            rotation: Rotation::None,
+            keymap: Keymap::default(),
+            size: (64, 32),
+        })
+    }
+
+    /// Runs the rom in the window until it gets closed
+    pub fn run(&mut self, rom: &Path, config: &Config) -> Result<(), Error> {
+        self.phosphor = Phosphor::for_rom(config, rom);
+        let (rotation, turn_keys) = Rotation::for_rom(config, rom);
+        self.rotation = rotation;
+        self.keymap = if turn_keys {
+            rotation.turn_keymap(config.keymap)
+        } else {
+            config.keymap
+        };
+        // Roms that are in the database have their title on the window
+        if let Some(entry) = config.database.find_file(rom) {
+            self.window.set_title(&format!("Chip-8 - {}", entry.name()));
+        }
+        // The window can't pick another rom, so it just closes
+        rom::run(self, rom, config).map(|_| ())
+    }
+}
+
+impl Input for GpuApp {
+    /// winit wants to own the loop, so instead the events that have built up are
+    /// pumped through every time the run loop asks for input
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        let pixels = &mut self.pixels;
+        let keymap = self.keymap;
+        let phosphor = &mut self.phosphor;
+        let mut quit = false;
+
+        self.event_loop.run_return(|event, _, control_flow| {
+            *control_flow = ControlFlow::Poll;
+            match event {
+                WinitEvent::WindowEvent { event, .. } => match event {
+                    WindowEvent::CloseRequested => quit = true,
+                    WindowEvent::Resized(size) => {
+                        // If the surface can't be resized, the old size keeps being used
+                        let _ = pixels.resize_surface(size.width, size.height);
+                    }
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(keycode),
+                                state,
+                                ..
+                            },
+                        ..
+                    } => {
+                        if keycode == VirtualKeyCode::Escape {
+                            quit = true;
+                        } else if keycode == VirtualKeyCode::F8 {
+                            // Turns the ghosting on or off
+                            if state == ElementState::Pressed {
+                                phosphor.toggle();
+                                chip8.request_redraw();
+                            }
+                        } else if let Some(key) = key_character(keycode).and_then(|c| keymap.key(c))
+                        {
+                            match state {
+                                ElementState::Pressed => chip8.press_key(key),
+                                ElementState::Released => chip8.release_key(key),
+                            }
+                        } else if let Some(key) =
+                            key_character(keycode).and_then(|c| keymap.second_key(c))
+                        {
+                            match state {
+                                ElementState::Pressed => chip8.press_second_key(key),
+                                ElementState::Released => chip8.release_second_key(key),
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+                // Every event that was waiting has been handled, so hand control
+                // back to the run loop
+                WinitEvent::MainEventsCleared => *control_flow = ControlFlow::Exit,
+                _ => {}
+            }
+        });
+
+        if quit {
+            Some(Event::Quit)
+        } else {
+            None
+        }
+    }
+
+    /// Fades the pixels out, which needs the screen to be drawn again
+    fn tick(&mut self, chip8: &mut Chip8) {
+        if self.phosphor.update(chip8) {
+            chip8.request_redraw();
+        }
+    }
+}
+
+impl Display for GpuApp {
+    /// Copies the screen into the GPU texture, one RGBA pixel at a time
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error> {
+        let screen = chip8.screen();
+        let palette = self.palette;
+        let phosphor = &self.phosphor;
+        let rotation = self.rotation;
+        let (width, height) = rotation.size(screen.size());
+
+        // MEGA-CHIP can switch to a bigger screen while it is running
+        if screen.size() != self.size {
+            self.size = screen.size();
+            self.pixels
+                .resize_buffer(width as u32, height as u32)
+                .map_err(gpu_error)?;
+        }
+
+        let megachip = chip8.megachip().filter(|megachip| megachip.is_on());
+        for (i, pixel) in self.pixels.frame_mut().chunks_exact_mut(4).enumerate() {
+            // The texture is the turned screen, so each of its pixels comes
+            // from somewhere else on the interpreter's
+            let (x, y) = match rotation.to_screen(i % width, i / width, screen.size()) {
+                Some(position) => position,
+                None => continue,
+            };
+            let color = match megachip {
+                Some(megachip) => megachip.color(x, y),
+                // CHIP-8X colors are in the screen itself
+                None => screen
+                    .color(x, y)
+                    .unwrap_or_else(|| palette.blend(phosphor.brightness(chip8, x, y))),
+            };
+            pixel.copy_from_slice(&[color[0], color[1], color[2], 0xff]);
+        }
+
+        self.pixels.render().map_err(gpu_error)
+    }
+}
+
+impl Audio for GpuApp {
+    /// There is no audio device without SDL, so this does nothing
+    fn set_playing(&mut self, _playing: bool) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Turns the errors from winit and pixels into an `io::Error` so that they can
+/// be returned the same way as the terminal's
+fn gpu_error<E: ToString>(error: E) -> Error {
+    Error::other(error.to_string())
+}
+
+/// Returns the character that is on the key, for the keys that can be in a
+/// keymap, winit's keycodes already follow the layout of the keyboard
+fn key_character(keycode: VirtualKeyCode) -> Option<char> {
+    use VirtualKeyCode::*;
+    const LETTERS: [VirtualKeyCode; 26] = [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    ];
+    const DIGITS: [VirtualKeyCode; 10] =
+        [Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9];
+
+    if let Some(position) = LETTERS.iter().position(|letter| *letter == keycode) {
+        return Some((b'a' + position as u8) as char);
+    }
+    if let Some(position) = DIGITS.iter().position(|digit| *digit == keycode) {
+        return Some((b'0' + position as u8) as char);
+    }
+    match keycode {
+        Apostrophe => Some('\''),
+        Comma => Some(','),
+        Period => Some('.'),
+        Semicolon => Some(';'),
+        Slash => Some('/'),
+        Minus => Some('-'),
+        Equals => Some('='),
+        LBracket => Some('['),
+        RBracket => Some(']'),
+        _ => None,
+    }
+}
@@ -0,0 +1,86 @@
+//! This module contains hi-res Chip-8, the version of the VIP's interpreter
+//! that used two pages of the display memory to get a 64x64 screen instead of
+//! the 64x32 one.
+//!
+//! The roms for it start with `1260`, which jumped into the code at `0x260`
+//! that set up the bigger screen on the VIP, and the rom's own program starts
+//! at `0x2c0`, right after that code. The interpreter sets the screen up
+//! itself, so that code never runs, the rom just starts at `0x2c0`. The 4x5
+//! font gets moved to where that code was, since `fx29` needs it somewhere,
+//! and `FONT_ADDRESS` is out of the way of everything else the rom has.
+//!
+//! Like CHIP-8X, it is only there once it is turned on with
+//! `Chip8::enable_hires`, which should be after the rom is loaded, since the
+//! font goes on top of the rom. The only instruction that it adds is:
+//! - `0230` clears the screen, the same as `00e0`
+//!
+//! ```rust
+//! use chip_8::{hires, Chip8};
+//!
+//! let rom = [0x12, 0x60];
+//! assert!(hires::is_hires_rom(&rom));
+//!
+//! let mut chip8 = Chip8::with_seed(0);
+//! chip8.load(&rom).unwrap();
+//! chip8.enable_hires();
+//! assert_eq!(chip8.screen().size(), (64, 64));
+//! assert_eq!(chip8.program_counter, 0x2c0);
+//! ```
+
+use crate::{chip8::Chip8, font::FONT_SIZE, screen::Screen};
+
+/// How many pixels wide the screen is
+pub const WIDTH: usize = 64;
+/// How many pixels tall the screen is, which is twice as many as normal
+pub const HEIGHT: usize = 64;
+
+/// Where the 4x5 font gets moved to, which is where the code that set up the
+/// screen was
+pub const FONT_ADDRESS: usize = 0x260;
+
+/// What every hi-res rom starts with, which is a jump to `0x260`
+const STARTUP_JUMP: [u8; 2] = [0x12, 0x60];
+/// Where the rom's own program starts
+pub const PROGRAM_START: usize = 0x2c0;
+
+/// Returns whether the rom looks like it was made for hi-res Chip-8, which is
+/// when it starts with `1260`
+pub fn is_hires_rom(rom: &[u8]) -> bool {
+    rom.starts_with(&STARTUP_JUMP)
+}
+
+impl Chip8 {
+    /// Turns on hi-res Chip-8, which makes the screen 64x64, moves the font,
+    /// and skips over the jump at the start of the rom if it is still there.
+    /// This should be called after the rom is loaded.
+    pub fn enable_hires(&mut self) {
+        self.hires = true;
+        self.screen = Screen::new(WIDTH, HEIGHT).expect("every screen has room for 64x64");
+        self.request_redraw();
+
+        // The font is copied from wherever it was, in case it was replaced,
+        // unless memory is too small for it to go there
+        if self.memory.len() >= FONT_ADDRESS + FONT_SIZE {
+            self.memory.copy_within(
+                self.font_address..self.font_address + FONT_SIZE,
+                FONT_ADDRESS,
+            );
+            self.font_address = FONT_ADDRESS;
+        }
+
+        let start = self.program_counter;
+        if self.memory.get(start..start + 2) == Some(&STARTUP_JUMP[..]) {
+            self.program_counter = PROGRAM_START;
+        }
+    }
+
+    /// Returns whether hi-res Chip-8 has been turned on
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    /// Returns where the 4x5 font that `fx29` points at starts in memory
+    pub fn font_address(&self) -> usize {
+        self.font_address
+    }
+}
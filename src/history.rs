@@ -0,0 +1,68 @@
+//! This module contains the list of roms that were played recently, which is
+//! kept in a file in the user's home directory so it is still there the next
+//! time the app is started
+
+use std::{
+    env, fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+/// How many roms are remembered, the oldest one gets forgotten after this
+const MAX_ROMS: usize = 10;
+
+/// The roms that were played recently, with the most recent one first
+pub struct History {
+    /// Where the list gets saved to
+    path: PathBuf,
+    roms: Vec<PathBuf>,
+}
+
+impl History {
+    /// Reads the list out of `~/.chip_8_history`, which has one rom per line,
+    /// if the file isn't there yet then nothing has been played
+    pub fn load() -> Self {
+        let path = env::var_os("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_default()
+            .join(".chip_8_history");
+        let roms = fs::read_to_string(&path)
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(PathBuf::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        History { path, roms }
+    }
+
+    /// The roms that were played recently, with the most recent one first
+    #[cfg(feature = "terminal")]
+    pub fn roms(&self) -> &[PathBuf] {
+        &self.roms
+    }
+
+    /// The rom that was played last, which is what `--resume` starts
+    pub fn last(&self) -> Option<&Path> {
+        self.roms.first().map(PathBuf::as_path)
+    }
+
+    /// Moves the rom to the top of the list and saves it
+    pub fn record(&mut self, rom: &Path) -> Result<(), Error> {
+        // The full path is saved, so the rom can be found again when the app is
+        // started from a different directory
+        let rom = rom.canonicalize().unwrap_or_else(|_| rom.to_path_buf());
+        self.roms.retain(|recent| *recent != rom);
+        self.roms.insert(0, rom);
+        self.roms.truncate(MAX_ROMS);
+
+        let mut contents = String::new();
+        for recent in self.roms.iter() {
+            contents.push_str(&recent.to_string_lossy());
+            contents.push('\n');
+        }
+        fs::write(&self.path, contents)
+    }
+}
@@ -0,0 +1,409 @@
+//! This module contains an experimental JIT, which compiles the rom to native
+//! code with cranelift instead of interpreting it one instruction at a time.
+//!
+//! Only runs of the arithmetic instructions (`6xnn`, `7xnn`, `8xyn`, and
+//! `annn`) get compiled, into a block that keeps the registers in native
+//! registers until it's done. Anything else, like jumps, drawing, and the
+//! timers, is still ran by the interpreter, so the two take turns, and there
+//! is nothing that the JIT does differently.
+//!
+//! Roms are allowed to write over their own code, so every block remembers the
+//! bytes that it was compiled from, and checks them before it runs. If they
+//! have changed, then that address is left to the interpreter from then on,
+//! since a rom that has done it once is likely to keep doing it.
+//!
+//! The instructions that get ran in a block aren't seen by anything that looks
+//! at every instruction, like the debugger or `Input::before_instruction`, so
+//! the JIT is only worth it as a turbo mode, for running a rom as fast as it
+//! can go.
+//!
+//! ```rust
+//! use chip_8::{jit::Jit, Chip8};
+//!
+//! let mut chip8 = Chip8::new();
+//! // `6005` sets register 0 to 5, `7003` adds 3 to it, `1204` then jumps back
+//! // to itself forever
+//! chip8.load(&[0x60, 0x05, 0x70, 0x03, 0x12, 0x04]).unwrap();
+//! let mut jit = Jit::new().unwrap();
+//! jit.frame(&mut chip8, 16).unwrap();
+//! assert_eq!(chip8.registers[0], 8);
+//! ```
+
+use crate::{
+    chip8::{Chip8, Opcode},
+    error::Chip8Error,
+    quirks::Quirks,
+};
+use cranelift_codegen::{
+    entity::EntityRef,
+    ir::{condcodes::IntCC, types, AbiParam, InstBuilder, MemFlags},
+    settings::{self, Configurable},
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Module};
+use std::{io::Error, mem};
+
+/// The most instructions that are put into one block, so that a block never
+/// runs for much longer than the cycles it was given
+const MAX_BLOCK_LENGTH: usize = 64;
+
+/// The instructions that can be put into a block, which are the ones that only
+/// change the registers and the index
+const COMPILED: [&str; 14] = [
+    "ld", "add", "ldy", "or", "and", "xor", "addy", "sub", "subn", "shr", "shry", "shl", "shly",
+    "ldi",
+];
+
+/// The compiled code for a block, it is given the registers and the index
+type BlockFunction = unsafe extern "C" fn(*mut u8, *mut usize);
+
+/// A run of instructions that has been compiled to native code
+struct Block {
+    /// The bytes that the block was compiled from, to see if the rom has
+    /// written over them since
+    bytes: Vec<u8>,
+    /// How many instructions are in the block
+    length: usize,
+    function: BlockFunction,
+}
+
+/// What is known about the code at an address
+enum Entry {
+    /// Nothing has tried to run it yet
+    Unknown,
+    /// It is always left to the interpreter, either because the instruction
+    /// there can't be compiled, or because the rom wrote over the block that
+    /// was there
+    Interpreted,
+    Compiled(Block),
+}
+
+/// Everything about the interpreter that changes what the instructions do, the
+/// blocks are thrown away whenever it changes
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Setup {
+    quirks: Quirks,
+    megachip: bool,
+    chip8x: bool,
+    hires: bool,
+}
+
+impl Setup {
+    fn of(chip8: &Chip8) -> Setup {
+        Setup {
+            quirks: chip8.quirks,
+            megachip: chip8.megachip().is_some(),
+            chip8x: chip8.is_chip8x(),
+            hires: chip8.is_hires(),
+        }
+    }
+}
+
+/// Runs a rom with the blocks that it can compile, and the interpreter for
+/// everything else
+pub struct Jit {
+    module: JITModule,
+    /// What is known about every address in memory, this is looked up for
+    /// every block that runs, so it is a list instead of a map
+    entries: Vec<Entry>,
+    /// What the blocks were compiled for
+    setup: Option<Setup>,
+}
+
+impl Jit {
+    /// Sets up cranelift for the computer that this is running on, which fails
+    /// if it doesn't know how to make code for it
+    pub fn new() -> Result<Jit, Error> {
+        let mut flags = settings::builder();
+        // The blocks don't call anything, so none of this is needed
+        flags
+            .set("use_colocated_libcalls", "false")
+            .map_err(jit_error)?;
+        flags.set("is_pic", "false").map_err(jit_error)?;
+        let isa = cranelift_native::builder()
+            .map_err(jit_error)?
+            .finish(settings::Flags::new(flags))
+            .map_err(jit_error)?;
+        Ok(Jit {
+            module: JITModule::new(JITBuilder::with_isa(isa, default_libcall_names())),
+            entries: Vec::new(),
+            setup: None,
+        })
+    }
+
+    /// Runs `cycles` instructions, the same as calling `Chip8::clock` that many
+    /// times
+    pub fn run(&mut self, chip8: &mut Chip8, cycles: usize) -> Result<(), Chip8Error> {
+        let setup = Setup::of(chip8);
+        if self.setup != Some(setup) {
+            // The code for the old blocks stays around, but this only happens
+            // when the quirks or extensions get changed
+            self.entries = (0..chip8.memory.len()).map(|_| Entry::Unknown).collect();
+            self.setup = Some(setup);
+        }
+
+        let mut remaining = cycles;
+        while remaining > 0 {
+            match self.block_at(chip8, chip8.program_counter) {
+                // A block that has more instructions than are left would run
+                // past the end of the frame, so the interpreter finishes it.
+                // The hooks only get called, and the journal only kept, by the
+                // interpreter, so it runs everything while they are there. It
+                // is also left to decide what happens out of bounds.
+                Some((length, function))
+                    if length <= remaining
+                        && !chip8.has_hooks()
+                        && !chip8.is_journal_on()
+                        && chip8.program_counter_in_bounds() =>
+                {
+                    // SAFETY: the block only reads and writes the 16 registers
+                    // and the index, which are what it is given
+                    unsafe { function(chip8.registers.as_mut_ptr(), &mut chip8.index) };
+                    chip8.program_counter += length * 2;
+                    remaining -= length;
+                }
+                _ => {
+                    chip8.clock()?;
+                    remaining -= 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs `cycles` instructions and then ticks the timers once, the same as
+    /// `Chip8::frame`
+    pub fn frame(&mut self, chip8: &mut Chip8, cycles: usize) -> Result<(), Chip8Error> {
+        self.run(chip8, cycles)?;
+        chip8.tick_timers();
+        Ok(())
+    }
+
+    /// Returns how many blocks have been compiled and can still be ran
+    pub fn block_count(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry, Entry::Compiled(_)))
+            .count()
+    }
+
+    /// Gets the block that starts at the address, compiling it if it hasn't
+    /// been yet, or nothing if the interpreter has to run the instruction there
+    fn block_at(&mut self, chip8: &Chip8, address: usize) -> Option<(usize, BlockFunction)> {
+        // The interpreter is the one that stops a program counter that has
+        // gone out of memory
+        match self.entries.get(address)? {
+            Entry::Interpreted => return None,
+            Entry::Compiled(block) => {
+                if chip8.memory[address..address + block.bytes.len()] == block.bytes[..] {
+                    return Some((block.length, block.function));
+                }
+                // The rom has written over the block, so it goes back to being
+                // interpreted
+                self.entries[address] = Entry::Interpreted;
+                return None;
+            }
+            Entry::Unknown => {}
+        }
+
+        match self.compile(chip8, address) {
+            Some(block) => {
+                let found = (block.length, block.function);
+                self.entries[address] = Entry::Compiled(block);
+                Some(found)
+            }
+            None => {
+                self.entries[address] = Entry::Interpreted;
+                None
+            }
+        }
+    }
+
+    /// Compiles the instructions starting at the address up until the first one
+    /// that can't be compiled, there has to be at least one
+    fn compile(&mut self, chip8: &Chip8, start: usize) -> Option<Block> {
+        // Finds how far the block goes
+        let mut opcodes = Vec::new();
+        let mut address = start;
+        while opcodes.len() < MAX_BLOCK_LENGTH && address + 1 < chip8.memory.len() {
+            let opcode =
+                Opcode::new((chip8.memory[address] as u16) << 8 | chip8.memory[address + 1] as u16);
+            let (mnemonic, _) = chip8.parse_opcode(&opcode);
+            if !is_compiled(mnemonic) {
+                break;
+            }
+            opcodes.push((mnemonic, opcode));
+            address += 2;
+        }
+        if opcodes.is_empty() {
+            return None;
+        }
+
+        let pointer = self.module.target_config().pointer_type();
+        let mut context = self.module.make_context();
+        context.func.signature.params.push(AbiParam::new(pointer));
+        context.func.signature.params.push(AbiParam::new(pointer));
+
+        let mut builder_context = FunctionBuilderContext::new();
+        let mut builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        builder.seal_block(entry);
+        let registers_pointer = builder.block_params(entry)[0];
+        let index_pointer = builder.block_params(entry)[1];
+        let flags = MemFlags::trusted();
+
+        // Every register is loaded into a variable at the start, and stored
+        // back at the end, so in between cranelift can keep them wherever
+        let registers: Vec<Variable> = (0..16)
+            .map(|i| {
+                let register = Variable::new(i);
+                builder.declare_var(register, types::I8);
+                let value = builder
+                    .ins()
+                    .load(types::I8, flags, registers_pointer, i as i32);
+                builder.def_var(register, value);
+                register
+            })
+            .collect();
+        // Only the registers that get changed are stored back
+        let mut written = [false; 16];
+        let vf = registers[0xf];
+        let quirks = chip8.quirks;
+        let mut index = None;
+
+        // Each instruction is done in the same order as the interpreter does
+        // it, so that using register f as x or y comes out the same
+        for (mnemonic, opcode) in opcodes.iter() {
+            let x = registers[opcode.x as usize];
+            let y = registers[opcode.y as usize];
+            match *mnemonic {
+                "ldi" => {}
+                // Everything but the first few can set register f
+                "ld" | "add" | "ldy" => written[opcode.x as usize] = true,
+                _ => {
+                    written[opcode.x as usize] = true;
+                    written[0xf] = true;
+                }
+            }
+            match *mnemonic {
+                "ld" => {
+                    let value = builder.ins().iconst(types::I8, opcode.nn as i64);
+                    builder.def_var(x, value);
+                }
+                "add" => {
+                    let value = builder.use_var(x);
+                    let sum = builder.ins().iadd_imm(value, opcode.nn as i64);
+                    builder.def_var(x, sum);
+                }
+                "ldy" => {
+                    let value = builder.use_var(y);
+                    builder.def_var(x, value);
+                }
+                "or" | "and" | "xor" => {
+                    let a = builder.use_var(x);
+                    let b = builder.use_var(y);
+                    let result = match *mnemonic {
+                        "or" => builder.ins().bor(a, b),
+                        "and" => builder.ins().band(a, b),
+                        _ => builder.ins().bxor(a, b),
+                    };
+                    builder.def_var(x, result);
+                    if quirks.vf_reset {
+                        let zero = builder.ins().iconst(types::I8, 0);
+                        builder.def_var(vf, zero);
+                    }
+                }
+                "addy" | "sub" | "subn" => {
+                    let a = builder.use_var(x);
+                    let b = builder.use_var(y);
+                    // The flag is 1 for a carry when adding, and 1 for no
+                    // borrow when subtracting
+                    let (result, flag) = match *mnemonic {
+                        "addy" => {
+                            let sum = builder.ins().iadd(a, b);
+                            (sum, builder.ins().icmp(IntCC::UnsignedLessThan, sum, a))
+                        }
+                        "sub" => (
+                            builder.ins().isub(a, b),
+                            builder.ins().icmp(IntCC::UnsignedGreaterThanOrEqual, a, b),
+                        ),
+                        _ => (
+                            builder.ins().isub(b, a),
+                            builder.ins().icmp(IntCC::UnsignedGreaterThanOrEqual, b, a),
+                        ),
+                    };
+                    builder.def_var(x, result);
+                    builder.def_var(vf, flag);
+                }
+                "shr" | "shry" | "shl" | "shly" => {
+                    let source = if mnemonic.ends_with('y') { y } else { x };
+                    let value = builder.use_var(source);
+                    let (shifted, bit) = if mnemonic.starts_with("shr") {
+                        (
+                            builder.ins().ushr_imm(value, 1),
+                            builder.ins().band_imm(value, 0b1),
+                        )
+                    } else {
+                        (
+                            builder.ins().ishl_imm(value, 1),
+                            builder.ins().ushr_imm(value, 7),
+                        )
+                    };
+                    builder.def_var(x, shifted);
+                    builder.def_var(vf, bit);
+                }
+                // Only the last one matters, so it is stored at the end
+                "ldi" => index = Some(opcode.nnn),
+                _ => unreachable!("{} isn't one of the compiled instructions", mnemonic),
+            }
+        }
+
+        for (i, register) in registers.iter().enumerate() {
+            if !written[i] {
+                continue;
+            }
+            let value = builder.use_var(*register);
+            builder
+                .ins()
+                .store(flags, value, registers_pointer, i as i32);
+        }
+        if let Some(nnn) = index {
+            let value = builder.ins().iconst(pointer, nnn as i64);
+            builder.ins().store(flags, value, index_pointer, 0);
+        }
+        builder.ins().return_(&[]);
+        builder.finalize();
+
+        // Anything going wrong here just means the interpreter runs it instead
+        let id = self
+            .module
+            .declare_anonymous_function(&context.func.signature)
+            .ok()?;
+        self.module.define_function(id, &mut context).ok()?;
+        self.module.clear_context(&mut context);
+        self.module.finalize_definitions().ok()?;
+        // SAFETY: the function was just built with this signature
+        let function = unsafe {
+            mem::transmute::<*const u8, BlockFunction>(self.module.get_finalized_function(id))
+        };
+
+        Some(Block {
+            bytes: chip8.memory[start..address].to_vec(),
+            length: opcodes.len(),
+            function,
+        })
+    }
+}
+
+/// Returns whether the instruction can be put into a block
+fn is_compiled(mnemonic: &str) -> bool {
+    COMPILED.contains(&mnemonic)
+}
+
+/// Turns cranelift's errors into an `io::Error`
+fn jit_error<E: ToString>(error: E) -> Error {
+    Error::other(error.to_string())
+}
@@ -0,0 +1,252 @@
+//! This module contains the undo journal, which keeps what each instruction
+//! changed so that the instructions can be undone again one at a time, newest
+//! first. This is what lets a debugger step backwards through a rom as well as
+//! forwards.
+//!
+//! Everything small (the registers, the index, the timers, the stack, the
+//! flags, where the random numbers are up to, XO-CHIP's audio pattern and
+//! pitch, and whether hi-res is on along with where the font is) is kept
+//! whole for every instruction, while only the bytes of memory and the bytes
+//! of the screen that the instruction changed are kept. The extra state that MEGA-CHIP
+//! keeps isn't in the journal, so its screen doesn't go back.
+//!
+//! ```rust
+//! use chip_8::Chip8;
+//!
+//! let mut chip8 = Chip8::with_seed(0);
+//! // `6005` sets register 0 to 5, `7001` adds 1 to it
+//! chip8.load(&[0x60, 0x05, 0x70, 0x01]).unwrap();
+//! chip8.enable_journal(100);
+//! chip8.clock().unwrap();
+//! chip8.clock().unwrap();
+//! assert_eq!(chip8.registers[0], 6);
+//! assert!(chip8.step_back());
+//! assert_eq!(chip8.registers[0], 5);
+//! assert_eq!(chip8.program_counter, 0x202);
+//! ```
+
+use crate::{audio::AudioState, chip8::Chip8, rng::Rng, screen::Screen};
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
+
+/// Everything about the interpreter that is small enough to keep all of from
+/// before every instruction
+#[derive(Debug, Clone)]
+struct Registers {
+    registers: [u8; 16],
+    index: usize,
+    delay: u8,
+    sound: u8,
+    program_counter: usize,
+    stack_pointer: usize,
+    stack: [usize; 16],
+    subroutines: [usize; 16],
+    flags: [u8; 16],
+    vblank: bool,
+    rng: Rng,
+    has_exited: bool,
+    audio: AudioState,
+    hires: bool,
+    font_address: usize,
+}
+
+/// How the screen was before an instruction changed it
+#[derive(Debug, Clone)]
+enum ScreenChange {
+    /// The bytes of the pixels that changed, and what they were
+    Bytes(Vec<(usize, u8)>),
+    /// The whole screen, for when more than it's pixels changed, like when
+    /// CHIP-8X colors get turned on, which is boxed since the screen is as
+    /// big as the biggest one
+    Whole(Box<Screen>),
+}
+
+/// What one instruction changed
+#[derive(Debug, Clone)]
+struct Entry {
+    registers: Registers,
+    /// The bytes of memory that were written, and what they were, in the order
+    /// that they were written
+    memory: Vec<(usize, u8)>,
+    screen: ScreenChange,
+}
+
+/// The instructions that can be undone, up to however many it was made to keep
+#[derive(Debug, Clone)]
+pub(crate) struct Journal {
+    capacity: usize,
+    entries: VecDeque<Entry>,
+    /// The instruction that is running right now, it goes in with the rest
+    /// once it is done
+    running: Option<Entry>,
+    /// The screen as it was after the last instruction, so it can be seen what
+    /// the next one changed
+    screen: Screen,
+}
+
+impl Chip8 {
+    /// Starts keeping the last `capacity` instructions that run, so that they
+    /// can be undone with `step_back`, anything that was kept before is
+    /// forgotten
+    pub fn enable_journal(&mut self, capacity: usize) {
+        self.journal = Some(Box::new(Journal {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            running: None,
+            screen: self.screen.clone(),
+        }));
+    }
+
+    /// Stops keeping the instructions, and forgets the ones that were kept
+    pub fn disable_journal(&mut self) {
+        self.journal = None;
+    }
+
+    /// Returns whether the instructions are being kept
+    pub fn is_journal_on(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// Returns how many instructions can be undone
+    pub fn journal_len(&self) -> usize {
+        self.journal
+            .as_ref()
+            .map(|journal| journal.entries.len())
+            .unwrap_or(0)
+    }
+
+    /// Undoes the last instruction that ran, and returns whether there was one
+    /// to undo. The keys are left alone, since they aren't the rom's to change.
+    pub fn step_back(&mut self) -> bool {
+        let journal = match &mut self.journal {
+            Some(journal) => journal,
+            None => return false,
+        };
+        let entry = match journal.entries.pop_back() {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        let Registers {
+            registers,
+            index,
+            delay,
+            sound,
+            program_counter,
+            stack_pointer,
+            stack,
+            subroutines,
+            flags,
+            vblank,
+            rng,
+            has_exited,
+            audio,
+            hires,
+            font_address,
+        } = entry.registers;
+        self.registers = registers;
+        self.index = index;
+        self.delay = delay;
+        self.sound = sound;
+        self.program_counter = program_counter;
+        self.stack_pointer = stack_pointer;
+        self.stack = stack;
+        self.subroutines = subroutines;
+        self.flags = flags;
+        self.vblank = vblank;
+        self.rng = rng;
+        self.has_exited = has_exited;
+        self.audio = audio;
+        self.hires = hires;
+        self.font_address = font_address;
+
+        // When the same byte was written more than once, the first value it
+        // had is the one that has to end up there
+        for (address, value) in entry.memory.iter().rev() {
+            self.memory[*address] = *value;
+        }
+        match entry.screen {
+            ScreenChange::Bytes(bytes) => self.screen.restore_bytes(&bytes),
+            ScreenChange::Whole(screen) => {
+                self.screen = *screen;
+                self.screen.mark_dirty();
+            }
+        }
+        journal.screen.clone_from(&self.screen);
+        self.request_redraw();
+        true
+    }
+
+    /// Keeps the state from before the instruction that is about to run
+    pub(crate) fn begin_journal_entry(&mut self) {
+        if self.journal.is_none() {
+            return;
+        }
+        let registers = Registers {
+            registers: self.registers,
+            index: self.index,
+            delay: self.delay,
+            sound: self.sound,
+            program_counter: self.program_counter,
+            stack_pointer: self.stack_pointer,
+            stack: self.stack,
+            subroutines: self.subroutines,
+            flags: self.flags,
+            vblank: self.vblank,
+            rng: self.rng.clone(),
+            has_exited: self.has_exited,
+            audio: self.audio,
+            hires: self.hires,
+            font_address: self.font_address,
+        };
+        if let Some(journal) = &mut self.journal {
+            journal.running = Some(Entry {
+                registers,
+                memory: Vec::new(),
+                screen: ScreenChange::Bytes(Vec::new()),
+            });
+        }
+    }
+
+    /// Keeps what was in memory before the instruction that is running wrote
+    /// over it
+    pub(crate) fn journal_write(&mut self, address: usize) {
+        let value = self.memory[address];
+        if let Some(Journal {
+            running: Some(entry),
+            ..
+        }) = self.journal.as_deref_mut()
+        {
+            entry.memory.push((address, value));
+        }
+    }
+
+    /// Adds the instruction that just ran to the journal, along with how it
+    /// changed the screen, the oldest one is forgotten once it is full
+    pub(crate) fn end_journal_entry(&mut self) {
+        let journal = match &mut self.journal {
+            Some(journal) => journal,
+            None => return,
+        };
+        let mut entry = match journal.running.take() {
+            Some(entry) => entry,
+            None => return,
+        };
+        if journal.screen.as_bytes() != self.screen.as_bytes()
+            || journal.screen.has_colors()
+            || self.screen.has_colors()
+        {
+            entry.screen = match journal.screen.changed_bytes(&self.screen) {
+                Some(bytes) => ScreenChange::Bytes(bytes),
+                None => ScreenChange::Whole(Box::new(journal.screen.clone())),
+            };
+            journal.screen.clone_from(&self.screen);
+        }
+        if journal.capacity == 0 {
+            return;
+        }
+        if journal.entries.len() == journal.capacity {
+            journal.entries.pop_front();
+        }
+        journal.entries.push_back(entry);
+    }
+}
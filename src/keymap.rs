@@ -0,0 +1,132 @@
+//! This module contains the keymap, which is which keys on the keyboard are
+//! pressed for each of the keys on the hex keypad
+
+use std::io::{Error, ErrorKind};
+
+/// The keys of the hex keypad, in the order that they are laid out on it, left
+/// to right and then top to bottom
+const KEYPAD: [u8; 16] = [
+    0x1, 0x2, 0x3, 0xc, //
+    0x4, 0x5, 0x6, 0xd, //
+    0x7, 0x8, 0x9, 0xe, //
+    0xa, 0x0, 0xb, 0xf, //
+];
+
+/// The layouts that can be picked by name, each one is the 4x4 block of keys
+/// on the left of the keyboard, in the same order as `KEYPAD`
+const LAYOUTS: [(&str, &str); 5] = [
+    ("qwerty", "1234qwerasdfzxcv"),
+    ("azerty", "1234azerqsdfwxcv"),
+    ("qwertz", "1234qwerasdfyxcv"),
+    ("dvorak", "1234',.paoeu;qjk"),
+    ("colemak", "1234qwfparstzxcd"),
+];
+
+/// The keys that press CHIP-8X's second keypad, which is the 4x4 block of keys
+/// on the right of the keyboard, in the same order as `KEYPAD`
+const SECOND_KEYPAD: &str = "7890uiopjkl;m,./";
+
+/// Which character on the keyboard presses each key of the hex keypad
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keymap {
+    /// The character for each key, in the same order as `KEYPAD`
+    characters: [char; 16],
+}
+
+impl Keymap {
+    /// Gets the keymap for one of the named layouts, or for the 16 characters
+    /// passed in, which go in the same order as the keypad:
+    /// ```text
+    /// 123c
+    /// 456d
+    /// 789e
+    /// a0bf
+    /// ```
+    pub fn from_name(name: &str) -> Result<Keymap, Error> {
+        let layout = LAYOUTS
+            .iter()
+            .find(|(layout_name, _)| *layout_name == name)
+            .map(|(_, layout)| *layout)
+            .unwrap_or(name);
+
+        let mapped: Vec<char> = layout.chars().map(|c| c.to_ascii_lowercase()).collect();
+        // Two keys on the keypad can't share one on the keyboard
+        let is_unique = mapped
+            .iter()
+            .enumerate()
+            .all(|(i, character)| !mapped[..i].contains(character));
+        if mapped.len() != KEYPAD.len() || !is_unique {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} isn't a layout, or 16 different keys in the order 123c456d789ea0bf",
+                    name
+                ),
+            ));
+        }
+        let mut characters = [' '; 16];
+        characters.copy_from_slice(&mapped);
+        Ok(Keymap { characters })
+    }
+
+    /// Returns the key on the keypad that the character presses, if there is one
+    pub fn key(&self, character: char) -> Option<u8> {
+        let character = character.to_ascii_lowercase();
+        self.characters
+            .iter()
+            .position(|mapped| *mapped == character)
+            .map(|position| KEYPAD[position])
+    }
+
+    /// Returns the character that presses the key on the keypad
+    pub fn character(&self, key: u8) -> char {
+        let position = KEYPAD
+            .iter()
+            .position(|mapped| *mapped == key & 0xf)
+            .unwrap_or_default();
+        self.characters[position]
+    }
+
+    /// Makes the character press the key on the keypad, for moving the keys
+    /// around
+    pub fn set_character(&mut self, key: u8, character: char) {
+        let position = KEYPAD
+            .iter()
+            .position(|mapped| *mapped == key & 0xf)
+            .unwrap_or_default();
+        self.characters[position] = character.to_ascii_lowercase();
+    }
+
+    /// Returns the key on CHIP-8X's second keypad that the character presses,
+    /// if there is one and it isn't already used by the first keypad
+    pub fn second_key(&self, character: char) -> Option<u8> {
+        let character = character.to_ascii_lowercase();
+        if self.characters.contains(&character) {
+            return None;
+        }
+        SECOND_KEYPAD
+            .chars()
+            .position(|mapped| mapped == character)
+            .map(|position| KEYPAD[position])
+    }
+}
+
+impl Default for Keymap {
+    /// The keys that have always been used, on a qwerty keyboard:
+    /// ```text
+    /// 1234
+    /// qwer
+    /// asdf
+    /// zxcv
+    /// ```
+    fn default() -> Self {
+        Keymap {
+            characters: [
+                '1', '2', '3', '4', //
+                'q', 'w', 'e', 'r', //
+                'a', 's', 'd', 'f', //
+                'z', 'x', 'c', 'v', //
+            ],
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! This module contains the keypad that is drawn next to the screen in the
+//! terminal, which shows which keys are down and which ones the rom has
+//! checked, and presses the keys that get clicked on with the mouse, and the
+//! smaller overlay that is drawn over the screen, which shows which character
+//! on the keyboard presses each key
+
+use crate::keymap::Keymap;
+use chip_8::Chip8;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Widget},
+};
+
+/// The keys in the order that the COSMAC VIP's keypad had them
+const LAYOUT: [[u8; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xc],
+    [0x4, 0x5, 0x6, 0xd],
+    [0x7, 0x8, 0x9, 0xe],
+    [0xa, 0x0, 0xb, 0xf],
+];
+/// How many columns and rows each key takes up, with the box around it
+const KEY_COLUMNS: u16 = 5;
+const KEY_ROWS: u16 = 3;
+/// How many columns and rows the whole keypad takes up, with the box around it
+pub const COLUMNS: u16 = KEY_COLUMNS * 4 + 2;
+pub const ROWS: u16 = KEY_ROWS * 4 + 2;
+/// How many columns and rows the overlay takes up, with the box around it,
+/// each key is its digit, an `=`, and the character that presses it, with a
+/// space after it
+const OVERLAY_COLUMNS: u16 = 4 * 4 - 1 + 2;
+const OVERLAY_ROWS: u16 = 4 + 2;
+
+/// Whether the keypad is shown, and where its keys were drawn so that clicks
+/// can be matched up with them
+pub struct Keypad {
+    /// Whether the keypad is shown at all
+    pub is_open: bool,
+    /// Where each key was drawn the last time, nothing can be clicked on
+    /// before it has been drawn
+    keys: Vec<(Rect, u8)>,
+    /// The key that the mouse is holding down
+    held: Option<u8>,
+}
+
+impl Keypad {
+    /// Creates a keypad, shown or not
+    pub fn new(is_open: bool) -> Self {
+        Keypad {
+            is_open,
+            keys: Vec::new(),
+            held: None,
+        }
+    }
+
+    /// Presses the key that the mouse is holding down again, for the start of
+    /// every frame, in case the frontend let go of it
+    pub fn hold_key(&self, chip8: &mut Chip8) {
+        if let Some(key) = self.held {
+            chip8.press_key(key);
+        }
+    }
+
+    /// Presses and lets go of the keys that the mouse clicks on, and returns
+    /// whether anything changed
+    pub fn handle_mouse(&mut self, mouse: &MouseEvent, chip8: &mut Chip8) -> bool {
+        let under = self
+            .keys
+            .iter()
+            .find(|(area, _)| {
+                (area.left()..area.right()).contains(&mouse.column)
+                    && (area.top()..area.bottom()).contains(&mouse.row)
+            })
+            .map(|(_, key)| *key);
+        let held = match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => under,
+            // Dragging moves from key to key, like a finger on a touchscreen,
+            // but only once a key has been clicked on
+            MouseEventKind::Drag(MouseButton::Left) if self.held.is_some() => under,
+            MouseEventKind::Up(MouseButton::Left) => None,
+            _ => return false,
+        };
+        if held == self.held {
+            return false;
+        }
+        if let Some(key) = self.held {
+            chip8.release_key(key);
+        }
+        if let Some(key) = held {
+            chip8.press_key(key);
+        }
+        self.held = held;
+        true
+    }
+
+    /// Draws the keypad into the area, with the `pressed` keys drawn with
+    /// their colors swapped, and the ones that the rom has checked in bold
+    pub fn draw(&mut self, chip8: &Chip8, pressed: u16, area: Rect, buffer: &mut Buffer) {
+        let pane = Block::default().borders(Borders::ALL).title("keypad");
+        let inner = pane.inner(area);
+        pane.render(area, buffer);
+
+        self.keys.clear();
+        let checked = chip8.checked_keys();
+        for (row, keys) in LAYOUT.iter().enumerate() {
+            for (column, key) in keys.iter().enumerate() {
+                let key_area = Rect::new(
+                    inner.x + column as u16 * KEY_COLUMNS,
+                    inner.y + row as u16 * KEY_ROWS,
+                    KEY_COLUMNS,
+                    KEY_ROWS,
+                )
+                .intersection(inner);
+                if key_area.is_empty() {
+                    continue;
+                }
+                let mut style = Style::default();
+                if checked & 1 << key != 0 {
+                    style = style.add_modifier(Modifier::BOLD);
+                }
+                if pressed & 1 << key != 0 || self.held == Some(*key) {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                let outline = Block::default().borders(Borders::ALL).style(style);
+                let label = outline.inner(key_area);
+                outline.render(key_area, buffer);
+                buffer.set_stringn(
+                    label.x + label.width / 2,
+                    label.y,
+                    format!("{:x}", key),
+                    label.width as usize,
+                    style,
+                );
+                self.keys.push((key_area, *key));
+            }
+        }
+    }
+}
+
+/// Draws the overlay in the top right corner of the screen, with the `pressed`
+/// keys drawn with their colors swapped. It is left out when the screen is too
+/// small for it.
+pub fn draw_overlay(keymap: &Keymap, pressed: u16, screen: Rect, buffer: &mut Buffer) {
+    if screen.width < OVERLAY_COLUMNS || screen.height < OVERLAY_ROWS {
+        return;
+    }
+    let area = Rect::new(
+        screen.right() - OVERLAY_COLUMNS,
+        screen.y,
+        OVERLAY_COLUMNS,
+        OVERLAY_ROWS,
+    );
+    // The pixels under it aren't drawn through it
+    Clear.render(area, buffer);
+    let pane = Block::default().borders(Borders::ALL).title("keys");
+    let inner = pane.inner(area);
+    pane.render(area, buffer);
+    for (row, keys) in LAYOUT.iter().enumerate() {
+        for (column, key) in keys.iter().enumerate() {
+            let style = if pressed & 1 << key != 0 {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            buffer.set_stringn(
+                inner.x + column as u16 * 4,
+                inner.y + row as u16,
+                format!("{:x}={}", key, keymap.character(*key)),
+                3,
+                style,
+            );
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! A Chip-8 interpreter that doesn't care what it is being drawn to.
+//!
+//! The interpreter itself lives in the `chip8` module, and everything that it
+//! needs from the outside world (the rom, the keys, and when the timers tick) is
+//! passed in through its public methods, so that any frontend can drive it.
+//!
+//! ```rust
+//! use chip_8::Chip8;
+//!
+//! let mut chip8 = Chip8::new();
+//! // `6005` sets register 0 to 5, `1202` then jumps back to itself forever
+//! chip8.load(&[0x60, 0x05, 0x12, 0x02]).unwrap();
+//! // run one frame worth of instructions, and tick the timers
+//! chip8.frame(16).unwrap();
+//! assert_eq!(chip8.registers[0], 5);
+//! ```
+//!
+//! # Without the standard library
+//! Turning off the default features builds the core of the interpreter with
+//! `no_std`, and without an allocator, so it can run on a microcontroller that
+//! draws the screen with its own display driver. Memory is a fixed 4K array,
+//! and the screen is kept in arrays that are only big enough for hi-res
+//! Chip-8's 64x64 pixels. Without an operating system there is nothing to get
+//! a random seed from, so the `rnd` instruction's numbers should come from a
+//! seed or a random source that is passed in, see
+//! `Chip8Builder::random_source`.
+//!
+//! The `alloc` feature adds everything that needs a heap back in, which is
+//! memory of other sizes, the hooks, the decode cache, devices, the journal,
+//! coverage, cheats, disassembling to text, the calls that filled the stack
+//! in `Chip8Error::StackOverflow`, and the modules that look through a rom,
+//! like `analysis` and `diff`. The `megachip` feature adds MEGA-CHIP, which
+//! needs `alloc` too, and makes the screen's arrays big enough for it's
+//! 256x192 pixels. The frontends and their run loop, fonts, Octo source, and
+//! the tone all need the standard library, which turns on both of them.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+pub mod analysis;
+pub mod audio;
+pub mod bounds;
+#[cfg(feature = "alloc")]
+pub mod breakpoint;
+pub mod builder;
+#[cfg(feature = "alloc")]
+pub mod cheats;
+pub mod chip8;
+pub mod chip8x;
+#[cfg(feature = "alloc")]
+pub mod coverage;
+#[cfg(feature = "alloc")]
+pub mod detect;
+pub mod devices;
+#[cfg(feature = "alloc")]
+pub mod diff;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod font;
+#[cfg(feature = "std")]
+pub mod frontend;
+pub mod hires;
+#[cfg(feature = "jit")]
+pub mod jit;
+#[cfg(feature = "alloc")]
+pub mod journal;
+pub mod load;
+#[cfg(feature = "megachip")]
+pub mod megachip;
+#[cfg(feature = "std")]
+pub mod octo;
+pub mod profile;
+pub mod protection;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quirks;
+mod rng;
+pub mod screen;
+#[cfg(feature = "alloc")]
+pub mod search;
+#[cfg(feature = "alloc")]
+pub mod symbols;
+pub mod timing;
+#[cfg(feature = "web")]
+pub mod web;
+
+pub use crate::builder::Chip8Builder;
+pub use crate::chip8::{CallFrame, Chip8, Instruction, Opcode, StepInfo};
+#[cfg(feature = "alloc")]
+pub use crate::chip8::{DisassembledInstruction, Hook};
+#[cfg(feature = "alloc")]
+pub use crate::error::{AssembleError, CheatError, ConditionError, SymbolError};
+pub use crate::error::{Chip8Error, LoadError};
+pub use crate::load::LoadInfo;
+pub use crate::quirks::Quirks;
+pub use crate::screen::Screen;
@@ -0,0 +1,163 @@
+//! This module contains the loading of roms into memory, which checks that
+//! the rom fits, and has a look at which part of it is code.
+
+#[cfg(feature = "alloc")]
+use crate::chip8::Opcode;
+#[cfg(feature = "megachip")]
+use crate::megachip;
+use crate::{chip8::Chip8, error::LoadError};
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::ops::Range;
+
+/// What was found out about a rom while it was being loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadInfo {
+    /// How many bytes of the rom were loaded into memory
+    pub size: usize,
+    /// Where the data after the code seems to start, this is only a guess from
+    /// following the jumps, calls, and skips from the load address, so code
+    /// that only gets reached with `bnnn` looks like data. It is `None` when
+    /// all of the rom looks like code, and always is without the `alloc`
+    /// feature, which following the code needs.
+    pub data_start: Option<usize>,
+}
+
+impl Chip8 {
+    /// Loads the bytes of the rom into the memory starting at the load
+    /// address, which is `0x200` unless it was changed. A rom that is empty,
+    /// or doesn't fit in the memory after the load address, isn't loaded at
+    /// all, except that MEGA-CHIP can keep roms that are much bigger than
+    /// memory, so it has to be turned on first for them.
+    pub fn load(&mut self, rom: &[u8]) -> Result<LoadInfo, LoadError> {
+        if rom.is_empty() {
+            return Err(LoadError::Empty);
+        }
+        let load_address = self.load_address();
+        #[cfg(feature = "megachip")]
+        let capacity = if self.megachip.is_some() {
+            megachip::MEMORY_SIZE
+        } else {
+            self.memory.len()
+        };
+        #[cfg(not(feature = "megachip"))]
+        let capacity = self.memory.len();
+        let room = capacity.saturating_sub(load_address);
+        if rom.len() > room {
+            return Err(LoadError::TooBig {
+                size: rom.len(),
+                room,
+                load_address,
+            });
+        }
+
+        // Only the start of a MEGA-CHIP rom goes in the normal memory, the
+        // rest is kept with MEGA-CHIP's state
+        let start = load_address.min(self.memory.len());
+        for (byte, value) in self.memory[start..].iter_mut().zip(rom.iter()) {
+            *byte = *value;
+        }
+        #[cfg(feature = "megachip")]
+        self.load_megachip(rom);
+
+        #[cfg(feature = "alloc")]
+        let data_start = {
+            let end = (start + rom.len()).min(self.memory.len());
+            let code_end = self.code_end(start, end);
+            if code_end < end {
+                Some(code_end)
+            } else {
+                None
+            }
+        };
+        #[cfg(not(feature = "alloc"))]
+        let data_start = None;
+        Ok(LoadInfo {
+            size: rom.len(),
+            data_start,
+        })
+    }
+
+    /// Returns where the last instruction that was reached from `start`,
+    /// without going past `end`, ends
+    #[cfg(feature = "alloc")]
+    fn code_end(&self, start: usize, end: usize) -> usize {
+        self.reachable(start, end)
+            .into_iter()
+            .map(|address| {
+                let opcode = self.opcode_at(address);
+                // The 2 bytes after this are part of the address
+                match self.parse_opcode(&opcode).0 {
+                    "ldhi" => address + 4,
+                    _ => address + 2,
+                }
+            })
+            .max()
+            .unwrap_or(start)
+    }
+
+    /// Follows every path through the code from `start` that stays before
+    /// `end`, and returns the address of every instruction that was reached
+    #[cfg(feature = "alloc")]
+    pub(crate) fn reachable(&self, start: usize, end: usize) -> Vec<usize> {
+        self.trace(start, start..end, None)
+    }
+
+    /// Follows every path through the code from `from` that stays inside of
+    /// `bounds`, and returns the address of every instruction that was
+    /// reached. When `calls` is given, the subroutines that get called are
+    /// put in it instead of being followed, so only the code of the one
+    /// subroutine is reached.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn trace(
+        &self,
+        from: usize,
+        bounds: Range<usize>,
+        mut calls: Option<&mut Vec<usize>>,
+    ) -> Vec<usize> {
+        let Range { start, end } = bounds;
+        let mut reached = vec![false; end.saturating_sub(start)];
+        let mut next = vec![from];
+        let mut addresses = Vec::new();
+        while let Some(address) = next.pop() {
+            if address < start || address + 1 >= end || reached[address - start] {
+                continue;
+            }
+            reached[address - start] = true;
+            addresses.push(address);
+            let opcode = self.opcode_at(address);
+            let (mnemonic, _) = self.parse_opcode(&opcode);
+
+            let target = opcode.nnn as usize;
+            match mnemonic {
+                // SUPER-CHIP's switches between the screen sizes aren't ran
+                // here, but the code still keeps going after them
+                "nai" if opcode.code == 0x00fe || opcode.code == 0x00ff => next.push(address + 2),
+                // Nothing comes after these, or where they go can't be known
+                // without running the rom
+                "ret" | "exit" | "jp0" | "nai" => {}
+                "jp" => next.push(target),
+                "call" => match &mut calls {
+                    Some(calls) => {
+                        calls.push(target);
+                        next.push(address + 2);
+                    }
+                    None => next.extend_from_slice(&[target, address + 2]),
+                },
+                "se" | "sne" | "sey" | "sney" | "skp" | "skpn" | "skp2" | "skpn2" => {
+                    next.extend_from_slice(&[address + 2, address + 4])
+                }
+                "ldhi" => next.push(address + 4),
+                _ => next.push(address + 2),
+            }
+        }
+        addresses
+    }
+
+    /// Reads the opcode at the address, which has to have a byte after it
+    #[cfg(feature = "alloc")]
+    pub(crate) fn opcode_at(&self, address: usize) -> Opcode {
+        Opcode::new((self.memory[address] as u16) << 8 | self.memory[address + 1] as u16)
+    }
+}
@@ -0,0 +1,42 @@
+//! This module contains the logging, which writes down what the interpreter
+//! and the run loop are doing to a file, so that when a rom runs wrong there
+//! is something more to go on than what ended up on the screen.
+//!
+//! Nothing gets logged unless `--log-level` is used, and the terminal can't be
+//! written to while it is showing the screen, so it always goes to a file.
+
+use crate::config::Config;
+use std::{fs::File, io::Error, sync::Mutex};
+use tracing::Level;
+
+/// The levels that can be picked, from the least to the most that gets logged
+const LEVELS: [(&str, Level); 5] = [
+    ("error", Level::ERROR),
+    ("warn", Level::WARN),
+    ("info", Level::INFO),
+    ("debug", Level::DEBUG),
+    ("trace", Level::TRACE),
+];
+
+/// Gets the level that goes by the name passed in
+pub fn level_from_name(name: &str) -> Option<Level> {
+    LEVELS
+        .iter()
+        .find(|(level_name, _)| *level_name == name)
+        .map(|(_, level)| *level)
+}
+
+/// Starts writing everything at the level that was picked, or anything more
+/// important, to the log file, if a level was picked at all
+pub fn init(config: &Config) -> Result<(), Error> {
+    let level = match config.log_level {
+        Some(level) => level,
+        None => return Ok(()),
+    };
+    let file = File::create(&config.log_file)?;
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(Mutex::new(file))
+        .try_init()
+        .map_err(Error::other)
+}
@@ -1,12 +1,156 @@
+// Without any of the frontends built in, only the commands like `screenshot`
+// can be used, and the rest of the app is left over
+#![cfg_attr(
+    not(any(feature = "terminal", feature = "sdl", feature = "gpu")),
+    allow(dead_code, unused)
+)]
+
+#[cfg(feature = "terminal")]
 mod app;
-mod chip8;
+mod assemble;
+#[cfg(feature = "terminal")]
+mod browser;
+mod callgraph;
+mod cheat_list;
+mod commands;
+mod config;
+mod database;
+#[cfg(feature = "terminal")]
+mod debugger;
+mod disassemble;
+mod flags;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod history;
+mod keymap;
+#[cfg(feature = "terminal")]
+mod keypad;
+mod logging;
+#[cfg(feature = "terminal")]
+mod memory_search;
+mod movie;
+mod netplay;
+mod palette;
+mod phosphor;
+mod remote;
+#[cfg(feature = "terminal")]
+mod renderer;
+mod rom;
+mod rotation;
+mod screenshot;
+#[cfg(feature = "scripting")]
+mod script;
+#[cfg(feature = "sdl")]
+mod sdl;
+mod sprites;
+mod states;
+#[cfg(feature = "terminal")]
+mod status;
+#[cfg(feature = "terminal")]
+mod threads;
+mod websocket;
 
+#[cfg(feature = "terminal")]
 use app::App;
+use config::{Config, FrontendKind};
+use history::History;
+use std::{
+    env,
+    io::{Error, ErrorKind},
+    path::PathBuf,
+    process,
+};
 
 // Welcome ladies, gentlemen, and others
-fn main() -> Result<(), std::io::Error> {
-    // Here we create a new instance of this application
-    let mut app = App::new();
-    // And run it
-    app.run()
+fn main() {
+    // Everything is ran in a separate function, so that if anything went wrong
+    // it gets shown after the terminal has been restored, so that it can
+    // actually be read
+    if let Err(error) = run() {
+        eprintln!("Error: {}", error);
+        process::exit(1);
+    }
+}
+
+/// Reads the options, and hands the interpreter off to whichever frontend was asked for
+fn run() -> Result<(), Error> {
+    // The first argument is the program's name, which isn't an option
+    let args: Vec<String> = env::args().skip(1).collect();
+    // Screenshots can be taken without opening the terminal at all
+    if args.first().map(String::as_str) == Some("screenshot") {
+        return screenshot::command(args[1..].to_vec());
+    }
+    // So can disassembling, which only prints the rom
+    if args.first().map(String::as_str) == Some("disassemble") {
+        return disassemble::command(args[1..].to_vec());
+    }
+    // And drawing which subroutines call which
+    if args.first().map(String::as_str) == Some("callgraph") {
+        return callgraph::command(args[1..].to_vec());
+    }
+    // And saving the sprites
+    if args.first().map(String::as_str) == Some("sprites") {
+        return sprites::command(args[1..].to_vec());
+    }
+    // And building Octo source
+    if args.first().map(String::as_str) == Some("assemble") {
+        return assemble::command(args[1..].to_vec());
+    }
+    // And comparing save states
+    if args.first().map(String::as_str) == Some("diff") {
+        return states::command(args[1..].to_vec());
+    }
+    let config = Config::from_args(args)?;
+    logging::init(&config)?;
+    let mut history = History::load();
+    let first_rom = first_rom(&config, &history);
+
+    match config.frontend {
+        #[cfg(feature = "terminal")]
+        FrontendKind::Terminal => {
+            // Here we create a new instance of this application
+            let mut app = App::new(&config);
+            // And run it, it loads the roms itself since it can show a menu
+            app.run(&config, first_rom, &mut history)
+        }
+        #[cfg(feature = "sdl")]
+        FrontendKind::Sdl => {
+            sdl::SdlApp::new()?.run(&window_rom(first_rom, &mut history)?, &config)
+        }
+        #[cfg(feature = "gpu")]
+        FrontendKind::Gpu => {
+            gpu::GpuApp::new(config.palette)?.run(&window_rom(first_rom, &mut history)?, &config)
+        }
+        // Each frontend is only there if the app was built with it
+        #[allow(unreachable_patterns)]
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "this frontend wasn't built in, turn on it's feature with `cargo build --features`",
+        )),
+    }
+}
+
+/// The rom that was passed in, or the one that was played last with `--resume`
+fn first_rom(config: &Config, history: &History) -> Option<PathBuf> {
+    match &config.rom {
+        Some(rom) => Some(rom.clone()),
+        None if config.resume => history.last().map(PathBuf::from),
+        None => None,
+    }
+}
+
+/// The windows don't have a menu to pick a rom from, so one has to be passed in
+#[cfg(any(feature = "sdl", feature = "gpu"))]
+fn window_rom(rom: Option<PathBuf>, history: &mut History) -> Result<PathBuf, Error> {
+    let rom = rom.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "the window needs the path to a rom, like `chip_8 --sdl roms/pong.ch8`, or `--resume`",
+        )
+    })?;
+    // Not being able to save the history isn't worth stopping the rom over
+    let _ = history.record(&rom);
+    Ok(rom)
 }
@@ -0,0 +1,460 @@
+//! This module contains MEGA-CHIP, an extension of Chip-8 with a 256x192 screen
+//! in 256 colors, sprites of any size, and digitized sound.
+//!
+//! It is built on top of the interpreter instead of into it, the extra state
+//! lives in a `MegaChip` that the interpreter only has once it is turned on with
+//! `Chip8::enable_megachip`, and the extra instructions only exist then. Roms
+//! start out in the normal mode, and switch to the big screen with `0011`.
+//!
+//! The instructions that are added are:
+//! - `0010` and `0011` turn the big screen off and on
+//! - `01nn nnnn` sets the index to the 24 bit address, which takes 4 bytes
+//! - `02nn` loads nn colors into the palette from the index, as ARGB
+//! - `03nn` and `04nn` set the width and height of sprites, 0 meaning 256
+//! - `05nn` sets how see through the screen is
+//! - `060n` plays the sound at the index, looping unless n is 1
+//! - `0700` stops the sound
+//! - `080n` picks how sprites get blended with what is under them
+//! - `09nn` sets the color that counts as a collision when it is drawn over
+//!
+//! While the big screen is on, `dxyn` draws a sprite of the set size where every
+//! byte is a color from the palette, with 0 being see through.
+
+use crate::{
+    chip8::{Chip8, Instruction, Opcode},
+    error::Chip8Error,
+    screen::Screen,
+};
+use alloc::{boxed::Box, vec, vec::Vec};
+
+/// The width of the big screen in pixels
+pub const WIDTH: usize = 256;
+/// The height of the big screen in pixels
+pub const HEIGHT: usize = 192;
+
+/// The index can hold a 24 bit address, so memory can be this big
+pub(crate) const MEMORY_SIZE: usize = 0x100_0000;
+
+/// How a sprite's colors are mixed with the colors that are already there
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Blend {
+    /// The sprite's color replaces what is there
+    Normal,
+    /// A quarter of the sprite's color is mixed in
+    Quarter,
+    /// Half of the sprite's color is mixed in
+    Half,
+    /// The colors are added together
+    Add,
+    /// The colors are multiplied together
+    Multiply,
+}
+
+/// A sound made of 8 bit samples that the rom asked to be played
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DigitizedSound {
+    /// How many samples are played a second
+    pub rate: u32,
+    /// The samples, where 128 is silence
+    pub samples: Vec<u8>,
+    /// Whether it starts over once it gets to the end
+    pub looping: bool,
+}
+
+/// What a frontend needs to do to the sound that it is playing
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SoundChange {
+    /// Starts playing the sound, instead of whatever was playing
+    Play(DigitizedSound),
+    /// Stops whatever is playing
+    Stop,
+}
+
+/// Everything that MEGA-CHIP adds on top of the interpreter
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MegaChip {
+    /// Whether the big screen is being used
+    is_on: bool,
+    /// The whole rom at the addresses it was loaded to, since it can be bigger
+    /// than the interpreter's memory, the interpreter's memory is still used
+    /// for anything inside of it
+    memory: Vec<u8>,
+    /// The colors that sprites are drawn with, as red, green, blue, and alpha
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
+    palette: [[u8; 4]; 256],
+    sprite_width: usize,
+    sprite_height: usize,
+    /// How see through the whole screen is, 255 being solid
+    alpha: u8,
+    blend: Blend,
+    collision_color: u8,
+    /// The palette index that was last drawn on every pixel, for collisions
+    indices: Vec<u8>,
+    /// The color of every pixel, after blending
+    colors: Vec<[u8; 3]>,
+    /// Set when the rom starts or stops a sound, until the frontend takes it
+    sound_change: Option<SoundChange>,
+}
+
+impl MegaChip {
+    /// Creates the state with the big screen off, every color in the palette
+    /// white, and 8x8 sprites
+    fn new() -> MegaChip {
+        MegaChip {
+            is_on: false,
+            memory: Vec::new(),
+            palette: [[0xff; 4]; 256],
+            sprite_width: 8,
+            sprite_height: 8,
+            alpha: 0xff,
+            blend: Blend::Normal,
+            collision_color: 0,
+            indices: vec![0; WIDTH * HEIGHT],
+            colors: vec![[0; 3]; WIDTH * HEIGHT],
+            sound_change: None,
+        }
+    }
+
+    /// Returns whether the big screen is being used
+    pub fn is_on(&self) -> bool {
+        self.is_on
+    }
+
+    /// Returns the color of the pixel at the coordinates on the big screen,
+    /// anything off of the screen is black
+    pub fn color(&self, x: usize, y: usize) -> [u8; 3] {
+        if x >= WIDTH || y >= HEIGHT {
+            return [0; 3];
+        }
+        self.colors[x + y * WIDTH]
+    }
+
+    /// Returns how see through the whole screen is, 255 being solid
+    pub fn alpha(&self) -> u8 {
+        self.alpha
+    }
+
+    /// Returns how sprites are being mixed with what is under them
+    pub fn blend(&self) -> Blend {
+        self.blend
+    }
+
+    /// Returns the change to the sound since the last time this was called,
+    /// for the frontend to start or stop playing it
+    pub fn take_sound_change(&mut self) -> Option<SoundChange> {
+        self.sound_change.take()
+    }
+
+    /// Turns every pixel on the big screen black
+    fn clear(&mut self) {
+        for index in self.indices.iter_mut() {
+            *index = 0;
+        }
+        for color in self.colors.iter_mut() {
+            *color = [0; 3];
+        }
+    }
+
+    /// Mixes the color from the palette into the pixel, the way the blend mode says to
+    fn blend_pixel(&mut self, position: usize, index: u8) {
+        let [red, green, blue, _] = self.palette[index as usize];
+        let old = self.colors[position];
+        let blend = self.blend;
+        let mix = |new: u8, old: u8| -> u8 {
+            let (new, old) = (new as u16, old as u16);
+            match blend {
+                Blend::Normal => new as u8,
+                Blend::Quarter => ((new + old * 3) / 4) as u8,
+                Blend::Half => ((new + old) / 2) as u8,
+                Blend::Add => (new + old).min(0xff) as u8,
+                Blend::Multiply => (new * old / 0xff) as u8,
+            }
+        };
+        self.colors[position] = [mix(red, old[0]), mix(green, old[1]), mix(blue, old[2])];
+        self.indices[position] = index;
+    }
+}
+
+impl Chip8 {
+    /// Turns on MEGA-CHIP, this needs to be done before the rom is loaded so
+    /// that all of it can be reached, even the parts bigger than memory
+    pub fn enable_megachip(&mut self) {
+        self.megachip = Some(Box::new(MegaChip::new()));
+    }
+
+    /// Returns the MEGA-CHIP state, if it has been turned on
+    pub fn megachip(&self) -> Option<&MegaChip> {
+        self.megachip.as_deref()
+    }
+
+    /// Returns the MEGA-CHIP state to be changed, if it has been turned on
+    pub fn megachip_mut(&mut self) -> Option<&mut MegaChip> {
+        self.megachip.as_deref_mut()
+    }
+
+    /// Returns whether the big screen is being drawn to
+    pub(crate) fn is_megachip_on(&self) -> bool {
+        self.megachip().map(MegaChip::is_on).unwrap_or(false)
+    }
+
+    /// Keeps the whole rom around for MEGA-CHIP, which can reach past the end
+    /// of memory
+    pub(crate) fn load_megachip(&mut self, rom: &[u8]) {
+        let start = self.load_address().min(MEMORY_SIZE);
+        if let Some(megachip) = &mut self.megachip {
+            let end = (start + rom.len()).min(MEMORY_SIZE);
+            megachip.memory = vec![0; end];
+            megachip.memory[start..].copy_from_slice(&rom[..end - start]);
+        }
+    }
+
+    /// Gets the MEGA-CHIP instruction for the opcode, which all start with 0
+    pub(crate) fn parse_megachip_opcode(opcode: &Opcode) -> (&'static str, Instruction) {
+        match opcode.code >> 8 {
+            0x00 => match opcode.code {
+                0x0010 => ("megaoff", Self::megaoff),
+                0x0011 => ("megaon", Self::megaon),
+                _ => ("nai", Self::nai),
+            },
+            0x01 => ("ldhi", Self::ldhi),
+            0x02 => ("ldpal", Self::ldpal),
+            0x03 => ("sprw", Self::sprw),
+            0x04 => ("sprh", Self::sprh),
+            0x05 => ("alpha", Self::alpha),
+            0x06 => ("digisnd", Self::digisnd),
+            0x07 => ("stopsnd", Self::stopsnd),
+            0x08 => ("bmode", Self::bmode),
+            0x09 => ("ccol", Self::ccol),
+            _ => ("nai", Self::nai),
+        }
+    }
+
+    /// Reads a byte from anywhere the index can point, past the end of memory
+    /// it comes from the rom, and past the end of that it is 0
+    fn read_megachip(&self, address: usize) -> u8 {
+        if address < self.memory.len() {
+            return self.memory[address];
+        }
+        self.megachip()
+            .and_then(|megachip| megachip.memory.get(address))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// Changes the MEGA-CHIP state, the instructions only exist when it is there
+    fn with_megachip(&mut self, change: impl FnOnce(&mut MegaChip)) -> Result<(), Chip8Error> {
+        if let Some(megachip) = &mut self.megachip {
+            change(megachip);
+        }
+        Ok(())
+    }
+
+    /// Opcode: `0010`
+    ///
+    /// Explanation: Turns the big screen off, and goes back to the normal one.
+    fn megaoff(&mut self, _opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.screen = Screen::new(64, 32).expect("every screen has room for 64x32");
+        self.request_redraw();
+        self.with_megachip(|megachip| {
+            megachip.is_on = false;
+            megachip.clear();
+        })
+    }
+
+    /// Opcode: `0011`
+    ///
+    /// Explanation: Turns the big screen on, which is 256x192 pixels.
+    fn megaon(&mut self, _opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.screen = Screen::new(WIDTH, HEIGHT).expect("MEGA-CHIP's screen is the biggest one");
+        self.request_redraw();
+        self.with_megachip(|megachip| {
+            megachip.is_on = true;
+            megachip.clear();
+        })
+    }
+
+    /// Opcode: `01nn nnnn`
+    ///
+    /// Explanation: Sets the index to the 24 bit address made of nn and the
+    /// next 2 bytes, which are skipped over.
+    fn ldhi(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let low = (self.read_megachip(self.program_counter + 2) as usize) << 8
+            | self.read_megachip(self.program_counter + 3) as usize;
+        self.index = (opcode.nn as usize) << 16 | low;
+        self.program_counter += 2;
+        Ok(())
+    }
+
+    /// Opcode: `02nn`
+    ///
+    /// Explanation: Loads nn colors into the palette, starting at color 1, from
+    /// the 4 bytes for each at the index, which are alpha, red, green, and blue.
+    fn ldpal(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let colors: Vec<[u8; 4]> = (0..opcode.nn as usize)
+            .map(|color| {
+                let address = self.index + color * 4;
+                let [alpha, red, green, blue] =
+                    [0, 1, 2, 3].map(|i| self.read_megachip(address + i));
+                [red, green, blue, alpha]
+            })
+            .collect();
+        self.with_megachip(|megachip| {
+            for (color, value) in colors.into_iter().enumerate() {
+                // Color 0 is always see through, so the palette starts after it
+                if let Some(entry) = megachip.palette.get_mut(color + 1) {
+                    *entry = value;
+                }
+            }
+        })
+    }
+
+    /// Opcode: `03nn`
+    ///
+    /// Explanation: Sets the width of sprites to nn pixels, 0 is 256.
+    fn sprw(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let width = if opcode.nn == 0 {
+            256
+        } else {
+            opcode.nn as usize
+        };
+        self.with_megachip(|megachip| megachip.sprite_width = width)
+    }
+
+    /// Opcode: `04nn`
+    ///
+    /// Explanation: Sets the height of sprites to nn pixels, 0 is 256.
+    fn sprh(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let height = if opcode.nn == 0 {
+            256
+        } else {
+            opcode.nn as usize
+        };
+        self.with_megachip(|megachip| megachip.sprite_height = height)
+    }
+
+    /// Opcode: `05nn`
+    ///
+    /// Explanation: Sets how see through the whole screen is, 255 being solid.
+    fn alpha(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let alpha = opcode.nn;
+        self.with_megachip(|megachip| megachip.alpha = alpha)
+    }
+
+    /// Opcode: `060n`
+    ///
+    /// Explanation: Plays the sound at the index, which starts with 2 bytes for
+    /// the sample rate, 3 bytes for the number of samples, and a byte that is
+    /// skipped. It loops unless n is 1.
+    fn digisnd(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let byte = |offset: usize| self.read_megachip(self.index + offset) as usize;
+        let rate = (byte(0) << 8 | byte(1)) as u32;
+        let length = byte(2) << 16 | byte(3) << 8 | byte(4);
+        let samples = (0..length)
+            .map(|sample| self.read_megachip(self.index + 6 + sample))
+            .collect();
+        let sound = DigitizedSound {
+            rate,
+            samples,
+            looping: opcode.n != 1,
+        };
+        self.with_megachip(|megachip| megachip.sound_change = Some(SoundChange::Play(sound)))
+    }
+
+    /// Opcode: `0700`
+    ///
+    /// Explanation: Stops the sound that is playing.
+    fn stopsnd(&mut self, _opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.with_megachip(|megachip| megachip.sound_change = Some(SoundChange::Stop))
+    }
+
+    /// Opcode: `080n`
+    ///
+    /// Explanation: Sets how sprites are mixed with what is under them, 0 is
+    /// normal, 1 and 2 are 25% and 50% see through, 3 adds, and 4 multiplies.
+    fn bmode(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let blend = match opcode.n {
+            1 => Blend::Quarter,
+            2 => Blend::Half,
+            3 => Blend::Add,
+            4 => Blend::Multiply,
+            _ => Blend::Normal,
+        };
+        self.with_megachip(|megachip| megachip.blend = blend)
+    }
+
+    /// Opcode: `09nn`
+    ///
+    /// Explanation: Sets the color that sets register f when a sprite is drawn over it.
+    fn ccol(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        let color = opcode.nn;
+        self.with_megachip(|megachip| megachip.collision_color = color)
+    }
+
+    /// Opcode: `dxyn` with the big screen on
+    ///
+    /// Explanation: Draws a sprite of the set width and height at coordinates
+    /// located in registers x and y, where each byte at the index is a color
+    /// from the palette, and color 0 isn't drawn. The sprites in the font are
+    /// still drawn the normal way, in color 255. If any pixel that is drawn on
+    /// was the collision color, register f is set to 1, otherwise 0.
+    pub(crate) fn drw_megachip(&mut self, opcode: &Opcode) -> Result<(), Chip8Error> {
+        self.has_drawn = true;
+        let x = self.registers[opcode.x as usize] as usize;
+        let y = self.registers[opcode.y as usize] as usize;
+        self.registers[0xf] = 0;
+
+        // Every pixel of the sprite with the color it is drawn in
+        let mut pixels = Vec::new();
+        if self.index < 0x200 {
+            for row in 0..opcode.n as usize {
+                let sprite = self.read_megachip(self.index + row);
+                for column in 0..8 {
+                    if sprite & (0b10000000 >> column) != 0 {
+                        pixels.push((x + column, y + row, 0xff));
+                    }
+                }
+            }
+        } else if let Some(megachip) = self.megachip() {
+            let (width, height) = (megachip.sprite_width, megachip.sprite_height);
+            for row in 0..height {
+                for column in 0..width {
+                    let color = self.read_megachip(self.index + row * width + column);
+                    if color != 0 {
+                        pixels.push((x + column, y + row, color));
+                    }
+                }
+            }
+        }
+
+        let megachip = match &mut self.megachip {
+            Some(megachip) => megachip,
+            None => return Ok(()),
+        };
+        for (pixel_x, pixel_y, color) in pixels {
+            // The big screen cuts sprites off at the edges
+            if pixel_x >= WIDTH || pixel_y >= HEIGHT {
+                continue;
+            }
+            let position = pixel_x + pixel_y * WIDTH;
+            // Nothing has been drawn where the color is 0, so it can't be hit
+            let under = megachip.indices[position];
+            if under != 0 && under == megachip.collision_color {
+                self.registers[0xf] = 1;
+            }
+            megachip.blend_pixel(position, color);
+            self.screen.set_pixel(pixel_x, pixel_y, true);
+        }
+        Ok(())
+    }
+
+    /// Clears the big screen along with the normal one, when it is on
+    pub(crate) fn cls_megachip(&mut self) {
+        if let Some(megachip) = &mut self.megachip {
+            megachip.clear();
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! This module contains the memory search that the debugger shows in it's
+//! memory pane, for finding where a game keeps something like the number of
+//! lives, and then making a cheat out of it. See the `search` module of the
+//! interpreter for how the searches narrow memory down.
+
+use chip_8::{
+    cheats::{Cheat, CheatKind},
+    search::{MemorySearch, SearchFilter},
+    Chip8,
+};
+use crossterm::event::KeyCode;
+use ratatui::{style::Modifier, text::Line};
+
+/// How long the line that a search is typed into can get
+const MAX_TYPED: usize = 16;
+/// How many addresses `PageUp` and `PageDown` move by
+const PAGE: usize = 16;
+
+/// The addresses that are left after the searches so far, and which one is
+/// picked
+pub struct SearchView {
+    search: MemorySearch,
+    selected: usize,
+    /// The search that has been typed so far, while one is being typed in
+    typing: Option<String>,
+    /// Why the last search that was typed in couldn't be used
+    error: Option<String>,
+}
+
+impl SearchView {
+    /// Starts a search with every address, remembering memory the way it is
+    /// now, so the first search can already look for what has changed
+    pub fn new(chip8: &Chip8) -> Self {
+        SearchView {
+            search: MemorySearch::new(chip8),
+            selected: 0,
+            typing: None,
+            error: None,
+        }
+    }
+
+    /// Handles a key, and returns whether it was used. `/` starts typing a
+    /// search in, which then gets every key until `Enter` runs it or `Esc`
+    /// stops, `+` adds a cheat that freezes the address that is picked at the
+    /// byte it is now, and `Delete` starts over with every address.
+    pub fn handle_key(&mut self, key: &KeyCode, chip8: &mut Chip8) -> bool {
+        if let Some(typing) = &mut self.typing {
+            match key {
+                KeyCode::Char(c) if typing.len() < MAX_TYPED => typing.push(*c),
+                KeyCode::Backspace => {
+                    typing.pop();
+                }
+                KeyCode::Enter => {
+                    match typing.parse::<SearchFilter>() {
+                        Ok(filter) => {
+                            self.search.filter(chip8, filter);
+                            self.selected = 0;
+                            self.error = None;
+                        }
+                        Err(error) => self.error = Some(error),
+                    }
+                    self.typing = None;
+                }
+                KeyCode::Esc => self.typing = None,
+                _ => {}
+            }
+            return true;
+        }
+
+        let last = self.search.addresses().len().saturating_sub(1);
+        match key {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(PAGE),
+            KeyCode::PageDown => self.selected = (self.selected + PAGE).min(last),
+            KeyCode::Char('/') => self.typing = Some(String::new()),
+            KeyCode::Char('+') => {
+                let picked = self.search.addresses().get(self.selected).copied();
+                if let Some((address, value)) =
+                    picked.and_then(|address| Some((address, *chip8.memory.get(address)?)))
+                {
+                    chip8.add_cheat(Cheat {
+                        address,
+                        value,
+                        kind: CheatKind::Freeze,
+                        enabled: true,
+                        name: String::new(),
+                    });
+                }
+            }
+            KeyCode::Delete => *self = SearchView::new(chip8),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Returns the title of the pane, which says how many addresses are left
+    pub fn title(&self) -> String {
+        format!("search  {} addresses left", self.search.addresses().len())
+    }
+
+    /// Returns the lines of the pane, with the line that a search is typed
+    /// into first, and then as many of the addresses around the one that is
+    /// picked as there is room for in `rows`, with their bytes now and at the
+    /// last search
+    pub fn render(&self, chip8: &Chip8, rows: usize) -> Vec<Line<'static>> {
+        let first_line = match (&self.typing, &self.error) {
+            (Some(typing), _) => format!("search for: {}_", typing),
+            (None, Some(error)) => format!("can't search: {}", error),
+            (None, None) => String::from("/ searches for a byte, changed, or unchanged"),
+        };
+        let mut lines = vec![Line::from(first_line)];
+
+        // The list scrolls along with the one that is picked
+        let addresses = self.search.addresses();
+        let first = (self.selected + 1).saturating_sub(rows.max(1));
+        for (index, address) in addresses.iter().enumerate().skip(first).take(rows) {
+            let line = format!(
+                "{:03x}  {:02x}  was {:02x}",
+                address,
+                chip8.memory.get(*address).copied().unwrap_or(0),
+                self.search.previous(*address).unwrap_or(0)
+            );
+            if index == self.selected {
+                lines.push(Line::styled(line, Modifier::REVERSED));
+            } else {
+                lines.push(Line::from(line));
+            }
+        }
+        lines
+    }
+}
@@ -0,0 +1,376 @@
+//! This module contains the recording and playing back of movies, which are the
+//! keys that were pressed in a run along with the seed that it used, so that the
+//! exact same run can be watched again.
+//!
+//! A movie file is plain text, the first line is `chip8-movie`, the second is
+//! `seed` followed by the seed, and every line after that is the frame that the
+//! keys changed at followed by all of the keys that are pressed from then on,
+//! as a 16 bit mask in hex where bit `n` is key `n`. Once a second there is
+//! also a `check` line with the frame and a checksum of the interpreter at the
+//! start of it, so playing the movie back can tell when the run has stopped
+//! matching, like when it's played with a different rom or different quirks:
+//!
+//! ```text
+//! chip8-movie
+//! seed 1234
+//! check 0 8c3e0a4ad2b1f3c7
+//! 0 0000
+//! check 60 0e51d2a9c4b07f13
+//! 95 0020
+//! 101 0000
+//! ```
+//!
+//! A movie can be branched, where it's played back up to a frame and then the
+//! keys are taken from the user from there on, and what they do gets written
+//! down after the part of the movie that was played.
+
+use chip_8::{
+    frontend::{Audio, Display, Event, Input},
+    Chip8,
+};
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+use tracing::info;
+
+/// The first line of every movie file
+const HEADER: &str = "chip8-movie";
+
+/// How many frames there are between checksums
+const CHECKSUM_FRAMES: u64 = 60;
+
+/// A recorded run
+pub struct Movie {
+    /// The seed that the `rnd` instruction was started with
+    pub seed: u64,
+    /// The frames that the keys changed at, and the keys that were pressed from
+    /// that frame on, in the order they happened
+    changes: Vec<(u64, u16)>,
+    /// The frames that were checked, and the checksum of the interpreter at
+    /// the start of them, in order
+    checks: Vec<(u64, u64)>,
+}
+
+impl Movie {
+    /// Creates an empty movie for a run that uses the seed
+    pub fn new(seed: u64) -> Self {
+        Movie {
+            seed,
+            changes: Vec::new(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// Reads a movie out of a file
+    pub fn load(path: &Path) -> Result<Movie, Error> {
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(invalid("the file isn't a movie"));
+        }
+        let seed = lines
+            .next()
+            .and_then(|line| line.strip_prefix("seed "))
+            .and_then(|seed| seed.parse().ok())
+            .ok_or_else(|| invalid("the movie doesn't have a seed"))?;
+
+        let mut movie = Movie::new(seed);
+        for line in lines.filter(|line| !line.is_empty()) {
+            if let Some(check) = line.strip_prefix("check ") {
+                let mut parts = check.split_whitespace();
+                let frame = parts.next().and_then(|frame| frame.parse().ok());
+                let checksum = parts
+                    .next()
+                    .and_then(|checksum| u64::from_str_radix(checksum, 16).ok());
+                match (frame, checksum) {
+                    (Some(frame), Some(checksum)) => movie.checks.push((frame, checksum)),
+                    _ => return Err(invalid(&format!("{} isn't a frame and checksum", line))),
+                }
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let frame = parts.next().and_then(|frame| frame.parse().ok());
+            let keys = parts
+                .next()
+                .and_then(|keys| u16::from_str_radix(keys, 16).ok());
+            match (frame, keys) {
+                (Some(frame), Some(keys)) => movie.changes.push((frame, keys)),
+                _ => return Err(invalid(&format!("{} isn't a frame and keys", line))),
+            }
+        }
+        Ok(movie)
+    }
+
+    /// Writes the movie to a file
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut contents = format!("{}\nseed {}\n", HEADER, self.seed);
+        // The checks go in with the changes, in the order of their frames, with
+        // a check going before the keys of the same frame since that's when it
+        // was taken
+        let mut checks = self.checks.iter().peekable();
+        for (frame, keys) in self.changes.iter() {
+            while let Some((check_frame, checksum)) =
+                checks.next_if(|(check_frame, _)| check_frame <= frame)
+            {
+                contents.push_str(&format!("check {} {:016x}\n", check_frame, checksum));
+            }
+            contents.push_str(&format!("{} {:04x}\n", frame, keys));
+        }
+        for (check_frame, checksum) in checks {
+            contents.push_str(&format!("check {} {:016x}\n", check_frame, checksum));
+        }
+        fs::write(path, contents)
+    }
+
+    /// Throws away everything from the frame on, so the run can go a
+    /// different way from there
+    fn truncate(&mut self, frame: u64) {
+        self.changes
+            .retain(|(change_frame, _)| *change_frame < frame);
+        self.checks.retain(|(check_frame, _)| *check_frame < frame);
+    }
+}
+
+/// When playing a movie back stops, and the user takes over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Branch {
+    /// At the start of the frame
+    Frame(u64),
+    /// As soon as a key that the movie isn't holding down gets pressed
+    Key,
+}
+
+impl Branch {
+    /// Gets the branch from a frame, or from `key`
+    pub fn from_name(name: &str) -> Option<Branch> {
+        match name {
+            "key" => Some(Branch::Key),
+            frame => frame.parse().ok().map(Branch::Frame),
+        }
+    }
+}
+
+/// Returns every key that is pressed, as a mask where bit `n` is key `n`
+pub fn pressed_keys(chip8: &Chip8) -> u16 {
+    (0..16)
+        .filter(|key| chip8.is_key_pressed(*key))
+        .fold(0, |keys, key| keys | 1 << key)
+}
+
+/// Presses exactly the keys in the mask, and lets go of the rest
+pub fn set_keys(chip8: &mut Chip8, keys: u16) {
+    chip8.release_all_keys();
+    for key in 0..16 {
+        if keys & 1 << key != 0 {
+            chip8.press_key(key);
+        }
+    }
+}
+
+/// Returns an FNV-1a hash of everything in the interpreter that a rom can
+/// change, other than the keys
+pub fn checksum(chip8: &Chip8) -> u64 {
+    let mut bytes = Vec::new();
+    let numbers = [
+        chip8.index,
+        chip8.program_counter,
+        chip8.stack_pointer,
+        chip8.delay as usize,
+        chip8.sound as usize,
+    ];
+    for number in numbers.iter().chain(chip8.stack.iter()) {
+        bytes.extend_from_slice(&(*number as u64).to_le_bytes());
+    }
+    bytes.extend_from_slice(&chip8.registers);
+    bytes.extend_from_slice(&chip8.memory);
+    bytes.extend_from_slice(chip8.screen().as_bytes());
+
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Creates the error for a movie file that can't be understood
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Whether the keys are being written down, or being read back
+enum Mode {
+    Recording,
+    /// How far through the movie's changes the run is
+    Playing(usize),
+}
+
+/// Wraps a frontend, and either writes down the keys it presses, or ignores
+/// them and presses the keys from a movie instead. Only the keys are taken from
+/// the movie, so the frontend can still quit and draw like normal.
+pub struct MovieFrontend<'a, F> {
+    frontend: &'a mut F,
+    movie: Movie,
+    mode: Mode,
+    /// When to stop playing and start writing down the frontend's keys instead
+    branch: Option<Branch>,
+    /// The frame that is about to run
+    frame: u64,
+    /// Why the movie stopped, this can't be returned from the middle of a
+    /// frame, so the run gets stopped and it gets returned from `finish`
+    error: Option<Error>,
+}
+
+impl<'a, F> MovieFrontend<'a, F> {
+    /// Starts writing down the keys into an empty movie
+    pub fn record(frontend: &'a mut F, seed: u64) -> Self {
+        MovieFrontend {
+            frontend,
+            movie: Movie::new(seed),
+            mode: Mode::Recording,
+            branch: None,
+            frame: 0,
+            error: None,
+        }
+    }
+
+    /// Starts pressing the keys from the movie, the interpreter needs to have
+    /// been started with the movie's seed
+    pub fn play(frontend: &'a mut F, movie: Movie) -> Self {
+        MovieFrontend {
+            frontend,
+            movie,
+            mode: Mode::Playing(0),
+            branch: None,
+            frame: 0,
+            error: None,
+        }
+    }
+
+    /// Makes playing the movie stop at the branch, after which the keys get
+    /// written down after the part that was played
+    pub fn branch_at(mut self, branch: Option<Branch>) -> Self {
+        self.branch = branch;
+        self
+    }
+
+    /// Returns what was recorded, or the movie that was played, or why the run
+    /// stopped matching the movie
+    pub fn finish(self) -> Result<Movie, Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.movie),
+        }
+    }
+
+    /// Returns the keys that the movie is holding down before its change at
+    /// `position`
+    fn movie_keys(&self, position: usize) -> u16 {
+        match position {
+            0 => 0,
+            position => self.movie.changes[position - 1].1,
+        }
+    }
+
+    /// Makes sure that the interpreter is the same as it was when the movie
+    /// was recorded, if this frame was checked
+    fn check(&mut self, chip8: &Chip8) {
+        let checks = &self.movie.checks;
+        if let Ok(position) = checks.binary_search_by_key(&self.frame, |(frame, _)| *frame) {
+            if checks[position].1 != checksum(chip8) {
+                self.error = Some(invalid(&format!(
+                    "the run stopped matching the movie at frame {}, check that it's the same rom with the same options",
+                    self.frame
+                )));
+            }
+        }
+    }
+}
+
+impl<'a, F: Input> Input for MovieFrontend<'a, F> {
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        let event = self.frontend.handle_input(chip8);
+        if self.error.is_some() {
+            return Some(Event::Quit);
+        }
+        event
+    }
+
+    /// The keys only ever change between frames, so this is where they get
+    /// written down, or set from the movie
+    fn start_frame(&mut self, chip8: &mut Chip8) {
+        self.frontend.start_frame(chip8);
+        // Once the movie has stopped matching, the frames that run before the
+        // run loop notices don't matter
+        if self.error.is_some() {
+            return;
+        }
+
+        if let Mode::Playing(position) = self.mode {
+            // Only the keys that the movie isn't holding down can have come
+            // from the user
+            let movie_keys = self.movie_keys(position);
+            let user_keys = pressed_keys(chip8) & !movie_keys;
+            let branch = match self.branch {
+                Some(Branch::Frame(frame)) => frame <= self.frame,
+                Some(Branch::Key) => user_keys != 0,
+                None => false,
+            };
+            if branch {
+                info!(frame = self.frame, "the movie branched");
+                self.movie.truncate(self.frame);
+                self.mode = Mode::Recording;
+                set_keys(chip8, user_keys);
+            }
+        }
+
+        match self.mode {
+            Mode::Recording => {
+                if self.frame.is_multiple_of(CHECKSUM_FRAMES) {
+                    self.movie.checks.push((self.frame, checksum(chip8)));
+                }
+                let keys = pressed_keys(chip8);
+                let last_keys = self.movie.changes.last().map(|(_, keys)| *keys);
+                if last_keys != Some(keys) {
+                    self.movie.changes.push((self.frame, keys));
+                }
+            }
+            Mode::Playing(mut position) => {
+                // The movie's keys are the only ones that count, so the ones
+                // that the frontend pressed get let go of
+                let mut keys = self.movie_keys(position);
+                while let Some((frame, next_keys)) = self.movie.changes.get(position) {
+                    if *frame > self.frame {
+                        break;
+                    }
+                    keys = *next_keys;
+                    position += 1;
+                }
+                self.mode = Mode::Playing(position);
+                set_keys(chip8, keys);
+                self.check(chip8);
+            }
+        }
+        self.frame += 1;
+    }
+
+    fn before_instruction(&mut self, chip8: &mut Chip8) {
+        self.frontend.before_instruction(chip8);
+    }
+
+    fn tick(&mut self, chip8: &mut Chip8) {
+        self.frontend.tick(chip8);
+    }
+}
+
+impl<'a, F: Display> Display for MovieFrontend<'a, F> {
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error> {
+        self.frontend.draw(chip8)
+    }
+}
+
+impl<'a, F: Audio> Audio for MovieFrontend<'a, F> {
+    fn set_playing(&mut self, playing: bool) -> Result<(), Error> {
+        self.frontend.set_playing(playing)
+    }
+}
@@ -0,0 +1,220 @@
+//! This module contains netplay, where one copy of the app (the host) sends the
+//! keys that it presses to another copy (the peer) over TCP, so that both of
+//! them run the exact same session.
+//!
+//! The interpreter gives the same results for the same keys and the same seed,
+//! so only those get sent. The host starts by sending `chip8-netplay` and the
+//! seed, and then a line for every frame with the frame and the keys that are
+//! pressed, as a 16 bit mask in hex. Once a second a checksum of the
+//! interpreter is added to the end of the line, so the peer can tell if it has
+//! stopped matching the host, like when the two are running different roms:
+//!
+//! ```text
+//! chip8-netplay 1234
+//! 0 0000 8c3e0a4ad2b1f3c7
+//! 1 0000
+//! 2 0020
+//! ```
+
+use crate::movie::{checksum, pressed_keys, set_keys};
+use chip_8::{
+    frontend::{Audio, Display, Event, Input},
+    Chip8,
+};
+use std::{
+    io::{BufRead, BufReader, Error, ErrorKind, Write},
+    net::{TcpListener, TcpStream},
+};
+
+/// The first thing that the host sends
+const HEADER: &str = "chip8-netplay";
+
+/// How many frames there are between checksums
+const CHECKSUM_FRAMES: u64 = 60;
+
+/// Which end of the session this is
+enum Role {
+    /// Sends it's keys, the writer is gone once the peer has left
+    Host(Option<TcpStream>),
+    /// Presses the keys that it is sent
+    Peer(BufReader<TcpStream>),
+}
+
+/// Wraps a frontend, and either sends the keys that it presses to the peer, or
+/// ignores them and presses the keys that the host sent. Only the keys are
+/// taken from the host, so the frontend can still quit and draw like normal.
+pub struct NetplayFrontend<'a, F> {
+    frontend: &'a mut F,
+    role: Role,
+    /// The seed that both ends start the interpreter with
+    seed: u64,
+    /// The frame that is about to run
+    frame: u64,
+    /// Set when the host has ended the session, so the peer stops too
+    ended: bool,
+    /// What went wrong, this can't be returned from the middle of a frame, so
+    /// the session gets stopped and it gets returned from `finish`
+    error: Option<Error>,
+}
+
+impl<'a, F> NetplayFrontend<'a, F> {
+    /// Waits for a peer to connect to the address, and sends it the seed
+    pub fn host(frontend: &'a mut F, address: &str, seed: u64) -> Result<Self, Error> {
+        let (mut stream, _) = TcpListener::bind(address)?.accept()?;
+        // Every frame is a tiny message, and waiting to put them together would
+        // only make the peer fall behind
+        stream.set_nodelay(true)?;
+        writeln!(stream, "{} {}", HEADER, seed)?;
+        Ok(NetplayFrontend {
+            frontend,
+            role: Role::Host(Some(stream)),
+            seed,
+            frame: 0,
+            ended: false,
+            error: None,
+        })
+    }
+
+    /// Connects to the host at the address, and gets the seed from it
+    pub fn join(frontend: &'a mut F, address: &str) -> Result<Self, Error> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let seed = line
+            .trim_end()
+            .strip_prefix(HEADER)
+            .and_then(|seed| seed.trim().parse().ok())
+            .ok_or_else(|| invalid(String::from("the host isn't running a netplay session")))?;
+        Ok(NetplayFrontend {
+            frontend,
+            role: Role::Peer(reader),
+            seed,
+            frame: 0,
+            ended: false,
+            error: None,
+        })
+    }
+
+    /// The seed that the interpreter has to be started with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns what went wrong during the session, if anything did
+    pub fn finish(self) -> Result<(), Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads the host's line for this frame, and presses it's keys
+    fn receive_frame(&mut self, chip8: &mut Chip8) -> Result<(), Error> {
+        let reader = match &mut self.role {
+            Role::Peer(reader) => reader,
+            Role::Host(_) => return Ok(()),
+        };
+        let mut line = String::new();
+        // The host closing the connection is how the session ends
+        if reader.read_line(&mut line)? == 0 {
+            self.ended = true;
+            return Ok(());
+        }
+
+        let mut parts = line.split_whitespace();
+        let frame: Option<u64> = parts.next().and_then(|frame| frame.parse().ok());
+        let keys = parts
+            .next()
+            .and_then(|keys| u16::from_str_radix(keys, 16).ok());
+        let keys = match (frame, keys) {
+            (Some(frame), Some(keys)) if frame == self.frame => keys,
+            _ => {
+                return Err(invalid(format!(
+                    "the host sent {} for frame {}",
+                    line.trim_end(),
+                    self.frame
+                )))
+            }
+        };
+        if let Some(expected) = parts.next() {
+            if u64::from_str_radix(expected, 16).ok() != Some(checksum(chip8)) {
+                return Err(invalid(format!(
+                    "the session stopped matching the host at frame {}, check that both are running the same rom",
+                    self.frame
+                )));
+            }
+        }
+        set_keys(chip8, keys);
+        Ok(())
+    }
+
+    /// Sends the keys that are pressed for this frame to the peer
+    fn send_frame(&mut self, chip8: &Chip8) {
+        if let Role::Host(writer) = &mut self.role {
+            if let Some(stream) = writer {
+                let mut line = format!("{} {:04x}", self.frame, pressed_keys(chip8));
+                if self.frame.is_multiple_of(CHECKSUM_FRAMES) {
+                    line.push_str(&format!(" {:016x}", checksum(chip8)));
+                }
+                // The host keeps playing if the peer leaves, it just stops
+                // sending to it
+                if writeln!(stream, "{}", line).is_err() {
+                    *writer = None;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, F: Input> Input for NetplayFrontend<'a, F> {
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        let event = self.frontend.handle_input(chip8);
+        if self.ended || self.error.is_some() {
+            return Some(Event::Quit);
+        }
+        event
+    }
+
+    /// The keys only ever change between frames, so this is where they get
+    /// sent, or set from what the host sent
+    fn start_frame(&mut self, chip8: &mut Chip8) {
+        self.frontend.start_frame(chip8);
+        // Once the session is over, the frames that run before the run loop
+        // notices don't matter
+        if self.ended || self.error.is_some() {
+            return;
+        }
+        self.send_frame(chip8);
+        if let Err(error) = self.receive_frame(chip8) {
+            self.error = Some(error);
+        }
+        self.frame += 1;
+    }
+
+    fn before_instruction(&mut self, chip8: &mut Chip8) {
+        self.frontend.before_instruction(chip8);
+    }
+
+    fn tick(&mut self, chip8: &mut Chip8) {
+        self.frontend.tick(chip8);
+    }
+}
+
+impl<'a, F: Display> Display for NetplayFrontend<'a, F> {
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error> {
+        self.frontend.draw(chip8)
+    }
+}
+
+impl<'a, F: Audio> Audio for NetplayFrontend<'a, F> {
+    fn set_playing(&mut self, playing: bool) -> Result<(), Error> {
+        self.frontend.set_playing(playing)
+    }
+}
+
+/// Creates the error for when the host and the peer don't agree
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,881 @@
+//! This module contains an assembler for Octo's assembly language, which is
+//! what most of the Chip-8 programs that are being written today are written
+//! in, so their source can be ran without having to build it in Octo first.
+//!
+//! Everything that is needed for Chip-8 and SUPER-CHIP programs is there:
+//! - `: name` puts a label at the next instruction, which can be used before
+//!   it is put anywhere, and `main` is where the program starts
+//! - `:const name value` names a number, and `:alias name v3` names a register
+//! - `:calc name { expression }` works out a number at build time, with no
+//!   precedence between the operators, so everything is worked out from right
+//!   to left unless brackets say otherwise
+//! - `:macro name arguments { body }` pastes the body in wherever the name is
+//!   used, with the arguments swapped for what comes after the name
+//! - `:byte`, `:org`, `:next`, `:unpack`, and `:call`
+//! - every instruction, with `if ... then`, `if ... begin ... else ... end`,
+//!   and `loop ... while ... again`
+//!
+//! XO-CHIP's `audio` and `pitch := vx` are there too, but the rest of its
+//! instructions aren't, since the interpreter doesn't run them, and neither is
+//! `:stringmode`. Like Octo, every token has to have whitespace
+//! around it, brackets included.
+//!
+//! ```rust
+//! use chip_8::octo;
+//!
+//! let rom = octo::assemble(
+//!     ": main
+//!        v0 := 5
+//!        loop
+//!          v0 += -1
+//!          if v0 != 0 then
+//!        again",
+//! )
+//! .unwrap();
+//! assert_eq!(rom, [0x12, 0x02, 0x60, 0x05, 0x70, 0xff, 0x30, 0x00, 0x12, 0x04]);
+//! ```
+
+use crate::{error::AssembleError, symbols::Symbols};
+use std::collections::{HashMap, VecDeque};
+
+/// Where the program goes in memory, the first instruction is always a jump
+/// to `main`
+const START: usize = 0x200;
+/// The biggest address that fits in an instruction
+const MAX_ADDRESS: usize = 0xfff;
+/// How many times macros can be used in one program, which stops a macro that
+/// uses itself from going forever
+const MAX_EXPANSIONS: usize = 100_000;
+
+/// A piece of the source, with the line it is on for when something is wrong
+/// with it
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    line: usize,
+}
+
+/// An address that can only be filled in once every label has been found
+enum Fixup {
+    /// The 12 bits at the bottom of the instruction at the address
+    Instruction(usize),
+    /// The two `vx := nn` instructions at the address that `:unpack` made,
+    /// with the nibble that goes on top
+    Unpack(usize, u8),
+}
+
+/// Where the jumps that the control flow needs go, once the end is found
+enum Flow {
+    /// The jump that skips to `else` or `end`
+    Begin(usize),
+    /// The jump at the end of the part before `else`, that skips over the rest
+    Else(usize),
+    /// Where the loop starts, and the jumps that each `while` leaves it with
+    Loop(usize, Vec<usize>),
+}
+
+/// What the other side of a comparison is
+#[derive(Clone, Copy)]
+enum Operand {
+    Register(u8),
+    Byte(u8),
+}
+
+/// What an `if` or a `while` checks
+enum Test {
+    Equal(u8, Operand),
+    NotEqual(u8, Operand),
+    /// The key in the register is pressed
+    Key(u8),
+    /// The key in the register isn't pressed
+    NotKey(u8),
+    /// `<`, `>`, `<=`, or `>=`, which Octo works out with register f
+    Compare(u8, &'static str, Operand),
+}
+
+/// A macro's arguments and the tokens of it's body
+struct Macro {
+    arguments: Vec<String>,
+    body: Vec<Token>,
+}
+
+/// Assembles the source, and returns the bytes of the rom that starts at
+/// `0x200`
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    assemble_with_symbols(source).map(|(rom, _)| rom)
+}
+
+/// Assembles the source, and returns the bytes of the rom along with the
+/// addresses of it's labels, for the debugger to show the code with
+pub fn assemble_with_symbols(source: &str) -> Result<(Vec<u8>, Symbols), AssembleError> {
+    let mut assembler = Assembler {
+        tokens: tokenize(source),
+        rom: Vec::new(),
+        here: START,
+        labels: HashMap::new(),
+        constants: HashMap::new(),
+        aliases: HashMap::new(),
+        macros: HashMap::new(),
+        fixups: Vec::new(),
+        flow: Vec::new(),
+        expansions: 0,
+        line: 1,
+    };
+    assembler.run()?;
+    // The labels are sorted, so an address with more than one always shows
+    // up as the same one
+    let mut labels: Vec<(&String, &usize)> = assembler.labels.iter().collect();
+    labels.sort();
+    let mut symbols = Symbols::new();
+    for (name, address) in labels {
+        symbols.insert(*address, name);
+    }
+    Ok((assembler.rom, symbols))
+}
+
+/// Splits the source up on whitespace, anything after a `#` is a comment
+fn tokenize(source: &str) -> VecDeque<Token> {
+    source
+        .lines()
+        .enumerate()
+        .flat_map(|(line, text)| {
+            let code = text.split('#').next().unwrap_or_default();
+            code.split_whitespace()
+                .map(|text| Token {
+                    text: text.to_string(),
+                    line: line + 1,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Reads a number in decimal, in hex with `0x`, or in binary with `0b`, which
+/// can all have a `-` in front of them
+fn parse_number(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let number = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    Some(if negative { -number } else { number })
+}
+
+/// Reads `v0` through `vf` as the number of the register
+fn parse_register(text: &str) -> Option<u8> {
+    let digit = text.strip_prefix('v').or_else(|| text.strip_prefix('V'))?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u8::from_str_radix(digit, 16).ok()
+}
+
+struct Assembler {
+    tokens: VecDeque<Token>,
+    /// The bytes from `START` on
+    rom: Vec<u8>,
+    /// Where the next byte goes
+    here: usize,
+    labels: HashMap<String, usize>,
+    /// The numbers from `:const` and `:calc`, which can have fractions
+    constants: HashMap<String, f64>,
+    aliases: HashMap<String, u8>,
+    macros: HashMap<String, Macro>,
+    /// The addresses that are waiting on labels, with the label and the line
+    /// it was used on
+    fixups: Vec<(Fixup, String, usize)>,
+    flow: Vec<Flow>,
+    expansions: usize,
+    /// The line of the last token that was read
+    line: usize,
+}
+
+impl Assembler {
+    /// Goes through every token, and then fills in the addresses
+    fn run(&mut self) -> Result<(), AssembleError> {
+        // The jump to `main` gets filled in once it is found
+        self.emit_address(0x1, "main")?;
+        while let Some(token) = self.tokens.pop_front() {
+            self.line = token.line;
+            self.statement(&token.text)?;
+        }
+        if !self.flow.is_empty() {
+            return Err(self.error("a `begin` or a `loop` was never finished"));
+        }
+
+        for (fixup, name, line) in std::mem::take(&mut self.fixups) {
+            self.line = line;
+            let address = match self.labels.get(&name) {
+                Some(address) => *address,
+                None if name == "main" => {
+                    return Err(self.error("there isn't a `: main`, which is where it starts"))
+                }
+                None => return Err(self.error(format!("there isn't a label called {}", name))),
+            };
+            if address > MAX_ADDRESS {
+                return Err(self.error(format!("{} is past {:#05x}", name, MAX_ADDRESS)));
+            }
+            match fixup {
+                Fixup::Instruction(at) => self.patch(at, address),
+                Fixup::Unpack(at, nibble) => {
+                    self.rom[at - START + 1] = nibble << 4 | (address >> 8) as u8;
+                    self.rom[at - START + 3] = address as u8;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Assembles whatever starts with the token
+    fn statement(&mut self, text: &str) -> Result<(), AssembleError> {
+        match text {
+            ":" => {
+                let name = self.next()?;
+                self.define(name, self.here)?;
+            }
+            ":const" => {
+                let name = self.next()?;
+                let value = self.next()?;
+                let value = self.value(&value)? as f64;
+                self.constants.insert(name, value);
+            }
+            ":alias" => {
+                let name = self.next()?;
+                let register = self.next_register()?;
+                self.aliases.insert(name, register);
+            }
+            ":calc" => {
+                let name = self.next()?;
+                self.expect("{")?;
+                let value = self.calc()?;
+                self.constants.insert(name, value);
+            }
+            ":byte" => {
+                let value = self.number_or_calc()?;
+                let byte = self.fit_byte(value)?;
+                self.emit(byte)?;
+            }
+            ":org" => {
+                let address = self.number_or_calc()?;
+                if address < START as i64 || address > MAX_ADDRESS as i64 {
+                    return Err(
+                        self.error(format!("{:#x} isn't somewhere the program can go", address))
+                    );
+                }
+                self.here = address as usize;
+            }
+            // The label goes on the byte after the next instruction's first,
+            // for code that changes the number in it
+            ":next" => {
+                let name = self.next()?;
+                self.define(name, self.here + 1)?;
+            }
+            ":unpack" => {
+                let nibble = self.next()?;
+                let nibble = self.value(&nibble)?;
+                if !(0..=0xf).contains(&nibble) {
+                    return Err(self.error(format!("{} doesn't fit in 4 bits", nibble)));
+                }
+                let name = self.next()?;
+                let at = self.here;
+                self.instruction(0x60, 0x00)?;
+                self.instruction(0x61, 0x00)?;
+                match self.known_address(&name)? {
+                    Some(address) => {
+                        self.rom[at - START + 1] = (nibble as u8) << 4 | (address >> 8) as u8;
+                        self.rom[at - START + 3] = address as u8;
+                    }
+                    None => self
+                        .fixups
+                        .push((Fixup::Unpack(at, nibble as u8), name, self.line)),
+                }
+            }
+            ":call" => {
+                let name = self.next()?;
+                self.emit_address(0x2, &name)?;
+            }
+            // These are only for Octo's debugger
+            ":breakpoint" => {
+                self.next()?;
+            }
+            ":monitor" => {
+                self.next()?;
+                self.next()?;
+            }
+            ":macro" => self.define_macro()?,
+            "clear" => self.instruction(0x00, 0xe0)?,
+            "return" | ";" => self.instruction(0x00, 0xee)?,
+            "exit" => self.instruction(0x00, 0xfd)?,
+            "lores" => self.instruction(0x00, 0xfe)?,
+            "hires" => self.instruction(0x00, 0xff)?,
+            "scroll-down" => {
+                let rows = self.next_nibble()?;
+                self.instruction(0x00, 0xc0 | rows)?;
+            }
+            "scroll-right" => self.instruction(0x00, 0xfb)?,
+            "scroll-left" => self.instruction(0x00, 0xfc)?,
+            "jump" => {
+                let name = self.next()?;
+                self.emit_address(0x1, &name)?;
+            }
+            "jump0" => {
+                let name = self.next()?;
+                self.emit_address(0xb, &name)?;
+            }
+            "sprite" => {
+                let x = self.next_register()?;
+                let y = self.next_register()?;
+                let rows = self.next_nibble()?;
+                self.instruction(0xd0 | x, y << 4 | rows)?;
+            }
+            "bcd" => self.register_instruction(0x33)?,
+            "save" => self.register_instruction(0x55)?,
+            "load" => self.register_instruction(0x65)?,
+            "saveflags" => self.register_instruction(0x75)?,
+            "loadflags" => self.register_instruction(0x85)?,
+            "delay" => {
+                self.expect(":=")?;
+                self.register_instruction(0x15)?;
+            }
+            "buzzer" => {
+                self.expect(":=")?;
+                self.register_instruction(0x18)?;
+            }
+            "pitch" => {
+                self.expect(":=")?;
+                self.register_instruction(0x3a)?;
+            }
+            "audio" => self.instruction(0xf0, 0x02)?,
+            "i" => self.index()?,
+            "if" => {
+                let test = self.test()?;
+                match self.next()?.as_str() {
+                    "then" => self.skip(&test, false)?,
+                    "begin" => {
+                        self.skip(&test, true)?;
+                        let jump = self.here;
+                        self.instruction(0x10, 0x00)?;
+                        self.flow.push(Flow::Begin(jump));
+                    }
+                    other => {
+                        return Err(self.error(format!("expected then or begin, not {}", other)))
+                    }
+                }
+            }
+            "else" => match self.flow.pop() {
+                Some(Flow::Begin(jump)) => {
+                    let over = self.here;
+                    self.instruction(0x10, 0x00)?;
+                    self.patch(jump, self.here);
+                    self.flow.push(Flow::Else(over));
+                }
+                _ => return Err(self.error("`else` isn't after an `if ... begin`")),
+            },
+            "end" => match self.flow.pop() {
+                Some(Flow::Begin(jump)) | Some(Flow::Else(jump)) => self.patch(jump, self.here),
+                _ => return Err(self.error("`end` isn't after an `if ... begin`")),
+            },
+            "loop" => self.flow.push(Flow::Loop(self.here, Vec::new())),
+            "while" => {
+                let test = self.test()?;
+                self.skip(&test, true)?;
+                let jump = self.here;
+                self.instruction(0x10, 0x00)?;
+                match self.flow.iter_mut().rev().find_map(|flow| match flow {
+                    Flow::Loop(_, whiles) => Some(whiles),
+                    _ => None,
+                }) {
+                    Some(whiles) => whiles.push(jump),
+                    None => return Err(self.error("`while` isn't inside of a `loop`")),
+                }
+            }
+            "again" => match self.flow.pop() {
+                Some(Flow::Loop(start, whiles)) => {
+                    self.instruction(0x10 | (start >> 8) as u8, start as u8)?;
+                    for jump in whiles {
+                        self.patch(jump, self.here);
+                    }
+                }
+                _ => return Err(self.error("`again` isn't after a `loop`")),
+            },
+            text if self.register(text).is_some() => {
+                let x = self.register(text).unwrap_or_default();
+                self.assignment(x)?;
+            }
+            text if self.macros.contains_key(text) => self.expand(text)?,
+            // Anything else is the name of a subroutine to call
+            text if parse_number(text).is_none() && !text.starts_with(':') => {
+                self.emit_address(0x2, text)?;
+            }
+            text => return Err(self.error(format!("didn't expect {}", text))),
+        }
+        Ok(())
+    }
+
+    /// Assembles what comes after a register at the start of a statement
+    fn assignment(&mut self, x: u8) -> Result<(), AssembleError> {
+        let operator = self.next()?;
+        let source = self.next()?;
+        let y = self.register(&source);
+        match (operator.as_str(), y) {
+            (":=", _) if source == "random" => {
+                let mask = self.next()?;
+                let mask = self.byte(&mask)?;
+                self.instruction(0xc0 | x, mask)
+            }
+            (":=", _) if source == "delay" => self.instruction(0xf0 | x, 0x07),
+            (":=", _) if source == "key" => self.instruction(0xf0 | x, 0x0a),
+            (":=", Some(y)) => self.instruction(0x80 | x, y << 4),
+            (":=", None) => {
+                let byte = self.byte(&source)?;
+                self.instruction(0x60 | x, byte)
+            }
+            ("+=", Some(y)) => self.instruction(0x80 | x, y << 4 | 0x4),
+            ("+=", None) => {
+                let byte = self.byte(&source)?;
+                self.instruction(0x70 | x, byte)
+            }
+            ("-=", Some(y)) => self.instruction(0x80 | x, y << 4 | 0x5),
+            // Taking away a number is adding it's negative
+            ("-=", None) => {
+                let byte = self.byte(&source)?;
+                self.instruction(0x70 | x, byte.wrapping_neg())
+            }
+            ("=-", Some(y)) => self.instruction(0x80 | x, y << 4 | 0x7),
+            ("|=", Some(y)) => self.instruction(0x80 | x, y << 4 | 0x1),
+            ("&=", Some(y)) => self.instruction(0x80 | x, y << 4 | 0x2),
+            ("^=", Some(y)) => self.instruction(0x80 | x, y << 4 | 0x3),
+            (">>=", Some(y)) => self.instruction(0x80 | x, y << 4 | 0x6),
+            ("<<=", Some(y)) => self.instruction(0x80 | x, y << 4 | 0xe),
+            _ => Err(self.error(format!("didn't expect {} {}", operator, source))),
+        }
+    }
+
+    /// Assembles what comes after `i` at the start of a statement
+    fn index(&mut self) -> Result<(), AssembleError> {
+        match self.next()?.as_str() {
+            ":=" => {
+                let target = self.next()?;
+                match target.as_str() {
+                    "hex" => self.register_instruction(0x29),
+                    "bighex" => self.register_instruction(0x30),
+                    "long" => Err(self.error("XO-CHIP's `i := long` can't be ran here")),
+                    name => self.emit_address(0xa, name),
+                }
+            }
+            "+=" => self.register_instruction(0x1e),
+            other => Err(self.error(format!("didn't expect i {}", other))),
+        }
+    }
+
+    /// Reads what an `if` or a `while` checks
+    fn test(&mut self) -> Result<Test, AssembleError> {
+        let x = self.next_register()?;
+        let operator = self.next()?;
+        match operator.as_str() {
+            "key" => return Ok(Test::Key(x)),
+            "-key" => return Ok(Test::NotKey(x)),
+            _ => {}
+        }
+        let other = self.next()?;
+        let other = match self.register(&other) {
+            Some(y) => Operand::Register(y),
+            None => Operand::Byte(self.byte(&other)?),
+        };
+        match operator.as_str() {
+            "==" => Ok(Test::Equal(x, other)),
+            "!=" => Ok(Test::NotEqual(x, other)),
+            "<" => Ok(Test::Compare(x, "<", other)),
+            ">" => Ok(Test::Compare(x, ">", other)),
+            "<=" => Ok(Test::Compare(x, "<=", other)),
+            ">=" => Ok(Test::Compare(x, ">=", other)),
+            other => Err(self.error(format!("{} isn't a comparison", other))),
+        }
+    }
+
+    /// Assembles the skip that skips the next instruction when the test is
+    /// `when`, `then` skips when it's false and `begin` and `while` skip the
+    /// jump after them when it's true
+    fn skip(&mut self, test: &Test, when: bool) -> Result<(), AssembleError> {
+        // `3xnn`, `4xnn`, `5xy0`, and `9xy0` skip when it's equal or not equal
+        let equal = |skip_equal: bool, x: u8, other: Operand| match (skip_equal, other) {
+            (true, Operand::Byte(byte)) => (0x30 | x, byte),
+            (false, Operand::Byte(byte)) => (0x40 | x, byte),
+            (true, Operand::Register(y)) => (0x50 | x, y << 4),
+            (false, Operand::Register(y)) => (0x90 | x, y << 4),
+        };
+        let (high, low) = match *test {
+            Test::Equal(x, other) => equal(when, x, other),
+            Test::NotEqual(x, other) => equal(!when, x, other),
+            Test::Key(x) if when => (0xe0 | x, 0x9e),
+            Test::Key(x) => (0xe0 | x, 0xa1),
+            Test::NotKey(x) if when => (0xe0 | x, 0xa1),
+            Test::NotKey(x) => (0xe0 | x, 0x9e),
+            // Register f gets the other side, and then the borrow from taking
+            // one away from the other, which is 0 when `<` or `>` are true and
+            // 1 when `>=` or `<=` are
+            Test::Compare(x, operator, other) => {
+                match other {
+                    Operand::Byte(byte) => self.instruction(0x6f, byte)?,
+                    Operand::Register(y) => self.instruction(0x8f, y << 4)?,
+                }
+                let subtract = if operator == "<" || operator == ">=" {
+                    0x7
+                } else {
+                    0x5
+                };
+                self.instruction(0x8f, x << 4 | subtract)?;
+                let flag = if operator.len() == 1 { 0 } else { 1 };
+                equal(when, 0xf, Operand::Byte(flag))
+            }
+        };
+        self.instruction(high, low)
+    }
+
+    /// Reads `:macro name arguments { body }`
+    fn define_macro(&mut self) -> Result<(), AssembleError> {
+        let name = self.next()?;
+        let mut arguments = Vec::new();
+        loop {
+            let argument = self.next()?;
+            if argument == "{" {
+                break;
+            }
+            arguments.push(argument);
+        }
+        // The body can have brackets in it, from `:calc`
+        let mut body = Vec::new();
+        let mut depth = 1;
+        loop {
+            let token = self.next_token()?;
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+            body.push(token);
+        }
+        self.macros.insert(name, Macro { arguments, body });
+        Ok(())
+    }
+
+    /// Puts the body of the macro in front of the rest of the tokens, with
+    /// the arguments swapped in
+    fn expand(&mut self, name: &str) -> Result<(), AssembleError> {
+        self.expansions += 1;
+        if self.expansions > MAX_EXPANSIONS {
+            return Err(self.error(format!("{} keeps using itself", name)));
+        }
+        let count = self.macros[name].arguments.len();
+        let mut values = Vec::new();
+        for _ in 0..count {
+            values.push(self.next()?);
+        }
+        let line = self.line;
+        let expanded: Vec<Token> = self.macros[name]
+            .body
+            .iter()
+            .map(|token| {
+                let text = match self.macros[name]
+                    .arguments
+                    .iter()
+                    .position(|argument| *argument == token.text)
+                {
+                    Some(i) => values[i].clone(),
+                    None => token.text.clone(),
+                };
+                // Anything wrong in the body is reported where it was used
+                Token { text, line }
+            })
+            .collect();
+        for token in expanded.into_iter().rev() {
+            self.tokens.push_front(token);
+        }
+        Ok(())
+    }
+
+    /// Works out the expression up to the `}`, which has already had the `{`
+    /// in front of it read
+    fn calc(&mut self) -> Result<f64, AssembleError> {
+        let mut tokens = Vec::new();
+        loop {
+            let token = self.next()?;
+            if token == "}" {
+                break;
+            }
+            tokens.push(token);
+        }
+        let mut position = 0;
+        let value = self.calc_expression(&tokens, &mut position)?;
+        if position < tokens.len() {
+            return Err(self.error(format!("didn't expect {} in the :calc", tokens[position])));
+        }
+        Ok(value)
+    }
+
+    /// A term, and then an operator and the rest of the expression if there
+    /// is one. The rest is worked out first, which is what makes it go from
+    /// right to left.
+    fn calc_expression(
+        &self,
+        tokens: &[String],
+        position: &mut usize,
+    ) -> Result<f64, AssembleError> {
+        let left = self.calc_term(tokens, position)?;
+        let operator = match tokens.get(*position) {
+            Some(operator) if operator != ")" => operator.clone(),
+            _ => return Ok(left),
+        };
+        *position += 1;
+        let right = self.calc_expression(tokens, position)?;
+        let (a, b) = (left as i64, right as i64);
+        let value = match operator.as_str() {
+            "+" => left + right,
+            "-" => left - right,
+            "*" => left * right,
+            "/" => left / right,
+            "%" => left % right,
+            "pow" => left.powf(right),
+            "min" => left.min(right),
+            "max" => left.max(right),
+            "&" => (a & b) as f64,
+            "|" => (a | b) as f64,
+            "^" => (a ^ b) as f64,
+            "<<" => (a << b) as f64,
+            ">>" => (a >> b) as f64,
+            "<" => (left < right) as u8 as f64,
+            ">" => (left > right) as u8 as f64,
+            "<=" => (left <= right) as u8 as f64,
+            ">=" => (left >= right) as u8 as f64,
+            "==" => (left == right) as u8 as f64,
+            "!=" => (left != right) as u8 as f64,
+            other => return Err(self.error(format!("{} isn't an operator", other))),
+        };
+        Ok(value)
+    }
+
+    /// A number, a name, something in brackets, or something with a unary
+    /// operator in front of it
+    fn calc_term(&self, tokens: &[String], position: &mut usize) -> Result<f64, AssembleError> {
+        let token = match tokens.get(*position) {
+            Some(token) => token.as_str(),
+            None => return Err(self.error("the :calc isn't finished")),
+        };
+        *position += 1;
+        let unary = |function: fn(f64) -> f64, position: &mut usize| {
+            self.calc_term(tokens, position).map(function)
+        };
+        match token {
+            "(" => {
+                let value = self.calc_expression(tokens, position)?;
+                match tokens.get(*position) {
+                    Some(bracket) if bracket == ")" => {
+                        *position += 1;
+                        Ok(value)
+                    }
+                    _ => Err(self.error("a bracket in the :calc isn't closed")),
+                }
+            }
+            "-" => unary(|value| -value, position),
+            "~" => unary(|value| !(value as i64) as f64, position),
+            "!" => unary(|value| (value == 0.0) as u8 as f64, position),
+            "sin" => unary(f64::sin, position),
+            "cos" => unary(f64::cos, position),
+            "tan" => unary(f64::tan, position),
+            "exp" => unary(f64::exp, position),
+            "log" => unary(f64::ln, position),
+            "abs" => unary(f64::abs, position),
+            "sqrt" => unary(f64::sqrt, position),
+            "sign" => unary(f64::signum, position),
+            "ceil" => unary(f64::ceil, position),
+            "floor" => unary(f64::floor, position),
+            // A byte of the program that has already been assembled
+            "@" => {
+                let address = self.calc_term(tokens, position)? as usize;
+                Ok(address
+                    .checked_sub(START)
+                    .and_then(|offset| self.rom.get(offset))
+                    .copied()
+                    .unwrap_or(0) as f64)
+            }
+            "HERE" => Ok(self.here as f64),
+            "PI" => Ok(std::f64::consts::PI),
+            "E" => Ok(std::f64::consts::E),
+            name => self.constant(name),
+        }
+    }
+
+    /// The value of a number, or of a name that has been given one already
+    fn constant(&self, text: &str) -> Result<f64, AssembleError> {
+        if let Some(number) = parse_number(text) {
+            return Ok(number as f64);
+        }
+        if let Some(value) = self.constants.get(text) {
+            return Ok(*value);
+        }
+        if let Some(address) = self.labels.get(text) {
+            return Ok(*address as f64);
+        }
+        Err(self.error(format!(
+            "{} isn't a number, or a name that has been given one yet",
+            text
+        )))
+    }
+
+    /// The value of a number or a name as a whole number
+    fn value(&self, text: &str) -> Result<i64, AssembleError> {
+        self.constant(text).map(|value| value.floor() as i64)
+    }
+
+    /// Reads a number, a name, or a `{ calc }` after the last token
+    fn number_or_calc(&mut self) -> Result<i64, AssembleError> {
+        let token = self.next()?;
+        if token == "{" {
+            return Ok(self.calc()?.floor() as i64);
+        }
+        self.value(&token)
+    }
+
+    /// Makes sure the value fits in a byte, negative ones go in as their two's
+    /// complement
+    fn fit_byte(&self, value: i64) -> Result<u8, AssembleError> {
+        if !(-128..=255).contains(&value) {
+            return Err(self.error(format!("{} doesn't fit in a byte", value)));
+        }
+        Ok(value as u8)
+    }
+
+    fn byte(&self, text: &str) -> Result<u8, AssembleError> {
+        let value = self.value(text)?;
+        self.fit_byte(value)
+    }
+
+    fn next_nibble(&mut self) -> Result<u8, AssembleError> {
+        let token = self.next()?;
+        match self.value(&token)? {
+            value @ 0..=0xf => Ok(value as u8),
+            value => Err(self.error(format!("{} doesn't fit in 4 bits", value))),
+        }
+    }
+
+    /// The register that the text is, or that it is an alias of
+    fn register(&self, text: &str) -> Option<u8> {
+        parse_register(text).or_else(|| self.aliases.get(text).copied())
+    }
+
+    fn next_register(&mut self) -> Result<u8, AssembleError> {
+        let token = self.next()?;
+        self.register(&token)
+            .ok_or_else(|| self.error(format!("{} isn't a register", token)))
+    }
+
+    /// Assembles `fxnn` with the register that comes next
+    fn register_instruction(&mut self, low: u8) -> Result<(), AssembleError> {
+        let x = self.next_register()?;
+        self.instruction(0xf0 | x, low)
+    }
+
+    /// Gives the label the address, every label can only be put in one place
+    fn define(&mut self, name: String, address: usize) -> Result<(), AssembleError> {
+        if self.labels.contains_key(&name) {
+            return Err(self.error(format!("there is already a label called {}", name)));
+        }
+        self.labels.insert(name, address);
+        Ok(())
+    }
+
+    /// The address that the text is, if it is known yet. Names that aren't
+    /// are taken to be labels that come later.
+    fn known_address(&self, text: &str) -> Result<Option<usize>, AssembleError> {
+        let value = if let Some(number) = parse_number(text) {
+            number
+        } else if let Some(value) = self.constants.get(text) {
+            value.floor() as i64
+        } else if let Some(address) = self.labels.get(text) {
+            *address as i64
+        } else {
+            return Ok(None);
+        };
+        if !(0..=MAX_ADDRESS as i64).contains(&value) {
+            return Err(self.error(format!("{:#x} isn't an address", value)));
+        }
+        Ok(Some(value as usize))
+    }
+
+    /// Assembles the instruction with the top nibble and an address, which is
+    /// filled in later if it is a label that hasn't been found yet
+    fn emit_address(&mut self, nibble: u8, text: &str) -> Result<(), AssembleError> {
+        let at = self.here;
+        self.instruction(nibble << 4, 0)?;
+        match self.known_address(text)? {
+            Some(address) => self.patch(at, address),
+            None => self
+                .fixups
+                .push((Fixup::Instruction(at), text.to_string(), self.line)),
+        }
+        Ok(())
+    }
+
+    /// Puts the address into the bottom 12 bits of the instruction
+    fn patch(&mut self, at: usize, address: usize) {
+        let offset = at - START;
+        self.rom[offset] = self.rom[offset] & 0xf0 | (address >> 8) as u8 & 0xf;
+        self.rom[offset + 1] = address as u8;
+    }
+
+    fn instruction(&mut self, high: u8, low: u8) -> Result<(), AssembleError> {
+        self.emit(high)?;
+        self.emit(low)
+    }
+
+    fn emit(&mut self, byte: u8) -> Result<(), AssembleError> {
+        if self.here > MAX_ADDRESS {
+            return Err(self.error("the program doesn't fit in memory"));
+        }
+        let offset = self.here - START;
+        if offset >= self.rom.len() {
+            self.rom.resize(offset + 1, 0);
+        }
+        self.rom[offset] = byte;
+        self.here += 1;
+        Ok(())
+    }
+
+    fn next_token(&mut self) -> Result<Token, AssembleError> {
+        match self.tokens.pop_front() {
+            Some(token) => {
+                self.line = token.line;
+                Ok(token)
+            }
+            None => Err(self.error("the program ends in the middle of something")),
+        }
+    }
+
+    fn next(&mut self) -> Result<String, AssembleError> {
+        self.next_token().map(|token| token.text)
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), AssembleError> {
+        let token = self.next()?;
+        if token != text {
+            return Err(self.error(format!("expected {}, not {}", text, token)));
+        }
+        Ok(())
+    }
+
+    fn error(&self, message: impl Into<String>) -> AssembleError {
+        AssembleError {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+}
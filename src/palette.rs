@@ -0,0 +1,99 @@
+//! This module contains the colors that the screen can be drawn with
+
+#[cfg(feature = "terminal")]
+use ratatui::style::{Color, Style};
+
+/// The colors that the pixels are drawn with
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    /// The name of the theme, or `custom` if the colors were passed in
+    pub name: &'static str,
+    /// The color of a pixel that is on, as red, green, and blue
+    pub on: [u8; 3],
+    /// The color of a pixel that is off, as red, green, and blue
+    pub off: [u8; 3],
+}
+
+impl Palette {
+    /// Every named theme, in the order that they get cycled through
+    pub const THEMES: [Palette; 5] = [
+        Palette {
+            name: "classic",
+            on: [0xff, 0xff, 0xff],
+            off: [0x00, 0x00, 0x00],
+        },
+        Palette {
+            name: "green",
+            on: [0x33, 0xff, 0x33],
+            off: [0x0a, 0x1a, 0x0a],
+        },
+        Palette {
+            name: "amber",
+            on: [0xff, 0xb0, 0x00],
+            off: [0x1a, 0x10, 0x00],
+        },
+        Palette {
+            name: "gameboy",
+            on: [0x0f, 0x38, 0x0f],
+            off: [0x9b, 0xbc, 0x0f],
+        },
+        Palette {
+            name: "octo",
+            on: [0xff, 0xcc, 0x00],
+            off: [0x99, 0x66, 0x00],
+        },
+    ];
+
+    /// Gets the theme that goes by the name passed in
+    pub fn from_theme(name: &str) -> Option<Palette> {
+        Palette::THEMES
+            .iter()
+            .find(|palette| palette.name == name)
+            .copied()
+    }
+
+    /// Returns the theme that comes after this one, wrapping back around to the
+    /// first one, custom colors go to the first theme
+    #[cfg(feature = "terminal")]
+    pub fn next(self) -> Palette {
+        let position = Palette::THEMES
+            .iter()
+            .position(|palette| palette.name == self.name);
+        match position {
+            Some(position) => Palette::THEMES[(position + 1) % Palette::THEMES.len()],
+            None => Palette::THEMES[0],
+        }
+    }
+
+    /// Returns the color in between the off color and the on color, for a
+    /// pixel that is `brightness` of the way from off to on
+    #[cfg(any(feature = "terminal", feature = "gpu"))]
+    pub fn blend(self, brightness: f32) -> [u8; 3] {
+        let brightness = brightness.clamp(0.0, 1.0);
+        let mut color = [0; 3];
+        for (i, channel) in color.iter_mut().enumerate() {
+            let (off, on) = (self.off[i] as f32, self.on[i] as f32);
+            *channel = (off + (on - off) * brightness).round() as u8;
+        }
+        color
+    }
+
+    /// Returns the style that draws with the palette's on color in front of
+    /// its off color, using 24-bit color
+    #[cfg(feature = "terminal")]
+    pub fn style(self) -> Style {
+        let [red, green, blue] = self.on;
+        let foreground = Color::Rgb(red, green, blue);
+        let [red, green, blue] = self.off;
+        Style::default()
+            .fg(foreground)
+            .bg(Color::Rgb(red, green, blue))
+    }
+}
+
+impl Default for Palette {
+    /// White pixels on a black background
+    fn default() -> Self {
+        Palette::THEMES[0]
+    }
+}
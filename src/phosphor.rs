@@ -0,0 +1,151 @@
+//! This module contains the filters that stop the screen from flickering,
+//! which go in between the interpreter's screen and whatever draws it.
+//!
+//! Most roms move a sprite by xor'ing it off and drawing it again somewhere
+//! else, which makes it flicker, since it is gone for some of the frames. There
+//! are two ways of hiding that:
+//! - ghosting makes the screen look like it is on an old phosphor display,
+//!   where a pixel that gets turned off fades out over a few frames instead of
+//!   going dark straight away, so it is still glowing by the time it gets
+//!   drawn again
+//! - blending draws every pixel that was on in either of the last two frames,
+//!   which is simpler, and is all that a lot of roms need
+//!
+//! The interpreter never sees any of this, it only changes how the frontends
+//! draw the screen, so it can be turned on and off while the rom runs. Which
+//! one a rom uses can be picked with the options, or in the database.
+
+use crate::config::Config;
+use chip_8::Chip8;
+use std::path::Path;
+
+/// How many frames a pixel takes to fade out with `--ghosting`, unless it was
+/// given a number
+pub const DEFAULT_FRAMES: u32 = 4;
+
+/// The ways that the flickering can be hidden
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// The pixels fade out over this many frames
+    Ghosting(u32),
+    /// The last two frames are drawn on top of each other
+    Blend,
+}
+
+impl Filter {
+    /// Gets the filter that goes by the name in the database, which is
+    /// `ghosting` or `blend`
+    pub fn from_name(name: &str) -> Option<Filter> {
+        match name {
+            "ghosting" => Some(Filter::Ghosting(DEFAULT_FRAMES)),
+            "blend" => Some(Filter::Blend),
+            _ => None,
+        }
+    }
+}
+
+/// How bright every pixel of the screen was the last time the timers ticked
+pub struct Phosphor {
+    /// The filter, which is `None` while it is off
+    filter: Option<Filter>,
+    /// The filter that was on last, so it can be turned back on the same way
+    last_filter: Filter,
+    width: usize,
+    /// How bright each pixel is, row by row, from 0 for off to 1 for on
+    brightness: Vec<f32>,
+    /// Which pixels were on the last time the timers ticked, for blending
+    previous: Vec<bool>,
+}
+
+impl Phosphor {
+    /// Creates the filter, it starts out off if there isn't one
+    pub fn new(filter: Option<Filter>) -> Self {
+        Phosphor {
+            filter,
+            last_filter: filter.unwrap_or(Filter::Ghosting(DEFAULT_FRAMES)),
+            width: 0,
+            brightness: Vec::new(),
+            previous: Vec::new(),
+        }
+    }
+
+    /// Creates the filter that the options say to use, or the database if
+    /// they didn't pick one
+    pub fn for_rom(config: &Config, rom: &Path) -> Self {
+        Phosphor::new(config.filter.or_else(|| {
+            config
+                .database
+                .find_file(rom)
+                .and_then(|entry| entry.filter)
+        }))
+    }
+
+    /// Returns whether the screen is being filtered
+    #[cfg(any(feature = "terminal", feature = "sdl"))]
+    pub fn is_on(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    /// Turns the filter on or off
+    pub fn toggle(&mut self) {
+        match self.filter.take() {
+            Some(filter) => {
+                self.last_filter = filter;
+                self.brightness.clear();
+                self.previous.clear();
+            }
+            None => self.filter = Some(self.last_filter),
+        }
+    }
+
+    /// Lights up the pixels that are on, and fades the rest a frame further,
+    /// or lets them stay lit for another frame when blending, this is called
+    /// every time the timers tick. Returns whether any pixels changed that
+    /// the rom didn't change, since the screen has to be drawn again when
+    /// they do, even if the rom didn't draw anything.
+    pub fn update(&mut self, chip8: &Chip8) -> bool {
+        let filter = match self.filter {
+            Some(filter) => filter,
+            None => return false,
+        };
+        let screen = chip8.screen();
+        let (width, height) = screen.size();
+        // A screen that changed size starts out with nothing fading
+        if self.width != width || self.brightness.len() != width * height {
+            self.width = width;
+            self.brightness = vec![0.0; width * height];
+            self.previous = vec![false; width * height];
+        }
+        let mut changed = false;
+        for (i, brightness) in self.brightness.iter_mut().enumerate() {
+            let lit = screen.get_pixel(i % width, i / width);
+            let next = match filter {
+                _ if lit => 1.0,
+                Filter::Ghosting(frames) => (*brightness - 1.0 / frames.max(1) as f32).max(0.0),
+                // A pixel that was on last frame stays on for this one
+                Filter::Blend if self.previous[i] => 1.0,
+                Filter::Blend => 0.0,
+            };
+            changed |= !lit && next != *brightness;
+            *brightness = next;
+            self.previous[i] = lit;
+        }
+        changed
+    }
+
+    /// Returns how bright the pixel is, from 0 to 1. A pixel that is on is
+    /// always all the way bright, even if the filter hasn't caught up to it yet.
+    pub fn brightness(&self, chip8: &Chip8, x: usize, y: usize) -> f32 {
+        let screen = chip8.screen();
+        if screen.get_pixel(x, y) {
+            return 1.0;
+        }
+        if x >= self.width {
+            return 0.0;
+        }
+        self.brightness
+            .get(y * self.width + x)
+            .copied()
+            .unwrap_or(0.0)
+    }
+}
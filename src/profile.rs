@@ -0,0 +1,83 @@
+//! This module contains the compatibility profiles, which put the quirks and
+//! the speed of each of the interpreters that roms were written for together
+//! under its name, so a rom for SUPER-CHIP can be ran right by picking
+//! `schip`, without having to know what each of the quirks does.
+//!
+//! ```rust
+//! use chip_8::{profile::Profile, Quirks};
+//!
+//! let profile = Profile::from_name("schip").unwrap();
+//! assert_eq!(profile.quirks, Quirks::SCHIP);
+//! assert_eq!(profile.speed, Some(1800));
+//! ```
+
+#[cfg(feature = "std")]
+use crate::frontend::Scheduler;
+use crate::quirks::Quirks;
+
+/// The quirks and the speed that an interpreter ran roms with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Profile {
+    /// What the profile goes by
+    pub name: &'static str,
+    /// Which version of the instructions that have changed it ran
+    pub quirks: Quirks,
+    /// How many instructions it ran a second, or `None` when they are timed
+    /// like they were on the COSMAC VIP, see the `timing` module
+    pub speed: Option<u64>,
+}
+
+impl Profile {
+    /// The original interpreter on the COSMAC VIP, with each instruction
+    /// taking as long as it did there
+    pub const VIP: Profile = Profile {
+        name: "vip",
+        quirks: Quirks::VIP,
+        speed: None,
+    };
+
+    /// CHIP-48 on the HP48 calculators, which SUPER-CHIP was built on. It left
+    /// the index one short of where the VIP did after `fx55` and `fx65`, which
+    /// isn't one of the quirks, so it is left where the VIP left it.
+    pub const CHIP48: Profile = Profile {
+        name: "chip48",
+        quirks: Quirks::CHIP48,
+        speed: Some(1800),
+    };
+
+    /// SUPER-CHIP on the HP48 calculators
+    pub const SCHIP: Profile = Profile {
+        name: "schip",
+        quirks: Quirks::SCHIP,
+        speed: Some(1800),
+    };
+
+    /// XO-CHIP, which runs a lot faster than the rest, and wraps sprites
+    /// around the edges of the screen. Only its quirks and speed are here, the
+    /// instructions that it added aren't.
+    pub const XOCHIP: Profile = Profile {
+        name: "xochip",
+        quirks: Quirks::XOCHIP,
+        speed: Some(60000),
+    };
+
+    /// Gets the profile that goes by the name passed in
+    pub fn from_name(name: &str) -> Option<Profile> {
+        match name {
+            "vip" => Some(Profile::VIP),
+            "chip48" => Some(Profile::CHIP48),
+            "schip" => Some(Profile::SCHIP),
+            "xochip" => Some(Profile::XOCHIP),
+            _ => None,
+        }
+    }
+
+    /// Creates a scheduler that runs roms as fast as the interpreter did
+    #[cfg(feature = "std")]
+    pub fn scheduler(&self) -> Scheduler {
+        match self.speed {
+            Some(speed) => Scheduler::with_clock_speed(speed),
+            None => Scheduler::with_vip_timing(),
+        }
+    }
+}
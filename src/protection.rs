@@ -0,0 +1,98 @@
+//! This module contains the write protection, which keeps roms from writing
+//! over the memory below `0x200` where the interpreter and the fonts are. A rom
+//! with a bad index can otherwise store its registers right over the font with
+//! `fx55` or `fx33`, and then every digit that it draws after that comes out
+//! wrong, long after the instruction that did it.
+//!
+//! ```rust
+//! use chip_8::{protection::WriteProtection, Chip8, Chip8Error};
+//!
+//! let mut chip8 = Chip8::with_seed(0);
+//! chip8.set_write_protection(WriteProtection::Trap);
+//! // `a000` points the index at the font, and `f055` stores register 0 there
+//! chip8.load(&[0xa0, 0x00, 0xf0, 0x55]).unwrap();
+//! chip8.clock().unwrap();
+//! assert_eq!(
+//!     chip8.clock(),
+//!     Err(Chip8Error::ProtectedWrite { pc: 0x202, address: 0 })
+//! );
+//! ```
+
+use crate::{chip8::Chip8, error::Chip8Error};
+use tracing::warn;
+
+/// Where the memory that the rom can write to starts, everything before it
+/// belonged to the interpreter on the COSMAC VIP, and holds the fonts here
+pub const PROTECTED_END: usize = 0x200;
+
+/// What happens when a rom writes below `PROTECTED_END`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WriteProtection {
+    /// The write goes through, like it always has
+    #[default]
+    Off,
+    /// The bytes that are protected are left alone and a warning gets logged,
+    /// while the rest of the instruction runs like normal
+    Ignore,
+    /// The instruction stops with `Chip8Error::ProtectedWrite`, without writing
+    /// anything
+    Trap,
+}
+
+impl WriteProtection {
+    /// Gets the write protection that goes by the name passed in
+    pub fn from_name(name: &str) -> Option<WriteProtection> {
+        match name {
+            "off" => Some(WriteProtection::Off),
+            "ignore" => Some(WriteProtection::Ignore),
+            "trap" => Some(WriteProtection::Trap),
+            _ => None,
+        }
+    }
+}
+
+impl Chip8 {
+    /// Sets what happens when the rom writes below `PROTECTED_END`
+    pub fn set_write_protection(&mut self, protection: WriteProtection) {
+        self.write_protection = protection;
+    }
+
+    /// Returns what happens when the rom writes below `PROTECTED_END`
+    pub fn write_protection(&self) -> WriteProtection {
+        self.write_protection
+    }
+
+    /// Makes sure that none of the `length` bytes from the index are
+    /// protected, when writing to them is trapped. They can wrap around to the
+    /// start of memory, so every one of them gets checked.
+    pub(crate) fn check_protected(&self, length: usize) -> Result<(), Chip8Error> {
+        if self.write_protection != WriteProtection::Trap {
+            return Ok(());
+        }
+        match (0..length)
+            .map(|offset| self.index_address(offset))
+            .find(|address| *address < PROTECTED_END)
+        {
+            Some(address) => Err(Chip8Error::ProtectedWrite {
+                pc: self.program_counter,
+                address,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns whether the byte at the address has to be left alone, which
+    /// gets logged since the rom is about to go wrong
+    pub(crate) fn is_protected(&self, address: usize) -> bool {
+        let protected = self.write_protection != WriteProtection::Off && address < PROTECTED_END;
+        if protected {
+            warn!(
+                pc = format_args!("{:03x}", self.program_counter),
+                address = format_args!("{:03x}", address),
+                "left a protected byte alone"
+            );
+        }
+        protected
+    }
+}
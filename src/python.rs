@@ -0,0 +1,160 @@
+//! This module contains the Python bindings, which let the interpreter be
+//! scripted from Python, for teaching, for testing roms, or for training
+//! something to play them.
+//!
+//! It is only built when the `python` feature is turned on, and the module
+//! gets built and installed with `maturin develop`, which is set up in
+//! `pyproject.toml`. Like the C API, Python owns the loop, and nothing is
+//! drawn, Python reads the screen itself.
+//!
+//! ```python
+//! import chip_8
+//!
+//! chip8 = chip_8.Chip8(seed=0)
+//! chip8.load(open("roms/pong.ch8", "rb").read())
+//! chip8.frame(16)
+//! chip8.press_key(0x1)
+//! # One byte for each pixel, which is 1 if it is on, row by row
+//! pixels = chip8.screen()
+//! ```
+
+use crate::chip8::Chip8;
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+
+/// The interpreter, as seen by Python. It holds onto hooks that can't be sent
+/// between threads, so Python can only use it from the thread that created it.
+#[pyclass(name = "Chip8", unsendable)]
+pub struct PyChip8 {
+    chip8: Chip8,
+}
+
+#[pymethods]
+impl PyChip8 {
+    /// Creates an interpreter with nothing loaded into it, the `rnd`
+    /// instruction gives the same numbers every time for the same seed
+    #[new]
+    #[pyo3(signature = (seed = None))]
+    fn new(seed: Option<u64>) -> Self {
+        PyChip8 {
+            chip8: seed.map_or_else(Chip8::new, Chip8::with_seed),
+        }
+    }
+
+    /// Loads the rom's bytes into memory, raises `ValueError` if it doesn't fit
+    fn load(&mut self, rom: &[u8]) -> PyResult<()> {
+        self.chip8
+            .load(rom)
+            .map(|_| ())
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    /// Runs exactly one instruction, raises `RuntimeError` when the rom runs
+    /// into something that the interpreter can't run
+    fn step(&mut self) -> PyResult<()> {
+        self.chip8.clock().map(|_| ()).map_err(runtime_error)
+    }
+
+    /// Runs `cycles` instructions and then ticks the timers, which is one
+    /// 60th of a second
+    fn frame(&mut self, cycles: usize) -> PyResult<()> {
+        self.chip8.frame(cycles).map_err(runtime_error)
+    }
+
+    /// Ticks the delay and sound timers down by one
+    fn tick_timers(&mut self) {
+        self.chip8.tick_timers();
+    }
+
+    /// Marks the key (`0x0` through `0xf`) as being held down
+    fn press_key(&mut self, key: u8) {
+        self.chip8.press_key(key);
+    }
+
+    /// Marks the key (`0x0` through `0xf`) as being let go of
+    fn release_key(&mut self, key: u8) {
+        self.chip8.release_key(key);
+    }
+
+    /// Returns whether the key (`0x0` through `0xf`) is being held down
+    fn is_key_pressed(&self, key: u8) -> bool {
+        self.chip8.is_key_pressed(key)
+    }
+
+    /// Returns the screen as one byte for each pixel, which is 1 if the pixel
+    /// is on and 0 if it isn't, row by row, so `numpy.frombuffer` can turn it
+    /// into an array of `height` rows and `width` columns
+    fn screen<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let screen = self.chip8.screen();
+        let (width, height) = screen.size();
+        let pixels: Vec<u8> = (0..width * height)
+            .map(|i| screen.get_pixel(i % width, i / width) as u8)
+            .collect();
+        PyBytes::new(py, &pixels)
+    }
+
+    /// How many pixels wide the screen is, which can change while it runs
+    #[getter]
+    fn width(&self) -> usize {
+        self.chip8.screen().width()
+    }
+
+    /// How many pixels tall the screen is, which can change while it runs
+    #[getter]
+    fn height(&self) -> usize {
+        self.chip8.screen().height()
+    }
+
+    /// Returns whether the pixel is on
+    fn pixel(&self, x: usize, y: usize) -> bool {
+        self.chip8.screen().get_pixel(x, y)
+    }
+
+    /// The 16 registers, `v0` through `vf`, as bytes
+    #[getter]
+    fn registers(&self) -> Vec<u8> {
+        self.chip8.registers.to_vec()
+    }
+
+    /// Where the next instruction is read from
+    #[getter]
+    fn program_counter(&self) -> usize {
+        self.chip8.program_counter
+    }
+
+    /// The index register, `i`
+    #[getter]
+    fn index(&self) -> usize {
+        self.chip8.index
+    }
+
+    /// A copy of all of memory
+    fn memory<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.chip8.memory)
+    }
+
+    /// Whether the sound timer is running, and a tone should be playing
+    #[getter]
+    fn sound_playing(&self) -> bool {
+        self.chip8.is_sound_playing()
+    }
+
+    /// Returns the instruction at the address, like `ld v0, 0x05`
+    fn disassemble(&self, address: usize) -> String {
+        self.chip8.disassemble(address)
+    }
+}
+
+/// Turns the errors from running the rom into Python's `RuntimeError`
+fn runtime_error(error: crate::error::Chip8Error) -> PyErr {
+    PyRuntimeError::new_err(error.to_string())
+}
+
+/// Adds the classes to the module, this is what `import chip_8` runs
+#[pymodule]
+pub fn chip_8(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyChip8>()
+}
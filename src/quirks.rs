@@ -0,0 +1,91 @@
+//! This module contains the quirks, which are the instructions whose definition
+//! has changed over the years between the different interpreters, so that roms
+//! written for any of them can be ran the way they expect
+
+/// Which version of each of the changed instructions the interpreter runs, the
+/// default is what this interpreter has always done
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quirks {
+    /// `8xy6` and `8xye` shift register x in place and ignore register y,
+    /// instead of shifting register y into register x
+    pub shift: bool,
+    /// `fx55` and `fx65` leave the index pointing after the last register that
+    /// was stored or loaded, instead of leaving it alone
+    pub memory_increment: bool,
+    /// `bnnn` jumps to nnn plus register x (the first digit of nnn), instead of
+    /// plus register 0
+    pub jump: bool,
+    /// `8xy1`, `8xy2`, and `8xy3` set register f to 0
+    pub vf_reset: bool,
+    /// `dxyn` waits for the next 60th of a second before drawing, like the
+    /// VIP waiting for the vertical blank, so only one sprite gets drawn a frame
+    pub display_wait: bool,
+    /// `dxyn` wraps the parts of a sprite that go past the right or bottom edge
+    /// around to the other side, instead of cutting them off
+    pub wrap: bool,
+    /// `fx1e` sets register f to 1 when the index goes past the end of
+    /// memory, which is `0xfff` normally, and to 0 when it doesn't, like the
+    /// interpreter for the Amiga did. The index wraps back around to the
+    /// start instead of going through the index policy.
+    pub index_carry: bool,
+}
+
+impl Quirks {
+    /// How the original interpreter on the COSMAC VIP behaved
+    pub const VIP: Quirks = Quirks {
+        shift: false,
+        memory_increment: true,
+        jump: false,
+        vf_reset: true,
+        display_wait: true,
+        wrap: false,
+        index_carry: false,
+    };
+
+    /// How SUPER-CHIP on the HP48 calculators behaved
+    pub const SCHIP: Quirks = Quirks {
+        shift: true,
+        memory_increment: false,
+        jump: true,
+        vf_reset: false,
+        display_wait: false,
+        wrap: false,
+        index_carry: false,
+    };
+
+    /// How CHIP-48 on the HP48 calculators behaved, which is SUPER-CHIP
+    /// without the index being left alone by `fx55` and `fx65`
+    pub const CHIP48: Quirks = Quirks {
+        shift: true,
+        memory_increment: true,
+        jump: true,
+        vf_reset: false,
+        display_wait: false,
+        wrap: false,
+        index_carry: false,
+    };
+
+    /// How XO-CHIP behaves
+    pub const XOCHIP: Quirks = Quirks {
+        shift: false,
+        memory_increment: true,
+        jump: false,
+        vf_reset: false,
+        display_wait: false,
+        wrap: true,
+        index_carry: false,
+    };
+
+    /// Gets the set of quirks that goes by the name passed in
+    pub fn from_name(name: &str) -> Option<Quirks> {
+        match name {
+            "default" => Some(Quirks::default()),
+            "vip" => Some(Quirks::VIP),
+            "schip" => Some(Quirks::SCHIP),
+            "chip48" => Some(Quirks::CHIP48),
+            "xochip" => Some(Quirks::XOCHIP),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,363 @@
+//! This module contains the remote control, which lets other programs drive
+//! the rom that is running over a socket, like a script that plays it, or a
+//! test that checks what it drew.
+//!
+//! `--remote` listens on a TCP address like `127.0.0.1:8009`, or on a Unix
+//! socket like `unix:/tmp/chip8.sock`, or for WebSockets on an address like
+//! `ws://127.0.0.1:8009`. Any number of programs can connect at once. Each
+//! line that they send is one of the commands in the `commands` module, and
+//! each command gets one line back.
+//!
+//! Over a WebSocket, each message is a command and the answers come back as
+//! messages too. Whenever the interpreter changes, every WebSocket also gets
+//! sent what it looks like now as JSON, with the screen, the registers, and
+//! the instructions around the program counter, which is what the debugger
+//! page that a browser gets from the same address shows.
+
+use crate::{commands::Commands, config::Config, websocket};
+use chip_8::{
+    frontend::{Audio, Display, Event, Input},
+    Chip8,
+};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::{
+    fs,
+    io::{Error, ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+};
+use tracing::info;
+
+/// What a Unix socket's address starts with
+const UNIX_PREFIX: &str = "unix:";
+
+/// What a WebSocket's address starts with
+const WEBSOCKET_PREFIX: &str = "ws://";
+
+/// How many instructions the debugger page gets sent before the program
+/// counter, and after it
+const DISASSEMBLY_BEFORE: i32 = 4;
+const DISASSEMBLY_AFTER: i32 = 12;
+
+/// Something that a program is connected to the remote control through
+trait Connection: Read + Write {}
+
+impl Connection for TcpStream {}
+
+#[cfg(unix)]
+impl Connection for UnixStream {}
+
+/// The socket that programs connect to
+enum Listener {
+    Tcp(TcpListener),
+    /// Browsers get the debugger page, or a WebSocket
+    WebSocket(TcpListener),
+    /// The socket's file gets removed again once the rom is done with it
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl Listener {
+    /// Starts listening on the address, without waiting for anything to
+    /// connect, so the rom keeps running
+    fn bind(address: &str) -> Result<Listener, Error> {
+        let listener = match address.strip_prefix(UNIX_PREFIX) {
+            #[cfg(unix)]
+            Some(path) => {
+                // A socket that was left behind by an app that didn't close
+                // cleanly would stop it from being made again
+                let path = PathBuf::from(path);
+                if path.exists() {
+                    fs::remove_file(&path)?;
+                }
+                let listener = UnixListener::bind(&path)?;
+                listener.set_nonblocking(true)?;
+                Listener::Unix(listener, path)
+            }
+            #[cfg(not(unix))]
+            Some(_) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Unix sockets can only be used on Unix, use a TCP address instead",
+                ))
+            }
+            None => match address.strip_prefix(WEBSOCKET_PREFIX) {
+                Some(address) => {
+                    let listener = TcpListener::bind(address)?;
+                    listener.set_nonblocking(true)?;
+                    Listener::WebSocket(listener)
+                }
+                None => {
+                    let listener = TcpListener::bind(address)?;
+                    listener.set_nonblocking(true)?;
+                    Listener::Tcp(listener)
+                }
+            },
+        };
+        info!(address, "the remote control is listening");
+        Ok(listener)
+    }
+
+    /// Returns a program that has connected, if one has
+    fn accept(&self) -> Option<Client> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept().ok()?;
+                stream.set_nonblocking(true).ok()?;
+                // Every answer is a tiny message, and waiting to put them
+                // together would only slow the program down
+                stream.set_nodelay(true).ok()?;
+                Some(Client::new(Box::new(stream), None))
+            }
+            Listener::WebSocket(listener) => loop {
+                let (mut stream, _) = listener.accept().ok()?;
+                stream.set_nodelay(true).ok()?;
+                // A browser that only wanted the page is done once it has it
+                if let Ok(true) = websocket::handshake(&mut stream) {
+                    return Some(Client::new(Box::new(stream), Some(Vec::new())));
+                }
+            },
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => {
+                let (stream, _) = listener.accept().ok()?;
+                stream.set_nonblocking(true).ok()?;
+                Some(Client::new(Box::new(stream), None))
+            }
+        }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            if let Listener::Unix(_, path) = self {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// A program that is connected, and what it has sent that isn't a whole line
+/// or message yet
+struct Client {
+    connection: Box<dyn Connection>,
+    unread: Vec<u8>,
+    /// For a WebSocket, the pieces of the message that is coming in, which is
+    /// `None` for anything else
+    websocket: Option<Vec<u8>>,
+    /// What the interpreter looked like the last time it was sent to a
+    /// WebSocket, so it's only sent again once it has changed
+    last_state: String,
+}
+
+impl Client {
+    fn new(connection: Box<dyn Connection>, websocket: Option<Vec<u8>>) -> Self {
+        Client {
+            connection,
+            unread: Vec::new(),
+            websocket,
+            last_state: String::new(),
+        }
+    }
+
+    /// Reads everything that the program has sent so far, and returns the
+    /// whole commands, or `None` once it has disconnected
+    fn read_commands(&mut self) -> Option<Vec<String>> {
+        let mut buffer = [0; 1024];
+        loop {
+            match self.connection.read(&mut buffer) {
+                Ok(0) => return None,
+                Ok(read) => self.unread.extend_from_slice(&buffer[..read]),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => return None,
+            }
+        }
+        let mut lines = Vec::new();
+        if let Some(partial) = &mut self.websocket {
+            while let Some(message) = websocket::read_message(&mut self.unread, partial) {
+                match message {
+                    websocket::Message::Text(text) => {
+                        lines.extend(text.lines().map(|line| line.trim().to_string()))
+                    }
+                    websocket::Message::Ping(payload) => {
+                        self.connection.write_all(&websocket::pong(&payload)).ok()?
+                    }
+                    websocket::Message::Close => return None,
+                }
+            }
+            return Some(lines);
+        }
+        while let Some(end) = self.unread.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.unread.drain(..=end).collect();
+            lines.push(String::from_utf8_lossy(&line).trim().to_string());
+        }
+        Some(lines)
+    }
+
+    /// Sends a line to the program, or a message if it's a WebSocket. A
+    /// program that can't be sent to has gone away, and anything that was
+    /// only partly sent would throw it off anyways.
+    fn send(&mut self, line: &str) -> Result<(), Error> {
+        match self.websocket {
+            Some(_) => self.connection.write_all(&websocket::text(line)),
+            None => writeln!(self.connection, "{}", line),
+        }
+    }
+
+    /// Sends the interpreter to a WebSocket if it has changed since the last
+    /// time
+    fn send_state(&mut self, state: &str) -> Result<(), Error> {
+        if self.websocket.is_none() || self.last_state == state {
+            return Ok(());
+        }
+        self.send(state)?;
+        self.last_state = state.to_string();
+        Ok(())
+    }
+}
+
+/// Wraps a frontend, and runs the commands that come in over the socket on top
+/// of whatever it does. Without an address it doesn't do anything, and only
+/// passes everything on to the frontend.
+pub struct RemoteFrontend<'a, F> {
+    frontend: &'a mut F,
+    listener: Option<Listener>,
+    clients: Vec<Client>,
+    commands: Commands,
+}
+
+impl<'a, F> RemoteFrontend<'a, F> {
+    /// Starts listening on the address from the options, if there is one
+    pub fn listen(frontend: &'a mut F, config: &Config) -> Result<Self, Error> {
+        let listener = match &config.remote {
+            Some(address) => Some(Listener::bind(address)?),
+            None => None,
+        };
+        Ok(RemoteFrontend {
+            frontend,
+            listener,
+            clients: Vec::new(),
+            commands: Commands::new(config),
+        })
+    }
+
+    /// Lets in the programs that have connected, and runs what they sent
+    fn serve(&mut self, chip8: &mut Chip8) {
+        let listener = match &self.listener {
+            Some(listener) => listener,
+            None => return,
+        };
+        while let Some(client) = listener.accept() {
+            self.clients.push(client);
+        }
+
+        let mut clients = std::mem::take(&mut self.clients);
+        clients.retain_mut(|client| {
+            let lines = match client.read_commands() {
+                Some(lines) => lines,
+                None => return false,
+            };
+            for line in lines.iter().filter(|line| !line.is_empty()) {
+                let answer = self.commands.run(chip8, line);
+                // A program that can't be answered has gone away
+                if client.send(&answer).is_err() {
+                    return false;
+                }
+            }
+            true
+        });
+
+        // The state is only worked out when there is a WebSocket to send it to
+        if clients.iter().any(|client| client.websocket.is_some()) {
+            let state = state(chip8);
+            clients.retain_mut(|client| client.send_state(&state).is_ok());
+        }
+        self.clients = clients;
+    }
+}
+
+/// Describes the interpreter as JSON, for the debugger page
+fn state(chip8: &Chip8) -> String {
+    let registers: Vec<String> = chip8
+        .registers
+        .iter()
+        .map(|register| register.to_string())
+        .collect();
+    let stack: Vec<String> = chip8
+        .call_stack()
+        .iter()
+        .map(|frame| frame.call_site.to_string())
+        .collect();
+    let pixels: String = chip8
+        .screen()
+        .as_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    // None of the instructions have anything in them that needs escaping
+    let disassembly: Vec<String> = (-DISASSEMBLY_BEFORE..DISASSEMBLY_AFTER)
+        .filter_map(|relative| chip8.get_relative_instruction(relative))
+        .map(|instruction| {
+            format!(
+                "{{\"address\":{},\"opcode\":{},\"text\":\"{}\"}}",
+                instruction.address, instruction.opcode, instruction
+            )
+        })
+        .collect();
+    let (width, height) = chip8.screen().size();
+    format!(
+        "{{\"paused\":{},\"pc\":{},\"i\":{},\"dt\":{},\"st\":{},\"v\":[{}],\"stack\":[{}],\"screen\":{{\"width\":{},\"height\":{},\"pixels\":\"{}\"}},\"disassembly\":[{}]}}",
+        chip8.is_paused(),
+        chip8.program_counter,
+        chip8.index,
+        chip8.delay,
+        chip8.sound,
+        registers.join(","),
+        stack.join(","),
+        width,
+        height,
+        pixels,
+        disassembly.join(",")
+    )
+}
+
+impl<'a, F: Input> Input for RemoteFrontend<'a, F> {
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        let event = self.frontend.handle_input(chip8);
+        self.serve(chip8);
+        if self.commands.should_quit() {
+            return Some(Event::Quit);
+        }
+        event
+    }
+
+    /// The keys that are held down get pressed again for every frame, in case
+    /// the frontend let go of them
+    fn start_frame(&mut self, chip8: &mut Chip8) {
+        self.frontend.start_frame(chip8);
+        self.commands.hold_keys(chip8);
+    }
+
+    fn before_instruction(&mut self, chip8: &mut Chip8) {
+        self.frontend.before_instruction(chip8);
+    }
+
+    fn tick(&mut self, chip8: &mut Chip8) {
+        self.frontend.tick(chip8);
+    }
+}
+
+impl<'a, F: Display> Display for RemoteFrontend<'a, F> {
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error> {
+        self.frontend.draw(chip8)
+    }
+}
+
+impl<'a, F: Audio> Audio for RemoteFrontend<'a, F> {
+    fn set_playing(&mut self, playing: bool) -> Result<(), Error> {
+        self.frontend.set_playing(playing)
+    }
+}
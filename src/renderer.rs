@@ -0,0 +1,267 @@
+//! This module contains the different ways that the interpreter's screen can be
+//! turned into characters for the terminal
+
+use crate::{phosphor::Phosphor, rotation::Rotation};
+use chip_8::Chip8;
+
+/// The different ways of drawing the screen, which can be switched between while
+/// the app is running
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Renderer {
+    /// Every pixel is drawn as a full block character, which is the default
+    Block,
+    /// Every two pixels on top of each other are drawn as one half block
+    /// character, so the screen is half as tall and the pixels are squarer
+    HalfBlock,
+    /// Every 2x4 group of pixels is drawn as a single braille character, so the
+    /// screen takes up an eighth of the space
+    Braille,
+}
+
+/// How many characters wide and tall each character of the renderer is drawn
+/// as, when the terminal has room to make the screen bigger
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Scale {
+    /// As big as fits in the terminal, keeping the pixels square
+    Auto,
+    /// Always the same number of columns and rows, when there is room for it
+    Fixed(usize, usize),
+}
+
+impl Scale {
+    /// Gets the scale written as `auto`, or as the columns and rows like `2x1`
+    pub fn from_name(name: &str) -> Option<Scale> {
+        if name == "auto" {
+            return Some(Scale::Auto);
+        }
+        let mut parts = name.splitn(2, 'x');
+        let columns = parts.next()?.parse().ok()?;
+        let rows = parts.next()?.parse().ok()?;
+        if columns == 0 || rows == 0 {
+            return None;
+        }
+        Some(Scale::Fixed(columns, rows))
+    }
+}
+
+impl Renderer {
+    /// Every renderer, in the order that they get cycled through
+    const ALL: [Renderer; 3] = [Renderer::Block, Renderer::HalfBlock, Renderer::Braille];
+
+    /// Gets the renderer that goes by the name passed in
+    pub fn from_name(name: &str) -> Option<Renderer> {
+        match name {
+            "block" => Some(Renderer::Block),
+            "half-block" => Some(Renderer::HalfBlock),
+            "braille" => Some(Renderer::Braille),
+            _ => None,
+        }
+    }
+
+    /// Returns the renderer that comes after this one, wrapping back around to
+    /// the first one
+    pub fn next(self) -> Renderer {
+        let position = Renderer::ALL
+            .iter()
+            .position(|renderer| *renderer == self)
+            .unwrap_or(0);
+        Renderer::ALL[(position + 1) % Renderer::ALL.len()]
+    }
+
+    /// Returns how many pixels wide and tall a single character is
+    fn cell_size(self) -> (usize, usize) {
+        match self {
+            Renderer::Block => (1, 1),
+            Renderer::HalfBlock => (1, 2),
+            Renderer::Braille => (2, 4),
+        }
+    }
+
+    /// Returns how many columns and rows each character gets scaled by at the
+    /// smallest size, so the pixels come out square. A character is about
+    /// twice as tall as it is wide, so a block has to be two wide.
+    pub fn aspect(self) -> (usize, usize) {
+        match self {
+            Renderer::Block => (2, 1),
+            Renderer::HalfBlock | Renderer::Braille => (1, 1),
+        }
+    }
+
+    /// Returns how many columns and rows are needed to draw a screen of the size passed in
+    pub fn size(self, width: usize, height: usize) -> (u16, u16) {
+        let (cell_width, cell_height) = self.cell_size();
+        (
+            width.div_ceil(cell_width) as u16,
+            height.div_ceil(cell_height) as u16,
+        )
+    }
+
+    /// Turns the interpreter's screen into the lines that need to be written to
+    /// the terminal, turned by the rotation, the pixels that are still fading
+    /// out are drawn as well
+    pub fn render(self, chip8: &Chip8, phosphor: &Phosphor, rotation: Rotation) -> Vec<String> {
+        let (width, height) = rotation.size(chip8.screen().size());
+        let (columns, rows) = self.size(width, height);
+        let pixel = |x, y| brightness(chip8, phosphor, rotation, x, y) > 0.0;
+
+        (0..rows)
+            .map(|row| {
+                (0..columns)
+                    .map(|column| self.character(&pixel, column, row))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns how bright each character of `render` is, which is the
+    /// brightest of the pixels in it, so the ones that only have pixels that
+    /// are fading out can be drawn dimmer
+    pub fn shades(self, chip8: &Chip8, phosphor: &Phosphor, rotation: Rotation) -> Vec<Vec<f32>> {
+        let (width, height) = rotation.size(chip8.screen().size());
+        let (columns, rows) = self.size(width, height);
+        let (cell_width, cell_height) = self.cell_size();
+
+        (0..rows as usize)
+            .map(|row| {
+                (0..columns as usize)
+                    .map(|column| {
+                        let (x, y) = (column * cell_width, row * cell_height);
+                        (0..cell_height)
+                            .flat_map(|dy| (0..cell_width).map(move |dx| (x + dx, y + dy)))
+                            .map(|(x, y)| brightness(chip8, phosphor, rotation, x, y))
+                            .fold(0.0, f32::max)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns the character that is drawn at the column and row
+    fn character(self, pixel: Pixel, column: u16, row: u16) -> char {
+        let (cell_width, cell_height) = self.cell_size();
+        let x = column as usize * cell_width;
+        let y = row as usize * cell_height;
+        match self {
+            Renderer::Block => block_character(pixel, x, y),
+            Renderer::HalfBlock => half_block_character(pixel, x, y),
+            Renderer::Braille => braille_character(pixel, x, y),
+        }
+    }
+}
+
+/// Returns how bright the pixel at the coordinates of the turned screen is,
+/// anything off the screen is off
+fn brightness(chip8: &Chip8, phosphor: &Phosphor, rotation: Rotation, x: usize, y: usize) -> f32 {
+    rotation
+        .to_screen(x, y, chip8.screen().size())
+        .map(|(x, y)| phosphor.brightness(chip8, x, y))
+        .unwrap_or(0.0)
+}
+
+/// Whether the pixel at the coordinates is lit, anything off the screen isn't
+type Pixel<'a> = &'a dyn Fn(usize, usize) -> bool;
+
+/// If the pixel is on it is drawn as a fill block character, otherwise an empty
+/// block (space)
+fn block_character(pixel: Pixel, x: usize, y: usize) -> char {
+    if pixel(x, y) {
+        '█'
+    } else {
+        ' '
+    }
+}
+
+/// Picks the character that has the top half, the bottom half, both halves,
+/// or neither filled in
+fn half_block_character(pixel: Pixel, x: usize, y: usize) -> char {
+    match (pixel(x, y), pixel(x, y + 1)) {
+        (true, true) => '█',
+        (true, false) => '▀',
+        (false, true) => '▄',
+        (false, false) => ' ',
+    }
+}
+
+/// Braille characters start at `U+2800`, and each of the 8 dots is one bit added
+/// on to that, numbered in this order:
+/// ```text
+/// 0 3
+/// 1 4
+/// 2 5
+/// 6 7
+/// ```
+fn braille_character(pixel: Pixel, x: usize, y: usize) -> char {
+    const DOTS: [(usize, usize, u32); 8] = [
+        (0, 0, 0x01),
+        (0, 1, 0x02),
+        (0, 2, 0x04),
+        (1, 0, 0x08),
+        (1, 1, 0x10),
+        (1, 2, 0x20),
+        (0, 3, 0x40),
+        (1, 3, 0x80),
+    ];
+
+    let mut dots = 0;
+    for (dot_x, dot_y, bit) in DOTS.iter() {
+        if pixel(x + dot_x, y + dot_y) {
+            dots |= bit;
+        }
+    }
+    // Every value from `U+2800` to `U+28FF` is a braille character, so this can't fail
+    std::char::from_u32(0x2800 + dots).unwrap_or(' ')
+}
+
+/// Repeats every character `columns` times across, and every line `rows`
+/// times down, which makes the screen bigger without changing its shape
+pub fn enlarge(lines: Vec<String>, (columns, rows): (usize, usize)) -> Vec<String> {
+    if (columns, rows) == (1, 1) {
+        return lines;
+    }
+    lines
+        .iter()
+        .flat_map(|line| {
+            let wide: String = line
+                .chars()
+                .flat_map(|character| std::iter::repeat_n(character, columns))
+                .collect();
+            std::iter::repeat_n(wide, rows)
+        })
+        .collect()
+}
+
+/// Repeats the brightness of every character the same way that `enlarge`
+/// repeats the characters
+pub fn enlarge_shades(shades: Vec<Vec<f32>>, (columns, rows): (usize, usize)) -> Vec<Vec<f32>> {
+    shades
+        .iter()
+        .flat_map(|row| {
+            let wide: Vec<f32> = row
+                .iter()
+                .flat_map(|shade| std::iter::repeat_n(*shade, columns))
+                .collect();
+            std::iter::repeat_n(wide, rows)
+        })
+        .collect()
+}
+
+/// Draws a box around the lines, which makes them a column wider and a row
+/// taller on each side. A `heavy` box is drawn with thicker lines, which is
+/// how the terminal shows that the tone is playing.
+pub fn add_border(lines: Vec<String>, heavy: bool) -> Vec<String> {
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let [top_left, top_right, bottom_left, bottom_right, across, down] = if heavy {
+        ['┏', '┓', '┗', '┛', '━', '┃']
+    } else {
+        ['┌', '┐', '└', '┘', '─', '│']
+    };
+    let edge: String = std::iter::repeat_n(across, width).collect();
+    let mut bordered = vec![format!("{}{}{}", top_left, edge, top_right)];
+    bordered.extend(lines.iter().map(|line| format!("{}{}{}", down, line, down)));
+    bordered.push(format!("{}{}{}", bottom_left, edge, bottom_right));
+    bordered
+}
@@ -0,0 +1,51 @@
+//! This module contains the random number generator that the `rnd` instruction
+//! uses, which is kept inside of the interpreter so that a run can be repeated
+//! exactly by starting it with the same seed. The parent program can pass in
+//! somewhere else to get the numbers from instead, like the hardware random
+//! number generator of a microcontroller.
+
+/// A SplitMix64 generator, it is tiny and fast, and any seed (even 0) works
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Rng {
+    state: u64,
+    /// Where the numbers come from instead of the seed, if the parent program
+    /// passed somewhere in. It belongs to the parent program, so it isn't saved
+    /// with the rest of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    source: Option<fn() -> u8>,
+}
+
+impl Rng {
+    /// Creates a generator that always gives the same numbers for the same seed
+    pub(crate) fn new(seed: u64) -> Rng {
+        Rng {
+            state: seed,
+            source: None,
+        }
+    }
+
+    /// Gets the numbers from the function instead of the seed, or from the
+    /// seed again without one
+    pub(crate) fn set_source(&mut self, source: Option<fn() -> u8>) {
+        self.source = source;
+    }
+
+    /// Returns the next number
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns the next number, cut down to a byte
+    pub(crate) fn next_u8(&mut self) -> u8 {
+        if let Some(source) = self.source {
+            return source();
+        }
+        // The top bits are the most random ones
+        (self.next_u64() >> 56) as u8
+    }
+}
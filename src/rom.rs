@@ -0,0 +1,429 @@
+//! This module contains the code for reading roms off of the disk, and for
+//! loading them again when they change
+
+#[cfg(feature = "gamepad")]
+use crate::gamepad::{GamepadFrontend, Mapping};
+#[cfg(feature = "scripting")]
+use crate::script::ScriptFrontend;
+use crate::{
+    cheat_list::CheatsFrontend,
+    config::{Config, Extension},
+    flags::FlagsFrontend,
+    movie::{Movie, MovieFrontend},
+    netplay::NetplayFrontend,
+    remote::RemoteFrontend,
+};
+use chip_8::{
+    frontend::{self, Audio, Display, Event, Frontend, Input, Scheduler},
+    hires, octo,
+    symbols::Symbols,
+    Chip8,
+};
+use std::{
+    fs::{self, File},
+    io::{Error, ErrorKind, Read},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tracing::{debug, info};
+
+/// Reads the rom at the path, Octo source in a `.8o` file gets assembled into
+/// one first
+pub fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    if path.extension().is_some_and(|extension| extension == "8o") {
+        let source = fs::read_to_string(path)?;
+        return octo::assemble(&source).map_err(|error| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{} can't be assembled, {}", path.display(), error),
+            )
+        });
+    }
+    // Opens the rom file
+    let mut rom_file = File::open(path)?;
+    // Creates a buffer to store the file
+    let mut rom: Vec<u8> = Vec::new();
+    // Writes to the buffer
+    rom_file.read_to_end(&mut rom)?;
+    Ok(rom)
+}
+
+/// Reads the rom at the path, and creates an interpreter with it loaded, using
+/// the seed for the `rnd` instruction if there is one, at the load address,
+/// with the extension turned on if one was asked for, or hi-res Chip-8 if the
+/// rom looks like it was made for it, and the font from the font file if there
+/// is one
+pub fn load(
+    path: &Path,
+    seed: Option<u64>,
+    load_address: usize,
+    extension: Option<Extension>,
+    font: Option<&Path>,
+) -> Result<Chip8, Error> {
+    let rom = read(path)?;
+    // Loads the rom into the interpreter's memory
+    let seed = seed.unwrap_or_else(rand::random);
+    let mut chip8 = Chip8::with_load_address(seed, load_address);
+    if let Some(font) = font {
+        chip8.load_font(&fs::read(font)?)?;
+    }
+    // This has to be on before the rom is loaded, since MEGA-CHIP can keep
+    // more of it
+    match extension {
+        Some(Extension::MegaChip) => chip8.enable_megachip(),
+        Some(Extension::Chip8X) => chip8.enable_chip8x(),
+        Some(Extension::HiRes) | None => {}
+    }
+    let info = chip8.load(&rom)?;
+    // Hi-res roms all start the same way, so they get it without having to
+    // ask, and it goes on after the rom since the font gets moved into it
+    let is_hires = extension.is_none() && load_address == 0x200 && hires::is_hires_rom(&rom);
+    if extension == Some(Extension::HiRes) || is_hires {
+        chip8.enable_hires();
+    }
+    // Which instructions run is always counted, for the debugger's coverage
+    // view and the `coverage` command, since it is only a number for each
+    // address. It goes on last, once memory is as big as it's going to be.
+    chip8.enable_coverage();
+    info!(
+        path = %path.display(),
+        size = info.size,
+        seed,
+        load_address = format_args!("{:03x}", load_address),
+        "loaded the rom"
+    );
+    Ok(chip8)
+}
+
+/// Loads the rom and runs it in the frontend until it asks to stop, with the
+/// gamepad pressing keys, the remote control listening, and the script hooked
+/// into it if there is one
+pub fn run<F: Frontend>(frontend: &mut F, path: &Path, config: &Config) -> Result<Event, Error> {
+    // The gamepad goes underneath everything else, so it presses keys the
+    // same way that the keyboard does
+    #[cfg(feature = "gamepad")]
+    let mut gamepad = GamepadFrontend::new(frontend, Mapping::load(path, config)?);
+    #[cfg(feature = "gamepad")]
+    let frontend = &mut gamepad;
+    #[cfg(not(feature = "gamepad"))]
+    {
+        if let Some(mapping) = &config.gamepad {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} can't be used, turn on gamepads with `cargo build --features gamepad`",
+                    mapping.display()
+                ),
+            ));
+        }
+    }
+    // The remote control presses keys the same way too, so they get recorded
+    // in movies and sent to netplay peers like any others
+    let mut remote = RemoteFrontend::listen(frontend, config)?;
+    let frontend = &mut remote;
+
+    #[cfg(feature = "scripting")]
+    {
+        if let Some(script) = &config.script {
+            let mut scripted = ScriptFrontend::load(frontend, script)?;
+            let event = run_session(&mut scripted, path, config)?;
+            scripted.finish()?;
+            return Ok(event);
+        }
+    }
+    #[cfg(not(feature = "scripting"))]
+    {
+        if let Some(script) = &config.script {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} can't be ran, turn on scripting with `cargo build --features scripting`",
+                    script.display()
+                ),
+            ));
+        }
+    }
+    run_session(frontend, path, config)
+}
+
+/// Runs the rom in the frontend until it asks to stop, recording
+/// or playing back a movie, or mirroring it over the network, if one of those
+/// was asked for. Otherwise the rom gets started over whenever the file
+/// changes, and it's flags and cheats are saved, which would all throw the
+/// others off.
+fn run_session<F: Frontend>(
+    frontend: &mut F,
+    path: &Path,
+    config: &Config,
+) -> Result<Event, Error> {
+    if config.host.is_some() || config.join.is_some() {
+        let mut netplay = match &config.host {
+            Some(address) => {
+                let seed = config.seed.unwrap_or_else(rand::random);
+                NetplayFrontend::host(frontend, address, seed)?
+            }
+            None => NetplayFrontend::join(frontend, config.join.as_deref().unwrap_or_default())?,
+        };
+        // Both ends have to start with the same seed to stay the same
+        let (mut chip8, scheduler) = start(path, Some(netplay.seed()), config)?;
+        let event = frontend::run_with(&mut chip8, &mut netplay, scheduler);
+        finish(&chip8, config)?;
+        let event = event?;
+        netplay.finish()?;
+        return Ok(event);
+    }
+
+    if let Some(movie_path) = &config.playback {
+        // The movie only plays back the same if the seed is the same
+        let movie = Movie::load(movie_path)?;
+        let (mut chip8, scheduler) = start(path, Some(movie.seed), config)?;
+        let mut player = MovieFrontend::play(frontend, movie).branch_at(config.branch);
+        let event = frontend::run_with(&mut chip8, &mut player, scheduler);
+        finish(&chip8, config)?;
+        let event = event?;
+        let movie = player.finish()?;
+        // The movie is saved with whatever the user did after it branched
+        if let Some(record_path) = &config.record {
+            movie.save(record_path)?;
+        }
+        return Ok(event);
+    }
+
+    if let Some(movie_path) = &config.record {
+        // The seed has to be known to be saved with the movie
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let (mut chip8, scheduler) = start(path, Some(seed), config)?;
+        let mut recorder = MovieFrontend::record(frontend, seed);
+        let event = frontend::run_with(&mut chip8, &mut recorder, scheduler);
+        finish(&chip8, config)?;
+        let event = event?;
+        recorder.finish()?.save(movie_path)?;
+        return Ok(event);
+    }
+
+    let (mut chip8, scheduler) = start(path, config.seed, config)?;
+    let mut cheats = CheatsFrontend::load(frontend, path, &mut chip8)?;
+    let mut flags = FlagsFrontend::load(&mut cheats, path, &mut chip8);
+    let event = frontend::run_with(
+        &mut chip8,
+        &mut HotReload::new(&mut flags, path, config),
+        scheduler,
+    );
+    finish(&chip8, config)?;
+    event
+}
+
+/// Writes the coverage report to the file from `--coverage` once the rom has
+/// stopped, which is done even when it stopped with an error, since that is
+/// when it's most useful to know how far the rom got
+fn finish(chip8: &Chip8, config: &Config) -> Result<(), Error> {
+    match &config.coverage {
+        Some(path) => save_coverage(chip8, path),
+        None => Ok(()),
+    }
+}
+
+/// Writes the report of which instructions have ran to the file, along with
+/// the code from where the rom was loaded that hasn't ran
+pub fn save_coverage(chip8: &Chip8, path: &Path) -> Result<(), Error> {
+    let coverage = chip8
+        .coverage()
+        .ok_or_else(|| Error::other("the instructions that ran aren't being counted"))?;
+    let report = coverage.report(chip8, chip8.load_address(), chip8.memory.len());
+    fs::write(path, report)
+}
+
+/// Reads the names of the addresses in the rom from `--symbols`, or from the
+/// `.sym` file next to the rom, like Octo saves. Octo source has it's labels
+/// as names when it doesn't have a symbol file, and any other rom without one
+/// doesn't have any names, but a `--symbols` file that isn't there is an error.
+pub fn symbols(path: &Path, config: &Config) -> Result<Symbols, Error> {
+    let (file, optional) = match &config.symbols {
+        Some(file) => (file.clone(), false),
+        None => (path.with_extension("sym"), true),
+    };
+    let text = match fs::read_to_string(&file) {
+        Ok(text) => text,
+        // A rom that can't be assembled gets it's error shown when it's read
+        Err(error) if optional && error.kind() == ErrorKind::NotFound => {
+            return Ok(labels(path).unwrap_or_default())
+        }
+        Err(error) => {
+            return Err(Error::new(
+                error.kind(),
+                format!("{} can't be read, {}", file.display(), error),
+            ))
+        }
+    };
+    text.parse().map_err(|error| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{} can't be read, {}", file.display(), error),
+        )
+    })
+}
+
+/// Returns the labels in the rom, if it is Octo source that can be assembled
+fn labels(path: &Path) -> Option<Symbols> {
+    if path.extension()? != "8o" {
+        return None;
+    }
+    let source = fs::read_to_string(path).ok()?;
+    octo::assemble_with_symbols(&source)
+        .ok()
+        .map(|(_, symbols)| symbols)
+}
+
+/// Loads the rom with the seed, and sets it up the way the options say to, or
+/// the way the database says to for the options that weren't picked. The
+/// scheduler that runs it as fast as it should run is returned along with it.
+pub fn start(path: &Path, seed: Option<u64>, config: &Config) -> Result<(Chip8, Scheduler), Error> {
+    let mut chip8 = load(
+        path,
+        seed,
+        config.load_address,
+        config.extension,
+        config.font.as_deref(),
+    )?;
+    let entry = config.database.find_file(path);
+    // The options go before the database, and the quirks or speed that were
+    // picked on their own go on top of the profile that they came with
+    let entry_profile = entry.and_then(|entry| entry.profile);
+    chip8.quirks = config
+        .quirks
+        .or(config.profile.map(|profile| profile.quirks))
+        .or_else(|| entry.and_then(|entry| entry.quirks))
+        .or(entry_profile.map(|profile| profile.quirks))
+        .unwrap_or_default();
+    chip8.quirks.wrap |= config.wrap;
+    chip8.quirks.index_carry |= config.index_carry;
+    chip8.set_break_on_unknown(config.break_on_unknown);
+    chip8.set_write_protection(config.write_protection);
+    chip8.set_program_counter_policy(config.program_counter_policy);
+    chip8.set_index_policy(config.index_policy);
+    // The VIP timing works out its own speed from the instructions
+    let scheduler = config
+        .vip_timing
+        .then(Scheduler::with_vip_timing)
+        .or_else(|| config.speed.map(Scheduler::with_clock_speed))
+        .or_else(|| config.profile.map(|profile| profile.scheduler()))
+        .or_else(|| {
+            entry
+                .and_then(|entry| entry.speed)
+                .map(Scheduler::with_clock_speed)
+        })
+        .or_else(|| entry_profile.map(|profile| profile.scheduler()))
+        .unwrap_or_default();
+    if let Some(entry) = entry {
+        info!(title = %entry.name(), "found the rom in the database");
+    }
+    debug!(
+        quirks = ?chip8.quirks,
+        profile = ?config.profile.or(entry_profile).map(|profile| profile.name),
+        "set up the rom"
+    );
+    // Makes sure the whole screen gets drawn, even before the rom draws anything
+    chip8.request_redraw();
+    Ok((chip8, scheduler))
+}
+
+/// Returns when the file was last changed, if that can be found out
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Wraps a frontend, and starts the rom over whenever the file is saved, so
+/// that a rom that is being written can be tried out as soon as it is assembled
+pub struct HotReload<'a, F> {
+    frontend: &'a mut F,
+    path: PathBuf,
+    /// The seed the rom gets started over with
+    seed: Option<u64>,
+    /// Where the rom gets loaded when it is started over
+    load_address: usize,
+    /// The extension the rom gets started over with
+    extension: Option<Extension>,
+    /// The font file the rom gets started over with
+    font: Option<PathBuf>,
+    /// When the file was changed the last time it was loaded
+    modified: Option<SystemTime>,
+}
+
+impl<'a, F> HotReload<'a, F> {
+    /// Watches the rom at the path, which should be the one that is already
+    /// loaded with the options
+    pub fn new(frontend: &'a mut F, path: &Path, config: &Config) -> Self {
+        HotReload {
+            frontend,
+            path: path.to_path_buf(),
+            seed: config.seed,
+            load_address: config.load_address,
+            extension: config.extension,
+            font: config.font.clone(),
+            modified: modified(path),
+        }
+    }
+}
+
+impl<'a, F: Input> Input for HotReload<'a, F> {
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        self.frontend.handle_input(chip8)
+    }
+
+    fn start_frame(&mut self, chip8: &mut Chip8) {
+        self.frontend.start_frame(chip8);
+    }
+
+    fn before_instruction(&mut self, chip8: &mut Chip8) {
+        self.frontend.before_instruction(chip8);
+    }
+
+    /// Checks the file every 60th of a second, there isn't a way to be told
+    /// about it that works everywhere, and looking at it is cheap
+    fn tick(&mut self, chip8: &mut Chip8) {
+        self.frontend.tick(chip8);
+
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return;
+        }
+        // The assembler might still be writing the file, if it can't be read
+        // then it gets tried again on the next tick
+        let reloaded = load(
+            &self.path,
+            self.seed,
+            self.load_address,
+            self.extension,
+            self.font.as_deref(),
+        );
+        if let Ok(mut reloaded) = reloaded {
+            info!("the rom changed, so it was started over");
+            reloaded.quirks = chip8.quirks;
+            reloaded.set_break_on_unknown(chip8.breaks_on_unknown());
+            reloaded.set_write_protection(chip8.write_protection());
+            reloaded.set_program_counter_policy(chip8.program_counter_policy());
+            reloaded.set_index_policy(chip8.index_policy());
+            // The saved flags and the cheats don't go away just because the
+            // rom changed
+            reloaded.flags = chip8.flags;
+            reloaded.set_cheats(chip8.cheats().to_vec());
+            *chip8 = reloaded;
+            chip8.request_redraw();
+            self.modified = modified;
+        }
+    }
+}
+
+impl<'a, F: Display> Display for HotReload<'a, F> {
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error> {
+        self.frontend.draw(chip8)
+    }
+}
+
+impl<'a, F: Audio> Audio for HotReload<'a, F> {
+    fn set_playing(&mut self, playing: bool) -> Result<(), Error> {
+        self.frontend.set_playing(playing)
+    }
+}
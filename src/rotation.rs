@@ -0,0 +1,114 @@
+//! This module contains the rotation of the screen, for the roms that were
+//! made to be played on a display that is turned on its side, like the ones
+//! that are taller than they are wide.
+//!
+//! The interpreter's screen isn't changed, the frontends just draw it turned,
+//! and the keymap can be turned to match so that the keys that the rom uses
+//! for up, down, left, and right (2, 8, 4, and 6) go the way they look like
+//! they should on the turned screen. Both can be picked with the options, or
+//! in the database.
+
+use crate::{config::Config, keymap::Keymap};
+use std::path::Path;
+
+/// How far the screen is turned clockwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+impl Rotation {
+    /// Gets the rotation from how many degrees it is, which is 0, 90, 180, or 270
+    pub fn from_name(name: &str) -> Option<Rotation> {
+        match name {
+            "0" => Some(Rotation::None),
+            "90" => Some(Rotation::Quarter),
+            "180" => Some(Rotation::Half),
+            "270" => Some(Rotation::ThreeQuarters),
+            _ => None,
+        }
+    }
+
+    /// Gets the rotation that the options say to use, or the database if they
+    /// didn't pick one, and whether the keys get turned with it
+    pub fn for_rom(config: &Config, rom: &Path) -> (Rotation, bool) {
+        let entry = config.database.find_file(rom);
+        let from_database = entry.and_then(|entry| entry.rotation);
+        let rotation = config
+            .rotation
+            .or(from_database.map(|(rotation, _)| rotation))
+            .unwrap_or_default();
+        let turn_keys = config.rotate_keys || from_database.is_some_and(|(_, keys)| keys);
+        (rotation, turn_keys)
+    }
+
+    /// How many quarter turns clockwise it is
+    fn quarters(self) -> usize {
+        match self {
+            Rotation::None => 0,
+            Rotation::Quarter => 1,
+            Rotation::Half => 2,
+            Rotation::ThreeQuarters => 3,
+        }
+    }
+
+    /// Returns how wide and tall a screen of the size is once it is turned
+    pub fn size(self, (width, height): (usize, usize)) -> (usize, usize) {
+        match self {
+            Rotation::None | Rotation::Half => (width, height),
+            Rotation::Quarter | Rotation::ThreeQuarters => (height, width),
+        }
+    }
+
+    /// Finds the pixel of the screen that ends up at `x` and `y` once it is
+    /// turned, or `None` if that is past the edge of the turned screen
+    #[cfg(any(feature = "terminal", feature = "gpu"))]
+    pub fn to_screen(self, x: usize, y: usize, size: (usize, usize)) -> Option<(usize, usize)> {
+        let (width, height) = size;
+        let (turned_width, turned_height) = self.size(size);
+        if x >= turned_width || y >= turned_height {
+            return None;
+        }
+        Some(match self {
+            Rotation::None => (x, y),
+            Rotation::Quarter => (y, height - 1 - x),
+            Rotation::Half => (width - 1 - x, height - 1 - y),
+            Rotation::ThreeQuarters => (width - 1 - y, x),
+        })
+    }
+
+    /// Finds where the pixel of the screen at `x` and `y` ends up once it is
+    /// turned, which has to be on the screen. Only SDL needs this, since it
+    /// only draws the pixels that are on.
+    #[cfg(feature = "sdl")]
+    pub fn to_turned(self, x: usize, y: usize, (width, height): (usize, usize)) -> (usize, usize) {
+        match self {
+            Rotation::None => (x, y),
+            Rotation::Quarter => (height - 1 - y, x),
+            Rotation::Half => (width - 1 - x, height - 1 - y),
+            Rotation::ThreeQuarters => (y, width - 1 - x),
+        }
+    }
+
+    /// Turns the keymap so that the keys for up, down, left, and right press
+    /// the ones that go that way on the turned screen. With the screen turned
+    /// a quarter clockwise, the rom's up is on the right, so the key that
+    /// would press right presses up instead.
+    pub fn turn_keymap(self, keymap: Keymap) -> Keymap {
+        // Up, right, down, and left, which is clockwise
+        const DIRECTIONS: [u8; 4] = [0x2, 0x6, 0x8, 0x4];
+        let characters: Vec<char> = DIRECTIONS
+            .iter()
+            .map(|key| keymap.character(*key))
+            .collect();
+        let mut turned = keymap;
+        for (i, character) in characters.into_iter().enumerate() {
+            turned.set_character(DIRECTIONS[(i + 4 - self.quarters()) % 4], character);
+        }
+        turned
+    }
+}
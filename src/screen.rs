@@ -0,0 +1,397 @@
+//! This module contains the screen, which keeps track of which pixels are on so
+//! that frontends and tests can ask about them one at a time, without needing
+//! to know how they are packed together
+//!
+//! It can also carry CHIP-8X colors, which are a background color for the
+//! whole screen, and a foreground color for every 8 pixel wide row of a byte.
+//!
+//! The pixels are kept in arrays that are big enough for the biggest screen,
+//! instead of being allocated for the size that it is, so the screen doesn't
+//! need an allocator. Only the start of them is used by a smaller screen.
+//!
+//! The biggest screen is MEGA-CHIP's 256x192 one with the `megachip` feature,
+//! and the 64x64 hi-res one without it, which keeps the arrays down to 512
+//! bytes each instead of 6K for anything that doesn't need MEGA-CHIP.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "serde")]
+use core::convert::TryFrom;
+
+/// The widest that a screen can be, which is MEGA-CHIP's
+#[cfg(feature = "megachip")]
+pub const MAX_WIDTH: usize = 256;
+/// How many bytes of pixels the biggest screen has, which is MEGA-CHIP's
+/// 256x192 one
+#[cfg(feature = "megachip")]
+pub const MAX_BYTES: usize = MAX_WIDTH / 8 * 192;
+
+/// The widest that a screen can be, which is 64 without MEGA-CHIP
+#[cfg(not(feature = "megachip"))]
+pub const MAX_WIDTH: usize = 64;
+/// How many bytes of pixels the biggest screen has, which is the 64x64 hi-res
+/// one without MEGA-CHIP
+#[cfg(not(feature = "megachip"))]
+pub const MAX_BYTES: usize = MAX_WIDTH / 8 * 64;
+
+/// The colors CHIP-8X can draw in, as red, green, and blue, in the order of
+/// their numbers
+pub const COLORS: [[u8; 3]; 8] = [
+    [0x00, 0x00, 0x00], // black
+    [0xff, 0x00, 0x00], // red
+    [0x00, 0x00, 0xff], // blue
+    [0xff, 0x00, 0xff], // violet
+    [0x00, 0xff, 0x00], // green
+    [0xff, 0xff, 0x00], // yellow
+    [0x00, 0xff, 0xff], // aqua
+    [0xff, 0xff, 0xff], // white
+];
+
+/// The colors the CHIP-8X background steps through, starting with dark blue
+const BACKGROUNDS: [[u8; 3]; 4] = [
+    [0x00, 0x00, 0x80], // dark blue
+    [0x00, 0x00, 0x00], // black
+    [0x00, 0x80, 0x00], // dark green
+    [0x80, 0x00, 0x00], // dark red
+];
+
+/// The colors of a CHIP-8X screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Colors {
+    /// Which of `BACKGROUNDS` is being used
+    background: usize,
+    /// The number of the color of every byte of the pixels, lined up with them
+    foreground: [u8; MAX_BYTES],
+}
+
+/// The pixels that the interpreter draws to, along with which of them have
+/// changed since the parent program last drew them
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SavedScreen", try_from = "SavedScreen")
+)]
+pub struct Screen {
+    width: usize,
+    height: usize,
+    /// Each byte is 8 horizontal pixels, with the most significant bit being the
+    /// left most pixel
+    pixels: [u8; MAX_BYTES],
+    /// This keeps track of which bytes of the pixels have changed since the
+    /// parent program last drew them
+    dirty: [bool; MAX_BYTES],
+    /// The CHIP-8X colors, only there once they have been turned on
+    colors: Option<Colors>,
+}
+
+impl Screen {
+    /// Creates a screen with every pixel off, the width needs to be a multiple
+    /// of 8. It is `None` when the screen doesn't fit in `MAX_WIDTH` and
+    /// `MAX_BYTES`.
+    pub fn new(width: usize, height: usize) -> Option<Screen> {
+        if width > MAX_WIDTH || width / 8 * height > MAX_BYTES {
+            return None;
+        }
+        Some(Screen {
+            width,
+            height,
+            pixels: [0; MAX_BYTES],
+            dirty: [false; MAX_BYTES],
+            colors: None,
+        })
+    }
+
+    /// Returns how many bytes of the arrays the screen uses
+    fn byte_count(&self) -> usize {
+        self.width / 8 * self.height
+    }
+
+    /// Returns the width of the screen in pixels
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the screen in pixels
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the width and height of the screen in pixels
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns whether the pixel at the coordinates is on, anything off the
+    /// screen is off
+    pub fn get_pixel(&self, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let (byte, bit) = self.locate(x, y);
+        self.pixels[byte] & bit != 0
+    }
+
+    /// Turns the pixel at the coordinates on or off, anything off the screen is
+    /// ignored
+    pub fn set_pixel(&mut self, x: usize, y: usize, is_on: bool) {
+        if x >= self.width || y >= self.height || self.get_pixel(x, y) == is_on {
+            return;
+        }
+        let (byte, bit) = self.locate(x, y);
+        self.pixels[byte] ^= bit;
+        self.dirty[byte] = true;
+    }
+
+    /// Flips the pixel at the coordinates, which is how sprites are drawn, and
+    /// returns whether it was turned off
+    pub fn toggle_pixel(&mut self, x: usize, y: usize) -> bool {
+        let was_on = self.get_pixel(x, y);
+        self.set_pixel(x, y, !was_on);
+        was_on
+    }
+
+    /// Returns the coordinates of every pixel that is on, going across each row
+    /// from the top
+    pub fn lit_pixels<'a>(&'a self) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let width = self.width;
+        (0..self.width * self.height)
+            .map(move |i| (i % width, i / width))
+            .filter(move |(x, y)| self.get_pixel(*x, *y))
+    }
+
+    /// Turns every pixel off
+    pub fn clear(&mut self) {
+        let used = self.byte_count();
+        for (pixel, is_dirty) in self.pixels[..used].iter_mut().zip(self.dirty.iter_mut()) {
+            // Only the pixels that were on actually change
+            if *pixel != 0 {
+                *is_dirty = true;
+            }
+            *pixel = 0;
+        }
+    }
+
+    /// Moves every pixel down by the number of rows, the ones that go off the
+    /// bottom are gone, and the rows at the top are turned off
+    pub fn scroll_down(&mut self, rows: usize) {
+        // Each row is a whole number of bytes, so whole rows of bytes can move
+        let used = self.byte_count();
+        let shift = (rows * self.width / 8).min(used);
+        self.pixels[..used].rotate_right(shift);
+        for byte in self.pixels[..shift].iter_mut() {
+            *byte = 0;
+        }
+        self.mark_dirty();
+    }
+
+    /// Moves every pixel right by the number of columns, the ones that go off
+    /// the right side are gone, and the columns on the left are turned off
+    pub fn scroll_right(&mut self, columns: usize) {
+        self.scroll_across(-(columns as isize));
+    }
+
+    /// Moves every pixel left by the number of columns, the ones that go off
+    /// the left side are gone, and the columns on the right are turned off
+    pub fn scroll_left(&mut self, columns: usize) {
+        self.scroll_across(columns as isize);
+    }
+
+    /// Moves the pixels in each row across, every pixel gets the one that was
+    /// `offset` columns to the right of it
+    fn scroll_across(&mut self, offset: isize) {
+        let row_bytes = self.width / 8;
+        let used = self.byte_count();
+        for row in self.pixels[..used].chunks_exact_mut(row_bytes) {
+            let mut old = [0; MAX_WIDTH / 8];
+            old[..row_bytes].copy_from_slice(row);
+            for (i, byte) in row.iter_mut().enumerate() {
+                *byte = (0..8).fold(0, |byte, bit| {
+                    let from = (i * 8 + bit) as isize + offset;
+                    // Anything from off the screen is off
+                    let is_on = from >= 0
+                        && (from as usize) < row_bytes * 8
+                        && old[from as usize / 8] & (0b10000000 >> (from as usize % 8)) != 0;
+                    byte | (is_on as u8) << (7 - bit)
+                });
+            }
+        }
+        self.mark_dirty();
+    }
+
+    /// Returns the packed pixels, where each byte is 8 horizontal pixels with
+    /// the most significant bit being the left most pixel
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.pixels[..self.byte_count()]
+    }
+
+    /// Returns which bytes of the screen have changed since `clear_dirty` was
+    /// last called, lined up with the bytes returned by `as_bytes`
+    pub fn dirty(&self) -> &[bool] {
+        &self.dirty[..self.byte_count()]
+    }
+
+    /// Marks every byte of the screen as changed
+    pub fn mark_dirty(&mut self) {
+        let used = self.byte_count();
+        for is_dirty in self.dirty[..used].iter_mut() {
+            *is_dirty = true;
+        }
+    }
+
+    /// Marks every byte of the screen as unchanged
+    pub fn clear_dirty(&mut self) {
+        for is_dirty in self.dirty.iter_mut() {
+            *is_dirty = false;
+        }
+    }
+
+    /// Turns on the CHIP-8X colors, with a dark blue background and every
+    /// pixel drawn in red
+    pub fn enable_colors(&mut self) {
+        self.colors = Some(Colors {
+            background: 0,
+            foreground: [1; MAX_BYTES],
+        });
+        self.mark_dirty();
+    }
+
+    /// Returns whether the screen has CHIP-8X colors
+    pub fn has_colors(&self) -> bool {
+        self.colors.is_some()
+    }
+
+    /// Returns the color of the pixel at the coordinates when the screen has
+    /// colors, which is the foreground color if it is on, and the background
+    /// color otherwise
+    pub fn color(&self, x: usize, y: usize) -> Option<[u8; 3]> {
+        let colors = self.colors.as_ref()?;
+        if !self.get_pixel(x, y) {
+            return Some(BACKGROUNDS[colors.background]);
+        }
+        let (byte, _) = self.locate(x, y);
+        Some(COLORS[colors.foreground[byte] as usize])
+    }
+
+    /// Moves the background on to the next of it's 4 colors, after the last one
+    /// it goes back to the first
+    pub fn next_background(&mut self) {
+        if let Some(colors) = &mut self.colors {
+            colors.background = (colors.background + 1) % BACKGROUNDS.len();
+            self.mark_dirty();
+        }
+    }
+
+    /// Sets the foreground color (`0` through `7`) of the 8 pixels wide row that
+    /// the coordinates are in, anything off the screen is ignored
+    pub fn set_foreground(&mut self, x: usize, y: usize, color: u8) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let (byte, _) = self.locate(x, y);
+        if let Some(colors) = &mut self.colors {
+            colors.foreground[byte] = color & 0x7;
+            self.dirty[byte] = true;
+        }
+    }
+
+    /// Returns the bytes of the pixels that are different on the other screen,
+    /// as where they are and what they are on this one. There aren't any when
+    /// the screens are different in more than their pixels, like their size
+    /// or their colors.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn changed_bytes(&self, other: &Screen) -> Option<Vec<(usize, u8)>> {
+        if self.size() != other.size() || self.colors != other.colors {
+            return None;
+        }
+        Some(
+            self.as_bytes()
+                .iter()
+                .zip(other.as_bytes().iter())
+                .enumerate()
+                .filter(|(_, (byte, other_byte))| byte != other_byte)
+                .map(|(offset, (byte, _))| (offset, *byte))
+                .collect(),
+        )
+    }
+
+    /// Puts bytes of the pixels back the way `changed_bytes` found them
+    #[cfg(feature = "alloc")]
+    pub(crate) fn restore_bytes(&mut self, bytes: &[(usize, u8)]) {
+        for (offset, byte) in bytes.iter() {
+            self.pixels[*offset] = *byte;
+            self.dirty[*offset] = true;
+        }
+    }
+
+    /// Works out which byte the pixel is in, and the bit for it in that byte
+    fn locate(&self, x: usize, y: usize) -> (usize, u8) {
+        (x / 8 + y * (self.width / 8), 0b10000000 >> (x % 8))
+    }
+}
+
+/// What a screen looks like when it is saved, which only has the bytes that
+/// the screen uses, so it is the same as it was before the screen was kept in
+/// arrays, and older saves still load
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedScreen {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    dirty: Vec<bool>,
+    colors: Option<SavedColors>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedColors {
+    background: usize,
+    foreground: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Screen> for SavedScreen {
+    fn from(screen: Screen) -> Self {
+        let used = screen.byte_count();
+        SavedScreen {
+            width: screen.width,
+            height: screen.height,
+            pixels: screen.pixels[..used].to_vec(),
+            dirty: screen.dirty[..used].to_vec(),
+            colors: screen.colors.map(|colors| SavedColors {
+                background: colors.background,
+                foreground: colors.foreground[..used].to_vec(),
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SavedScreen> for Screen {
+    type Error = &'static str;
+
+    fn try_from(saved: SavedScreen) -> Result<Self, Self::Error> {
+        let used = saved.width / 8 * saved.height;
+        let mut screen =
+            Screen::new(saved.width, saved.height).ok_or("the screen is too big to be loaded")?;
+        let fits = |length: usize| length == used;
+        if !fits(saved.pixels.len()) || !fits(saved.dirty.len()) {
+            return Err("the screen's pixels don't match its size");
+        }
+        screen.pixels[..used].copy_from_slice(&saved.pixels);
+        screen.dirty[..used].copy_from_slice(&saved.dirty);
+        if let Some(colors) = saved.colors {
+            if !fits(colors.foreground.len()) {
+                return Err("the screen's colors don't match its size");
+            }
+            let mut foreground = [1; MAX_BYTES];
+            foreground[..used].copy_from_slice(&colors.foreground);
+            screen.colors = Some(Colors {
+                background: colors.background % BACKGROUNDS.len(),
+                foreground,
+            });
+        }
+        Ok(screen)
+    }
+}
@@ -0,0 +1,192 @@
+//! This module contains screenshots, which save the interpreter's screen to an
+//! image file pixel for pixel, no matter how the terminal is drawing it
+
+use crate::{config::Config, logging, palette::Palette, rom};
+use chip_8::{
+    frontend::{Audio, Display, Event, Input},
+    Chip8,
+};
+#[cfg(feature = "terminal")]
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    fs::File,
+    io::{BufWriter, Error, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// How many frames the `screenshot` command runs the rom for, if it isn't told
+const DEFAULT_FRAMES: u64 = 60;
+
+/// Saves the screen to the path, scaled up so every pixel is `scale` pixels
+/// wide and tall. A path ending in `.pbm` is saved as a black and white
+/// bitmap, and anything else is saved as a PNG in the palette's colors, or the
+/// MEGA-CHIP or CHIP-8X colors when one of them is on.
+pub fn save(chip8: &Chip8, path: &Path, scale: u32, palette: Palette) -> Result<(), Error> {
+    let screen = chip8.screen();
+    write_image(
+        path,
+        (screen.width(), screen.height()),
+        scale,
+        |x, y| screen.get_pixel(x, y),
+        |x, y| pixel_color(chip8, palette, x, y),
+    )
+}
+
+/// Saves a picture that is `size` pixels wide and tall to the path, scaled up
+/// so every pixel is `scale` pixels wide and tall. A path ending in `.pbm` is
+/// saved as a black and white bitmap of which pixels are on, and anything else
+/// is saved as a PNG in the colors of the pixels.
+pub fn write_image<O, C>(
+    path: &Path,
+    size: (usize, usize),
+    scale: u32,
+    is_on: O,
+    color: C,
+) -> Result<(), Error>
+where
+    O: Fn(usize, usize) -> bool,
+    C: Fn(usize, usize) -> [u8; 3],
+{
+    let scale = scale.max(1) as usize;
+    let (image_width, image_height) = (size.0 * scale, size.1 * scale);
+
+    // Every row of the image, with one bool for every pixel in it, for the
+    // bitmap which only has black and white
+    let rows: Vec<Vec<bool>> = (0..image_height)
+        .map(|y| {
+            (0..image_width)
+                .map(|x| is_on(x / scale, y / scale))
+                .collect()
+        })
+        .collect();
+
+    let mut file = BufWriter::new(File::create(path)?);
+    let is_pbm = path
+        .extension()
+        .map(|extension| extension.eq_ignore_ascii_case("pbm"))
+        .unwrap_or(false);
+    if is_pbm {
+        // The binary version of the format packs 8 pixels into each byte, with
+        // 1 being black, so the pixels that are on are drawn in black
+        write!(file, "P4\n{} {}\n", image_width, image_height)?;
+        for row in rows.iter() {
+            let bytes: Vec<u8> = row
+                .chunks(8)
+                .map(|pixels| {
+                    pixels
+                        .iter()
+                        .enumerate()
+                        .fold(0, |byte, (i, is_on)| byte | (*is_on as u8) << (7 - i))
+                })
+                .collect();
+            file.write_all(&bytes)?;
+        }
+    } else {
+        let mut encoder = png::Encoder::new(&mut file, image_width as u32, image_height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        let data: Vec<u8> = (0..image_height)
+            .flat_map(|y| (0..image_width).map(move |x| (x / scale, y / scale)))
+            .flat_map(|(x, y)| color(x, y).to_vec())
+            .collect();
+        writer.write_image_data(&data).map_err(png_error)?;
+    }
+    file.flush()
+}
+
+/// Returns a name for a screenshot that won't already be taken, in the
+/// directory that the app was started in, from the time it was taken
+#[cfg(feature = "terminal")]
+pub fn file_name() -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_millis())
+        .unwrap_or(0);
+    PathBuf::from(format!("screenshot-{}.png", millis))
+}
+
+/// Runs `chip_8 screenshot [options] [--frames <number>] <rom> <image>`, which
+/// runs the rom without showing it for a number of frames, and then saves the
+/// screen to the image. Every other option works the same as it does normally.
+pub fn command(mut args: Vec<String>) -> Result<(), Error> {
+    let mut frames = DEFAULT_FRAMES;
+    if let Some(position) = args.iter().position(|arg| arg == "--frames") {
+        args.remove(position);
+        if position >= args.len() {
+            return Err(invalid("--frames needs a value after it"));
+        }
+        let value = args.remove(position);
+        frames = value
+            .parse()
+            .map_err(|_| invalid(&format!("{} isn't a number of frames", value)))?;
+    }
+    let image = match args.pop() {
+        Some(image) if !image.starts_with("--") => PathBuf::from(image),
+        _ => return Err(invalid("screenshot needs a rom and an image to save to")),
+    };
+
+    let config = Config::from_args(args)?;
+    logging::init(&config)?;
+    let path = config
+        .rom
+        .clone()
+        .ok_or_else(|| invalid("screenshot needs a rom and an image to save to"))?;
+    // The same scheduler as always runs it, so it gets just as far as it
+    // would have in the same amount of time
+    let (mut chip8, mut scheduler) = rom::start(&path, config.seed, &config)?;
+    scheduler.advance(
+        &mut chip8,
+        &mut Headless,
+        Duration::from_nanos(16_666_667 * frames),
+    )?;
+    save(&chip8, &image, config.screenshot_scale, config.palette)
+}
+
+/// A frontend that doesn't show anything, for running a rom without a terminal
+pub struct Headless;
+
+impl Input for Headless {
+    fn handle_input(&mut self, _chip8: &mut Chip8) -> Option<Event> {
+        None
+    }
+}
+
+impl Display for Headless {
+    fn draw(&mut self, _chip8: &Chip8) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Audio for Headless {
+    fn set_playing(&mut self, _playing: bool) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Returns the color of the pixel in the image, which is the palette's unless
+/// MEGA-CHIP or CHIP-8X is drawing in it's own colors
+fn pixel_color(chip8: &Chip8, palette: Palette, x: usize, y: usize) -> [u8; 3] {
+    match chip8.megachip().filter(|megachip| megachip.is_on()) {
+        Some(megachip) => megachip.color(x, y),
+        None => chip8
+            .screen()
+            .color(x, y)
+            .unwrap_or(if chip8.screen().get_pixel(x, y) {
+                palette.on
+            } else {
+                palette.off
+            }),
+    }
+}
+
+/// Creates the error for a `screenshot` command that doesn't make sense
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+/// Turns the errors from the PNG encoder into an `io::Error`
+fn png_error(error: png::EncodingError) -> Error {
+    Error::other(error.to_string())
+}
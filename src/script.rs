@@ -0,0 +1,275 @@
+//! This module contains scripting, where a rhai script can hook into the
+//! interpreter to look at and change it while a rom runs, for cheats, automated
+//! testing, or patching a rom without touching the file.
+//!
+//! The script gets ran once when it is loaded, and then any of these functions
+//! that it defines get called:
+//!
+//! - `on_instruction()` right before every instruction
+//! - `on_frame()` after every frame, 60 times a second
+//! - `on_key(key)` when a key (`0x0` through `0xf`) gets pressed
+//!
+//! From inside of those, the interpreter can be used with `reg(x)`,
+//! `set_reg(x, value)`, `peek(address)`, `poke(address, value)`, `pc()`,
+//! `set_pc(address)`, `index()`, `set_index(address)`, `delay()`,
+//! `set_delay(value)`, `sound()`, `set_sound(value)`, `is_key_pressed(key)`,
+//! `press_key(key)`, and `release_key(key)`. For example, this keeps register
+//! 5 from ever going below 3:
+//!
+//! ```text
+//! fn on_frame() {
+//!     if reg(5) < 3 {
+//!         set_reg(5, 3);
+//!     }
+//! }
+//! ```
+//!
+//! It is only built when the `scripting` feature is turned on.
+
+use chip_8::{
+    frontend::{Audio, Display, Event, Input},
+    Chip8,
+};
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use std::{cell::RefCell, io::Error, mem, path::Path, rc::Rc};
+
+/// What a function that the script calls returns
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// A script that has been loaded, and the interpreter that it can reach
+struct Script {
+    engine: Engine,
+    ast: AST,
+    /// The variables that the script made when it was ran, which stay around
+    /// between the hooks
+    scope: Scope<'static>,
+    /// The functions that the script calls can't borrow the interpreter, so it
+    /// gets swapped in here while a hook is running
+    chip8: Rc<RefCell<Chip8>>,
+    has_instruction_hook: bool,
+    has_frame_hook: bool,
+    has_key_hook: bool,
+}
+
+impl Script {
+    /// Reads the script, sets up the functions that it can call, and runs it
+    fn load(path: &Path) -> Result<Script, Error> {
+        let chip8 = Rc::new(RefCell::new(Chip8::new()));
+        let mut engine = Engine::new();
+        register_functions(&mut engine, &chip8);
+
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(script_error)?;
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(script_error)?;
+
+        let has_function = |name: &str, parameters: usize| {
+            ast.iter_functions()
+                .any(|function| function.name == name && function.params.len() == parameters)
+        };
+        Ok(Script {
+            has_instruction_hook: has_function("on_instruction", 0),
+            has_frame_hook: has_function("on_frame", 0),
+            has_key_hook: has_function("on_key", 1),
+            engine,
+            ast,
+            scope,
+            chip8,
+        })
+    }
+
+    /// Calls one of the script's functions with the interpreter swapped in
+    fn call(&mut self, chip8: &mut Chip8, name: &str, args: Vec<Dynamic>) -> Result<(), Error> {
+        mem::swap(&mut *self.chip8.borrow_mut(), chip8);
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(&mut self.scope, &self.ast, name, args);
+        mem::swap(&mut *self.chip8.borrow_mut(), chip8);
+        result.map(|_| ()).map_err(script_error)
+    }
+}
+
+/// Wraps a frontend, and calls the script's hooks as the rom runs
+pub struct ScriptFrontend<'a, F> {
+    frontend: &'a mut F,
+    script: Script,
+    /// The keys that were pressed the last time they were checked, so only the
+    /// new ones get passed to `on_key`
+    keys: [bool; 16],
+    /// What went wrong in the script, this can't be returned from the middle of
+    /// a frame, so the rom gets stopped and it gets returned from `finish`
+    error: Option<Error>,
+}
+
+impl<'a, F> ScriptFrontend<'a, F> {
+    /// Loads and runs the script at the path
+    pub fn load(frontend: &'a mut F, path: &Path) -> Result<Self, Error> {
+        Ok(ScriptFrontend {
+            frontend,
+            script: Script::load(path)?,
+            keys: [false; 16],
+            error: None,
+        })
+    }
+
+    /// Returns what went wrong in the script, if anything did
+    pub fn finish(self) -> Result<(), Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Calls the hook, unless the script has already broken
+    fn call(&mut self, chip8: &mut Chip8, name: &str, args: Vec<Dynamic>) {
+        if self.error.is_none() {
+            if let Err(error) = self.script.call(chip8, name, args) {
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+impl<'a, F: Input> Input for ScriptFrontend<'a, F> {
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        let event = self.frontend.handle_input(chip8);
+        if self.script.has_key_hook {
+            for key in 0..16 {
+                let is_pressed = chip8.is_key_pressed(key);
+                if is_pressed && !self.keys[key as usize] {
+                    self.call(chip8, "on_key", vec![Dynamic::from(key as i64)]);
+                }
+                self.keys[key as usize] = is_pressed;
+            }
+        }
+        if self.error.is_some() {
+            return Some(Event::Quit);
+        }
+        event
+    }
+
+    fn start_frame(&mut self, chip8: &mut Chip8) {
+        self.frontend.start_frame(chip8);
+    }
+
+    fn before_instruction(&mut self, chip8: &mut Chip8) {
+        self.frontend.before_instruction(chip8);
+        if self.script.has_instruction_hook {
+            self.call(chip8, "on_instruction", Vec::new());
+        }
+    }
+
+    fn tick(&mut self, chip8: &mut Chip8) {
+        self.frontend.tick(chip8);
+        if self.script.has_frame_hook {
+            self.call(chip8, "on_frame", Vec::new());
+        }
+    }
+}
+
+impl<'a, F: Display> Display for ScriptFrontend<'a, F> {
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error> {
+        self.frontend.draw(chip8)
+    }
+}
+
+impl<'a, F: Audio> Audio for ScriptFrontend<'a, F> {
+    fn set_playing(&mut self, playing: bool) -> Result<(), Error> {
+        self.frontend.set_playing(playing)
+    }
+}
+
+/// Gives the script the functions that it uses to reach the interpreter
+fn register_functions(engine: &mut Engine, chip8: &Rc<RefCell<Chip8>>) {
+    let state = chip8.clone();
+    engine.register_fn("reg", move |x: i64| -> ScriptResult<i64> {
+        Ok(state.borrow().registers[register(x)?] as i64)
+    });
+    let state = chip8.clone();
+    engine.register_fn("set_reg", move |x: i64, value: i64| -> ScriptResult<()> {
+        state.borrow_mut().registers[register(x)?] = value as u8;
+        Ok(())
+    });
+    let state = chip8.clone();
+    engine.register_fn("peek", move |address: i64| -> ScriptResult<i64> {
+        let chip8 = state.borrow();
+        Ok(chip8.memory[memory_address(&chip8, address)?] as i64)
+    });
+    let state = chip8.clone();
+    engine.register_fn(
+        "poke",
+        move |address: i64, value: i64| -> ScriptResult<()> {
+            let mut chip8 = state.borrow_mut();
+            let address = memory_address(&chip8, address)?;
+            chip8.memory[address] = value as u8;
+            Ok(())
+        },
+    );
+    let state = chip8.clone();
+    engine.register_fn("pc", move || state.borrow().program_counter as i64);
+    let state = chip8.clone();
+    engine.register_fn("set_pc", move |address: i64| -> ScriptResult<()> {
+        let mut chip8 = state.borrow_mut();
+        chip8.program_counter = memory_address(&chip8, address)?;
+        Ok(())
+    });
+    let state = chip8.clone();
+    engine.register_fn("index", move || state.borrow().index as i64);
+    let state = chip8.clone();
+    engine.register_fn("set_index", move |address: i64| -> ScriptResult<()> {
+        let mut chip8 = state.borrow_mut();
+        chip8.index = memory_address(&chip8, address)?;
+        Ok(())
+    });
+    let state = chip8.clone();
+    engine.register_fn("delay", move || state.borrow().delay as i64);
+    let state = chip8.clone();
+    engine.register_fn("set_delay", move |value: i64| {
+        state.borrow_mut().delay = value as u8;
+    });
+    let state = chip8.clone();
+    engine.register_fn("sound", move || state.borrow().sound as i64);
+    let state = chip8.clone();
+    engine.register_fn("set_sound", move |value: i64| {
+        state.borrow_mut().sound = value as u8;
+    });
+    let state = chip8.clone();
+    engine.register_fn("is_key_pressed", move |key: i64| {
+        state.borrow().is_key_pressed(key as u8)
+    });
+    let state = chip8.clone();
+    engine.register_fn("press_key", move |key: i64| {
+        state.borrow_mut().press_key(key as u8);
+    });
+    let state = chip8.clone();
+    engine.register_fn("release_key", move |key: i64| {
+        state.borrow_mut().release_key(key as u8);
+    });
+}
+
+/// Makes sure the script is asking for one of the 16 registers
+fn register(x: i64) -> ScriptResult<usize> {
+    if (0..16).contains(&x) {
+        Ok(x as usize)
+    } else {
+        Err(format!("there is no register {}", x).into())
+    }
+}
+
+/// Makes sure the script is asking for an address that is inside of memory
+fn memory_address(chip8: &Chip8, address: i64) -> ScriptResult<usize> {
+    if address >= 0 && (address as usize) < chip8.memory.len() {
+        Ok(address as usize)
+    } else {
+        Err(format!("{:#05x} is outside of memory", address).into())
+    }
+}
+
+/// Turns the errors from the script into an `io::Error`, so it can be shown
+/// like any other error
+fn script_error(error: Box<EvalAltResult>) -> Error {
+    Error::other(format!("script: {}", error))
+}
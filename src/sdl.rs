@@ -0,0 +1,368 @@
+//! This module contains the SDL2 frontend, which draws the interpreter's screen
+//! to a window that can be scaled to any size, and plays a real tone, or the
+//! XO-CHIP audio pattern once the rom has loaded one.
+//!
+//! It is only built when the `sdl` feature is turned on, since it needs the SDL2
+//! library to be installed on the system.
+
+use crate::{config::Config, keymap::Keymap, phosphor::Phosphor, rom, rotation::Rotation};
+use chip_8::{
+    audio::{Buzzer, PatternPlayer, MAX_FREQUENCY, MIN_FREQUENCY, PATTERN_SIZE},
+    frontend::{Audio, Display, Event, Input},
+    megachip::{DigitizedSound, SoundChange},
+    Chip8,
+};
+use sdl2::{
+    audio::{AudioCallback, AudioDevice, AudioQueue, AudioSpecDesired},
+    event::Event as SdlEvent,
+    keyboard::{Keycode, Mod},
+    pixels::Color,
+    rect::Rect,
+    render::Canvas,
+    video::Window,
+    AudioSubsystem, EventPump,
+};
+use std::{io::Error, path::Path};
+
+/// How many times bigger than the interpreter's screen the window starts out as
+const STARTING_SCALE: u32 = 10;
+
+/// How much `F10` and `F11` turn the tone down and up
+const VOLUME_STEP: f32 = 0.1;
+
+/// How much `Shift+F10` and `Shift+F11` lower and raise the tone, which is a
+/// semitone
+const TONE_STEP: f32 = 1.059_463;
+
+/// The buzzer that gets played by SDL on it's own audio thread, or the rom's
+/// audio pattern when it has one
+struct Beeper {
+    buzzer: Buzzer,
+    /// How many samples the device plays in a second
+    freq: i32,
+    /// The rom's pattern, and how many bits of it play in a second, which
+    /// get copied over from the interpreter every frame
+    pattern: Option<([u8; PATTERN_SIZE], f64)>,
+    player: PatternPlayer,
+}
+
+impl AudioCallback for Beeper {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        match &self.pattern {
+            Some((pattern, rate)) => {
+                let volume = self.buzzer.volume;
+                self.player
+                    .fill(pattern, *rate, self.freq as f64, volume, out)
+            }
+            None => self.buzzer.fill(self.freq as f32, out),
+        }
+    }
+}
+
+/// The state that is needed for the SDL window, keyboard, and speaker
+pub struct SdlApp {
+    canvas: Canvas<Window>,
+    event_pump: EventPump,
+    beeper: AudioDevice<Beeper>,
+    keymap: Keymap,
+    /// Fades the pixels out instead of turning them off straight away, when
+    /// it is on
+    phosphor: Phosphor,
+    /// How far the screen is turned
+    rotation: Rotation,
+    audio: AudioSubsystem,
+    /// The MEGA-CHIP sound that is playing, along with the queue it is being
+    /// played from
+    sound: Option<(AudioQueue<u8>, DigitizedSound)>,
+}
+
+impl SdlApp {
+    /// Opens the window and the audio device
+    pub fn new() -> Result<Self, Error> {
+        let sdl_context = sdl2::init().map_err(sdl_error)?;
+
+        let video = sdl_context.video().map_err(sdl_error)?;
+        let window = video
+            .window("Chip-8", 64 * STARTING_SCALE, 32 * STARTING_SCALE)
+            .position_centered()
+            .resizable()
+            .build()
+            .map_err(sdl_error)?;
+        let mut canvas = window
+            .into_canvas()
+            .present_vsync()
+            .build()
+            .map_err(sdl_error)?;
+        // Lets SDL take care of scaling the screen up to whatever size the window is
+        canvas.set_logical_size(64, 32).map_err(sdl_error)?;
+
+        let event_pump = sdl_context.event_pump().map_err(sdl_error)?;
+
+        let audio = sdl_context.audio().map_err(sdl_error)?;
+        let spec = AudioSpecDesired {
+            freq: Some(44100),
+            channels: Some(1),
+            samples: None,
+        };
+        let beeper = audio
+            .open_playback(None, &spec, |spec| Beeper {
+                buzzer: Buzzer::default(),
+                freq: spec.freq,
+                pattern: None,
+                player: PatternPlayer::new(),
+            })
+            .map_err(sdl_error)?;
+
+        Ok(SdlApp {
+            canvas,
+            event_pump,
+            beeper,
+            keymap: Keymap::default(),
+            phosphor: Phosphor::new(None),
+            rotation: Rotation::None,
+            audio,
+            sound: None,
+        })
+    }
+
+    /// Runs the rom in the window until it gets closed
+    pub fn run(&mut self, rom: &Path, config: &Config) -> Result<(), Error> {
+        self.beeper.lock().buzzer = config.buzzer.clone();
+        self.phosphor = Phosphor::for_rom(config, rom);
+        let (rotation, turn_keys) = Rotation::for_rom(config, rom);
+        self.rotation = rotation;
+        self.keymap = if turn_keys {
+            rotation.turn_keymap(config.keymap)
+        } else {
+            config.keymap
+        };
+        // Roms that are in the database have their title on the window
+        if let Some(entry) = config.database.find_file(rom) {
+            let title = format!("Chip-8 - {}", entry.name());
+            // A title with a nul in it can't be shown, but the rom still runs
+            let _ = self.canvas.window_mut().set_title(&title);
+        }
+        // The window can't pick another rom, so it just closes
+        rom::run(self, rom, config).map(|_| ())
+    }
+
+    /// Maps the keyboard to the hex keypad with the keymap, SDL's keycodes
+    /// already follow the layout of the keyboard, and are named after the
+    /// character on the key
+    fn map_key(keymap: &Keymap, keycode: Keycode) -> Option<u8> {
+        SdlApp::key_character(keycode).and_then(|character| keymap.key(character))
+    }
+
+    /// Maps the keyboard to CHIP-8X's second keypad, the same way as `map_key`
+    fn map_second_key(keymap: &Keymap, keycode: Keycode) -> Option<u8> {
+        SdlApp::key_character(keycode).and_then(|character| keymap.second_key(character))
+    }
+
+    /// Returns the character on the key, if it has just one
+    fn key_character(keycode: Keycode) -> Option<char> {
+        let name = keycode.name();
+        let mut characters = name.chars();
+        match (characters.next(), characters.next()) {
+            (Some(character), None) => Some(character),
+            _ => None,
+        }
+    }
+
+    /// Changes the buzzer for the keys that do, returning whether the key was
+    /// one of them. `F9` goes to the next waveform, `F10` and `F11` turn it
+    /// down and up, and with `Shift` they lower and raise the tone instead.
+    fn change_buzzer(buzzer: &mut Buzzer, keycode: Keycode, keymod: Mod) -> bool {
+        let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+        match keycode {
+            Keycode::F9 => buzzer.waveform = buzzer.waveform.next(),
+            Keycode::F10 if shift => {
+                buzzer.frequency = (buzzer.frequency / TONE_STEP).max(MIN_FREQUENCY)
+            }
+            Keycode::F11 if shift => {
+                buzzer.frequency = (buzzer.frequency * TONE_STEP).min(MAX_FREQUENCY)
+            }
+            Keycode::F10 => buzzer.volume = (buzzer.volume - VOLUME_STEP).max(0.0),
+            Keycode::F11 => buzzer.volume = (buzzer.volume + VOLUME_STEP).min(1.0),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Starts or stops the MEGA-CHIP sound, a new sound gets it's own queue
+    /// since each one can be played at a different rate
+    fn change_sound(&mut self, change: SoundChange) -> Result<(), Error> {
+        self.sound = None;
+        if let SoundChange::Play(sound) = change {
+            let spec = AudioSpecDesired {
+                freq: Some(sound.rate as i32),
+                channels: Some(1),
+                samples: None,
+            };
+            let queue = self.audio.open_queue(None, &spec).map_err(sdl_error)?;
+            queue.queue(&sound.samples);
+            queue.resume();
+            self.sound = Some((queue, sound));
+        }
+        Ok(())
+    }
+}
+
+impl Input for SdlApp {
+    /// Unlike the terminal, SDL knows when keys are let go of, so the keys
+    /// stay pressed for as long as they are held down
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        for event in self.event_pump.poll_iter() {
+            match event {
+                SdlEvent::Quit { .. }
+                | SdlEvent::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => return Some(Event::Quit),
+                // Turns the ghosting on or off
+                SdlEvent::KeyDown {
+                    keycode: Some(Keycode::F8),
+                    ..
+                } => {
+                    self.phosphor.toggle();
+                    chip8.request_redraw();
+                }
+                SdlEvent::KeyDown {
+                    keycode: Some(keycode),
+                    keymod,
+                    ..
+                } if SdlApp::change_buzzer(&mut self.beeper.lock().buzzer, keycode, keymod) => {}
+                SdlEvent::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    if let Some(key) = SdlApp::map_key(&self.keymap, keycode) {
+                        chip8.press_key(key);
+                    } else if let Some(key) = SdlApp::map_second_key(&self.keymap, keycode) {
+                        chip8.press_second_key(key);
+                    }
+                }
+                SdlEvent::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    if let Some(key) = SdlApp::map_key(&self.keymap, keycode) {
+                        chip8.release_key(key);
+                    } else if let Some(key) = SdlApp::map_second_key(&self.keymap, keycode) {
+                        chip8.release_second_key(key);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Fades the pixels out, plays the MEGA-CHIP sounds, and keeps a looping
+    /// one from running out, and hands the XO-CHIP audio pattern over to the
+    /// audio thread
+    fn tick(&mut self, chip8: &mut Chip8) {
+        if self.phosphor.update(chip8) {
+            chip8.request_redraw();
+        }
+        let pattern = chip8
+            .audio_pattern()
+            .map(|pattern| (pattern, chip8.playback_rate()));
+        self.beeper.lock().pattern = pattern;
+        if let Some(change) = chip8.megachip_mut().and_then(|m| m.take_sound_change()) {
+            // There isn't anywhere to return the error to, the rom just stays quiet
+            let _ = self.change_sound(change);
+        }
+        if let Some((queue, sound)) = &self.sound {
+            if sound.looping && (queue.size() as usize) < sound.samples.len() {
+                queue.queue(&sound.samples);
+            }
+        }
+    }
+}
+
+impl Display for SdlApp {
+    /// Draws every pixel that is on as a 1x1 rectangle, SDL scales it up to the
+    /// size of the window
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), Error> {
+        let screen = chip8.screen();
+        // MEGA-CHIP can switch to a bigger screen while it is running, and
+        // the window is as big as the screen is once it is turned
+        let (width, height) = self.rotation.size(screen.size());
+        let (width, height) = (width as u32, height as u32);
+        let rotation = self.rotation;
+        let pixel = |x, y| {
+            let (x, y) = rotation.to_turned(x, y, screen.size());
+            Rect::new(x as i32, y as i32, 1, 1)
+        };
+        if self.canvas.logical_size() != (width, height) {
+            self.canvas
+                .set_logical_size(width, height)
+                .map_err(sdl_error)?;
+        }
+
+        self.canvas.set_draw_color(Color::RGB(0, 0, 0));
+        self.canvas.clear();
+        match chip8.megachip().filter(|megachip| megachip.is_on()) {
+            Some(megachip) => {
+                // Every pixel can be a different color, so they are drawn one at
+                // a time
+                for (x, y) in screen.lit_pixels() {
+                    let [r, g, b] = megachip.color(x, y);
+                    self.canvas.set_draw_color(Color::RGB(r, g, b));
+                    self.canvas.fill_rect(pixel(x, y)).map_err(sdl_error)?;
+                }
+            }
+            // CHIP-8X colors the background as well as the pixels that are on
+            None if screen.has_colors() => {
+                for y in 0..screen.height() {
+                    for x in 0..screen.width() {
+                        let [r, g, b] = screen.color(x, y).unwrap_or([0; 3]);
+                        self.canvas.set_draw_color(Color::RGB(r, g, b));
+                        self.canvas.fill_rect(pixel(x, y)).map_err(sdl_error)?;
+                    }
+                }
+            }
+            None => {
+                let pixels: Vec<Rect> = screen.lit_pixels().map(|(x, y)| pixel(x, y)).collect();
+                self.canvas.set_draw_color(Color::RGB(255, 255, 255));
+                self.canvas.fill_rects(&pixels).map_err(sdl_error)?;
+                // The pixels that are fading out get a shade of gray each
+                if self.phosphor.is_on() {
+                    for y in 0..screen.height() {
+                        for x in 0..screen.width() {
+                            let brightness = self.phosphor.brightness(chip8, x, y);
+                            if brightness <= 0.0 || brightness >= 1.0 {
+                                continue;
+                            }
+                            let gray = (brightness * 255.0).round() as u8;
+                            self.canvas.set_draw_color(Color::RGB(gray, gray, gray));
+                            self.canvas.fill_rect(pixel(x, y)).map_err(sdl_error)?;
+                        }
+                    }
+                }
+            }
+        }
+        self.canvas.present();
+        Ok(())
+    }
+}
+
+impl Audio for SdlApp {
+    /// Resumes or pauses the square wave
+    fn set_playing(&mut self, playing: bool) -> Result<(), Error> {
+        if playing {
+            self.beeper.resume();
+        } else {
+            self.beeper.pause();
+        }
+        Ok(())
+    }
+}
+
+/// SDL returns it's errors as all sorts of different types, this turns them into
+/// an `io::Error` so that they can be returned the same way as the terminal's
+fn sdl_error<E: ToString>(error: E) -> Error {
+    Error::other(error.to_string())
+}
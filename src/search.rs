@@ -0,0 +1,100 @@
+//! This module contains the memory search, which narrows memory down to the
+//! bytes that behave a certain way, for finding where a game keeps something
+//! like the number of lives. It starts with every address, and each search
+//! keeps only the ones that are equal to a value, or that have changed, or
+//! haven't changed, since the search before it. Searching for the lives,
+//! losing one, and searching for the ones that changed soon leaves only a few.
+//!
+//! ```rust
+//! use chip_8::{
+//!     search::{MemorySearch, SearchFilter},
+//!     Chip8,
+//! };
+//!
+//! let mut chip8 = Chip8::with_seed(0);
+//! chip8.memory[0x300] = 3;
+//! let mut search = MemorySearch::new(&chip8);
+//! search.filter(&chip8, SearchFilter::Equal(3));
+//! // The rom loses a life
+//! chip8.memory[0x300] = 2;
+//! search.filter(&chip8, SearchFilter::Changed);
+//! assert_eq!(search.addresses(), [0x300]);
+//! ```
+
+use crate::chip8::Chip8;
+use alloc::{format, string::String, vec::Vec};
+use core::str::FromStr;
+
+/// Which bytes a search keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchFilter {
+    /// The ones that are equal to the value
+    Equal(u8),
+    /// The ones that are different from what they were at the last search
+    Changed,
+    /// The ones that are the same as what they were at the last search
+    Unchanged,
+}
+
+impl FromStr for SearchFilter {
+    type Err = String;
+
+    /// Reads a filter, which is `changed`, `unchanged`, or a byte in hex with
+    /// or without a `=` or `==` in front of it
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim() {
+            "changed" => Ok(SearchFilter::Changed),
+            "unchanged" => Ok(SearchFilter::Unchanged),
+            text => {
+                let value = text.trim_start_matches('=').trim();
+                u8::from_str_radix(value.trim_start_matches("0x"), 16)
+                    .map(SearchFilter::Equal)
+                    .map_err(|_| format!("{} isn't a hex byte, changed, or unchanged", text))
+            }
+        }
+    }
+}
+
+/// The addresses that are left after the searches so far, and what memory
+/// was at the last one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemorySearch {
+    addresses: Vec<usize>,
+    previous: Vec<u8>,
+}
+
+impl MemorySearch {
+    /// Starts a search with every address in memory
+    pub fn new(chip8: &Chip8) -> Self {
+        MemorySearch {
+            addresses: (0..chip8.memory.len()).collect(),
+            previous: chip8.memory.to_vec(),
+        }
+    }
+
+    /// Keeps only the addresses that the filter does, and remembers memory
+    /// the way it is now for the next one
+    pub fn filter(&mut self, chip8: &Chip8, filter: SearchFilter) {
+        let previous = &self.previous;
+        self.addresses.retain(|address| {
+            let now = chip8.memory.get(*address).copied();
+            let before = previous.get(*address).copied();
+            match filter {
+                SearchFilter::Equal(value) => now == Some(value),
+                SearchFilter::Changed => now != before,
+                SearchFilter::Unchanged => now == before,
+            }
+        });
+        self.previous = chip8.memory.to_vec();
+    }
+
+    /// Returns the addresses that are left, in order
+    pub fn addresses(&self) -> &[usize] {
+        &self.addresses
+    }
+
+    /// Returns what the byte at the address was at the last search
+    pub fn previous(&self, address: usize) -> Option<u8> {
+        self.previous.get(address).copied()
+    }
+}
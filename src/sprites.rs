@@ -0,0 +1,219 @@
+//! This module contains the sprite viewer, which draws the sprites that the
+//! analysis finds in a rom as small pictures in the debugger's memory pane, so the
+//! graphics of a rom can be looked through, and the `sprites` command, which
+//! saves all of them to an image
+
+use crate::{config::Config, rom, screenshot};
+use chip_8::Chip8;
+#[cfg(feature = "terminal")]
+use crossterm::event::KeyCode;
+#[cfg(feature = "terminal")]
+use ratatui::{
+    style::Modifier,
+    text::{Line, Span},
+};
+use std::{
+    io::{Error, ErrorKind},
+    ops::Range,
+    path::PathBuf,
+};
+
+/// How many sprites are shown next to each other
+#[cfg(feature = "terminal")]
+const SPRITES_SHOWN: usize = 5;
+/// How many columns each sprite gets, 8 pixels and 2 spaces after them
+#[cfg(feature = "terminal")]
+const SPRITE_COLUMNS: usize = 10;
+/// How many rows of pixels are shown, 2 of them go on each line
+#[cfg(feature = "terminal")]
+const PIXEL_ROWS: usize = 16;
+/// How many bytes of the sprite that is picked are shown on each line
+#[cfg(feature = "terminal")]
+const BYTES_PER_LINE: usize = 16;
+/// How many sprites go on each row of the image that the `sprites` command saves
+const SPRITES_PER_ROW: usize = 16;
+
+/// The sprites that were found in memory, and which one is picked
+#[cfg(feature = "terminal")]
+pub struct SpriteViewer {
+    sprites: Vec<Range<usize>>,
+    selected: usize,
+}
+
+#[cfg(feature = "terminal")]
+impl SpriteViewer {
+    /// Looks for the sprites by following the code from the load address
+    pub fn new(chip8: &Chip8) -> Self {
+        SpriteViewer {
+            sprites: chip8
+                .analyze(chip8.load_address(), chip8.memory.len())
+                .sprites(),
+            selected: 0,
+        }
+    }
+
+    /// Returns the address of the sprite that is picked
+    pub fn selected(&self) -> Option<usize> {
+        self.sprites.get(self.selected).map(|sprite| sprite.start)
+    }
+
+    /// Picks the first sprite at or after the address, or the last one if
+    /// they are all before it
+    pub fn select_address(&mut self, address: usize) {
+        self.selected = self
+            .sprites
+            .iter()
+            .position(|sprite| sprite.start >= address)
+            .unwrap_or_else(|| self.sprites.len().saturating_sub(1));
+    }
+
+    /// Moves between the sprites, and returns whether the key was used
+    pub fn handle_key(&mut self, key: &KeyCode) -> bool {
+        let last = self.sprites.len().saturating_sub(1);
+        match key {
+            KeyCode::Left => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Right => self.selected = (self.selected + 1).min(last),
+            KeyCode::Up => self.selected = self.selected.saturating_sub(SPRITES_SHOWN),
+            KeyCode::Down => self.selected = (self.selected + SPRITES_SHOWN).min(last),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Returns the title of the pane, which says which sprite is picked
+    pub fn title(&self) -> String {
+        if self.sprites.is_empty() {
+            return String::from("sprites  none were found");
+        }
+        format!("sprites  {} of {}", self.selected + 1, self.sprites.len())
+    }
+
+    /// Returns the lines of the pane, with the sprites around the one that is
+    /// picked drawn with half blocks under their addresses, and the bytes of
+    /// the one that is picked
+    pub fn render(&self, chip8: &Chip8) -> Vec<Line<'static>> {
+        let selected = match self.sprites.get(self.selected) {
+            Some(sprite) => sprite,
+            None => return Vec::new(),
+        };
+        let mut lines = vec![Line::from("Enter shows it in memory")];
+
+        // The sprites are shown a page at a time
+        let first = self.selected / SPRITES_SHOWN * SPRITES_SHOWN;
+        let shown = &self.sprites[first..(first + SPRITES_SHOWN).min(self.sprites.len())];
+        let mut labels = Vec::new();
+        for (i, sprite) in shown.iter().enumerate() {
+            let label = format!("{:03x}", sprite.start);
+            let padding = " ".repeat(SPRITE_COLUMNS - label.len());
+            if first + i == self.selected {
+                labels.push(Span::styled(label, Modifier::REVERSED));
+            } else {
+                labels.push(Span::raw(label));
+            }
+            labels.push(Span::raw(padding));
+        }
+        lines.push(Line::from(labels));
+        for line in 0..PIXEL_ROWS / 2 {
+            let (top, bottom) = (line * 2, line * 2 + 1);
+            let row: String = shown
+                .iter()
+                .map(|sprite| {
+                    let pixels: String = (0..8)
+                        .map(|x| {
+                            match (
+                                is_on(chip8, sprite, x, top),
+                                is_on(chip8, sprite, x, bottom),
+                            ) {
+                                (true, true) => '█',
+                                (true, false) => '▀',
+                                (false, true) => '▄',
+                                (false, false) => ' ',
+                            }
+                        })
+                        .collect();
+                    format!("{}{}", pixels, " ".repeat(SPRITE_COLUMNS - 8))
+                })
+                .collect();
+            lines.push(Line::from(row));
+        }
+
+        lines.push(Line::from(""));
+        let bytes = chip8.memory.get(selected.clone()).unwrap_or_default();
+        for chunk in bytes.chunks(BYTES_PER_LINE) {
+            let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+            lines.push(Line::from(hex.join(" ")));
+        }
+        lines
+    }
+}
+
+/// Returns whether the pixel of the sprite is on, each byte is a row of 8
+/// pixels, and anything past the end of it is off
+fn is_on(chip8: &Chip8, sprite: &Range<usize>, x: usize, y: usize) -> bool {
+    let address = sprite.start + y;
+    x < 8
+        && address < sprite.end
+        && chip8
+            .memory
+            .get(address)
+            .map(|byte| byte << x & 0x80 != 0)
+            .unwrap_or(false)
+}
+
+/// Runs `chip_8 sprites [options] <rom> <image>`, which saves every sprite
+/// that is found in the rom to the image, in rows of 16 with a pixel between
+/// them. The image is saved the same way as a screenshot, so the theme and
+/// `--screenshot-scale` work the same as they do for those.
+pub fn command(mut args: Vec<String>) -> Result<(), Error> {
+    let image = match args.pop() {
+        Some(image) if !image.starts_with("--") => PathBuf::from(image),
+        _ => return Err(invalid("sprites needs a rom and an image to save to")),
+    };
+    let config = Config::from_args(args)?;
+    let path = config
+        .rom
+        .clone()
+        .ok_or_else(|| invalid("sprites needs a rom and an image to save to"))?;
+    let (chip8, _) = rom::start(&path, config.seed, &config)?;
+    let end = chip8.load_address() + rom::read(&path)?.len();
+    let sprites = chip8.analyze(chip8.load_address(), end).sprites();
+    if sprites.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "no sprites were found in the rom",
+        ));
+    }
+
+    // Every sprite gets a cell as tall as the tallest one, with a row and a
+    // column of pixels around it that are always off
+    let cell_width = 8 + 1;
+    let cell_height = sprites.iter().map(|sprite| sprite.len()).max().unwrap_or(0) + 1;
+    let columns = sprites.len().min(SPRITES_PER_ROW);
+    let rows = sprites.len().div_ceil(SPRITES_PER_ROW);
+    let pixel = |x: usize, y: usize| {
+        let (column, row) = (x / cell_width, y / cell_height);
+        sprites
+            .get(row * SPRITES_PER_ROW + column)
+            .map(|sprite| is_on(&chip8, sprite, x % cell_width, y % cell_height))
+            .unwrap_or(false)
+    };
+    screenshot::write_image(
+        &image,
+        (columns * cell_width + 1, rows * cell_height + 1),
+        config.screenshot_scale,
+        // The cells start a pixel in, so there is a gap around the outside too
+        |x, y| x > 0 && y > 0 && pixel(x - 1, y - 1),
+        |x, y| {
+            if x > 0 && y > 0 && pixel(x - 1, y - 1) {
+                config.palette.on
+            } else {
+                config.palette.off
+            }
+        },
+    )
+}
+
+/// Creates the error for a `sprites` command that doesn't make sense
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
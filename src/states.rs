@@ -0,0 +1,66 @@
+//! This module contains save states, which are the whole state of the
+//! interpreter written to a file as JSON, and the `diff` command that compares
+//! two of them. They need the app to be built with `--features serde`.
+
+use chip_8::Chip8;
+#[cfg(feature = "serde")]
+use std::fs;
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+/// Saves the state of the interpreter to the file
+#[cfg(feature = "serde")]
+pub fn save(chip8: &Chip8, path: &Path) -> Result<(), Error> {
+    fs::write(path, serde_json::to_string(chip8).map_err(Error::other)?)
+}
+
+/// Reads a state back from a file that `save` wrote
+#[cfg(feature = "serde")]
+pub fn load(path: &Path) -> Result<Chip8, Error> {
+    let text = fs::read_to_string(path)
+        .map_err(|error| Error::new(error.kind(), format!("{}: {}", path.display(), error)))?;
+    serde_json::from_str(&text).map_err(|error| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{} isn't a save state, {}", path.display(), error),
+        )
+    })
+}
+
+#[cfg(not(feature = "serde"))]
+pub fn save(_chip8: &Chip8, _path: &Path) -> Result<(), Error> {
+    Err(not_built())
+}
+
+#[cfg(not(feature = "serde"))]
+pub fn load(_path: &Path) -> Result<Chip8, Error> {
+    Err(not_built())
+}
+
+/// Why save states can't be used, when the app was built without them
+#[cfg(not(feature = "serde"))]
+fn not_built() -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        "save states can't be used, turn them on with `cargo build --features serde`",
+    )
+}
+
+/// Runs `chip_8 diff <before> <after>`, which prints the registers, the
+/// ranges of memory, and the parts of the screen that are different between
+/// the two save states
+pub fn command(args: Vec<String>) -> Result<(), Error> {
+    let (before, after) = match args.as_slice() {
+        [before, after] => (load(Path::new(before))?, load(Path::new(after))?),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "diff needs two save states, like `chip_8 diff before.json after.json`",
+            ))
+        }
+    };
+    print!("{}", before.diff(&after));
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! This module contains the status bar, which is drawn in a line under the
+//! screen in the terminal to show how the rom is running
+
+use chip_8::Chip8;
+use std::time::{Duration, Instant};
+
+/// How often the instructions and frames a second get worked out again
+const SAMPLE_TIME: Duration = Duration::from_secs(1);
+
+/// What the status bar shows, and the counts that it is worked out from
+pub struct StatusBar {
+    /// Whether the bar is shown at all
+    pub is_open: bool,
+    /// The title or file name of the rom that is running
+    rom: String,
+    /// How many instructions and frames have ran since the sample started
+    instructions: u64,
+    frames: u64,
+    /// When the current sample started
+    sample_start: Instant,
+    /// The instructions and frames a second from the last sample
+    instructions_per_second: u64,
+    frames_per_second: u64,
+}
+
+impl StatusBar {
+    /// Creates a status bar, shown or not
+    pub fn new(is_open: bool) -> Self {
+        StatusBar {
+            is_open,
+            rom: String::new(),
+            instructions: 0,
+            frames: 0,
+            sample_start: Instant::now(),
+            instructions_per_second: 0,
+            frames_per_second: 0,
+        }
+    }
+
+    /// Starts over for the next rom, which gets shown by it's title or file name
+    pub fn start(&mut self, rom: &str) {
+        *self = StatusBar {
+            rom: rom.to_string(),
+            ..StatusBar::new(self.is_open)
+        };
+    }
+
+    /// Counts an instruction that is about to run
+    pub fn count_instruction(&mut self) {
+        self.instructions += 1;
+    }
+
+    /// Counts a frame that has finished, and works out the instructions and
+    /// frames a second once a whole sample has gone by
+    pub fn count_frame(&mut self) {
+        self.frames += 1;
+        let elapsed = self.sample_start.elapsed();
+        if elapsed >= SAMPLE_TIME {
+            let per_second = |count: u64| (count as f64 / elapsed.as_secs_f64()).round() as u64;
+            self.instructions_per_second = per_second(self.instructions);
+            self.frames_per_second = per_second(self.frames);
+            self.instructions = 0;
+            self.frames = 0;
+            self.sample_start = Instant::now();
+        }
+    }
+
+    /// Returns the line of the bar, cut off or padded out with spaces so that
+    /// it is exactly `width` characters wide
+    pub fn render(&self, chip8: &Chip8, width: usize) -> String {
+        let speed = if chip8.is_paused() {
+            String::from("paused")
+        } else {
+            format!("{}x", chip8.speed())
+        };
+        let line = format!(
+            "{}  PC {:03x}  {}  {} IPS  {} FPS  {}  DT {}  ST {}",
+            self.rom,
+            chip8.program_counter,
+            chip8.disassemble(chip8.program_counter),
+            self.instructions_per_second,
+            self.frames_per_second,
+            speed,
+            chip8.delay,
+            chip8.sound
+        );
+        line.chars()
+            .chain(std::iter::repeat(' '))
+            .take(width)
+            .collect()
+    }
+}
@@ -0,0 +1,147 @@
+//! This module contains the symbol files that give the addresses in a rom
+//! names, like the ones Octo saves along with the rom, so the tools can say
+//! `game_loop` instead of `0x23a`.
+//!
+//! Each line has a name and an address, in either order, with spaces, `=`, or
+//! `:` between them. Addresses are in hex, with a `0x` or `$` in front of them
+//! or without one, and anything after a `#` is a comment:
+//!
+//! ```text
+//! # The main loop
+//! game_loop 0x23a
+//! 0x2f0 draw_score
+//! lives = $300
+//! ```
+//!
+//! When neither of them has a `0x` or `$` and both could be hex, like
+//! `add 200`, the first one is taken as the address.
+//!
+//! ```rust
+//! use chip_8::symbols::Symbols;
+//!
+//! let symbols: Symbols = "main 0x200\n0x23a game_loop".parse().unwrap();
+//! assert_eq!(symbols.name(0x23a), Some("game_loop"));
+//! assert_eq!(symbols.address("main"), Some(0x200));
+//! ```
+
+use crate::error::SymbolError;
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+};
+use core::str::FromStr;
+
+/// The names of the addresses in a rom, which can be looked up either way
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Symbols {
+    by_address: BTreeMap<usize, String>,
+    by_name: BTreeMap<String, usize>,
+}
+
+impl Symbols {
+    /// Creates a symbol table without any names in it
+    pub fn new() -> Self {
+        Symbols::default()
+    }
+
+    /// Gives the address a name. An address can have more than one name, but
+    /// the first one it was given is the one that it shows up as.
+    pub fn insert(&mut self, address: usize, name: &str) {
+        self.by_address
+            .entry(address)
+            .or_insert_with(|| name.to_string());
+        self.by_name.insert(name.to_string(), address);
+    }
+
+    /// Returns the name that the address shows up as, if it has one
+    pub fn name(&self, address: usize) -> Option<&str> {
+        self.by_address.get(&address).map(String::as_str)
+    }
+
+    /// Returns the address that has the name
+    pub fn address(&self, name: &str) -> Option<usize> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Returns every address that has a name, and the name it shows up as, in
+    /// order of the addresses
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str)> + '_ {
+        self.by_address
+            .iter()
+            .map(|(address, name)| (*address, name.as_str()))
+    }
+
+    /// Returns how many names there are
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    /// Returns whether there aren't any names
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+}
+
+impl FromStr for Symbols {
+    type Err = SymbolError;
+
+    /// Reads a symbol file, see the module for how they are written
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut symbols = Symbols::new();
+        for (number, line) in text.lines().enumerate() {
+            let error = |message: String| SymbolError {
+                line: number + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap_or_default();
+            let mut parts = line
+                .split(|c: char| c.is_whitespace() || c == '=' || c == ':')
+                .filter(|part| !part.is_empty());
+            let (first, second) = match (parts.next(), parts.next(), parts.next()) {
+                (None, _, _) => continue,
+                (Some(first), Some(second), None) => (first, second),
+                _ => {
+                    return Err(error(format!(
+                        "{} should be a name and an address",
+                        line.trim()
+                    )))
+                }
+            };
+            // An address with `0x` or `$` in front of it is always the
+            // address, otherwise it's whichever one is a hex number
+            let (name, address) = match (prefixed(first), prefixed(second)) {
+                (Some(address), _) => (second, address),
+                (None, Some(address)) => (first, address),
+                (None, None) => match (parse_hex(first), parse_hex(second)) {
+                    (Some(address), _) => (second, Some(address)),
+                    (None, Some(address)) => (first, Some(address)),
+                    (None, None) => {
+                        return Err(error(format!(
+                            "neither {} nor {} is a hex address",
+                            first, second
+                        )))
+                    }
+                },
+            };
+            let address =
+                address.ok_or_else(|| error(format!("{} isn't a hex address", line.trim())))?;
+            symbols.insert(address, name);
+        }
+        Ok(symbols)
+    }
+}
+
+/// Parses an address with `0x` or `$` in front of it, which is `None` when it
+/// doesn't have either, and `Some(None)` when what comes after it isn't hex
+fn prefixed(part: &str) -> Option<Option<usize>> {
+    part.strip_prefix("0x")
+        .or_else(|| part.strip_prefix("0X"))
+        .or_else(|| part.strip_prefix('$'))
+        .map(parse_hex)
+}
+
+/// Parses a hex number without anything in front of it
+fn parse_hex(digits: &str) -> Option<usize> {
+    usize::from_str_radix(digits, 16).ok()
+}
@@ -0,0 +1,142 @@
+//! This module contains the threads that the terminal frontend is split up
+//! into, so that the interpreter never has to wait on the terminal.
+//!
+//! Reading from the terminal waits until there is an event, and writing a
+//! frame can get stuck for as long as the terminal takes to draw it, so each
+//! of them happens on it's own thread. The interpreter's thread only ever
+//! talks to them through channels: the events come in from one, and the
+//! frames that it draws go out through the other.
+
+use crossterm::event::{self, Event};
+use std::{
+    io::{stdout, Error, ErrorKind, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender, TryIter},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How long the thread that reads the events waits for one at a time, before
+/// checking whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The queue of events from the thread that reads them from the terminal,
+/// which are key presses and releases, and the terminal being resized
+pub struct KeyReceiver {
+    receiver: Receiver<Event>,
+    /// Tells the thread to stop once this is dropped
+    stop: Arc<AtomicBool>,
+}
+
+impl KeyReceiver {
+    /// Starts the thread that reads the events, which keeps going until this
+    /// is dropped
+    pub fn spawn() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        thread::spawn(move || {
+            // Only waiting for a little while at a time means that the thread
+            // never gets stuck reading, and sees when it has been told to stop
+            while !stopped.load(Ordering::Relaxed) {
+                match event::poll(POLL_INTERVAL) {
+                    Ok(false) => {}
+                    // Reading won't wait, since there is an event ready
+                    Ok(true) => match event::read() {
+                        // Stops if nothing is listening for events anymore
+                        Ok(event) => {
+                            if sender.send(event).is_err() {
+                                break;
+                            }
+                        }
+                        Err(_) => break,
+                    },
+                    // The terminal can't be read from anymore, which the
+                    // receiver sees as the channel closing
+                    Err(_) => break,
+                }
+            }
+        });
+        KeyReceiver { receiver, stop }
+    }
+
+    /// Returns every event that has come in since the last time, without
+    /// waiting for any more
+    pub fn pending(&self) -> TryIter<'_, Event> {
+        self.receiver.try_iter()
+    }
+
+    /// Waits for the next event, which is `None` if the terminal can't be read
+    /// from anymore
+    pub fn wait(&self) -> Option<Event> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl Drop for KeyReceiver {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Sends each frame that gets drawn to the thread that writes to the terminal
+pub struct FrameSender {
+    /// This is gone once nothing else is going to be drawn
+    sender: Option<Sender<Vec<u8>>>,
+}
+
+impl FrameSender {
+    /// Creates a sender that can't send anything, for before the terminal is
+    /// being written to
+    pub fn closed() -> Self {
+        FrameSender { sender: None }
+    }
+
+    /// Sends everything that was written for a frame, which fails if the
+    /// terminal isn't being written to
+    pub fn send(&self, frame: Vec<u8>) -> Result<(), Error> {
+        match &self.sender {
+            Some(sender) => sender.send(frame).map_err(|_| {
+                Error::new(ErrorKind::BrokenPipe, "the terminal stopped being drawn to")
+            }),
+            None => Err(Error::new(
+                ErrorKind::BrokenPipe,
+                "the terminal isn't being drawn to",
+            )),
+        }
+    }
+
+    /// Lets `write_frames` know that there won't be any more frames
+    pub fn close(&mut self) {
+        self.sender = None;
+    }
+}
+
+/// Creates the sender for the frames, and where they come out
+pub fn frame_channel() -> (FrameSender, Receiver<Vec<u8>>) {
+    let (sender, receiver) = mpsc::channel();
+    (
+        FrameSender {
+            sender: Some(sender),
+        },
+        receiver,
+    )
+}
+
+/// Writes the frames to the terminal as they come in, until the sender is
+/// closed. The frames that pile up while the terminal is busy get written all
+/// at once, so a slow terminal doesn't fall further and further behind.
+pub fn write_frames(receiver: Receiver<Vec<u8>>) -> Result<(), Error> {
+    let mut stdout = stdout();
+    while let Ok(mut frame) = receiver.recv() {
+        for next in receiver.try_iter() {
+            frame.extend(next);
+        }
+        stdout.write_all(&frame)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//! This module contains how long each instruction took to run on the COSMAC
+//! VIP, for the run loop's VIP timing. Running a flat number of instructions a
+//! second makes the slow instructions like `dxyn` and `fx33` just as fast as
+//! `6xnn`, so roms that were tuned on the VIP can run too fast, or unevenly
+//! when they draw a lot.
+//!
+//! The times are the ones that were measured on the original interpreter, in
+//! microseconds. The time `dxyn` takes goes up with how many rows it draws, so
+//! the time for the tallest sprite is split up by row.
+//!
+//! ```rust
+//! use chip_8::Chip8;
+//! use std::time::Duration;
+//!
+//! let mut chip8 = Chip8::with_seed(0);
+//! // `6005` puts 5 in register 0, and `d005` draws 5 rows of a sprite
+//! chip8.load(&[0x60, 0x05, 0xd0, 0x05]).unwrap();
+//! assert_eq!(chip8.clock().unwrap().vip_time(), Duration::from_micros(27));
+//! assert_eq!(chip8.clock().unwrap().vip_time(), Duration::from_micros(7578));
+//! ```
+
+use crate::chip8::StepInfo;
+use core::time::Duration;
+
+/// How long `dxyn` takes to draw the tallest sprite, which is 15 rows
+const DRAW_TIME: u64 = 22734;
+/// How long the instructions that the VIP didn't have are counted as taking,
+/// which is about as long as the ones that do the same sort of thing
+const OTHER_TIME: u64 = 200;
+
+impl StepInfo {
+    /// Returns how long the instruction took to run on the COSMAC VIP
+    pub fn vip_time(&self) -> Duration {
+        let micros = match self.mnemonic {
+            "cls" => 109,
+            "ret" | "jp" | "call" | "jp0" => 105,
+            "se" | "sne" | "ldi" => 55,
+            "sey" | "sney" | "skp" | "skpn" => 73,
+            "ld" => 27,
+            "add" | "ldxdt" | "ldk" | "lddt" | "ldst" => 45,
+            "ldy" | "or" | "and" | "xor" | "addy" | "sub" | "subn" | "shr" | "shry" | "shl"
+            | "shly" => 200,
+            "rnd" => 164,
+            // A height of 0 draws SUPER-CHIP's 16x16 sprites, which are counted
+            // as 16 rows
+            "drw" => {
+                let rows = match self.opcode & 0xf {
+                    0 => 16,
+                    rows => rows as u64,
+                };
+                DRAW_TIME * rows / 15
+            }
+            "addi" => 86,
+            "ldf" => 91,
+            "ldb" => 927,
+            "ldix" | "ldxi" => 605,
+            _ => OTHER_TIME,
+        };
+        Duration::from_micros(micros)
+    }
+}
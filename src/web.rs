@@ -0,0 +1,223 @@
+//! This module contains the browser frontend, which draws the interpreter's
+//! screen to a canvas and plays the tone with the Web Audio API.
+//!
+//! It is only built when the `web` feature is turned on, and is meant to be
+//! compiled to `wasm32-unknown-unknown` with `wasm-pack build -- --features web`.
+//! The browser doesn't let the interpreter own a loop or read the system clock,
+//! so the page calls `WebApp::update` from `requestAnimationFrame` with the time
+//! that has passed, and passes in the keys and roms itself, see `www/index.html`.
+
+use crate::{
+    audio::{Buzzer, Waveform},
+    chip8::Chip8,
+    frontend::{Audio, Display, Event, Input, Scheduler},
+};
+use std::{io, time::Duration};
+use wasm_bindgen::{prelude::*, Clamped, JsCast};
+use web_sys::{
+    AudioContext, CanvasRenderingContext2d, GainNode, HtmlCanvasElement, ImageData, OscillatorNode,
+    OscillatorType,
+};
+
+/// The parts of the page that the interpreter draws to and plays sound through
+struct Canvas {
+    context: CanvasRenderingContext2d,
+    /// The RGBA pixels that get copied to the canvas
+    image: Vec<u8>,
+    /// The key presses and releases that the page has passed in since the
+    /// last time the input was handled
+    key_events: Vec<(u8, bool)>,
+    /// What the tone sounds like, the browser makes the tone itself
+    buzzer: Buzzer,
+    audio: Option<(AudioContext, OscillatorNode, GainNode)>,
+}
+
+impl Canvas {
+    /// Sets the oscillator to the buzzer's waveform and frequency
+    fn tune(&self) {
+        if let Some((_, oscillator, _)) = &self.audio {
+            oscillator.set_type(match self.buzzer.waveform {
+                Waveform::Square => OscillatorType::Square,
+                Waveform::Sine => OscillatorType::Sine,
+                Waveform::Triangle => OscillatorType::Triangle,
+            });
+            oscillator.frequency().set_value(self.buzzer.frequency);
+        }
+    }
+}
+
+impl Input for Canvas {
+    /// Passes on the key events that the page has queued up
+    fn handle_input(&mut self, chip8: &mut Chip8) -> Option<Event> {
+        for (key, is_pressed) in self.key_events.drain(..) {
+            if is_pressed {
+                chip8.press_key(key);
+            } else {
+                chip8.release_key(key);
+            }
+        }
+        None
+    }
+}
+
+impl Display for Canvas {
+    /// Turns the packed screen into RGBA pixels and puts them on the canvas,
+    /// the page scales the canvas up with css
+    fn draw(&mut self, chip8: &Chip8) -> Result<(), io::Error> {
+        let screen = chip8.screen();
+        let (width, height) = screen.size();
+        // The screen can get bigger if the rom turns on MEGA-CHIP
+        self.image.resize(width * height * 4, 0);
+
+        for (i, pixel) in self.image.chunks_exact_mut(4).enumerate() {
+            let color = if screen.get_pixel(i % width, i / width) {
+                0xff
+            } else {
+                0x00
+            };
+            pixel.copy_from_slice(&[color, color, color, 0xff]);
+        }
+
+        let image = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&self.image[..]),
+            width as u32,
+            height as u32,
+        )
+        .map_err(js_error)?;
+        self.context
+            .put_image_data(&image, 0.0, 0.0)
+            .map_err(js_error)
+    }
+}
+
+impl Audio for Canvas {
+    /// The oscillator is always running, so the tone is turned on and off with
+    /// the volume
+    fn set_playing(&mut self, playing: bool) -> Result<(), io::Error> {
+        if let Some((_, _, gain)) = &self.audio {
+            gain.gain()
+                .set_value(if playing { self.buzzer.volume } else { 0.0 });
+        }
+        Ok(())
+    }
+}
+
+/// The interpreter, as seen by the page
+#[wasm_bindgen]
+pub struct WebApp {
+    chip8: Chip8,
+    scheduler: Scheduler,
+    canvas: Canvas,
+}
+
+#[wasm_bindgen]
+impl WebApp {
+    /// Hooks the interpreter up to the canvas with the id that was passed in
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas_id: &str) -> Result<WebApp, JsValue> {
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or_else(|| JsValue::from_str("there is no document to draw to"))?;
+        let canvas: HtmlCanvasElement = document
+            .get_element_by_id(canvas_id)
+            .ok_or_else(|| JsValue::from_str("the canvas couldn't be found"))?
+            .dyn_into()?;
+        canvas.set_width(64);
+        canvas.set_height(32);
+        let context: CanvasRenderingContext2d = canvas
+            .get_context("2d")?
+            .ok_or_else(|| JsValue::from_str("the canvas doesn't support 2d drawing"))?
+            .dyn_into()?;
+
+        Ok(WebApp {
+            chip8: Chip8::new(),
+            scheduler: Scheduler::new(),
+            canvas: Canvas {
+                context,
+                image: vec![0; 64 * 32 * 4],
+                key_events: Vec::new(),
+                buzzer: Buzzer::default(),
+                // Browsers only let audio start after the user has done
+                // something, so the audio is set up later by `enable_audio`
+                audio: None,
+            },
+        })
+    }
+
+    /// Resets the interpreter and loads a new rom into it, this is what the page
+    /// calls when a rom file gets dropped onto it. A rom that can't be loaded
+    /// leaves the one that was running alone.
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), JsValue> {
+        let mut chip8 = Chip8::new();
+        chip8
+            .load(rom)
+            .map_err(|error| JsValue::from_str(&error.to_string()))?;
+        self.chip8 = chip8;
+        self.scheduler = Scheduler::new();
+        Ok(())
+    }
+
+    /// Sets up the tone, this has to be called from something the user did,
+    /// like a click or a key press
+    pub fn enable_audio(&mut self) -> Result<(), JsValue> {
+        if self.canvas.audio.is_some() {
+            return Ok(());
+        }
+        let context = AudioContext::new()?;
+        let oscillator = context.create_oscillator()?;
+        let gain = context.create_gain()?;
+        gain.gain().set_value(0.0);
+        oscillator.connect_with_audio_node(&gain)?;
+        gain.connect_with_audio_node(&context.destination())?;
+        oscillator.start()?;
+        self.canvas.audio = Some((context, oscillator, gain));
+        self.canvas.tune();
+        Ok(())
+    }
+
+    /// Changes what the tone sounds like, the waveform is `square`, `sine`, or
+    /// `triangle`, and the volume is from 0 to 1
+    pub fn set_buzzer(
+        &mut self,
+        waveform: &str,
+        frequency: f32,
+        volume: f32,
+    ) -> Result<(), JsValue> {
+        let waveform = Waveform::from_name(waveform)
+            .ok_or_else(|| JsValue::from_str("the waveform isn't square, sine, or triangle"))?;
+        self.canvas.buzzer = Buzzer::new(waveform, frequency, volume);
+        self.canvas.tune();
+        Ok(())
+    }
+
+    /// Queues up a key (`0x0` through `0xf`) being pressed
+    pub fn key_down(&mut self, key: u8) {
+        self.canvas.key_events.push((key, true));
+    }
+
+    /// Queues up a key (`0x0` through `0xf`) being let go of
+    pub fn key_up(&mut self, key: u8) {
+        self.canvas.key_events.push((key, false));
+    }
+
+    /// Runs everything that should have happened in the `elapsed_ms`
+    /// milliseconds since the last time this was called
+    pub fn update(&mut self, elapsed_ms: f64) -> Result<(), JsValue> {
+        // A tab that was in the background can come back with a huge amount of
+        // time that has passed, so it is capped instead of running it all at once
+        let elapsed = Duration::from_micros((elapsed_ms.clamp(0.0, 250.0) * 1000.0) as u64);
+        self.canvas.handle_input(&mut self.chip8);
+        self.scheduler
+            .advance(&mut self.chip8, &mut self.canvas, elapsed)
+            .map_err(|error| JsValue::from_str(&error.to_string()))
+    }
+}
+
+/// Turns the errors from the browser into an `io::Error` so that they can be
+/// returned through the frontend traits
+fn js_error(error: JsValue) -> io::Error {
+    let message = error
+        .as_string()
+        .unwrap_or_else(|| String::from("the browser returned an error"));
+    io::Error::other(message)
+}
@@ -0,0 +1,224 @@
+//! This module contains just enough of WebSockets for the remote control to
+//! talk to a browser, which is the handshake, and text messages going both
+//! ways. A connection that asks for a page instead of a WebSocket gets the
+//! debugger page in `www/debugger.html`, which connects back to the same
+//! address.
+//!
+//! Messages that are split up into pieces are put back together as they come
+//! in, and pings get answered, but nothing else that WebSockets can do is
+//! needed, so it isn't here.
+
+use std::{
+    io::{Error, ErrorKind, Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+/// The page that is sent to a browser that opens the address
+const PAGE: &str = include_str!("../www/debugger.html");
+
+/// What gets added to the browser's key before it is hashed, so it can tell
+/// that the server really speaks WebSockets
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// How long a browser gets to send its request, the rom is held up until it
+/// does, so this is kept short
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The biggest request that is read, anything bigger isn't from a browser
+const MAX_REQUEST: usize = 8192;
+
+/// The kinds of frames that are handled
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xa;
+
+/// Something that came in over a WebSocket
+#[derive(Debug, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    /// A ping, which has to be sent back as a pong with the same bytes
+    Ping(Vec<u8>),
+    /// The browser is going away
+    Close,
+}
+
+/// Reads the browser's request, and either turns the connection into a
+/// WebSocket, or sends it the debugger page. Returns whether it is a WebSocket
+/// now, the connection should be closed if it isn't.
+pub fn handshake(stream: &mut TcpStream) -> Result<bool, Error> {
+    // The request has to be read all at once, so the connection is left
+    // blocking until it has been
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 || request.len() > MAX_REQUEST {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "the request didn't finish",
+            ));
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    stream.set_read_timeout(None)?;
+    stream.set_nonblocking(true)?;
+
+    let request = String::from_utf8_lossy(&request);
+    let key = request.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+            Some(value.trim().to_string())
+        } else {
+            None
+        }
+    });
+
+    match key {
+        Some(key) => {
+            let accept = base64(&sha1_smol::Sha1::from(key + GUID).digest().bytes());
+            write!(
+                stream,
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                accept
+            )?;
+            Ok(true)
+        }
+        None => {
+            // Nothing waits on the page being sent, so it's fine for this to
+            // block for a moment
+            stream.set_nonblocking(false)?;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                PAGE.len(),
+                PAGE
+            )?;
+            Ok(false)
+        }
+    }
+}
+
+/// Takes the next whole message off of the front of what has been read, if
+/// all of it is there. The pieces of a message that was split up are kept in
+/// `partial` until the last one comes in.
+pub fn read_message(unread: &mut Vec<u8>, partial: &mut Vec<u8>) -> Option<Message> {
+    loop {
+        let (first, payload) = read_frame(unread)?;
+        let last = first & 0x80 != 0;
+        match first & 0xf {
+            TEXT | CONTINUATION => {
+                partial.extend_from_slice(&payload);
+                if last {
+                    let text = String::from_utf8_lossy(partial).into_owned();
+                    partial.clear();
+                    return Some(Message::Text(text));
+                }
+            }
+            CLOSE => return Some(Message::Close),
+            PING => return Some(Message::Ping(payload)),
+            // Pongs, and binary messages that the page never sends
+            _ => {}
+        }
+    }
+}
+
+/// Takes one frame off of the front of what has been read, returning the
+/// first byte of it, with the kind of frame and whether it's the last piece,
+/// and its unmasked payload
+fn read_frame(unread: &mut Vec<u8>) -> Option<(u8, Vec<u8>)> {
+    let first = *unread.first()?;
+    let second = *unread.get(1)?;
+    let masked = second & 0x80 != 0;
+    let (length, mut start) = match second & 0x7f {
+        126 => (
+            u16::from_be_bytes([*unread.get(2)?, *unread.get(3)?]) as usize,
+            4,
+        ),
+        127 => {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(unread.get(2..10)?);
+            (u64::from_be_bytes(bytes) as usize, 10)
+        }
+        length => (length as usize, 2),
+    };
+    // Everything that a browser sends is masked with the 4 bytes before the
+    // payload
+    let mask = if masked {
+        let mask = [
+            *unread.get(start)?,
+            *unread.get(start + 1)?,
+            *unread.get(start + 2)?,
+            *unread.get(start + 3)?,
+        ];
+        start += 4;
+        mask
+    } else {
+        [0; 4]
+    };
+    let end = start.checked_add(length)?;
+    if unread.len() < end {
+        return None;
+    }
+    let payload = unread[start..end]
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| byte ^ mask[i % 4])
+        .collect();
+    unread.drain(..end);
+    Some((first, payload))
+}
+
+/// Creates a text message to send to the browser
+pub fn text(text: &str) -> Vec<u8> {
+    frame(TEXT, text.as_bytes())
+}
+
+/// Creates the pong for a ping
+pub fn pong(payload: &[u8]) -> Vec<u8> {
+    frame(PONG, payload)
+}
+
+/// Creates an unmasked frame that is all of a message, which is how the
+/// server sends everything
+fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        length if length < 126 => frame.push(length as u8),
+        length if length <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Encodes the bytes in base64, which is only needed for the handshake
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | (*byte as u32) << (16 - i * 8)
+        });
+        // Every 3 bytes become 4 characters, with `=` for the bytes that
+        // weren't there
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - i * 6) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
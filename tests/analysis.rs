@@ -0,0 +1,92 @@
+//! Checks that the bytes of a rom get sorted into code, sprites, and data
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use chip_8::{analysis::ByteKind, Quirks};
+use common::{assemble, start};
+
+#[test]
+fn sprites_that_get_drawn_arent_code() {
+    let mut rom = assemble(&[
+        0xa208, // ldi 0x208
+        0xd013, // drw v0, v1, 3
+        0x120c, // jp 0x20c, over the sprite
+        0x0000, // never reached
+    ]);
+    // The sprite, then a bit of padding that isn't drawn
+    rom.extend_from_slice(&[0x3c, 0x42, 0x3c, 0x00]);
+    rom.extend_from_slice(&assemble(&[0x120c])); // jp to itself
+    let chip8 = start(&rom, Quirks::default());
+    let analysis = chip8.analyze(0x200, 0x200 + rom.len());
+
+    let kinds: Vec<ByteKind> = (0x200..0x20e)
+        .map(|address| analysis.kind(address))
+        .collect();
+    let (code, sprite, unknown) = (ByteKind::Code, ByteKind::Sprite, ByteKind::Unknown);
+    assert_eq!(
+        kinds,
+        vec![
+            code, code, code, code, code, code, unknown, unknown, sprite, sprite, sprite, unknown,
+            code, code,
+        ]
+    );
+}
+
+#[test]
+fn pointers_are_only_sprites_when_something_is_drawn() {
+    let mut rom = assemble(&[
+        0xa206, // ldi 0x206
+        0xf265, // ldxi v2, which reads the data
+        0x1204, // jp to itself
+    ]);
+    rom.extend_from_slice(&[1, 2, 3]);
+    let chip8 = start(&rom, Quirks::default());
+    let analysis = chip8.analyze(0x200, 0x200 + rom.len());
+    assert_eq!(analysis.kind(0x206), ByteKind::Unknown);
+    assert_eq!(analysis.kind(0x300), ByteKind::Unknown);
+    assert_eq!(analysis.end(), 0x209);
+}
+
+#[test]
+fn each_sprite_goes_until_the_next_one() {
+    let mut rom = assemble(&[
+        0xa20a, // ldi 0x20a
+        0xd015, // drw v0, v1, 5
+        0xa20d, // ldi 0x20d, in the middle of the first one
+        0xd012, // drw v0, v1, 2
+        0x1208, // jp to itself
+    ]);
+    rom.extend_from_slice(&[0xf0, 0x90, 0x90, 0x90, 0xf0]);
+    let chip8 = start(&rom, Quirks::default());
+    let analysis = chip8.analyze(0x200, 0x200 + rom.len());
+    assert_eq!(analysis.sprites(), vec![0x20a..0x20d, 0x20d..0x20f]);
+}
+
+#[test]
+fn the_call_graph_has_each_subroutine_once() {
+    let rom = assemble(&[
+        0x2206, // 200: call 206
+        0x220a, // 202: call 20a
+        0x1204, // 204: jp 204
+        0x220a, // 206: call 20a
+        0x00ee, // 208: ret
+        0x00ee, // 20a: ret
+    ]);
+    let chip8 = start(&rom, Quirks::default());
+    let mut graph = chip8.call_graph(0x200, 0x200 + rom.len());
+    assert_eq!(graph.entry(), 0x200);
+    assert_eq!(
+        graph.subroutines().collect::<Vec<_>>(),
+        vec![0x200, 0x206, 0x20a]
+    );
+    assert_eq!(
+        graph.calls().collect::<Vec<_>>(),
+        vec![(0x200, 0x206), (0x200, 0x20a), (0x206, 0x20a)]
+    );
+
+    // Calls that were seen while it ran go in too
+    graph.add_call(0x20a, 0x300);
+    assert_eq!(graph.subroutines().last(), Some(0x300));
+    assert!(graph.calls().any(|call| call == (0x20a, 0x300)));
+}
@@ -0,0 +1,144 @@
+//! Checks the arithmetic and shift instructions against every kind of input,
+//! since what they put in register f is the part that interpreters most often
+//! get subtly wrong
+
+mod common;
+
+use chip_8::Quirks;
+use common::{assemble, start};
+use proptest::prelude::*;
+
+/// Runs the one instruction with the registers set to `registers`, and with
+/// or without the shift quirk, and returns what the registers end up as
+fn run(opcode: u16, registers: [u8; 16], shift: bool) -> [u8; 16] {
+    let quirks = Quirks {
+        shift,
+        ..Quirks::default()
+    };
+    let mut chip8 = start(&assemble(&[opcode]), quirks);
+    chip8.registers = registers;
+    chip8.clock().unwrap();
+    chip8.registers
+}
+
+/// The opcode `8xyn`
+fn opcode(x: usize, y: usize, n: u16) -> u16 {
+    0x8000 | (x as u16) << 8 | (y as u16) << 4 | n
+}
+
+/// Checks that only register x and register f changed
+fn assert_only_changed(before: [u8; 16], after: [u8; 16], x: usize) -> Result<(), TestCaseError> {
+    for register in (0..15).filter(|register| *register != x) {
+        prop_assert_eq!(after[register], before[register], "register {:x}", register);
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn addy_carries_into_register_f(registers: [u8; 16], x in 0usize..15, y in 0usize..15) {
+        let (vx, vy) = (registers[x], registers[y]);
+        let after = run(opcode(x, y, 0x4), registers, false);
+        // The result and the carry make up the whole sum
+        prop_assert_eq!(
+            after[x] as u16 + after[0xf] as u16 * 0x100,
+            vx as u16 + vy as u16
+        );
+        assert_only_changed(registers, after, x)?;
+    }
+
+    #[test]
+    fn sub_sets_register_f_when_nothing_is_borrowed(
+        registers: [u8; 16],
+        x in 0usize..15,
+        y in 0usize..15,
+    ) {
+        let (vx, vy) = (registers[x], registers[y]);
+        let after = run(opcode(x, y, 0x5), registers, false);
+        prop_assert_eq!(after[x], vx.wrapping_sub(vy));
+        prop_assert_eq!(after[0xf], (vx >= vy) as u8);
+        assert_only_changed(registers, after, x)?;
+    }
+
+    #[test]
+    fn subn_sets_register_f_when_nothing_is_borrowed(
+        registers: [u8; 16],
+        x in 0usize..15,
+        y in 0usize..15,
+    ) {
+        let (vx, vy) = (registers[x], registers[y]);
+        let after = run(opcode(x, y, 0x7), registers, false);
+        prop_assert_eq!(after[x], vy.wrapping_sub(vx));
+        prop_assert_eq!(after[0xf], (vy >= vx) as u8);
+        assert_only_changed(registers, after, x)?;
+    }
+
+    #[test]
+    fn shr_puts_the_bit_that_falls_off_in_register_f(
+        registers: [u8; 16],
+        x in 0usize..15,
+        y in 0usize..15,
+        shift: bool,
+    ) {
+        // With the shift quirk register x is shifted in place, and without it
+        // register y is shifted into register x
+        let source = if shift { registers[x] } else { registers[y] };
+        let after = run(opcode(x, y, 0x6), registers, shift);
+        prop_assert_eq!(after[x], source >> 1);
+        prop_assert_eq!(after[0xf], source & 1);
+        // Nothing gets lost
+        prop_assert_eq!(after[x] << 1 | after[0xf], source);
+        assert_only_changed(registers, after, x)?;
+    }
+
+    #[test]
+    fn shl_puts_the_bit_that_falls_off_in_register_f(
+        registers: [u8; 16],
+        x in 0usize..15,
+        y in 0usize..15,
+        shift: bool,
+    ) {
+        let source = if shift { registers[x] } else { registers[y] };
+        let after = run(opcode(x, y, 0xe), registers, shift);
+        prop_assert_eq!(after[x], source << 1);
+        prop_assert_eq!(after[0xf], source >> 7);
+        prop_assert_eq!(after[0xf] << 7 | after[x] >> 1, source);
+        assert_only_changed(registers, after, x)?;
+    }
+
+    #[test]
+    fn register_f_is_read_before_the_flag_is_written(
+        registers: [u8; 16],
+        x in 0usize..15,
+        stand_in in 0usize..15,
+        n in prop::sample::select(vec![0x4u16, 0x5, 0x6, 0x7, 0xe]),
+        shift: bool,
+    ) {
+        // Using register f as y comes out the same as using another register
+        // with the same value in it
+        prop_assume!(x != stand_in);
+        let mut copied = registers;
+        copied[stand_in] = registers[0xf];
+        let with_f = run(opcode(x, 0xf, n), registers, shift);
+        let with_stand_in = run(opcode(x, stand_in, n), copied, shift);
+        prop_assert_eq!(with_f[x], with_stand_in[x]);
+        prop_assert_eq!(with_f[0xf], with_stand_in[0xf]);
+    }
+
+    #[test]
+    fn register_f_keeps_the_flag_instead_of_the_result(
+        registers: [u8; 16],
+        y in 0usize..15,
+        stand_in in 0usize..15,
+        n in prop::sample::select(vec![0x4u16, 0x5, 0x6, 0x7, 0xe]),
+        shift: bool,
+    ) {
+        // Using register f as x leaves the flag in it, which is the same flag
+        // that another register with the same value in it would have given
+        prop_assume!(y != stand_in);
+        let mut copied = registers;
+        copied[stand_in] = registers[0xf];
+        let flag = run(opcode(stand_in, y, n), copied, shift)[0xf];
+        prop_assert_eq!(run(opcode(0xf, y, n), registers, shift)[0xf], flag);
+    }
+}
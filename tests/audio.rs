@@ -0,0 +1,78 @@
+//! Checks the buzzer's waveforms, XO-CHIP's audio pattern and pitch, and that
+//! the pattern gets turned into samples at the rate the pitch says
+#![cfg(feature = "std")]
+
+mod common;
+
+use chip_8::{
+    audio::{Buzzer, PatternPlayer, Waveform},
+    octo,
+};
+use common::{assemble, run};
+
+#[test]
+fn the_rom_loads_a_pattern_and_sets_the_pitch() {
+    let chip8 = run(&assemble(&[0x6060, 0xa300, 0xf002, 0xf03a]), 4);
+    assert_eq!(chip8.audio_pattern(), Some([0; 16]));
+    assert_eq!(chip8.pitch(), 0x60);
+    // 32 higher than 64 is two thirds of the way to twice as fast
+    assert!((chip8.playback_rate() - 4000.0 * 2f64.powf(32.0 / 48.0)).abs() < 1e-9);
+    assert_eq!(chip8.disassemble(0x204), "audio");
+    assert_eq!(chip8.disassemble(0x206), "ldpitch v0");
+
+    // Without a pattern, the normal tone gets played
+    let chip8 = run(&assemble(&[0x1200]), 1);
+    assert_eq!(chip8.audio_pattern(), None);
+    assert_eq!(chip8.playback_rate(), 4000.0);
+}
+
+#[test]
+fn octo_has_audio_and_pitch() {
+    let rom = octo::assemble(": main audio pitch := v3").unwrap();
+    assert_eq!(rom[2..], [0xf0, 0x02, 0xf3, 0x3a]);
+}
+
+#[test]
+fn the_pattern_is_resampled_to_the_device_rate() {
+    // Alternating bytes of on and off, starting with the highest bit
+    let mut pattern = [0; 16];
+    pattern.iter_mut().step_by(2).for_each(|byte| *byte = 0xff);
+
+    // A device that is twice as fast plays each bit for 2 samples
+    let mut player = PatternPlayer::new();
+    let mut samples = [0.0; 40];
+    player.fill(&pattern, 4000.0, 8000.0, 0.5, &mut samples);
+    assert!(samples[..16].iter().all(|&sample| sample == 0.5));
+    assert!(samples[16..32].iter().all(|&sample| sample == -0.5));
+    assert!(samples[32..].iter().all(|&sample| sample == 0.5));
+
+    // It carries on where it left off, and loops back around after 128 bits
+    let mut samples = [0.0; 256];
+    player.fill(&pattern, 4000.0, 8000.0, 0.5, &mut samples);
+    assert!(samples[..8].iter().all(|&sample| sample == 0.5));
+    assert!(samples[216..232].iter().all(|&sample| sample == 0.5));
+
+    // A sample that covers more than one bit gets the average of them
+    let mut player = PatternPlayer::new();
+    let mut samples = [0.0; 2];
+    player.fill(&[0xf0; 16], 8000.0, 1000.0, 1.0, &mut samples);
+    assert_eq!(samples, [0.0, 0.0]);
+}
+
+#[test]
+fn the_buzzer_plays_the_waveform_it_was_given() {
+    // A quarter of a cycle a sample, so each sample lands on a corner
+    let levels = |waveform| {
+        let mut samples = [0.0f32; 4];
+        Buzzer::new(waveform, 1000.0, 0.5).fill(4000.0, &mut samples);
+        samples.map(|sample| (sample * 100.0).round() / 100.0)
+    };
+    assert_eq!(levels(Waveform::Square), [0.5, 0.5, 0.5, -0.5]);
+    assert_eq!(levels(Waveform::Sine), [0.0, 0.5, 0.0, -0.5]);
+    assert_eq!(levels(Waveform::Triangle), [-0.5, 0.0, 0.5, 0.0]);
+
+    assert_eq!(Waveform::from_name("sine"), Some(Waveform::Sine));
+    assert_eq!(Waveform::from_name("saw"), None);
+    // The volume can't go past what the device can play
+    assert_eq!(Buzzer::new(Waveform::Sine, 440.0, 3.0).volume, 1.0);
+}
@@ -0,0 +1,179 @@
+//! Checks what happens when the program counter or the index go out of
+//! bounds, with each of the policies
+
+mod common;
+
+use chip_8::{
+    bounds::{IndexPolicy, ProgramCounterPolicy},
+    Chip8Error, Quirks,
+};
+use common::{assemble, start};
+
+#[test]
+fn trapping_stops_below_the_rom_and_past_the_end() {
+    // `1100` jumps below the rom
+    let mut chip8 = start(&assemble(&[0x1100]), Quirks::default());
+    chip8.clock().unwrap();
+    assert_eq!(
+        chip8.clock(),
+        Err(Chip8Error::ProgramCounterOutOfBounds { pc: 0x100 })
+    );
+
+    // The last byte of memory doesn't have room for a second one after it
+    let last = chip8.memory.len() - 1;
+    chip8.program_counter = last;
+    assert_eq!(
+        chip8.clock(),
+        Err(Chip8Error::ProgramCounterOutOfBounds { pc: last })
+    );
+}
+
+#[test]
+fn wrapping_goes_back_around_to_the_start() {
+    let mut chip8 = start(&assemble(&[0x1200]), Quirks::default());
+    chip8.set_program_counter_policy(ProgramCounterPolicy::Wrap);
+    let last = chip8.memory.len() - 1;
+    // `6105` split between the end and the start of memory
+    chip8.memory[last] = 0x61;
+    chip8.memory[0] = 0x05;
+    chip8.program_counter = last;
+    chip8.clock().unwrap();
+    assert_eq!(chip8.registers[1], 5);
+
+    chip8.program_counter = chip8.memory.len() + 0x200;
+    assert_eq!(chip8.clock().unwrap().mnemonic, "jp");
+}
+
+#[test]
+fn halting_pauses_where_it_went_out_of_bounds() {
+    let mut chip8 = start(&assemble(&[0x1100]), Quirks::default());
+    chip8.set_program_counter_policy(ProgramCounterPolicy::Halt);
+    chip8.clock().unwrap();
+    assert_eq!(chip8.clock().unwrap().mnemonic, "nai");
+    assert!(chip8.is_paused());
+    assert_eq!(chip8.program_counter, 0x100);
+    assert!(!chip8.program_counter_in_bounds());
+}
+
+#[test]
+fn the_index_past_the_end_is_trapped_wrapped_or_clamped() {
+    // `f265` loads registers 0 through 2 from the last byte of memory onwards
+    let rom = assemble(&[0xf265]);
+    let mut chip8 = start(&rom, Quirks::default());
+    let last = chip8.memory.len() - 1;
+    chip8.memory[last] = 0xaa;
+    chip8.memory[0] = 0xbb;
+    chip8.memory[1] = 0xcc;
+    chip8.index = last;
+    assert_eq!(
+        chip8.clock(),
+        Err(Chip8Error::IndexOutOfBounds {
+            pc: 0x200,
+            index: last
+        })
+    );
+
+    chip8.set_index_policy(IndexPolicy::Wrap);
+    chip8.clock().unwrap();
+    assert_eq!(chip8.registers[..3], [0xaa, 0xbb, 0xcc]);
+
+    chip8.set_index_policy(IndexPolicy::Clamp);
+    chip8.program_counter = 0x200;
+    chip8.clock().unwrap();
+    assert_eq!(chip8.registers[..3], [0xaa, 0xaa, 0xaa]);
+}
+
+#[test]
+fn adding_to_the_index_past_the_end_is_trapped() {
+    // `6001` puts 1 in register 0, `afff` points the index at the last byte,
+    // and `f01e` adds register 0 to it
+    let mut chip8 = start(&assemble(&[0x6001, 0xafff, 0xf01e]), Quirks::default());
+    chip8.clock().unwrap();
+    chip8.clock().unwrap();
+    assert_eq!(
+        chip8.clock(),
+        Err(Chip8Error::IndexOutOfBounds {
+            pc: 0x204,
+            index: 0x1000
+        })
+    );
+    // The index is left where it was
+    assert_eq!(chip8.index, 0xfff);
+}
+
+#[test]
+fn adding_to_the_index_past_the_end_wraps_around_the_4k() {
+    let mut chip8 = start(&assemble(&[0x6003, 0xaffe, 0xf01e]), Quirks::default());
+    chip8.set_index_policy(IndexPolicy::Wrap);
+    for _ in 0..3 {
+        chip8.clock().unwrap();
+    }
+    assert_eq!(chip8.index, 0x001);
+}
+
+#[test]
+fn adding_to_the_index_past_the_end_is_clamped_to_the_last_byte() {
+    let mut chip8 = start(&assemble(&[0x6003, 0xaffe, 0xf01e]), Quirks::default());
+    chip8.set_index_policy(IndexPolicy::Clamp);
+    for _ in 0..3 {
+        chip8.clock().unwrap();
+    }
+    assert_eq!(chip8.index, 0xfff);
+}
+
+#[test]
+fn the_index_carry_quirk_sets_register_f() {
+    // `6001` puts 1 in register 0, `affe` points the index near the end of
+    // the 4K, and `f01e` adds register 0 to it twice
+    let rom = assemble(&[0x6001, 0xaffe, 0xf01e, 0xf01e]);
+    let quirks = Quirks {
+        index_carry: true,
+        ..Quirks::default()
+    };
+    let mut chip8 = start(&rom, quirks);
+    for _ in 0..3 {
+        chip8.clock().unwrap();
+    }
+    assert_eq!((chip8.index, chip8.registers[0xf]), (0xfff, 0));
+    // The index wraps around instead of being trapped
+    chip8.clock().unwrap();
+    assert_eq!((chip8.index, chip8.registers[0xf]), (0, 1));
+
+    // Without it register f is left alone
+    let mut chip8 = start(&rom, Quirks::default());
+    chip8.set_index_policy(IndexPolicy::Wrap);
+    chip8.registers[0xf] = 9;
+    for _ in 0..4 {
+        chip8.clock().unwrap();
+    }
+    assert_eq!((chip8.index, chip8.registers[0xf]), (0, 9));
+}
+
+#[test]
+fn the_memory_increment_quirk_keeps_the_index_in_bounds() {
+    // `affe` points the index at the second to last byte, and `f165` loads 2
+    // registers from there, which leaves the index past the end
+    let rom = assemble(&[0xaffe, 0xf165]);
+    let quirks = Quirks {
+        memory_increment: true,
+        ..Quirks::default()
+    };
+    let mut chip8 = start(&rom, quirks);
+    chip8.clock().unwrap();
+    assert_eq!(
+        chip8.clock(),
+        Err(Chip8Error::IndexOutOfBounds {
+            pc: 0x202,
+            index: 0x1000
+        })
+    );
+    assert_eq!(chip8.index, 0xffe);
+
+    for (policy, last_index) in [(IndexPolicy::Wrap, 0), (IndexPolicy::Clamp, 0xfff)] {
+        let mut chip8 = start(&rom, quirks);
+        chip8.set_index_policy(policy);
+        chip8.clock().unwrap();
+        chip8.clock().unwrap();
+        assert_eq!(chip8.index, last_index);
+    }
+}
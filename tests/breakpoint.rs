@@ -0,0 +1,101 @@
+//! Checks that breakpoint conditions get parsed and worked out right
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use chip_8::{
+    breakpoint::{Breakpoint, Condition},
+    symbols::Symbols,
+    Chip8, ConditionError, Quirks,
+};
+use common::{assemble, start};
+
+fn condition(text: &str) -> Condition {
+    text.parse().unwrap()
+}
+
+#[test]
+fn looks_at_the_state() {
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.registers[3] = 0x1f;
+    chip8.registers[0xf] = 1;
+    chip8.index = 0x300;
+    chip8.memory[0x301] = 42;
+
+    assert!(condition("v3 == 0x1F && i >= 0x300").is_true(&chip8));
+    assert!(!condition("v3 == 0x1f && i > 0x300").is_true(&chip8));
+    assert!(condition("[i + 1] == 42").is_true(&chip8));
+    assert!(condition("vf").is_true(&chip8));
+    assert!(condition("!v0 && pc == 512").is_true(&chip8));
+    // Memory that isn't there reads as 0
+    assert!(condition("[0xffff] == 0").is_true(&chip8));
+}
+
+#[test]
+fn and_goes_before_or() {
+    let chip8 = Chip8::with_seed(0);
+    assert!(condition("1 || 0 && 0").is_true(&chip8));
+    assert!(!condition("(1 || 0) && 0").is_true(&chip8));
+}
+
+#[test]
+fn says_where_a_condition_is_wrong() {
+    assert_eq!(
+        "v3 == 0x1f &&".parse::<Condition>(),
+        Err(ConditionError::UnexpectedEnd)
+    );
+    assert_eq!(
+        "vg == 1".parse::<Condition>(),
+        Err(ConditionError::Unexpected {
+            position: 0,
+            found: String::from("vg"),
+        })
+    );
+    assert_eq!(
+        "v0 == 1 $".parse::<Condition>(),
+        Err(ConditionError::Unexpected {
+            position: 8,
+            found: String::from("$"),
+        })
+    );
+}
+
+#[test]
+fn only_hits_when_it_becomes_true() {
+    let rom = assemble(&[
+        0x7001, // add v0, 0x01
+        0x1200, // jp 0x200
+    ]);
+    let mut chip8 = start(&rom, Quirks::default());
+    let mut breakpoint = Breakpoint::new(condition("v0 >= 2"), &chip8);
+    let mut hits = Vec::new();
+    for step in 0..8 {
+        if breakpoint.hit(&chip8) {
+            hits.push(step);
+        }
+        chip8.clock().unwrap();
+    }
+    // It stays true once v0 gets to 2, so it is only hit the once
+    assert_eq!(hits, vec![3]);
+}
+
+#[test]
+fn names_from_a_symbol_file_are_addresses() {
+    let symbols: Symbols = "game_loop 0x204\nScore 0x300".parse().unwrap();
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.memory[0x301] = 7;
+    chip8.index = 0x300;
+
+    let condition = Condition::with_symbols("[Score + 1] == 7 && i == Score", &symbols).unwrap();
+    assert!(condition.is_true(&chip8));
+    // A name alone stops at it
+    let condition = Condition::with_symbols("game_loop", &symbols).unwrap();
+    assert!(!condition.is_true(&chip8));
+    chip8.program_counter = 0x204;
+    assert!(condition.is_true(&chip8));
+    // The registers still mean what they always do, and names that aren't in
+    // the file can't be used
+    assert!(Condition::with_symbols("V0 == 0", &symbols).is_ok());
+    assert!(Condition::with_symbols("score", &symbols).is_err());
+    assert!("game_loop".parse::<Condition>().is_err());
+}
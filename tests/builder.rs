@@ -0,0 +1,75 @@
+//! Checks that the interpreter gets built with the choices that were made
+
+mod common;
+
+use chip_8::{chip8::FONT, Chip8, Quirks};
+use common::{assemble, run_cycles, screen_to_text};
+
+#[test]
+fn builds_the_same_as_new_by_default() {
+    let built = Chip8::builder().seed(0).build();
+    let new = Chip8::with_seed(0);
+    assert_eq!(built.memory, new.memory);
+    assert_eq!(built.quirks, new.quirks);
+    assert_eq!(built.program_counter, 0x200);
+}
+
+#[test]
+fn draws_with_a_custom_font() {
+    // The 7 is a solid 4x5 block instead
+    let mut font = FONT;
+    font[7] = [0xf0; 5];
+    let mut chip8 = Chip8::builder()
+        .seed(0)
+        .quirks(Quirks::SCHIP)
+        .font(font)
+        .load_address(0x300)
+        .build();
+    chip8
+        .load(&assemble(&[
+            0x6007, // ld v0, 0x07
+            0xf029, // ldf v0
+            0x6100, // ld v1, 0x00
+            0xd115, // drw v1, v1, 5
+        ]))
+        .unwrap();
+    run_cycles(&mut chip8, 4);
+
+    assert_eq!(chip8.memory.len(), 4096);
+    assert_eq!(chip8.quirks, Quirks::SCHIP);
+    let screen = screen_to_text(&chip8);
+    for row in screen.lines().take(5) {
+        assert!(row.starts_with("####."));
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn memory_always_fits_the_fonts() {
+    let chip8 = Chip8::builder().memory_size(0).build();
+    assert_eq!(chip8.memory[..5], FONT[0]);
+    assert!(chip8.memory.len() >= 0xf0);
+}
+
+#[test]
+fn rnd_uses_the_random_source() {
+    let rom = assemble(&[
+        0xc0ff, // rnd v0, 0xff
+        0xc10f, // rnd v1, 0x0f
+        0x1204, // jp to itself
+    ]);
+    let mut chip8 = Chip8::builder().random_source(|| 0x5a).build();
+    chip8.load(&rom).unwrap();
+    run_cycles(&mut chip8, 2);
+    assert_eq!(chip8.registers[..2], [0x5a, 0x0a]);
+
+    // Without it, the numbers come from the seed again
+    let mut seeded = Chip8::with_seed(0);
+    seeded.load(&rom).unwrap();
+    chip8 = Chip8::builder().seed(0).random_source(|| 0x5a).build();
+    chip8.set_random_source(None);
+    chip8.load(&rom).unwrap();
+    run_cycles(&mut seeded, 2);
+    run_cycles(&mut chip8, 2);
+    assert_eq!(chip8.registers, seeded.registers);
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "alloc")]
+use chip_8::{
+    cheats::{self, Cheat, CheatKind},
+    Chip8,
+};
+
+fn cheat(line: &str) -> Cheat {
+    line.parse().unwrap()
+}
+
+#[test]
+fn frozen_bytes_come_back_every_frame() {
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.add_cheat(cheat("freeze 300 09 lives"));
+    chip8.add_cheat(cheat("patch 301 05"));
+    assert_eq!(chip8.memory[0x300..0x302], [9, 5]);
+
+    chip8.memory[0x300] = 8;
+    chip8.memory[0x301] = 4;
+    chip8.tick_timers();
+    // Only the frozen one gets written again
+    assert_eq!(chip8.memory[0x300..0x302], [9, 4]);
+
+    chip8.set_cheat_enabled(0, false);
+    chip8.memory[0x300] = 7;
+    chip8.tick_timers();
+    assert_eq!(chip8.memory[0x300], 7);
+
+    // A patch is written again when it's turned back on
+    chip8.set_cheat_enabled(1, false);
+    chip8.set_cheat_enabled(1, true);
+    assert_eq!(chip8.memory[0x301], 5);
+
+    assert_eq!(
+        chip8.remove_cheat(0).map(|cheat| cheat.name),
+        Some(String::from("lives"))
+    );
+    assert_eq!(chip8.cheats().len(), 1);
+    assert!(chip8.remove_cheat(1).is_none());
+}
+
+#[test]
+fn cheats_that_are_off_arent_written() {
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.set_cheats(vec![
+        cheat("off freeze 300 09"),
+        cheat("on patch 0x301 0x05"),
+    ]);
+    chip8.tick_timers();
+    assert_eq!(chip8.memory[0x300..0x302], [0, 5]);
+    // Anything outside of memory is left alone
+    chip8.add_cheat(cheat("freeze ffff 01"));
+    chip8.tick_timers();
+}
+
+#[test]
+fn the_file_reads_back_the_same() {
+    let text = "\
+# Found with the memory search
+on freeze 2f0 03 lives never run out
+
+off patch 31a 00
+";
+    let cheats = cheats::parse(text).unwrap();
+    assert_eq!(
+        cheats[0],
+        Cheat {
+            address: 0x2f0,
+            value: 3,
+            kind: CheatKind::Freeze,
+            enabled: true,
+            name: String::from("lives never run out"),
+        }
+    );
+    assert!(!cheats[1].enabled);
+    assert_eq!(
+        cheats::to_text(&cheats),
+        "on freeze 2f0 03 lives never run out\noff patch 31a 00\n"
+    );
+    assert_eq!(cheats::parse(&cheats::to_text(&cheats)).unwrap(), cheats);
+
+    let error = cheats::parse("freeze 2f0 03\nfreeze 2f0 300\n").unwrap_err();
+    assert_eq!(error.line, 2);
+    assert!(cheats::parse("poke 2f0 03").is_err());
+    assert!(cheats::parse("freeze zz 03").is_err());
+}
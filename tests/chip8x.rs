@@ -0,0 +1,79 @@
+//! Checks the CHIP-8X instructions, which only exist once it has been turned on
+//! in the interpreter
+
+mod common;
+
+use chip_8::{screen::COLORS, Chip8};
+use common::{assemble, run_cycles};
+
+/// Loads the rom into a fresh interpreter with CHIP-8X turned on
+fn start(rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.enable_chip8x();
+    chip8.load(rom).unwrap();
+    chip8
+}
+
+#[test]
+fn steps_the_background_and_adds_digits() {
+    let rom = assemble(&[
+        0x02a0, // bgcol
+        0x02a0, // bgcol
+        0x6036, // ld v0, 0x36
+        0x6125, // ld v1, 0x25
+        0x5011, // addn v0, v1
+        0x120a, // jp to itself
+    ]);
+
+    let mut chip8 = start(&rom);
+    run_cycles(&mut chip8, 5);
+    // Pixels that are off show the background, which is now dark green
+    assert_eq!(chip8.screen().color(0, 0), Some([0x00, 0x80, 0x00]));
+    // 3 + 2 is 5, and 6 + 5 wraps around at 8 to 3
+    assert_eq!(chip8.registers[0], 0x53);
+}
+
+#[test]
+fn draws_pixels_in_their_zone_color() {
+    let rom = assemble(&[
+        0x6011, // ld v0, 0x11
+        0x6104, // ld v1, 4, green
+        0xb000, // col v0, v0, 0
+        0x6208, // ld v2, 8
+        0xf229, // ldf v2
+        0x6408, // ld v4, 8
+        0xd445, // drw v4, v4, 5
+        0x6400, // ld v4, 0
+        0xd445, // drw v4, v4, 5
+        0x1212, // jp to itself
+    ]);
+
+    let mut chip8 = start(&rom);
+    run_cycles(&mut chip8, 9);
+    let screen = chip8.screen();
+    // The zones from (8, 4) to (23, 11) are green
+    assert_eq!(screen.color(8, 8), Some(COLORS[4]));
+    assert_eq!(screen.color(11, 12), Some(COLORS[1]));
+    // Everything else is still red
+    assert_eq!(screen.color(0, 0), Some(COLORS[1]));
+}
+
+#[test]
+fn reads_the_second_keypad() {
+    let rom = assemble(&[
+        0x6005, // ld v0, 5
+        0xe0f2, // skp2 v0
+        0x6101, // ld v1, 1
+        0xe0f5, // skpn2 v0
+        0x6201, // ld v2, 1
+        0x120a, // jp to itself
+    ]);
+
+    let mut chip8 = start(&rom);
+    chip8.press_second_key(5);
+    run_cycles(&mut chip8, 5);
+    assert_eq!(chip8.registers[1], 0);
+    assert_eq!(chip8.registers[2], 1);
+    // The first keypad is separate from the second one
+    assert!(!chip8.is_key_pressed(5));
+}
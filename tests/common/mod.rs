@@ -0,0 +1,94 @@
+//! Helpers that the integration tests share, for running roms without a
+//! frontend and checking what ends up on the screen
+
+// Every test file gets it's own copy of this module, and not all of them use
+// every helper
+#![allow(dead_code)]
+
+use chip_8::{Chip8, Quirks};
+use std::{env, fs, path::PathBuf};
+
+/// Turns a list of opcodes into the bytes of a rom
+pub fn assemble(opcodes: &[u16]) -> Vec<u8> {
+    opcodes
+        .iter()
+        .flat_map(|opcode| opcode.to_be_bytes().to_vec())
+        .collect()
+}
+
+/// Loads the rom into a fresh interpreter with a fixed seed, and runs `cycles`
+/// instructions, ticking the timers in between like a frontend would
+pub fn run(rom: &[u8], cycles: usize) -> Chip8 {
+    let mut chip8 = start(rom, Quirks::default());
+    run_cycles(&mut chip8, cycles);
+    chip8
+}
+
+/// Loads the rom into a fresh interpreter with a fixed seed and the quirks
+pub fn start(rom: &[u8], quirks: Quirks) -> Chip8 {
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.quirks = quirks;
+    chip8.load(rom).unwrap();
+    chip8
+}
+
+/// Runs `cycles` instructions, ticking the timers in between like a frontend
+/// would, the rom stopping with an error fails the test
+pub fn run_cycles(chip8: &mut Chip8, cycles: usize) {
+    // Splits the cycles up into frames of 16 instructions, with whatever is
+    // left over in the last one
+    let mut remaining = cycles;
+    while remaining > 0 {
+        let frame = remaining.min(16);
+        chip8
+            .frame(frame)
+            .unwrap_or_else(|error| panic!("the rom stopped running: {}", error));
+        remaining -= frame;
+    }
+}
+
+/// Draws the screen as text, with `#` for a pixel that is on and `.` for one
+/// that is off, one line for every row
+pub fn screen_to_text(chip8: &Chip8) -> String {
+    let screen = chip8.screen();
+    let mut text = String::new();
+    for y in 0..screen.height() {
+        for x in 0..screen.width() {
+            text.push(if screen.get_pixel(x, y) { '#' } else { '.' });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Compares the screen to the snapshot in `tests/snapshots/<name>.txt`.
+///
+/// Run the tests with `BLESS=1` to write the current screen as the snapshot
+/// instead, for a new test or when the screen is meant to have changed, and
+/// then look over the new snapshot before committing it.
+pub fn assert_snapshot(name: &str, chip8: &Chip8) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name));
+    let actual = screen_to_text(chip8);
+
+    if env::var_os("BLESS").is_some() {
+        fs::write(&path, &actual).expect("the snapshot couldn't be written");
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "there isn't a snapshot at {}, run the tests with BLESS=1 to create it",
+            path.display()
+        )
+    });
+    assert!(
+        actual == expected,
+        "the screen doesn't match {}, run the tests with BLESS=1 if it was meant to change\n\nexpected:\n{}\nactual:\n{}",
+        path.display(),
+        expected,
+        actual
+    );
+}
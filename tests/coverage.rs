@@ -0,0 +1,85 @@
+#![cfg(feature = "alloc")]
+mod common;
+
+use chip_8::{coverage::HEAT_LEVELS, Quirks};
+use common::{assemble, run_cycles, start};
+
+#[test]
+fn counts_each_instruction_that_runs() {
+    let mut chip8 = start(
+        &assemble(&[
+            0x6003, // 200: ld v0, 3
+            0x70ff, // 202: add v0, -1
+            0x3000, // 204: se v0, 0
+            0x1202, // 206: jp 202
+            0x1208, // 208: jp 208
+        ]),
+        Quirks::default(),
+    );
+    // Nothing gets counted until it is turned on
+    chip8.clock().unwrap();
+    assert!(chip8.coverage().is_none());
+
+    chip8.enable_coverage();
+    run_cycles(&mut chip8, 11);
+    let coverage = chip8.coverage().unwrap();
+    assert_eq!(coverage.count(0x200), 0);
+    assert_eq!(coverage.count(0x202), 3);
+    assert_eq!(coverage.count(0x206), 2);
+    assert_eq!(coverage.count(0x208), 3);
+    assert_eq!(coverage.hottest(), 3);
+    assert!(coverage.covers(0x209));
+    assert!(!coverage.covers(0x20a));
+    assert_eq!(coverage.ranges(), vec![0x202..0x20a]);
+
+    // Turning it on again starts it over
+    chip8.enable_coverage();
+    assert_eq!(chip8.coverage().unwrap().ranges(), vec![]);
+}
+
+#[test]
+fn the_hottest_instructions_are_the_hottest_level() {
+    let mut chip8 = start(
+        &assemble(&[
+            0x6001, // 200: ld v0, 1
+            0x1204, // 202: jp 204
+            0x1204, // 204: jp 204
+        ]),
+        Quirks::default(),
+    );
+    chip8.enable_coverage();
+    run_cycles(&mut chip8, 1000);
+    let coverage = chip8.coverage().unwrap();
+    assert_eq!(coverage.heat(0x200..0x202), 1);
+    assert_eq!(coverage.heat(0x204..0x206), HEAT_LEVELS);
+    // A range takes the hottest instruction in it
+    assert_eq!(coverage.heat(0x200..0x208), HEAT_LEVELS);
+    assert_eq!(coverage.heat(0x206..0x300), 0);
+}
+
+#[test]
+fn the_report_has_the_code_that_hasnt_ran() {
+    let mut chip8 = start(
+        &assemble(&[
+            0x3000, // 200: se v0, 0
+            0x6105, // 202: ld v1, 5, which is always skipped
+            0x1204, // 204: jp 204
+        ]),
+        Quirks::default(),
+    );
+    chip8.enable_coverage();
+    run_cycles(&mut chip8, 3);
+    let report = chip8.coverage().unwrap().report(&chip8, 0x200, 0x206);
+    assert!(
+        report.starts_with("# 2 instructions ran, 4 bytes\n"),
+        "{}",
+        report
+    );
+    assert!(report.contains("\n200-201\n204-205\n"), "{}", report);
+    assert!(
+        report.contains("# The code that hasn't ran\n202  ld v1, 0x05\n"),
+        "{}",
+        report
+    );
+    assert!(report.contains("204  2"), "{}", report);
+}
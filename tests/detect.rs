@@ -0,0 +1,52 @@
+//! Checks that the interpreter a rom was made for gets guessed from its code
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use chip_8::{detect, Quirks};
+use common::assemble;
+
+#[test]
+fn superchip_instructions_suggest_superchip() {
+    let rom = assemble(&[
+        0x00ff, // high
+        0xf030, // ldhf v0
+        0xd010, // drw v0, v1, 0
+        0x8016, // shry v0, v1
+        0x1208, // jp to itself
+    ]);
+    let suggestion = detect::suggest(&rom, 0x200).unwrap();
+    assert_eq!(suggestion.quirks, Quirks::SCHIP);
+    assert_eq!(
+        suggestion.reasons,
+        vec![
+            "00fe and 00ff switch the screen size",
+            "fx30 uses the big font",
+            "dxy0 draws 16x16 sprites",
+        ]
+    );
+}
+
+#[test]
+fn shifting_another_register_suggests_the_vip() {
+    let rom = assemble(&[
+        0x8016, // shry v0, v1
+        0x801e, // shly v0, v1
+        0x1204, // jp to itself
+    ]);
+    let suggestion = detect::suggest(&rom, 0x200).unwrap();
+    assert_eq!(suggestion.quirks, Quirks::VIP);
+    assert_eq!(suggestion.reasons.len(), 1);
+}
+
+#[test]
+fn data_and_plain_code_suggest_nothing() {
+    let mut rom = assemble(&[
+        0x8006, // shry v0, v0
+        0xa206, // ldi 0x206
+        0x1204, // jp to itself
+    ]);
+    // Looks like fx75 and dxy0, but is a sprite that never runs
+    rom.extend_from_slice(&[0xf0, 0x75, 0xd1, 0x20]);
+    assert_eq!(detect::suggest(&rom, 0x200), None);
+}
@@ -0,0 +1,79 @@
+//! Checks that the devices mapped over memory get the reads and writes
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use chip_8::{devices::Device, Quirks};
+use common::{assemble, start};
+use std::sync::{Arc, Mutex};
+
+/// Counts up every time it is read, and remembers everything written to it
+struct Counter {
+    next: u8,
+    written: Arc<Mutex<Vec<(usize, u8)>>>,
+}
+
+impl Device for Counter {
+    fn read(&mut self, _offset: usize) -> u8 {
+        self.next += 1;
+        self.next
+    }
+
+    fn write(&mut self, offset: usize, value: u8) {
+        self.written.lock().unwrap().push((offset, value));
+    }
+}
+
+#[test]
+fn reads_and_writes_go_to_the_device() {
+    let rom = assemble(&[
+        0xaf00, // ldi 0xf00
+        0xf265, // ldxi v2
+        0x63ea, // ld v3, 0xea
+        0xf333, // ldb v3
+    ]);
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let mut chip8 = start(&rom, Quirks::default());
+    chip8.map_device(
+        0xf01..0xf03,
+        Box::new(Counter {
+            next: 0,
+            written: Arc::clone(&written),
+        }),
+    );
+    chip8.memory[0xf00] = 0x55;
+    for _ in 0..4 {
+        chip8.clock().unwrap();
+    }
+
+    // Only the bytes under the device come from it
+    assert_eq!(chip8.registers[..3], [0x55, 1, 2]);
+    // 234 gets written as 2, 3, 4, and the 2 goes to memory
+    assert_eq!(*written.lock().unwrap(), vec![(0, 3), (1, 4)]);
+    assert_eq!(chip8.memory[0xf00..0xf03], [2, 0, 0]);
+}
+
+#[test]
+fn memory_is_back_once_the_devices_are_unmapped() {
+    let rom = assemble(&[
+        0xaf00, // ldi 0xf00
+        0xf065, // ldxi v0
+        0xf065, // ldxi v0
+    ]);
+    let mut chip8 = start(&rom, Quirks::default());
+    chip8.map_device(
+        0xf00..0xf01,
+        Box::new(Counter {
+            next: 0x10,
+            written: Arc::default(),
+        }),
+    );
+    chip8.memory[0xf00] = 0x42;
+    chip8.clock().unwrap();
+    chip8.clock().unwrap();
+    assert_eq!(chip8.registers[0], 0x11);
+
+    chip8.unmap_devices();
+    chip8.clock().unwrap();
+    assert_eq!(chip8.registers[0], 0x42);
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "alloc")]
+use chip_8::Chip8;
+
+#[test]
+fn the_same_states_have_no_diff() {
+    let chip8 = Chip8::with_seed(0);
+    let diff = chip8.diff(&Chip8::with_seed(0));
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "the states are the same\n");
+}
+
+#[test]
+fn bytes_next_to_each_other_are_one_range() {
+    let before = Chip8::with_seed(0);
+    let mut after = Chip8::with_seed(0);
+    after.memory[0x300] = 1;
+    after.memory[0x301] = 2;
+    after.memory[0x310] = 3;
+    after.program_counter = 0x20a;
+    after.delay = 0x3c;
+
+    let diff = before.diff(&after);
+    let ranges: Vec<(usize, usize)> = diff
+        .memory()
+        .iter()
+        .map(|change| (change.start, change.end()))
+        .collect();
+    assert_eq!(ranges, [(0x300, 0x302), (0x310, 0x311)]);
+    assert_eq!(diff.memory()[0].before, [0, 0]);
+    let names: Vec<&str> = diff
+        .registers()
+        .iter()
+        .map(|change| change.name.as_str())
+        .collect();
+    assert_eq!(names, ["pc", "dt"]);
+    assert_eq!(
+        diff.to_string(),
+        "registers\n  pc       200 -> 20a\n  dt       00 -> 3c\n\
+         memory\n  300-301  00 00 -> 01 02\n  310      00 -> 03\n"
+    );
+}
+
+#[test]
+fn rows_of_pixels_next_to_each_other_are_one_region() {
+    let before = Chip8::with_seed(0);
+    let mut after = Chip8::with_seed(0);
+    after.screen_mut().set_pixel(4, 2, true);
+    after.screen_mut().set_pixel(9, 3, true);
+    after.screen_mut().set_pixel(0, 20, true);
+
+    let diff = before.diff(&after);
+    let regions: Vec<(usize, usize, usize, usize, usize)> = diff
+        .screen()
+        .iter()
+        .map(|change| {
+            (
+                change.left,
+                change.top,
+                change.right,
+                change.bottom,
+                change.pixels,
+            )
+        })
+        .collect();
+    assert_eq!(regions, [(4, 2, 9, 3, 2), (0, 20, 0, 20, 1)]);
+}
@@ -0,0 +1,449 @@
+//! Runs roms through the interpreter and through `reference`, a second one
+//! that was written separately, with the same keys pressed at the same times,
+//! and checks that they are in the same state every few instructions. A
+//! divergence gets reported with the instructions it happened in, and what is
+//! different.
+//!
+//! Besides the roms here, every `.ch8` in `tests/roms` (or the directory in
+//! `DIFF_ROMS`) is ran for 10 seconds with the keys pressed one at a time, and
+//! compared every 100 instructions, or however many `DIFF_EVERY` says. The
+//! reference only knows the original instructions, so a rom that uses any of
+//! the extensions is compared up until the first one it uses.
+
+mod common;
+mod reference;
+
+use chip_8::{Chip8, Quirks};
+use common::assemble;
+use proptest::prelude::*;
+use reference::{next_random, Reference, Stop};
+use std::{cell::Cell, env, fs, path::PathBuf};
+
+/// The seed that the random numbers of both interpreters start from
+const SEED: u32 = 0x1234;
+
+thread_local! {
+    /// Where the interpreter's random numbers are up to, each test has it's
+    /// own thread, and so it's own numbers
+    static RANDOM: Cell<u32> = const { Cell::new(SEED) };
+}
+
+/// The interpreter's random source, which is the same generator as the
+/// reference's
+fn random() -> u8 {
+    RANDOM.with(|random| {
+        let mut state = random.get();
+        let byte = next_random(&mut state);
+        random.set(state);
+        byte
+    })
+}
+
+/// A key being pressed or let go of at the start of a frame
+#[derive(Debug, Clone, Copy)]
+struct KeyEvent {
+    frame: usize,
+    key: u8,
+    pressed: bool,
+}
+
+/// How the comparison went, when neither of them did something different
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    /// They ran every frame and ended up the same
+    Finished,
+    /// They both stopped on something that can't be done, at the same place
+    BothStopped,
+    /// The reference ran into an instruction it doesn't know, after this many
+    /// instructions that were the same
+    Unsupported { opcode: u16, after: usize },
+}
+
+/// Finds everything that is different between the two, as lines that say what
+/// each of them has
+fn differences(chip8: &Chip8, reference: &Reference) -> Vec<String> {
+    let mut differences = Vec::new();
+    let mut compare = |name: &str, actual: String, expected: String| {
+        if actual != expected {
+            differences.push(format!(
+                "{}: the interpreter has {}, the reference has {}",
+                name, actual, expected
+            ));
+        }
+    };
+    compare(
+        "registers",
+        format!("{:02x?}", chip8.registers),
+        format!("{:02x?}", reference.v),
+    );
+    compare(
+        "index",
+        format!("{:03x}", chip8.index),
+        format!("{:03x}", reference.i),
+    );
+    compare(
+        "program counter",
+        format!("{:03x}", chip8.program_counter),
+        format!("{:03x}", reference.pc),
+    );
+    // The interpreter keeps where each call was, the reference keeps where
+    // each one goes back to, and the interpreter gives the most recent first
+    #[cfg(feature = "alloc")]
+    let returns: Vec<usize> = chip8
+        .call_stack()
+        .iter()
+        .rev()
+        .map(|frame| frame.call_site + 2)
+        .collect();
+    // Without a heap there is no call stack to get, only the stack itself
+    #[cfg(not(feature = "alloc"))]
+    let returns: Vec<usize> = chip8.stack[..chip8.stack_pointer.min(chip8.stack.len())]
+        .iter()
+        .map(|call| call + 2)
+        .collect();
+    compare(
+        "stack",
+        format!("{:03x?}", returns),
+        format!("{:03x?}", reference.stack),
+    );
+    compare(
+        "timers",
+        format!("delay {} sound {}", chip8.delay, chip8.sound),
+        format!("delay {} sound {}", reference.delay, reference.sound),
+    );
+    if let Some(address) = (0..reference::MEMORY_SIZE)
+        .find(|address| chip8.memory.get(*address) != reference.memory.get(*address))
+    {
+        compare(
+            &format!("memory at {:03x}", address),
+            format!("{:02x?}", chip8.memory.get(address)),
+            format!("{:02x?}", reference.memory.get(address)),
+        );
+    }
+    let screen = chip8.screen();
+    if screen.size() != (reference::WIDTH, reference::HEIGHT) {
+        compare(
+            "screen size",
+            format!("{:?}", screen.size()),
+            format!("{:?}", (reference::WIDTH, reference::HEIGHT)),
+        );
+    } else {
+        let pixels = (0..reference::HEIGHT)
+            .flat_map(|y| (0..reference::WIDTH).map(move |x| (x, y)))
+            .filter(|(x, y)| screen.get_pixel(*x, *y) != reference.screen[*y][*x])
+            .count();
+        if pixels > 0 {
+            differences.push(format!("screen: {} pixels are different", pixels));
+        }
+    }
+    differences
+}
+
+/// Runs the rom in both for `frames` frames of `cycles` instructions, pressing
+/// the keys in the script, and compares them every `every` instructions, and
+/// at the end. A divergence is returned as the error, with what is different.
+/// The reference always has the default quirks, the interpreter has `quirks`.
+fn compare(
+    rom: &[u8],
+    quirks: Quirks,
+    script: &[KeyEvent],
+    frames: usize,
+    cycles: usize,
+    every: usize,
+) -> Result<Outcome, String> {
+    RANDOM.with(|random| random.set(SEED));
+    // The reference doesn't have SUPER-CHIP's big font, so where the
+    // interpreter keeps it is left empty like it is in the reference, for when
+    // a rom reads or draws from there
+    let mut chip8 = Chip8::builder()
+        .random_source(random)
+        .big_font([[0; 10]; 16])
+        .quirks(quirks)
+        .build();
+    chip8.load(rom).map_err(|error| error.to_string())?;
+    let mut reference = Reference::new(rom, SEED);
+
+    let mut count = 0;
+    // The last time they were the same, for saying where they went different
+    let mut last_same = 0;
+    let mut check = |chip8: &Chip8, reference: &Reference, count: usize| {
+        let differences = differences(chip8, reference);
+        if differences.is_empty() {
+            last_same = count;
+            return Ok(());
+        }
+        Err(format!(
+            "the interpreter and the reference went different somewhere in instructions {} through {}\n{}",
+            last_same + 1,
+            count,
+            differences.join("\n")
+        ))
+    };
+
+    for frame in 0..frames {
+        for event in script.iter().filter(|event| event.frame == frame) {
+            if event.pressed {
+                chip8.press_key(event.key);
+            } else {
+                chip8.release_key(event.key);
+            }
+            reference.keys[event.key as usize] = event.pressed;
+        }
+        for _ in 0..cycles {
+            let pc = chip8.program_counter;
+            let stopped = match (chip8.clock(), reference.step()) {
+                (_, Err(Stop::Unsupported(opcode))) => {
+                    return Ok(Outcome::Unsupported {
+                        opcode,
+                        after: count,
+                    })
+                }
+                (Ok(_), Ok(())) => false,
+                (Err(_), Err(Stop::Fault(_))) => true,
+                (Err(error), Ok(())) => {
+                    return Err(format!(
+                        "the interpreter stopped at {:03x} ({}), and the reference kept going",
+                        pc, error
+                    ))
+                }
+                (Ok(_), Err(Stop::Fault(reason))) => {
+                    return Err(format!(
+                    "the reference stopped at {:03x} because it {}, and the interpreter kept going",
+                    pc, reason
+                ))
+                }
+            };
+            count += 1;
+            if stopped {
+                return Ok(Outcome::BothStopped);
+            }
+            if count % every == 0 {
+                check(&chip8, &reference, count)?;
+            }
+        }
+        chip8.tick_timers();
+        reference.tick();
+    }
+    check(&chip8, &reference, count)?;
+    Ok(Outcome::Finished)
+}
+
+/// Presses each key for a few frames, one after the other
+fn every_key(frames_each: usize) -> Vec<KeyEvent> {
+    (0..16u8)
+        .flat_map(|key| {
+            let frame = key as usize * frames_each * 2;
+            vec![
+                KeyEvent {
+                    frame,
+                    key,
+                    pressed: true,
+                },
+                KeyEvent {
+                    frame: frame + frames_each,
+                    key,
+                    pressed: false,
+                },
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn matches_on_a_rom_that_uses_every_instruction() {
+    let rom = assemble(&[
+        0x00e0, // 200: cls
+        0xf00a, // 202: ldk v0, waits for key 0
+        0x6a05, // 204: ld va, 5
+        0xfa29, // 206: ldf va
+        0x6b3c, // 208: ld vb, 60, so the digit is cut off at the right edge
+        0x6c1e, // 20a: ld vc, 30, and at the bottom
+        0xdbc5, // 20c: drw vb, vc, 5
+        0xdbc5, // 20e: drw vb, vc, 5, which collides and rubs it out
+        0xc1ff, // 210: rnd v1, 0xff
+        0xc20f, // 212: rnd v2, 0x0f
+        0x8314, // 214: addy v3, v1
+        0x8425, // 216: sub v4, v2
+        0x8537, // 218: subn v5, v3
+        0x8616, // 21a: shr v6, v1
+        0x872e, // 21c: shl v7, v2
+        0x8811, // 21e: or v8, v1
+        0x8922, // 220: and v9, v2
+        0x8d13, // 222: xor vd, v1
+        0x8e10, // 224: ldy ve, v1
+        0x7e07, // 226: add ve, 7
+        0x2250, // 228: call 250
+        0xa300, // 22a: ldi 0x300
+        0xf133, // 22c: ldb v1
+        0xf555, // 22e: ldix v5
+        0xf065, // 230: ldxi v0
+        0xf11e, // 232: addi v1
+        0x6009, // 234: ld v0, 9
+        0xf015, // 236: lddt v0
+        0xf018, // 238: ldst v0
+        0xf607, // 23a: ldxdt v6
+        0x6002, // 23c: ld v0, 2
+        0xe09e, // 23e: skp v0
+        0xb23e, // 240: jp v0 + 23e, back to the skp until key 2 is held
+        0xe1a1, // 242: sknp v1
+        0x3100, // 244: se v1, 0
+        0x4100, // 246: sne v1, 0
+        0x5120, // 248: se v1, v2
+        0x9120, // 24a: sne v1, v2
+        0x1200, // 24c: jp 200
+        0x0000, // 24e
+        0xd125, // 250: drw v1, v2, 5
+        0x00ee, // 252: ret
+    ]);
+    let script = [
+        KeyEvent {
+            frame: 2,
+            key: 0,
+            pressed: true,
+        },
+        KeyEvent {
+            frame: 3,
+            key: 0,
+            pressed: false,
+        },
+        KeyEvent {
+            frame: 10,
+            key: 2,
+            pressed: true,
+        },
+        KeyEvent {
+            frame: 12,
+            key: 2,
+            pressed: false,
+        },
+    ];
+    let outcome = compare(&rom, Quirks::default(), &script, 120, 10, 1)
+        .unwrap_or_else(|error| panic!("{}", error));
+    assert_eq!(outcome, Outcome::Finished);
+}
+
+#[test]
+fn reports_where_they_went_different() {
+    // An extension stops the comparison, without it being a divergence
+    let outcome = compare(
+        &assemble(&[0x6001, 0x00ff]),
+        Quirks::default(),
+        &[],
+        1,
+        2,
+        1,
+    );
+    assert_eq!(
+        outcome,
+        Ok(Outcome::Unsupported {
+            opcode: 0x00ff,
+            after: 1
+        })
+    );
+
+    // Something that isn't the same, which here is the interpreter shifting
+    // register 1 in place, where the reference shifts register 2 into it
+    let rom = assemble(&[0x6103, 0x6205, 0x8126, 0x1206]);
+    let quirks = Quirks {
+        shift: true,
+        ..Quirks::default()
+    };
+    let error = compare(&rom, quirks, &[], 1, 4, 4).unwrap_err();
+    assert!(error.contains("instructions 1 through 4"), "{}", error);
+    assert!(error.contains("registers"), "{}", error);
+}
+
+/// One instruction from the original set, with random registers and numbers,
+/// where the jumps and calls land on one of the `length` instructions of the
+/// program, and the index points somewhere that isn't the program or the edge
+/// of memory
+fn instruction(kind: u8, operands: u16, length: usize) -> u16 {
+    let target = 0x200 + (operands as usize % length) as u16 * 2;
+    let xy = operands & 0x0ff0;
+    match kind {
+        0 => 0x00e0,
+        1 => 0x00ee,
+        2 => 0x1000 | target,
+        3 => 0x2000 | target,
+        4 => 0x3000 | (operands & 0x0fff),
+        5 => 0x4000 | (operands & 0x0fff),
+        6 => 0x5000 | xy,
+        7 => 0x6000 | (operands & 0x0fff),
+        8 => 0x7000 | (operands & 0x0fff),
+        9 => 0x8000 | xy | [0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0xe][operands as usize % 9],
+        10 => 0x9000 | xy,
+        11 => 0xa300 | (operands & 0x07ff),
+        12 => 0xc000 | (operands & 0x0fff),
+        13 => 0xd000 | xy | (operands % 15 + 1),
+        14 => 0xe000 | (operands & 0x0f00) | [0x9e, 0xa1][operands as usize % 2],
+        _ => {
+            let low = [0x07, 0x0a, 0x15, 0x18, 0x1e, 0x29, 0x33, 0x55, 0x65];
+            0xf000 | (operands & 0x0f00) | low[operands as usize % low.len()]
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn random_programs_run_the_same(
+        program in prop::collection::vec((0u8..16, any::<u16>()), 1..48),
+        presses in prop::collection::vec((0usize..20, 0u8..16, any::<bool>()), 0..8),
+    ) {
+        let length = program.len();
+        let opcodes: Vec<u16> = program
+            .iter()
+            .map(|(kind, operands)| instruction(*kind, *operands, length))
+            .collect();
+        let script: Vec<KeyEvent> = presses
+            .iter()
+            .map(|(frame, key, pressed)| KeyEvent { frame: *frame, key: *key, pressed: *pressed })
+            .collect();
+        if let Err(error) = compare(&assemble(&opcodes), Quirks::default(), &script, 20, 10, 1) {
+            return Err(TestCaseError::fail(format!("{:04x?}\n{}", opcodes, error)));
+        }
+    }
+}
+
+/// Every rom in `tests/roms`, or the directory in `DIFF_ROMS`
+fn roms() -> Vec<PathBuf> {
+    let directory = env::var_os("DIFF_ROMS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests")
+                .join("roms")
+        });
+    let mut roms: Vec<PathBuf> = fs::read_dir(&directory)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "ch8"))
+                .collect()
+        })
+        .unwrap_or_default();
+    roms.sort();
+    roms
+}
+
+#[test]
+fn roms_run_the_same() {
+    let every = env::var("DIFF_EVERY")
+        .ok()
+        .and_then(|every| every.parse().ok())
+        .filter(|every| *every > 0)
+        .unwrap_or(100);
+    let script = every_key(15);
+    let roms = roms();
+    if roms.is_empty() {
+        eprintln!("skipping, there aren't any roms to compare");
+    }
+    let mut failures = Vec::new();
+    for path in roms {
+        let rom = fs::read(&path).unwrap();
+        match compare(&rom, Quirks::default(), &script, 600, 10, every) {
+            Ok(outcome) => eprintln!("{}: {:?}", path.display(), outcome),
+            Err(error) => failures.push(format!("{}: {}", path.display(), error)),
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
@@ -0,0 +1,73 @@
+//! Checks the C API, by calling it the same way that C would
+#![cfg(feature = "ffi")]
+
+mod common;
+
+use chip_8::ffi::*;
+use common::assemble;
+use std::ptr;
+
+#[test]
+fn runs_a_rom_and_hands_out_the_screen() {
+    let rom = assemble(&[
+        0x6000, // ld v0, 0
+        0xf029, // ldf v0
+        0xd005, // drw v0, v0, 5
+        0xe09e, // skp v0
+        0x1206, // jp back to the skp
+        0x120a, // jp to itself
+    ]);
+    unsafe {
+        let chip8 = chip8_create_with_seed(0);
+        assert_eq!(chip8_load_rom(chip8, rom.as_ptr(), rom.len()), 0);
+        for _ in 0..4 {
+            assert_eq!(chip8_step(chip8), 0);
+        }
+        assert_eq!(chip8_take_redraw(chip8), 1);
+
+        let (mut width, mut height) = (0, 0);
+        let pixels = chip8_framebuffer(chip8, &mut width, &mut height);
+        assert_eq!((width, height), (64, 32));
+        // The top row of the 0 in the font is the 4 pixels on the left
+        assert_eq!(*pixels, 0xf0);
+        assert_eq!(*pixels.add(width / 8), 0x90);
+
+        // It waits on the skip until key 0 is pressed
+        chip8_step(chip8);
+        assert_eq!((*chip8).program_counter, 0x206);
+        chip8_key_down(chip8, 0);
+        chip8_step(chip8);
+        chip8_step(chip8);
+        assert_eq!((*chip8).program_counter, 0x20a);
+        chip8_key_up(chip8, 0);
+        assert!(!(*chip8).is_key_pressed(0));
+
+        chip8_destroy(chip8);
+    }
+}
+
+#[test]
+fn fails_instead_of_crashing() {
+    unsafe {
+        assert_eq!(chip8_step(ptr::null_mut()), -1);
+        assert!(chip8_framebuffer(ptr::null(), ptr::null_mut(), ptr::null_mut()).is_null());
+        chip8_destroy(ptr::null_mut());
+
+        // A rom that is too big leaves the one that was loaded alone
+        let chip8 = chip8_create();
+        let rom = assemble(&[0x1200]);
+        assert_eq!(chip8_load_rom(chip8, rom.as_ptr(), rom.len()), 0);
+        let too_big = vec![0; 0x1000];
+        assert_eq!(chip8_load_rom(chip8, too_big.as_ptr(), too_big.len()), -1);
+        assert_eq!((&(*chip8).memory)[0x200..0x202], [0x12, 0x00]);
+        assert_eq!(chip8_load_rom(chip8, ptr::null(), 0), -1);
+
+        // Sound plays until the timers tick it down
+        assert_eq!(chip8_sound_playing(chip8), 0);
+        (*chip8).sound = 1;
+        assert_eq!(chip8_sound_playing(chip8), 1);
+        chip8_tick_timers(chip8);
+        assert_eq!(chip8_sound_playing(chip8), 0);
+        chip8_destroy(chip8);
+    }
+}
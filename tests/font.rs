@@ -0,0 +1,34 @@
+//! Checks that other fonts get loaded over the built in ones
+#![cfg(feature = "std")]
+
+use chip_8::{
+    chip8::{BIG_FONT, FONT},
+    font::{BIG_FONT_SIZE, FONT_SIZE},
+    Chip8,
+};
+
+#[test]
+fn loads_only_the_small_font() {
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.load_font(&[0xf0; FONT_SIZE]).unwrap();
+    assert_eq!(chip8.memory[..FONT_SIZE], [0xf0; FONT_SIZE][..]);
+    // The big font is still the one it started with
+    assert_eq!(chip8.memory[FONT_SIZE..FONT_SIZE + 10], BIG_FONT[0]);
+}
+
+#[test]
+fn builds_with_both_fonts() {
+    let mut bytes = vec![0x90; FONT_SIZE];
+    bytes.extend_from_slice(&[0x18; BIG_FONT_SIZE]);
+    let chip8 = Chip8::builder().font_bytes(&bytes).unwrap().build();
+    assert_eq!(chip8.memory[..FONT_SIZE + BIG_FONT_SIZE], bytes[..]);
+}
+
+#[test]
+fn rejects_fonts_of_the_wrong_size() {
+    let mut chip8 = Chip8::with_seed(0);
+    assert!(chip8.load_font(&[0xff; FONT_SIZE - 1]).is_err());
+    assert!(chip8.load_font(&[]).is_err());
+    // Nothing gets changed when the font is wrong
+    assert_eq!(chip8.memory[..5], FONT[0]);
+}
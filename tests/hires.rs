@@ -0,0 +1,93 @@
+//! Checks hi-res Chip-8, with it's 64x64 screen, the program starting after
+//! the code that set it up, and the font that gets moved
+
+mod common;
+
+use chip_8::{chip8::FONT, hires, Chip8};
+use common::{assemble, run_cycles};
+
+/// Loads the rom into a fresh interpreter, and turns on hi-res Chip-8
+fn start(rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.load(rom).unwrap();
+    chip8.enable_hires();
+    chip8
+}
+
+/// A hi-res rom, which is the jump at the start, the setup code that never
+/// runs, and then the program at `0x2c0`
+fn hires_rom(program: &[u16]) -> Vec<u8> {
+    let mut rom = assemble(&[0x1260]);
+    rom.resize(hires::PROGRAM_START - 0x200, 0);
+    rom.extend(assemble(program));
+    rom
+}
+
+#[test]
+fn roms_that_jump_to_260_are_hires() {
+    assert!(hires::is_hires_rom(&hires_rom(&[])));
+    assert!(!hires::is_hires_rom(&assemble(&[0x1202])));
+    assert!(!hires::is_hires_rom(&[]));
+}
+
+#[test]
+fn the_program_starts_after_the_setup_code() {
+    let chip8 = start(&hires_rom(&[0x12c0]));
+    assert!(chip8.is_hires());
+    assert_eq!(chip8.screen().size(), (64, 64));
+    assert_eq!(chip8.program_counter, 0x2c0);
+
+    // A rom that doesn't start with the jump is left where it is
+    let chip8 = start(&assemble(&[0x1200]));
+    assert_eq!(chip8.program_counter, 0x200);
+}
+
+#[test]
+fn draws_on_the_bottom_half_and_clears_it() {
+    let mut chip8 = start(&hires_rom(&[
+        0x6000, // ld v0, 0
+        0x6130, // ld v1, 48
+        0xf029, // ldf v0
+        0xd015, // drw v0, v1, 5
+        0x0230, // cls
+        0x12ca, // jp to itself
+    ]));
+    run_cycles(&mut chip8, 4);
+    // The top row of the 0 in the font is 4 pixels on
+    assert!((0..4).all(|x| chip8.screen().get_pixel(x, 48)));
+    #[cfg(feature = "alloc")]
+    assert_eq!(chip8.disassemble(0x2c8), "cls");
+
+    run_cycles(&mut chip8, 1);
+    assert_eq!(chip8.screen().lit_pixels().count(), 0);
+}
+
+#[test]
+fn the_font_is_moved_over_the_setup_code() {
+    let mut chip8 = start(&hires_rom(&[0x6107, 0xf129]));
+    run_cycles(&mut chip8, 2);
+    assert_eq!(chip8.font_address(), hires::FONT_ADDRESS);
+    assert_eq!(chip8.index, hires::FONT_ADDRESS + 7 * 5);
+    assert_eq!(chip8.memory[chip8.index..chip8.index + 5], FONT[7]);
+}
+
+// Font files are only read with the standard library
+#[cfg(feature = "std")]
+#[test]
+fn other_fonts_are_loaded_where_the_font_is_now() {
+    let mut chip8 = start(&hires_rom(&[]));
+    let mut font = [0xaa; 80];
+    font[..5].copy_from_slice(&[1, 2, 3, 4, 5]);
+    chip8.load_font(&font).unwrap();
+    assert_eq!(
+        chip8.memory[hires::FONT_ADDRESS..hires::FONT_ADDRESS + 5],
+        [1, 2, 3, 4, 5]
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cls_is_only_an_instruction_in_hires() {
+    let chip8 = common::run(&assemble(&[0x0230]), 0);
+    assert_eq!(chip8.disassemble(0x200), "nai 0230");
+}
@@ -0,0 +1,76 @@
+//! Checks that the JIT runs roms exactly the same as the interpreter does
+#![cfg(feature = "jit")]
+
+mod common;
+
+use chip_8::{jit::Jit, Chip8, Quirks};
+use common::{assemble, run_cycles, start};
+
+/// Runs `cycles` instructions of the rom with the JIT, in frames of 16 like
+/// `common::run_cycles`
+fn run_jit(rom: &[u8], quirks: Quirks, cycles: usize) -> Chip8 {
+    let mut chip8 = start(rom, quirks);
+    let mut jit = Jit::new().expect("cranelift supports this computer");
+    let mut remaining = cycles;
+    while remaining > 0 {
+        let frame = remaining.min(16);
+        jit.frame(&mut chip8, frame)
+            .unwrap_or_else(|error| panic!("the rom stopped running: {}", error));
+        remaining -= frame;
+    }
+    chip8
+}
+
+/// Runs the rom with both the interpreter and the JIT, and checks that they
+/// end up the same
+fn assert_same(rom: &[u8], quirks: Quirks, cycles: usize) -> Chip8 {
+    let mut interpreted = start(rom, quirks);
+    run_cycles(&mut interpreted, cycles);
+    let compiled = run_jit(rom, quirks, cycles);
+    assert_eq!(compiled.registers, interpreted.registers);
+    assert_eq!(compiled.index, interpreted.index);
+    assert_eq!(compiled.program_counter, interpreted.program_counter);
+    compiled
+}
+
+#[test]
+fn matches_the_interpreter() {
+    let rom = assemble(&[
+        0x60f0, // ld v0, 0xf0
+        0x7125, // add v1, 0x25
+        0x8014, // addy v0, v1
+        0x8215, // sub v2, v1
+        0x8317, // subn v3, v1
+        0x8f14, // addy vf, v1, which makes register f the result and the flag
+        0x84f0, // ldy v4, vf
+        0x8106, // shr v1
+        0x850e, // shl v5, v0
+        0x8f16, // shr vf, v1
+        0x8601, // or v6, v0
+        0x8712, // and v7, v1
+        0x8803, // xor v8, v0
+        0xa123, // ldi 0x123
+        0xa456, // ldi 0x456
+        0x1200, // jp to the start
+    ]);
+    // The quirks change what the shifts and the logic instructions do
+    for quirks in [Quirks::default(), Quirks::VIP, Quirks::SCHIP].iter() {
+        assert_same(&rom, *quirks, 16 * 20 + 5);
+    }
+}
+
+#[test]
+fn falls_back_for_self_modifying_code() {
+    let rom = assemble(&[
+        0x6105, // ld v1, 5
+        0x8214, // addy v2, v1
+        0x6061, // ld v0, 0x61
+        0x6109, // ld v1, 0x09
+        0xa200, // ldi 0x200
+        0xf155, // ldix v1, which writes `6109` over the first instruction
+        0x1200, // jp to the start
+    ]);
+    let chip8 = assert_same(&rom, Quirks::default(), 7 * 3);
+    // The first time adds 5, and then every other time adds 9
+    assert_eq!(chip8.registers[2], 5 + 9 + 9);
+}
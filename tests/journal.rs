@@ -0,0 +1,92 @@
+//! Checks that the instructions that ran can be undone one at a time
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use chip_8::Quirks;
+use common::{assemble, screen_to_text, start};
+
+#[test]
+fn undoes_registers_memory_and_the_screen() {
+    let rom = assemble(&[
+        0x60ea, // ld v0, 0xea
+        0xa300, // ldi 0x300
+        0xf033, // ldb v0
+        0xf029, // ldf v0
+        0xd005, // drw v0, v0, 5
+        0xc0ff, // rnd v0, 0xff
+    ]);
+    let mut chip8 = start(&rom, Quirks::SCHIP);
+    chip8.enable_journal(100);
+    let mut before = Vec::new();
+    for _ in 0..6 {
+        before.push((
+            chip8.registers,
+            chip8.index,
+            chip8.program_counter,
+            chip8.memory.clone(),
+            screen_to_text(&chip8),
+        ));
+        chip8.clock().unwrap();
+    }
+    assert_eq!(chip8.memory[0x300..0x303], [2, 3, 4]);
+    assert_eq!(chip8.journal_len(), 6);
+    let random = chip8.registers[0];
+
+    // Every instruction goes back to exactly how it was before it ran
+    for (registers, index, program_counter, memory, screen) in before.into_iter().rev() {
+        assert!(chip8.step_back());
+        assert_eq!(chip8.registers, registers);
+        assert_eq!(chip8.index, index);
+        assert_eq!(chip8.program_counter, program_counter);
+        assert_eq!(chip8.memory, memory);
+        assert_eq!(screen_to_text(&chip8), screen);
+    }
+    assert!(!chip8.step_back());
+
+    // Running it all again gives the same random number
+    for _ in 0..6 {
+        chip8.clock().unwrap();
+    }
+    assert_eq!(chip8.registers[0], random);
+}
+
+#[test]
+fn only_keeps_the_last_instructions() {
+    let rom = assemble(&[
+        0x7001, // add v0, 0x01
+        0x1200, // jp 0x200
+    ]);
+    let mut chip8 = start(&rom, Quirks::default());
+    chip8.enable_journal(3);
+    for _ in 0..10 {
+        chip8.clock().unwrap();
+    }
+    assert_eq!(chip8.journal_len(), 3);
+    while chip8.step_back() {}
+    // 5 adds ran, and the last of them is still kept
+    assert_eq!(chip8.registers[0], 4);
+}
+
+#[test]
+fn undoes_the_audio_pattern_and_pitch() {
+    let rom = assemble(&[
+        0x6060, // ld v0, 0x60
+        0xa200, // ldi 0x200, so the pattern is the start of the rom
+        0xf002, // audio
+        0xf03a, // ldpitch v0
+    ]);
+    let mut chip8 = start(&rom, Quirks::XOCHIP);
+    chip8.enable_journal(100);
+    for _ in 0..4 {
+        chip8.clock().unwrap();
+    }
+    assert!(chip8.audio_pattern().is_some());
+    assert_eq!(chip8.pitch(), 0x60);
+
+    assert!(chip8.step_back());
+    assert_ne!(chip8.pitch(), 0x60);
+    assert!(chip8.audio_pattern().is_some());
+    assert!(chip8.step_back());
+    assert_eq!(chip8.audio_pattern(), None);
+}
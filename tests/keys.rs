@@ -0,0 +1,19 @@
+//! Checks that the interpreter keeps track of which keys the rom checks, which
+//! is how the keypad shows the keys that a rom uses
+
+mod common;
+
+use common::{assemble, run};
+
+#[test]
+fn only_the_keys_that_get_checked_are_remembered() {
+    let rom = assemble(&[
+        0x6105, // v1 := 5
+        0xe19e, // skip if key v1 is down
+        0x620c, // v2 := 0xc
+        0xe2a1, // skip if key v2 is up
+        0xf30a, // wait for any key, which doesn't say which ones are used
+    ]);
+    let chip8 = run(&rom, 20);
+    assert_eq!(chip8.checked_keys(), 1 << 0x5 | 1 << 0xc);
+}
@@ -0,0 +1,44 @@
+//! Checks what gets found out about roms while they are loaded
+
+mod common;
+
+use chip_8::Chip8;
+#[cfg(feature = "alloc")]
+use chip_8::LoadInfo;
+use common::assemble;
+
+// Following the code needs a heap
+#[cfg(feature = "alloc")]
+#[test]
+fn finds_the_data_after_the_code() {
+    let mut rom = assemble(&[
+        0x2206, // call 0x206
+        0x1202, // jp to itself
+        0x0000, // padding
+        0xa20e, // ldi 0x20e
+        0x3000, // se v0, 0x00
+        0xd015, // drw v0, v1, 5
+        0x00ee, // ret
+    ]);
+    // A sprite, which is never ran
+    rom.extend_from_slice(&[0xf0, 0x90, 0xf0, 0x90, 0xf0]);
+
+    let mut chip8 = Chip8::with_seed(0);
+    assert_eq!(
+        chip8.load(&rom),
+        Ok(LoadInfo {
+            size: 19,
+            data_start: Some(0x20e),
+        })
+    );
+}
+
+#[test]
+fn all_code_has_no_data() {
+    let rom = assemble(&[
+        0x6005, // ld v0, 0x05
+        0x1200, // jp 0x200
+    ]);
+    let info = Chip8::with_seed(0).load(&rom).unwrap();
+    assert_eq!(info.data_start, None);
+}
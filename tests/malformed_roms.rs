@@ -0,0 +1,145 @@
+//! Roms that used to make the interpreter panic, these are allowed to stop with
+//! an error but not to crash, the fuzzer in `fuzz` looks for more of them
+
+mod common;
+
+#[cfg(feature = "alloc")]
+use chip_8::CallFrame;
+use chip_8::{Chip8, Chip8Error, LoadError};
+use common::{assemble, start};
+
+/// Runs the rom until it stops with an error, or for `cycles` instructions
+fn run_until_error(chip8: &mut Chip8, cycles: usize) {
+    for _ in 0..cycles {
+        if chip8.clock().is_err() {
+            break;
+        }
+    }
+}
+
+#[test]
+fn jump_to_address_zero() {
+    let mut chip8 = start(&assemble(&[0x1000]), Default::default());
+    chip8.clock().unwrap();
+    assert_eq!(chip8.program_counter, 0);
+    run_until_error(&mut chip8, 100);
+}
+
+#[test]
+fn call_and_jump_with_register_below_two() {
+    for opcode in [0x2001, 0xb000].iter() {
+        let mut chip8 = start(&assemble(&[*opcode]), Default::default());
+        run_until_error(&mut chip8, 100);
+    }
+}
+
+#[test]
+fn wait_for_key_at_address_zero() {
+    let mut chip8 = start(&assemble(&[0x1000]), Default::default());
+    // fx0a waits by running itself again
+    chip8.memory[0] = 0xf0;
+    chip8.memory[1] = 0x0a;
+    run_until_error(&mut chip8, 100);
+    assert_eq!(chip8.program_counter, 0);
+}
+
+#[test]
+fn draw_past_the_bottom_of_the_registers() {
+    let opcodes = [
+        0x61ff, // ld v1, 0xff
+        0xd11f, // drw v1, v1, 15, the rows go past 0xff
+    ];
+    let mut chip8 = start(&assemble(&opcodes), Default::default());
+    run_until_error(&mut chip8, opcodes.len());
+}
+
+#[test]
+fn rom_bigger_than_memory() {
+    let mut chip8 = Chip8::new();
+    assert_eq!(
+        chip8.load(&[0x12; 0x2000]),
+        Err(LoadError::TooBig {
+            size: 0x2000,
+            room: chip8.memory.len() - 0x200,
+            load_address: 0x200
+        })
+    );
+    // None of it gets loaded
+    assert_eq!(chip8.memory[0x200], 0);
+}
+
+#[test]
+fn empty_rom() {
+    assert_eq!(Chip8::new().load(&[]), Err(LoadError::Empty));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn relative_instructions_outside_of_memory() {
+    let mut chip8 = Chip8::new();
+    chip8.program_counter = 0;
+    assert_eq!(chip8.get_relative_instruction(-1), None);
+    chip8.program_counter = chip8.memory.len() - 1;
+    assert_eq!(chip8.get_relative_instruction(0), None);
+    assert_eq!(chip8.get_relative_instruction(i32::MIN), None);
+    assert_eq!(chip8.get_relative_instruction(i32::MAX), None);
+    chip8.program_counter = usize::MAX;
+    assert_eq!(chip8.get_relative_instruction(1), None);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn relative_instructions_inside_of_memory() {
+    let mut chip8 = start(&[0x61, 0x05, 0xd1, 0x25], Default::default());
+    chip8.program_counter = 0x202;
+    let instruction = chip8.get_relative_instruction(-1).unwrap();
+    assert_eq!(instruction.address, 0x200);
+    assert_eq!(instruction.opcode, 0x6105);
+    assert_eq!(instruction.mnemonic, "ld");
+    assert_eq!(instruction.operands, "v1, 0x05");
+    assert_eq!(
+        chip8.get_relative_instruction(0).unwrap().to_string(),
+        "drw v1, v2, 5"
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn calls_that_fill_the_stack_say_how_it_got_there() {
+    let opcodes = [
+        0x2204, // call 0x204
+        0x0000, // never reached
+        0x2204, // call 0x204, which calls itself until the stack is full
+    ];
+    let mut chip8 = start(&assemble(&opcodes), Default::default());
+    let error = loop {
+        if let Err(error) = chip8.clock() {
+            break error;
+        }
+    };
+    let mut calls = vec![
+        CallFrame {
+            call_site: 0x204,
+            subroutine: 0x204,
+        };
+        15
+    ];
+    calls.push(CallFrame {
+        call_site: 0x200,
+        subroutine: 0x204,
+    });
+    // Every level of the stack was used before it was full
+    assert_eq!(calls.len(), 16);
+    assert_eq!(error, Chip8Error::StackOverflow { pc: 0x204, calls });
+    // The calls are written out oldest first
+    assert!(error
+        .to_string()
+        .contains("the stack is full, after calls at 0x200, 0x204, 0x204,"));
+}
+
+#[test]
+fn returning_without_a_call() {
+    let mut chip8 = start(&assemble(&[0x00ee]), Default::default());
+    assert_eq!(chip8.clock(), Err(Chip8Error::StackUnderflow { pc: 0x200 }));
+    assert_eq!(chip8.stack_pointer, 0);
+}
@@ -0,0 +1,90 @@
+//! Checks the MEGA-CHIP instructions, which only exist once it has been turned
+//! on in the interpreter
+#![cfg(feature = "megachip")]
+
+mod common;
+
+use chip_8::{megachip::SoundChange, Chip8};
+use common::{assemble, run_cycles};
+
+/// Loads the rom into a fresh interpreter with MEGA-CHIP turned on
+fn start(rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.enable_megachip();
+    chip8.load(rom).unwrap();
+    chip8
+}
+
+#[test]
+fn draws_sprites_in_palette_colors() {
+    let rom = assemble(&[
+        0x0011, // megaon
+        0x0100, 0x0220, // ldhi to the palette
+        0x0201, // ldpal with 1 color
+        0x0100, 0x0224, // ldhi to the sprite
+        0x0302, // sprw 2
+        0x0401, // sprh 1
+        0x6003, // ld v0, 3
+        0x6104, // ld v1, 4
+        0x0901, // ccol 1
+        0xd010, // drw v0, v1
+        0xd010, // drw v0, v1
+        0x121a, // jp to itself
+        0x0000, //
+        0x0000, //
+        0xff12, 0x3456, // the color, as ARGB
+        0x0100, // the sprite, color 1 and then nothing
+    ]);
+
+    let mut chip8 = start(&rom);
+    run_cycles(&mut chip8, 13);
+    assert_eq!(chip8.screen().size(), (256, 192));
+    let megachip = chip8.megachip().expect("MEGA-CHIP was turned on");
+    assert!(megachip.is_on());
+    assert_eq!(megachip.color(3, 4), [0x12, 0x34, 0x56]);
+    // Color 0 is see through, so nothing is drawn next to it
+    assert_eq!(megachip.color(4, 4), [0, 0, 0]);
+    assert!(chip8.screen().get_pixel(3, 4));
+    assert!(!chip8.screen().get_pixel(4, 4));
+    // The second sprite is drawn over color 1, which is the collision color
+    assert_eq!(chip8.registers[0xf], 1);
+}
+
+#[test]
+fn plays_digitized_sound() {
+    let rom = assemble(&[
+        0x0100, 0x020a, // ldhi to the sound
+        0x0601, // digisnd without looping
+        0x1206, // jp to itself
+        0x0000, //
+        0x1f40, // 8000 samples a second
+        0x0000, 0x0300, // 3 samples, and the skipped byte
+        0x8090, 0xa000, // the samples
+    ]);
+
+    let mut chip8 = start(&rom);
+    run_cycles(&mut chip8, 2);
+    let megachip = chip8.megachip_mut().expect("MEGA-CHIP was turned on");
+    match megachip.take_sound_change() {
+        Some(SoundChange::Play(sound)) => {
+            assert_eq!(sound.rate, 8000);
+            assert_eq!(sound.samples, [0x80, 0x90, 0xa0]);
+            assert!(!sound.looping);
+        }
+        _ => panic!("the sound didn't start playing"),
+    }
+    assert!(megachip.take_sound_change().is_none());
+}
+
+#[test]
+fn the_index_goes_past_the_4k() {
+    let rom = assemble(&[
+        0x0011, // megaon
+        0x0101, 0x2345, // ldhi 0x012345
+        0x6001, // ld v0, 1
+        0xf01e, // add i, v0
+    ]);
+    let mut chip8 = start(&rom);
+    run_cycles(&mut chip8, 4);
+    assert_eq!(chip8.index, 0x12346);
+}
@@ -0,0 +1,169 @@
+//! Checks that Octo source gets assembled into the same bytes that Octo would
+//! make, and that it runs the way it reads
+#![cfg(feature = "std")]
+
+mod common;
+
+use chip_8::{
+    octo::{assemble, assemble_with_symbols},
+    AssembleError, Quirks,
+};
+use common::{run_cycles, start};
+
+#[test]
+fn labels_constants_and_aliases() {
+    let rom = assemble(
+        "
+        :const speed 3
+        :alias x v4
+        :calc double { speed * 2 }
+        : main
+          x := speed
+          x += double
+          i := sprite
+          jump main
+        : sprite
+          :byte 0b11110000 :byte { double + 1 }
+        ",
+    )
+    .unwrap();
+    assert_eq!(
+        rom,
+        [
+            0x12, 0x02, // jump main
+            0x64, 0x03, // x := speed
+            0x74, 0x06, // x += double
+            0xa2, 0x0a, // i := sprite, which comes after it
+            0x12, 0x02, // jump main
+            0xf0, 0x07,
+        ]
+    );
+}
+
+#[test]
+fn calc_goes_from_right_to_left() {
+    let rom = assemble(
+        "
+        :calc a { 2 * 3 + 1 }
+        :calc b { ( 2 * 3 ) + 1 }
+        : main :byte a :byte b
+        ",
+    )
+    .unwrap();
+    assert_eq!(rom[2..], [8, 7]);
+}
+
+#[test]
+fn control_flow_runs_the_way_it_reads() {
+    let rom = assemble(
+        "
+        : main
+          v0 := 0
+          v1 := 0
+          loop
+            v0 += 1
+            if v0 != 5 begin
+              v1 += 1
+            else
+              v1 += 10
+            end
+            while v0 != 7
+          again
+          if v0 == 7 then v2 := 1
+          if v0 == v1 then v3 := 1
+          v5 := 8
+          v5 -= 3
+        : done
+          jump done
+        ",
+    )
+    .unwrap();
+    let mut chip8 = start(&rom, Quirks::default());
+    run_cycles(&mut chip8, 200);
+    // 6 times through with `!= 5` true, and once with it false
+    assert_eq!(chip8.registers[..2], [7, 16]);
+    assert_eq!(chip8.registers[2..6], [1, 0, 0, 5]);
+}
+
+#[test]
+fn comparisons_use_register_f() {
+    let rom = assemble(": main if v1 < 5 then v2 := 1 if v1 >= v3 begin end").unwrap();
+    assert_eq!(
+        rom[2..],
+        [
+            0x6f, 0x05, 0x8f, 0x17, 0x4f, 0x00, // vf := 5, vf =- v1, skip if vf != 0
+            0x62, 0x01, // v2 := 1
+            0x8f, 0x30, 0x8f, 0x17, 0x3f, 0x01, // vf := v3, vf =- v1, skip if vf == 1
+            0x12, 0x12, // the jump over the empty begin
+        ]
+    );
+}
+
+#[test]
+fn macros_unpack_and_next() {
+    let rom = assemble(
+        "
+        :macro set register value { register := value }
+        : main
+          set v3 0x12
+          :unpack 0xa data
+          :next target
+          v6 := 0
+        : data
+        ",
+    )
+    .unwrap();
+    assert_eq!(
+        rom,
+        [
+            0x12, 0x02, // jump main
+            0x63, 0x12, // set v3 0x12
+            0x60, 0xa2, 0x61, 0x0a, // :unpack 0xa data
+            0x66, 0x00, // v6 := 0, with target on the 0
+        ]
+    );
+    let rom = assemble(": main :next target v6 := 0 i := target").unwrap();
+    assert_eq!(rom[4..], [0xa2, 0x03]);
+}
+
+#[test]
+fn errors_say_which_line() {
+    assert_eq!(
+        assemble("v0 := 1"),
+        Err(AssembleError {
+            line: 1,
+            message: String::from("there isn't a `: main`, which is where it starts"),
+        })
+    );
+    assert_eq!(
+        assemble(": main\n  v0 := 1\n  jump nowhere"),
+        Err(AssembleError {
+            line: 3,
+            message: String::from("there isn't a label called nowhere"),
+        })
+    );
+    assert_eq!(
+        assemble(": main\n  v0 := 300"),
+        Err(AssembleError {
+            line: 2,
+            message: String::from("300 doesn't fit in a byte"),
+        })
+    );
+}
+
+#[test]
+fn the_labels_come_out_as_symbols() {
+    let (rom, symbols) = assemble_with_symbols(
+        ": main
+           draw_ship
+         : forever
+           jump forever
+         : draw_ship
+           return",
+    )
+    .unwrap();
+    assert_eq!(rom.len(), 8);
+    assert_eq!(symbols.address("main"), Some(0x202));
+    assert_eq!(symbols.name(0x204), Some("forever"));
+    assert_eq!(symbols.name(0x206), Some("draw_ship"));
+}
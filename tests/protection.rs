@@ -0,0 +1,50 @@
+//! Checks that roms can't write over the fonts while the memory is protected
+
+mod common;
+
+use chip_8::{protection::WriteProtection, Chip8Error, Quirks};
+use common::{assemble, start};
+
+/// Points the index at the font, and stores registers 0 and 1 there, then
+/// stores the digits of register 0 right before `0x200`
+fn rom() -> Vec<u8> {
+    assemble(&[
+        0x6007, // ld v0, 7
+        0x6108, // ld v1, 8
+        0xa000, // ldi 0x000
+        0xf155, // ldix v1
+        0xa1ff, // ldi 0x1ff
+        0xf033, // ldb v0
+    ])
+}
+
+#[test]
+fn ignored_writes_leave_the_font_alone() {
+    let mut chip8 = start(&rom(), Quirks::default());
+    let font = chip8.memory[..0x200].to_vec();
+    chip8.set_write_protection(WriteProtection::Ignore);
+    for _ in 0..6 {
+        chip8.clock().unwrap();
+    }
+    assert_eq!(chip8.memory[..0x200], font[..]);
+    // Only the bytes that aren't protected get written
+    assert_eq!(chip8.memory[0x200..0x202], [0, 7]);
+}
+
+#[test]
+fn trapped_writes_stop_the_rom() {
+    let mut chip8 = start(&rom(), Quirks::default());
+    let font = chip8.memory[..0x200].to_vec();
+    chip8.set_write_protection(WriteProtection::Trap);
+    for _ in 0..3 {
+        chip8.clock().unwrap();
+    }
+    assert_eq!(
+        chip8.clock(),
+        Err(Chip8Error::ProtectedWrite {
+            pc: 0x206,
+            address: 0
+        })
+    );
+    assert_eq!(chip8.memory[..0x200], font[..]);
+}
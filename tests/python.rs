@@ -0,0 +1,85 @@
+//! Checks the Python module, by running Python against it in the same process
+#![cfg(feature = "python")]
+
+mod common;
+
+use common::assemble;
+use pyo3::{prelude::*, types::PyDict};
+use std::ffi::CString;
+
+/// Runs the Python code with `chip_8` imported, and `rom` set to the bytes of
+/// the rom, any assert in it that fails fails the test
+fn run_python(rom: &[u8], code: &str) {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let module = PyModule::new(py, "chip_8").unwrap();
+        chip_8::python::chip_8(&module).unwrap();
+        let globals = PyDict::new(py);
+        globals.set_item("chip_8", module).unwrap();
+        globals.set_item("rom", rom).unwrap();
+        let code = CString::new(code).unwrap();
+        if let Err(error) = py.run(&code, Some(&globals), None) {
+            panic!("{}", error);
+        }
+    });
+}
+
+#[test]
+fn runs_a_rom_and_reads_the_screen() {
+    let rom = assemble(&[
+        0x6005, // ld v0, 5
+        0xf029, // ldf v0
+        0xd015, // drw v0, v1, 5
+        0xe09e, // skp v0
+        0x1206, // jp back to the skp
+    ]);
+    run_python(
+        &rom,
+        r#"
+chip8 = chip_8.Chip8(seed=0)
+chip8.load(bytes(rom))
+for _ in range(3):
+    chip8.step()
+assert chip8.registers[0] == 5
+assert chip8.index == 5 * 5
+assert (chip8.width, chip8.height) == (64, 32)
+
+# The top row of the 5 in the font is 4 pixels, starting at 5
+screen = chip8.screen()
+assert len(screen) == 64 * 32
+assert list(screen[:10]) == [0] * 5 + [1] * 4 + [0]
+assert chip8.pixel(5, 0) and not chip8.pixel(9, 0)
+
+# It waits on the skip until key 5 is pressed
+chip8.frame(2)
+assert chip8.program_counter == 0x206
+chip8.press_key(5)
+chip8.step()
+assert chip8.program_counter == 0x20a
+assert chip8.disassemble(0x200) == "ld v0, 0x05"
+"#,
+    );
+}
+
+#[test]
+fn errors_are_raised_as_exceptions() {
+    run_python(
+        &assemble(&[0x00ee]),
+        r#"
+chip8 = chip_8.Chip8()
+try:
+    chip8.load(bytes(0x1000))
+    assert False, "a rom that doesn't fit loaded"
+except ValueError:
+    pass
+
+chip8.load(bytes(rom))
+try:
+    chip8.step()
+    assert False, "returned without a call"
+except RuntimeError:
+    pass
+assert chip8.memory()[0x200:0x202] == b"\x00\xee"
+"#,
+    );
+}
@@ -0,0 +1,255 @@
+//! A second Chip-8 interpreter that is written separately from the real one,
+//! as simply as it can be, for the differential tests to check the real one
+//! against. It only knows the original 35 instructions, with the quirks that
+//! `Quirks::default()` has, and none of the extensions.
+//!
+//! Where Chip-8 itself doesn't say what happens, like how deep the stack goes
+//! or how big memory is, it does what the real interpreter does by default, so
+//! that only the differences that matter show up.
+
+// Every test file gets it's own copy of this module, and not all of them use
+// every part of it
+#![allow(dead_code)]
+
+/// How many bytes of memory there are, the same as the real interpreter
+pub const MEMORY_SIZE: usize = 0x1000;
+/// How many calls can be nested
+pub const STACK_DEPTH: usize = 16;
+/// Where roms get loaded, and where nothing below can be ran
+pub const PROGRAM_START: usize = 0x200;
+/// The size of the screen
+pub const WIDTH: usize = 64;
+pub const HEIGHT: usize = 32;
+
+/// The 4x5 font for the digits 0 through f, which goes at the start of memory
+const FONT: [u8; 80] = [
+    0xf0, 0x90, 0x90, 0x90, 0xf0, 0x20, 0x60, 0x20, 0x20, 0x70, 0xf0, 0x10, 0xf0, 0x80, 0xf0, 0xf0,
+    0x10, 0xf0, 0x10, 0xf0, 0x90, 0x90, 0xf0, 0x10, 0x10, 0xf0, 0x80, 0xf0, 0x10, 0xf0, 0xf0, 0x80,
+    0xf0, 0x90, 0xf0, 0xf0, 0x10, 0x20, 0x40, 0x40, 0xf0, 0x90, 0xf0, 0x90, 0xf0, 0xf0, 0x90, 0xf0,
+    0x10, 0xf0, 0xf0, 0x90, 0xf0, 0x90, 0x90, 0xe0, 0x90, 0xe0, 0x90, 0xe0, 0xf0, 0x80, 0x80, 0x80,
+    0xf0, 0xe0, 0x90, 0x90, 0x90, 0xe0, 0xf0, 0x80, 0xf0, 0x80, 0xf0, 0xf0, 0x80, 0xf0, 0x80, 0x80,
+];
+
+/// Why the reference stopped running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stop {
+    /// The opcode isn't one of the original instructions, so there is nothing
+    /// to compare against from here on
+    Unsupported(u16),
+    /// The rom did something that can't be done, like returning without a call
+    Fault(&'static str),
+}
+
+/// A tiny random number generator, the differential tests give the real
+/// interpreter a copy of it so that `cxnn` gets the same numbers in both
+pub fn next_random(state: &mut u32) -> u8 {
+    *state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+    (*state >> 16) as u8
+}
+
+/// Everything that Chip-8 has
+pub struct Reference {
+    pub v: [u8; 16],
+    pub i: usize,
+    pub pc: usize,
+    /// The addresses that `00ee` returns to, the last one is the top
+    pub stack: Vec<usize>,
+    pub delay: u8,
+    pub sound: u8,
+    pub memory: Vec<u8>,
+    /// The screen, row by row
+    pub screen: [[bool; WIDTH]; HEIGHT],
+    pub keys: [bool; 16],
+    random: u32,
+}
+
+impl Reference {
+    /// Loads the rom, with the random numbers starting from `seed`
+    pub fn new(rom: &[u8], seed: u32) -> Reference {
+        let mut memory = vec![0; MEMORY_SIZE];
+        memory[..FONT.len()].copy_from_slice(&FONT);
+        memory[PROGRAM_START..PROGRAM_START + rom.len()].copy_from_slice(rom);
+        Reference {
+            v: [0; 16],
+            i: 0,
+            pc: PROGRAM_START,
+            stack: Vec::new(),
+            delay: 0,
+            sound: 0,
+            memory,
+            screen: [[false; WIDTH]; HEIGHT],
+            keys: [false; 16],
+            random: seed,
+        }
+    }
+
+    /// Counts the timers down, 60 times a second
+    pub fn tick(&mut self) {
+        self.delay = self.delay.saturating_sub(1);
+        self.sound = self.sound.saturating_sub(1);
+    }
+
+    /// Reads the byte at the address, or stops if it isn't in memory
+    fn read(&self, address: usize) -> Result<u8, Stop> {
+        self.memory
+            .get(address)
+            .copied()
+            .ok_or(Stop::Fault("read past the end of memory"))
+    }
+
+    /// Writes the byte to the address, or stops if it isn't in memory
+    fn write(&mut self, address: usize, value: u8) -> Result<(), Stop> {
+        match self.memory.get_mut(address) {
+            Some(byte) => {
+                *byte = value;
+                Ok(())
+            }
+            None => Err(Stop::Fault("wrote past the end of memory")),
+        }
+    }
+
+    /// Runs one instruction
+    pub fn step(&mut self) -> Result<(), Stop> {
+        if self.pc < PROGRAM_START || self.pc + 1 >= MEMORY_SIZE {
+            return Err(Stop::Fault("ran outside of the program"));
+        }
+        let opcode = (self.memory[self.pc] as u16) << 8 | self.memory[self.pc + 1] as u16;
+        let x = (opcode >> 8 & 0xf) as usize;
+        let y = (opcode >> 4 & 0xf) as usize;
+        let n = (opcode & 0xf) as usize;
+        let nn = (opcode & 0xff) as u8;
+        let nnn = (opcode & 0xfff) as usize;
+        let next = self.pc + 2;
+        // Where to go after this one, which is the next instruction unless it
+        // jumps or skips
+        let mut pc = next;
+        let skip = next + 2;
+
+        match opcode >> 12 {
+            0x0 if opcode == 0x00e0 => self.screen = [[false; WIDTH]; HEIGHT],
+            0x0 if opcode == 0x00ee => {
+                pc = self
+                    .stack
+                    .pop()
+                    .ok_or(Stop::Fault("returned without a call"))?
+            }
+            0x1 => pc = nnn,
+            0x2 => {
+                if self.stack.len() == STACK_DEPTH {
+                    return Err(Stop::Fault("called too deep"));
+                }
+                self.stack.push(next);
+                pc = nnn;
+            }
+            0x3 if self.v[x] == nn => pc = skip,
+            0x4 if self.v[x] != nn => pc = skip,
+            0x5 if n == 0 && self.v[x] == self.v[y] => pc = skip,
+            0x3 | 0x4 => {}
+            0x5 if n == 0 => {}
+            0x6 => self.v[x] = nn,
+            0x7 => self.v[x] = self.v[x].wrapping_add(nn),
+            0x8 => {
+                let (a, b) = (self.v[x], self.v[y]);
+                let (result, flag) = match n {
+                    0x0 => (b, None),
+                    0x1 => (a | b, None),
+                    0x2 => (a & b, None),
+                    0x3 => (a ^ b, None),
+                    0x4 => (a.wrapping_add(b), Some((a as u16 + b as u16 > 0xff) as u8)),
+                    0x5 => (a.wrapping_sub(b), Some((a >= b) as u8)),
+                    0x6 => (b >> 1, Some(b & 1)),
+                    0x7 => (b.wrapping_sub(a), Some((b >= a) as u8)),
+                    0xe => (b << 1, Some(b >> 7)),
+                    _ => return Err(Stop::Unsupported(opcode)),
+                };
+                self.v[x] = result;
+                if let Some(flag) = flag {
+                    self.v[0xf] = flag;
+                }
+            }
+            0x9 if n == 0 && self.v[x] != self.v[y] => pc = skip,
+            0x9 if n == 0 => {}
+            0xa => self.i = nnn,
+            0xb => pc = nnn + self.v[0] as usize,
+            0xc => self.v[x] = next_random(&mut self.random) & nn,
+            // A height of 0 is SUPER-CHIP's 16x16 sprite
+            0xd if n == 0 => return Err(Stop::Unsupported(opcode)),
+            0xd => {
+                let (left, top) = (self.v[x] as usize % WIDTH, self.v[y] as usize % HEIGHT);
+                let mut collided = 0;
+                for row in 0..n {
+                    let sprite = self.read(self.i + row)?;
+                    for column in 0..8 {
+                        let (px, py) = (left + column, top + row);
+                        // Anything past the edges is cut off
+                        if px >= WIDTH || py >= HEIGHT || sprite & 0x80 >> column == 0 {
+                            continue;
+                        }
+                        if self.screen[py][px] {
+                            collided = 1;
+                        }
+                        self.screen[py][px] = !self.screen[py][px];
+                    }
+                }
+                self.v[0xf] = collided;
+            }
+            0xe if nn == 0x9e || nn == 0xa1 => {
+                let held = *self
+                    .keys
+                    .get(self.v[x] as usize)
+                    .ok_or(Stop::Fault("checked a key that isn't on the keypad"))?;
+                // `ex9e` skips when it is held, and `exa1` when it isn't
+                if held == (nn == 0x9e) {
+                    pc = skip;
+                }
+            }
+            0xf => match nn {
+                0x07 => self.v[x] = self.delay,
+                // Waits on this instruction until a key is held down, and
+                // takes the lowest one that is
+                0x0a => match self.keys.iter().position(|held| *held) {
+                    Some(key) => self.v[x] = key as u8,
+                    None => pc = self.pc,
+                },
+                0x15 => self.delay = self.v[x],
+                0x18 => self.sound = self.v[x],
+                0x1e => {
+                    if self.i + self.v[x] as usize >= MEMORY_SIZE {
+                        return Err(Stop::Fault("moved the index past the end of memory"));
+                    }
+                    self.i += self.v[x] as usize
+                }
+                0x29 => self.i = self.v[x] as usize * 5,
+                0x33 => {
+                    if self.i + 3 > MEMORY_SIZE {
+                        return Err(Stop::Fault("wrote past the end of memory"));
+                    }
+                    let value = self.v[x];
+                    self.write(self.i, value / 100)?;
+                    self.write(self.i + 1, value / 10 % 10)?;
+                    self.write(self.i + 2, value % 10)?;
+                }
+                0x55 => {
+                    if self.i + x + 1 > MEMORY_SIZE {
+                        return Err(Stop::Fault("wrote past the end of memory"));
+                    }
+                    for register in 0..=x {
+                        self.write(self.i + register, self.v[register])?;
+                    }
+                }
+                0x65 => {
+                    if self.i + x + 1 > MEMORY_SIZE {
+                        return Err(Stop::Fault("read past the end of memory"));
+                    }
+                    for register in 0..=x {
+                        self.v[register] = self.read(self.i + register)?;
+                    }
+                }
+                _ => return Err(Stop::Unsupported(opcode)),
+            },
+            _ => return Err(Stop::Unsupported(opcode)),
+        }
+        self.pc = pc;
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+//! Checks how the scheduler splits time up into frames
+#![cfg(feature = "std")]
+
+use chip_8::{
+    frontend::{Audio, Display, Event, Input, Scheduler, MAX_BACKLOG_FRAMES},
+    Chip8,
+};
+use std::{io::Error, time::Duration};
+
+/// A frontend that doesn't do anything
+struct Headless;
+
+impl Input for Headless {
+    fn handle_input(&mut self, _chip8: &mut Chip8) -> Option<Event> {
+        None
+    }
+}
+
+impl Display for Headless {
+    fn draw(&mut self, _chip8: &Chip8) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Audio for Headless {
+    fn set_playing(&mut self, _playing: bool) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn waits_for_the_rest_of_the_frame() {
+    // `1200` jumps to itself forever
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.load(&[0x12, 0x00]).unwrap();
+    let mut scheduler = Scheduler::new();
+    assert_eq!(scheduler.until_next_frame(), Duration::new(0, 16_666_667));
+
+    // One frame runs, and the time left over counts towards the next one
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::from_millis(20))
+        .unwrap();
+    assert_eq!(scheduler.until_next_frame(), Duration::new(0, 13_333_334));
+
+    // Exactly enough for the next frame leaves a whole frame to wait
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::new(0, 13_333_334))
+        .unwrap();
+    assert_eq!(scheduler.until_next_frame(), Duration::new(0, 16_666_667));
+}
+
+#[test]
+fn a_paused_rom_doesnt_run() {
+    // `7001` adds 1 to register 0, `1200` jumps back to it
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.load(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    chip8.delay = 10;
+    chip8.set_paused(true);
+    let mut scheduler = Scheduler::new();
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(chip8.registers[0], 0);
+    assert_eq!(chip8.delay, 10);
+
+    // The time that passed while it was paused doesn't get made up for, only
+    // the next frame runs once it is resumed
+    chip8.set_paused(false);
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::new(0, 16_666_667))
+        .unwrap();
+    assert_eq!(chip8.registers[0], 8);
+    assert_eq!(chip8.delay, 9);
+}
+
+#[test]
+fn vip_timing_runs_as_fast_as_the_vip_did() {
+    // `7001` adds 1 to register 0, `1200` jumps back to it, which takes 150
+    // microseconds each time around
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.load(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    let mut scheduler = Scheduler::with_vip_timing();
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::new(0, 16_666_667))
+        .unwrap();
+    assert_eq!(chip8.registers[0], 112);
+
+    // `d005` drawing in between takes a lot longer, and the frame that goes
+    // over takes the time away from the next one
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.load(&[0xd0, 0x05, 0x70, 0x01, 0x12, 0x00]).unwrap();
+    let mut scheduler = Scheduler::with_vip_timing();
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::new(0, 16_666_667))
+        .unwrap();
+    assert_eq!(chip8.registers[0], 2);
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::new(0, 16_666_667))
+        .unwrap();
+    assert_eq!(chip8.registers[0], 4);
+}
+
+/// A frontend that counts how many times it draws
+struct CountDraws(usize);
+
+impl Input for CountDraws {
+    fn handle_input(&mut self, _chip8: &mut Chip8) -> Option<Event> {
+        None
+    }
+}
+
+impl Display for CountDraws {
+    fn draw(&mut self, _chip8: &Chip8) -> Result<(), Error> {
+        self.0 += 1;
+        Ok(())
+    }
+}
+
+impl Audio for CountDraws {
+    fn set_playing(&mut self, _playing: bool) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn the_speed_changes_how_many_frames_run() {
+    // The delay timer counts the frames down, `1200` jumps to itself forever
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.load(&[0x12, 0x00]).unwrap();
+    chip8.delay = 10;
+    let mut scheduler = Scheduler::new();
+    chip8.set_speed(2.0);
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::from_millis(20))
+        .unwrap();
+    assert_eq!(chip8.delay, 8);
+
+    chip8.set_speed(0.5);
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::from_millis(40))
+        .unwrap();
+    assert_eq!(chip8.delay, 7);
+}
+
+#[test]
+fn fast_forwarding_only_draws_the_last_frame() {
+    // `d005` draws every frame, `1200` jumps back to it
+    let rom = [0xd0, 0x05, 0x12, 0x00];
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.load(&rom).unwrap();
+    chip8.set_speed(8.0);
+    let mut frontend = CountDraws(0);
+    Scheduler::new()
+        .advance(&mut chip8, &mut frontend, Duration::new(0, 16_666_667))
+        .unwrap();
+    assert_eq!(frontend.0, 1);
+
+    // Catching up at normal speed still draws every frame
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.load(&rom).unwrap();
+    let mut frontend = CountDraws(0);
+    Scheduler::new()
+        .advance(&mut chip8, &mut frontend, Duration::new(0, 16_666_667 * 8))
+        .unwrap();
+    assert_eq!(frontend.0, 8);
+}
+
+#[test]
+fn frame_advance_runs_one_whole_frame() {
+    // `7001` adds 1 to register 0, `1200` jumps back to it
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.load(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    chip8.delay = 10;
+    chip8.set_paused(true);
+    chip8.step_frame();
+    let mut scheduler = Scheduler::new();
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(chip8.registers[0], 8);
+    assert_eq!(chip8.delay, 9);
+    assert!(chip8.is_paused());
+
+    // It stays paused until the next one
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(chip8.registers[0], 8);
+    chip8.step_frame();
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::new(0, 16_666_667))
+        .unwrap();
+    assert_eq!(chip8.registers[0], 17);
+    assert_eq!(chip8.delay, 8);
+}
+
+#[test]
+fn a_stall_only_catches_up_a_few_frames() {
+    // The delay timer counts the frames down, `1200` jumps to itself forever
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.load(&[0x12, 0x00]).unwrap();
+    chip8.delay = 200;
+    let mut scheduler = Scheduler::new();
+    scheduler
+        .advance(&mut chip8, &mut Headless, Duration::from_secs(60))
+        .unwrap();
+    assert_eq!(chip8.delay, 200 - MAX_BACKLOG_FRAMES as u8);
+    // The rest of the time is dropped, not kept for later
+    assert_eq!(scheduler.until_next_frame(), Duration::new(0, 16_666_667));
+}
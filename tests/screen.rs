@@ -0,0 +1,61 @@
+//! Checks the screen's pixels line up with each other no matter how they are
+//! asked for
+
+use chip_8::{
+    screen::{MAX_BYTES, MAX_WIDTH},
+    Screen,
+};
+
+#[test]
+fn set_and_get_pixels() {
+    let mut screen = Screen::new(64, 32).unwrap();
+    screen.set_pixel(9, 3, true);
+    screen.set_pixel(63, 31, true);
+    // Pixels off of the screen are ignored
+    screen.set_pixel(64, 0, true);
+
+    assert!(screen.get_pixel(9, 3));
+    assert!(screen.get_pixel(63, 31));
+    assert!(!screen.get_pixel(8, 3));
+    assert!(!screen.get_pixel(64, 0));
+    assert_eq!(screen.lit_pixels().collect::<Vec<_>>(), [(9, 3), (63, 31)]);
+    // The second byte of the fourth row, with the pixel second from the left
+    assert_eq!(screen.as_bytes()[3 * 8 + 1], 0b01000000);
+}
+
+#[test]
+fn toggling_reports_collisions() {
+    let mut screen = Screen::new(64, 32).unwrap();
+    assert!(!screen.toggle_pixel(5, 5));
+    assert!(screen.toggle_pixel(5, 5));
+    assert_eq!(screen.lit_pixels().count(), 0);
+
+    screen.set_pixel(1, 1, true);
+    screen.clear();
+    assert!(!screen.get_pixel(1, 1));
+}
+
+#[test]
+fn scrolls_the_pixels() {
+    let mut screen = Screen::new(64, 32).unwrap();
+    screen.set_pixel(0, 0, true);
+    screen.set_pixel(6, 1, true);
+    screen.set_pixel(63, 31, true);
+
+    screen.scroll_down(2);
+    // The pixel on the bottom row went off the screen
+    assert_eq!(screen.lit_pixels().collect::<Vec<_>>(), [(0, 2), (6, 3)]);
+    // The pixel that crosses into the next byte moves with the rest
+    screen.scroll_right(4);
+    assert_eq!(screen.lit_pixels().collect::<Vec<_>>(), [(4, 2), (10, 3)]);
+    screen.scroll_left(8);
+    assert_eq!(screen.lit_pixels().collect::<Vec<_>>(), [(2, 3)]);
+    assert!(screen.dirty().iter().all(|is_dirty| *is_dirty));
+}
+
+#[test]
+fn a_screen_that_is_too_big_isnt_made() {
+    assert!(Screen::new(MAX_WIDTH, MAX_BYTES / (MAX_WIDTH / 8)).is_some());
+    assert!(Screen::new(MAX_WIDTH + 8, 1).is_none());
+    assert!(Screen::new(MAX_WIDTH, MAX_BYTES / (MAX_WIDTH / 8) + 1).is_none());
+}
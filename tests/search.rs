@@ -0,0 +1,42 @@
+#![cfg(feature = "alloc")]
+use chip_8::{
+    search::{MemorySearch, SearchFilter},
+    Chip8,
+};
+
+#[test]
+fn each_search_narrows_it_down() {
+    let mut chip8 = Chip8::with_seed(0);
+    // The font is in memory too, which has its own 3s
+    chip8.memory.iter_mut().for_each(|byte| *byte = 0);
+    chip8.memory[0x300] = 3;
+    chip8.memory[0x310] = 3;
+    chip8.memory[0x320] = 3;
+    let mut search = MemorySearch::new(&chip8);
+    assert_eq!(search.addresses().len(), chip8.memory.len());
+
+    search.filter(&chip8, SearchFilter::Equal(3));
+    assert_eq!(search.addresses(), [0x300, 0x310, 0x320]);
+
+    // One goes down, and another one changes and goes back
+    chip8.memory[0x300] = 2;
+    chip8.memory[0x310] = 9;
+    search.filter(&chip8, SearchFilter::Changed);
+    assert_eq!(search.addresses(), [0x300, 0x310]);
+    assert_eq!(search.previous(0x300), Some(2));
+
+    chip8.memory[0x310] = 3;
+    search.filter(&chip8, SearchFilter::Unchanged);
+    assert_eq!(search.addresses(), [0x300]);
+}
+
+#[test]
+fn filters_can_be_typed() {
+    assert_eq!("03".parse(), Ok(SearchFilter::Equal(3)));
+    assert_eq!("== 0xff".parse(), Ok(SearchFilter::Equal(0xff)));
+    assert_eq!(" =1f".parse(), Ok(SearchFilter::Equal(0x1f)));
+    assert_eq!("changed".parse(), Ok(SearchFilter::Changed));
+    assert_eq!("unchanged".parse(), Ok(SearchFilter::Unchanged));
+    assert!("100".parse::<SearchFilter>().is_err());
+    assert!("more".parse::<SearchFilter>().is_err());
+}
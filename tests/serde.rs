@@ -0,0 +1,91 @@
+//! Checks that the interpreter keeps running the same after being saved and
+//! loaded with serde
+#![cfg(feature = "serde")]
+
+mod common;
+
+use chip_8::{Chip8, Quirks};
+use common::{assemble, run_cycles, screen_to_text, start};
+use serde_json::Value;
+
+/// Draws random sprites all over the screen, so the random numbers, the
+/// registers, the index, the stack, and the screen all matter
+fn rom() -> Vec<u8> {
+    assemble(&[
+        0x2206, // call 0x206
+        0x1200, // jp 0x200
+        0x0000, // padding
+        0xc03f, // rnd v0, 0x3f
+        0xc11f, // rnd v1, 0x1f
+        0xf229, // ldf v2
+        0x7201, // add v2, 0x01
+        0xd015, // drw v0, v1, 5
+        0x00ee, // ret
+    ])
+}
+
+/// Saves the interpreter to json and loads it back
+fn round_trip(chip8: &Chip8) -> Chip8 {
+    let json = serde_json::to_string(chip8).expect("the state can be saved");
+    serde_json::from_str(&json).expect("the state can be loaded")
+}
+
+#[test]
+fn runs_the_same_after_loading() {
+    let mut original = start(&rom(), Quirks::default());
+    original.enable_decode_cache();
+    run_cycles(&mut original, 100);
+
+    let mut loaded = round_trip(&original);
+    assert_eq!(loaded.registers, original.registers);
+    assert_eq!(loaded.program_counter, original.program_counter);
+    assert_eq!(screen_to_text(&loaded), screen_to_text(&original));
+
+    // The random numbers have to keep going from the same place as well
+    run_cycles(&mut original, 100);
+    run_cycles(&mut loaded, 100);
+    assert_eq!(loaded.registers, original.registers);
+    assert_eq!(loaded.index, original.index);
+    assert_eq!(loaded.stack, original.stack);
+    assert_eq!(screen_to_text(&loaded), screen_to_text(&original));
+}
+
+#[test]
+fn keeps_the_extensions() {
+    let mut chip8 = start(&rom(), Quirks::default());
+    chip8.enable_chip8x();
+    run_cycles(&mut chip8, 20);
+    let loaded = round_trip(&chip8);
+    assert!(loaded.is_chip8x());
+    assert_eq!(loaded.screen(), chip8.screen());
+}
+
+/// Saves the interpreter to json, changes the field, and tries to load it back
+fn load_changed(chip8: &Chip8, field: &str, value: Value) -> Result<Chip8, serde_json::Error> {
+    let mut json = serde_json::to_value(chip8).expect("the state can be saved");
+    json[field] = value;
+    serde_json::from_value(json)
+}
+
+#[test]
+fn states_that_cant_happen_arent_loaded() {
+    let chip8 = start(&rom(), Quirks::default());
+    let error = load_changed(&chip8, "stack_pointer", 20.into())
+        .err()
+        .expect("a stack pointer past the top of the stack isn't loaded");
+    assert!(error.to_string().contains("the stack pointer is 20"));
+    assert!(load_changed(&chip8, "stack_pointer", 16.into()).is_ok());
+    assert!(load_changed(&chip8, "memory", Value::Array(Vec::new())).is_err());
+}
+
+#[test]
+fn the_decode_cache_is_made_the_size_of_memory() {
+    let mut chip8 = start(&rom(), Quirks::default());
+    chip8.enable_decode_cache();
+    // Neither a cache that is too short nor one that is far too long to
+    // allocate matters, it gets made again
+    for length in [2, u64::MAX] {
+        let mut loaded = load_changed(&chip8, "decode_cache", length.into()).unwrap();
+        run_cycles(&mut loaded, 100);
+    }
+}
@@ -0,0 +1,370 @@
+//! Runs the single-step test vectors, where each one sets the interpreter up,
+//! runs exactly one instruction, and says what everything should be after it.
+//! That checks every instruction on it's own, including the corners that roms
+//! rarely get to, without needing any roms.
+//!
+//! The vectors in `tests/vectors` are always ran, along with every `.json` in
+//! the directory that `SINGLE_STEP_VECTORS` is set to, for running a bigger
+//! set of them that isn't kept in this repo. Each file is a list of tests like
+//! this one, where the numbers are decimal, since JSON doesn't have hex:
+//!
+//! ```json
+//! {
+//!   "name": "8xy4 carries into vf",
+//!   "quirks": "default",
+//!   "opcode": "8124",
+//!   "initial": { "pc": 512, "v": [0, 200, 100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0] },
+//!   "final": { "v": [0, 44, 100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1] }
+//! }
+//! ```
+//!
+//! The states can have:
+//! - `pc`, `i`, `delay`, and `sound`
+//! - `v`, all 16 registers
+//! - `stack`, the addresses that `00ee` returns to, with the top last
+//! - `keys`, the keys that are held down
+//! - `ram`, pairs of an address and the byte at it
+//! - `screen`, pairs of the coordinates of each pixel that is on
+//!
+//! Anything the initial state leaves out is what a freshly loaded interpreter
+//! has, so memory has the font in it, and the screen is clear. Anything the
+//! final state leaves out is expected to be the same as it was, except for the
+//! program counter, which is expected to be on the next instruction. `ram` in
+//! the final state only lists the bytes that changed, every other byte is
+//! still checked to be the same. `opcode` is written to memory at `pc` before
+//! it runs, it can be left out when `ram` already has it. `quirks` is the name
+//! of a set of quirks, like with `--quirks`, and is the default set without it.
+
+use chip_8::{Chip8, Quirks};
+use serde_json::Value;
+use std::{env, fs, path::PathBuf};
+
+/// Everything that a vector can say about the interpreter
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct State {
+    pc: usize,
+    i: usize,
+    v: [u8; 16],
+    /// The return addresses, with the top last
+    stack: Vec<usize>,
+    delay: u8,
+    sound: u8,
+    keys: Vec<u8>,
+    /// All of memory, not only the bytes that the vector listed
+    memory: Vec<u8>,
+    /// Every pixel that is on, going across each row from the top
+    screen: Vec<(usize, usize)>,
+}
+
+impl State {
+    /// Reads what the interpreter has
+    fn of(chip8: &Chip8) -> State {
+        let depth = chip8.stack_pointer.min(chip8.stack.len());
+        State {
+            pc: chip8.program_counter,
+            i: chip8.index,
+            v: chip8.registers,
+            // The interpreter keeps where each call was, which is one
+            // instruction before where it returns to
+            stack: chip8.stack[..depth].iter().map(|call| call + 2).collect(),
+            delay: chip8.delay,
+            sound: chip8.sound,
+            keys: (0..16).filter(|key| chip8.is_key_pressed(*key)).collect(),
+            memory: chip8.memory.to_vec(),
+            screen: chip8.screen().lit_pixels().collect(),
+        }
+    }
+
+    /// Sets the interpreter up to be in this state
+    fn apply(&self, chip8: &mut Chip8) {
+        chip8.program_counter = self.pc;
+        chip8.index = self.i;
+        chip8.registers = self.v;
+        chip8.stack_pointer = self.stack.len();
+        for (level, address) in self.stack.iter().enumerate() {
+            chip8.stack[level] = address - 2;
+        }
+        chip8.delay = self.delay;
+        chip8.sound = self.sound;
+        for key in &self.keys {
+            chip8.press_key(*key);
+        }
+        chip8.memory.copy_from_slice(&self.memory);
+        for (x, y) in &self.screen {
+            chip8.screen_mut().set_pixel(*x, *y, true);
+        }
+    }
+
+    /// Reads the state out of the vector, where anything that is left out is
+    /// the same as `base`
+    fn parse(value: &Value, base: &State) -> Result<State, String> {
+        let mut state = base.clone();
+        let object = value.as_object().ok_or("the state isn't an object")?;
+        for (field, value) in object {
+            match field.as_str() {
+                "pc" => state.pc = number(value)?,
+                "i" => state.i = number(value)?,
+                "delay" => state.delay = byte(value)?,
+                "sound" => state.sound = byte(value)?,
+                "v" => {
+                    let registers = list(value, byte)?;
+                    if registers.len() != 16 {
+                        return Err(String::from("v needs all 16 registers"));
+                    }
+                    state.v.copy_from_slice(&registers);
+                }
+                "stack" => state.stack = list(value, number)?,
+                "keys" => {
+                    state.keys = list(value, byte)?;
+                    state.keys.sort_unstable();
+                    state.keys.dedup();
+                }
+                "ram" => {
+                    for (address, byte) in list(value, |pair| pair_of(pair, number, byte))? {
+                        *state
+                            .memory
+                            .get_mut(address)
+                            .ok_or_else(|| format!("{} isn't in memory", address))? = byte;
+                    }
+                }
+                "screen" => {
+                    state.screen = list(value, |pair| pair_of(pair, number, number))?;
+                    // In the order that the interpreter gives them
+                    state.screen.sort_by_key(|(x, y)| (*y, *x));
+                }
+                other => return Err(format!("{} isn't something that a state can have", other)),
+            }
+        }
+        Ok(state)
+    }
+
+    /// Lists everything that is different between this and `expected`
+    fn differences(&self, expected: &State) -> Vec<String> {
+        let mut differences = Vec::new();
+        let mut compare = |name: &str, actual: String, expected: String| {
+            if actual != expected {
+                differences.push(format!("{}: expected {}, got {}", name, expected, actual));
+            }
+        };
+        compare(
+            "pc",
+            format!("{:03x}", self.pc),
+            format!("{:03x}", expected.pc),
+        );
+        compare(
+            "i",
+            format!("{:03x}", self.i),
+            format!("{:03x}", expected.i),
+        );
+        compare(
+            "v",
+            format!("{:02x?}", self.v),
+            format!("{:02x?}", expected.v),
+        );
+        compare(
+            "stack",
+            format!("{:03x?}", self.stack),
+            format!("{:03x?}", expected.stack),
+        );
+        compare("delay", self.delay.to_string(), expected.delay.to_string());
+        compare("sound", self.sound.to_string(), expected.sound.to_string());
+        compare(
+            "keys",
+            format!("{:x?}", self.keys),
+            format!("{:x?}", expected.keys),
+        );
+        for (address, (actual, expected)) in self.memory.iter().zip(&expected.memory).enumerate() {
+            compare(
+                &format!("ram at {:03x}", address),
+                format!("{:02x}", actual),
+                format!("{:02x}", expected),
+            );
+        }
+        compare(
+            "screen",
+            format!("{:?}", self.screen),
+            format!("{:?}", expected.screen),
+        );
+        differences
+    }
+}
+
+/// Reads a number that has to be positive
+fn number(value: &Value) -> Result<usize, String> {
+    value
+        .as_u64()
+        .map(|number| number as usize)
+        .ok_or_else(|| format!("{} isn't a positive number", value))
+}
+
+/// Reads a number that has to fit in a byte
+fn byte(value: &Value) -> Result<u8, String> {
+    value
+        .as_u64()
+        .filter(|number| *number <= 0xff)
+        .map(|number| number as u8)
+        .ok_or_else(|| format!("{} isn't a byte", value))
+}
+
+/// Reads a list, with each item read by `item`
+fn list<T>(value: &Value, item: impl Fn(&Value) -> Result<T, String>) -> Result<Vec<T>, String> {
+    value
+        .as_array()
+        .ok_or_else(|| format!("{} isn't a list", value))?
+        .iter()
+        .map(item)
+        .collect()
+}
+
+/// Reads a list of exactly two things
+fn pair_of<A, B>(
+    value: &Value,
+    first: impl Fn(&Value) -> Result<A, String>,
+    second: impl Fn(&Value) -> Result<B, String>,
+) -> Result<(A, B), String> {
+    match value.as_array().map(Vec::as_slice) {
+        Some([a, b]) => Ok((first(a)?, second(b)?)),
+        _ => Err(format!("{} isn't a pair", value)),
+    }
+}
+
+/// Runs one vector, and returns what went wrong, if anything
+fn run_vector(vector: &Value) -> Result<(), String> {
+    let quirks = match vector.get("quirks") {
+        Some(name) => name
+            .as_str()
+            .and_then(Quirks::from_name)
+            .ok_or_else(|| format!("{} isn't a set of quirks", name))?,
+        None => Quirks::default(),
+    };
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.quirks = quirks;
+
+    let mut initial = State::parse(
+        vector
+            .get("initial")
+            .ok_or("it doesn't have an initial state")?,
+        &State::of(&chip8),
+    )?;
+    if let Some(opcode) = vector.get("opcode") {
+        let opcode = opcode
+            .as_str()
+            .and_then(|opcode| u16::from_str_radix(opcode, 16).ok())
+            .ok_or_else(|| format!("{} isn't an opcode", opcode))?;
+        let bytes = opcode.to_be_bytes();
+        initial
+            .memory
+            .get_mut(initial.pc..initial.pc + 2)
+            .ok_or("pc isn't in memory")?
+            .copy_from_slice(&bytes);
+    }
+    initial.apply(&mut chip8);
+
+    let next = State {
+        pc: initial.pc + 2,
+        ..initial.clone()
+    };
+    let expected = State::parse(
+        vector.get("final").ok_or("it doesn't have a final state")?,
+        &next,
+    )?;
+
+    chip8
+        .clock()
+        .map_err(|error| format!("the instruction stopped the rom: {}", error))?;
+    let differences = State::of(&chip8).differences(&expected);
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(differences.join("\n"))
+    }
+}
+
+/// Runs every vector in the file, and returns a line for each one that failed
+fn run_file(path: &PathBuf) -> Vec<String> {
+    let vectors: Value = match fs::read_to_string(path)
+        .map_err(|error| error.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|error| error.to_string()))
+    {
+        Ok(vectors) => vectors,
+        Err(error) => return vec![format!("{}: {}", path.display(), error)],
+    };
+    let vectors = match vectors.as_array() {
+        Some(vectors) => vectors,
+        None => return vec![format!("{}: it isn't a list of tests", path.display())],
+    };
+    vectors
+        .iter()
+        .enumerate()
+        .filter_map(|(number, vector)| {
+            let name = vector
+                .get("name")
+                .and_then(Value::as_str)
+                .map_or_else(|| format!("test {}", number), String::from);
+            run_vector(vector)
+                .err()
+                .map(|error| format!("{}: {}\n{}", path.display(), name, error))
+        })
+        .collect()
+}
+
+/// Every `.json` in the directory, in order
+fn vector_files(directory: PathBuf) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(&directory)
+        .unwrap_or_else(|error| panic!("{} couldn't be read: {}", directory.display(), error))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Runs every file, and fails with every vector that didn't pass
+fn assert_vectors_pass(files: Vec<PathBuf>) {
+    let failures: Vec<String> = files.iter().flat_map(run_file).collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+#[test]
+fn vectors_in_the_repo_pass() {
+    let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("vectors");
+    assert_vectors_pass(vector_files(directory));
+}
+
+#[test]
+fn vectors_from_single_step_vectors_pass() {
+    match env::var_os("SINGLE_STEP_VECTORS") {
+        Some(directory) => assert_vectors_pass(vector_files(PathBuf::from(directory))),
+        None => eprintln!("skipping, SINGLE_STEP_VECTORS isn't set"),
+    }
+}
+
+#[test]
+fn wrong_vectors_fail() {
+    // Says that 6105 loads 6 instead of 5, and leaves the pc alone
+    let vector = serde_json::json!({
+        "opcode": "6105",
+        "initial": {},
+        "final": { "pc": 512, "v": [0, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0] }
+    });
+    let error = run_vector(&vector).unwrap_err();
+    assert!(error.contains("pc: expected 200, got 202"), "{}", error);
+    assert!(error.contains("v: expected [00, 06"), "{}", error);
+
+    // The opcode went into memory, so any other byte that changes is caught
+    let vector = serde_json::json!({
+        "initial": { "ram": [[512, 96], [513, 5]] },
+        "final": { "ram": [[600, 1]] }
+    });
+    let error = run_vector(&vector).unwrap_err();
+    assert!(
+        error.contains("ram at 258: expected 01, got 00"),
+        "{}",
+        error
+    );
+}
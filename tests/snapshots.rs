@@ -0,0 +1,151 @@
+//! Runs small roms and compares the screen that they leave behind to the
+//! snapshots in `tests/snapshots`, which protects drawing from breaking
+
+mod common;
+
+use chip_8::{Chip8, Quirks};
+use common::{assemble, assert_snapshot, run, run_cycles, start};
+
+/// Jumps back to itself forever, at the address it is put at
+fn halt(address: u16) -> u16 {
+    0x1000 | address
+}
+
+/// Draws an `f` from the font at the coordinates, with or without the wrap quirk
+fn draw_f_at(x: u8, y: u8, wrap: bool) -> Chip8 {
+    let opcodes = [
+        0x600f,            // ld v0, 0xf
+        0xf029,            // ld f, v0
+        0x6100 | x as u16, // ld v1, x
+        0x6200 | y as u16, // ld v2, y
+        0xd125,            // drw v1, v2, 5
+        halt(0x20a),
+    ];
+    let quirks = Quirks {
+        wrap,
+        ..Quirks::default()
+    };
+    let mut chip8 = start(&assemble(&opcodes), quirks);
+    run_cycles(&mut chip8, opcodes.len());
+    chip8
+}
+
+#[test]
+fn font() {
+    // Draws every hex digit from the built in font, 8 to a row
+    let mut opcodes = Vec::new();
+    for digit in 0..16u16 {
+        opcodes.push(0x6000 | digit); // ld v0, digit
+        opcodes.push(0xf029); // ld f, v0
+        opcodes.push(0x6100 | ((digit % 8) * 8)); // ld v1, x
+        opcodes.push(0x6200 | ((digit / 8) * 8)); // ld v2, y
+        opcodes.push(0xd125); // drw v1, v2, 5
+    }
+    opcodes.push(halt(0x200 + opcodes.len() as u16 * 2));
+
+    let chip8 = run(&assemble(&opcodes), opcodes.len());
+    assert_snapshot("font", &chip8);
+}
+
+#[test]
+fn big_font() {
+    // Draws every hex digit from the big font, 7 to a row with a gap between them
+    let mut opcodes = Vec::new();
+    for digit in 0..16u16 {
+        opcodes.push(0x6000 | digit); // ld v0, digit
+        opcodes.push(0xf030); // ld hf, v0
+        opcodes.push(0x6100 | ((digit % 7) * 9)); // ld v1, x
+        opcodes.push(0x6200 | ((digit / 7) * 11)); // ld v2, y
+        opcodes.push(0xd12a); // drw v1, v2, 10
+    }
+    opcodes.push(halt(0x200 + opcodes.len() as u16 * 2));
+
+    let chip8 = run(&assemble(&opcodes), opcodes.len());
+    assert_snapshot("big_font", &chip8);
+}
+
+#[test]
+fn xor_and_collision() {
+    let opcodes = [
+        0x6008, // ld v0, 8
+        0xf029, // ld f, v0
+        0x6104, // ld v1, 4
+        0x6204, // ld v2, 4
+        0xd125, // drw v1, v2, 5
+        0x6000, // ld v0, 0
+        0xf029, // ld f, v0
+        0xd125, // drw v1, v2, 5, which only leaves the middle bar of the 8
+        0x8300, // ld v3, v0, which is 0, so the flag below is the only thing shown
+        0xf329, // ld f, v3
+        0x6114, // ld v1, 20
+        0x3f01, // se vf, 1, skips the jump if the second draw collided
+        0x1220, // jp 0x220
+        0xd125, // drw v1, v2, 5, only drawn when there was a collision
+        halt(0x21c),
+        0x0000,
+        halt(0x220),
+    ];
+    let chip8 = run(&assemble(&opcodes), 14);
+    assert_snapshot("xor_and_collision", &chip8);
+}
+
+#[test]
+fn wraps_around_the_right_edge() {
+    // The right half ends up on the left side
+    let chip8 = draw_f_at(62, 0, true);
+    assert_snapshot("wraps_around_the_right_edge", &chip8);
+}
+
+#[test]
+fn wraps_around_the_bottom_edge() {
+    // The bottom 2 rows end up at the top
+    let chip8 = draw_f_at(8, 29, true);
+    assert_snapshot("wraps_around_the_bottom_edge", &chip8);
+}
+
+#[test]
+fn clips_at_the_right_edge() {
+    // Only the left 2 columns are drawn
+    let chip8 = draw_f_at(62, 0, false);
+    assert_snapshot("clips_at_the_right_edge", &chip8);
+}
+
+#[test]
+fn clips_at_the_bottom_edge() {
+    // Only the top 3 rows are drawn
+    let chip8 = draw_f_at(8, 29, false);
+    assert_snapshot("clips_at_the_bottom_edge", &chip8);
+}
+
+#[test]
+fn clips_at_the_corner() {
+    // Only the top left pixel is drawn
+    let chip8 = draw_f_at(63, 31, false);
+    assert_snapshot("clips_at_the_corner", &chip8);
+}
+
+#[test]
+fn coordinates_past_the_edge_wrap_around() {
+    // 64 + 4 and 32 + 2 start at (4, 2), even though the sprite gets clipped
+    let chip8 = draw_f_at(68, 34, false);
+    assert_eq!(
+        chip8.screen().as_bytes(),
+        draw_f_at(4, 2, false).screen().as_bytes()
+    );
+}
+
+#[test]
+fn clear_screen() {
+    let opcodes = [
+        0x6001, // ld v0, 1
+        0xf029, // ld f, v0
+        0xd005, // drw v0, v0, 5
+        0x00e0, // cls
+        0x6002, // ld v0, 2
+        0xf029, // ld f, v0
+        0xd005, // drw v0, v0, 5, draws a 2 at (2, 2) onto the cleared screen
+        halt(0x20e),
+    ];
+    let chip8 = run(&assemble(&opcodes), opcodes.len());
+    assert_snapshot("clear_screen", &chip8);
+}
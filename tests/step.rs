@@ -0,0 +1,139 @@
+//! Checks what `Chip8::clock` says about each instruction that it runs, and
+//! what the hooks get told
+
+mod common;
+
+use chip_8::{Quirks, StepInfo};
+use common::{assemble, start};
+#[cfg(feature = "alloc")]
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn says_what_each_instruction_did() {
+    let rom = assemble(&[
+        0x6105, // ld v1, 0x05
+        0x3105, // se v1, 0x05, which skips
+        0x00e0, // cls, which gets skipped
+        0x3106, // se v1, 0x06, which doesn't skip
+        0xd115, // drw v1, v1, 5
+        0x1200, // jp 0x200
+    ]);
+    let mut chip8 = start(&rom, Quirks::default());
+    let step = |pc, opcode, mnemonic, drew, jumped, skipped| StepInfo {
+        pc,
+        opcode,
+        mnemonic,
+        drew,
+        jumped,
+        skipped,
+    };
+
+    assert_eq!(
+        chip8.clock().unwrap(),
+        step(0x200, 0x6105, "ld", false, false, false)
+    );
+    assert_eq!(
+        chip8.clock().unwrap(),
+        step(0x202, 0x3105, "se", false, false, true)
+    );
+    assert_eq!(
+        chip8.clock().unwrap(),
+        step(0x206, 0x3106, "se", false, false, false)
+    );
+    assert_eq!(
+        chip8.clock().unwrap(),
+        step(0x208, 0xd115, "drw", true, false, false)
+    );
+    assert_eq!(
+        chip8.clock().unwrap(),
+        step(0x20a, 0x1200, "jp", false, true, false)
+    );
+}
+
+#[test]
+fn drawing_is_still_redrawn_after_other_instructions() {
+    let rom = assemble(&[
+        0x00e0, // cls
+        0x6000, // ld v0, 0x00
+    ]);
+    let mut chip8 = start(&rom, Quirks::default());
+    chip8.take_redraw();
+    assert!(chip8.clock().unwrap().drew);
+    assert!(!chip8.clock().unwrap().drew);
+    // The frontend still has to see that the screen was cleared
+    assert!(chip8.take_redraw());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn hooks_see_every_instruction() {
+    let rom = assemble(&[
+        0x6105, // ld v1, 0x05
+        0x1200, // jp 0x200
+    ]);
+    let mut chip8 = start(&rom, Quirks::default());
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let before = Arc::clone(&seen);
+    chip8.set_pre_hook(Box::new(move |chip8, step| {
+        before
+            .lock()
+            .unwrap()
+            .push(("before", step.pc, chip8.registers[1]));
+    }));
+    let after = Arc::clone(&seen);
+    chip8.set_post_hook(Box::new(move |chip8, step| {
+        assert_eq!(step.jumped, step.mnemonic == "jp");
+        after
+            .lock()
+            .unwrap()
+            .push(("after", step.pc, chip8.registers[1]));
+    }));
+
+    chip8.clock().unwrap();
+    chip8.clock().unwrap();
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec![
+            ("before", 0x200, 0),
+            ("after", 0x200, 5),
+            ("before", 0x202, 5),
+            ("after", 0x202, 5),
+        ]
+    );
+
+    chip8.clear_hooks();
+    chip8.clock().unwrap();
+    assert_eq!(seen.lock().unwrap().len(), 4);
+}
+
+#[test]
+fn unknown_opcodes_pause_the_rom_when_asked_to() {
+    let rom = assemble(&[
+        0x6105, // ld v1, 0x05
+        0xffff, // not an instruction
+        0x6106, // ld v1, 0x06
+    ]);
+    let mut chip8 = start(&rom, Quirks::default());
+    chip8.set_break_on_unknown(true);
+    chip8.clock().unwrap();
+    assert_eq!(chip8.clock().unwrap().mnemonic, "nai");
+    assert!(chip8.is_paused());
+    assert_eq!(chip8.program_counter, 0x202);
+    #[cfg(feature = "alloc")]
+    {
+        let unknown = chip8.unknown_opcode().unwrap();
+        assert_eq!((unknown.address, unknown.opcode), (0x202, 0xffff));
+    }
+
+    // It stays stuck on it until it is told not to
+    chip8.set_paused(false);
+    chip8.clock().unwrap();
+    assert_eq!(chip8.program_counter, 0x202);
+    chip8.set_break_on_unknown(false);
+    chip8.set_paused(false);
+    chip8.clock().unwrap();
+    chip8.clock().unwrap();
+    assert_eq!(chip8.registers[1], 6);
+    #[cfg(feature = "alloc")]
+    assert_eq!(chip8.unknown_opcode(), None);
+}
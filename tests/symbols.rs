@@ -0,0 +1,45 @@
+#![cfg(feature = "alloc")]
+use chip_8::symbols::Symbols;
+
+#[test]
+fn names_and_addresses_can_go_either_way_around() {
+    let symbols: Symbols = "\
+# Comments and blank lines are left out
+
+main 0x200
+0x23a game_loop   # the main loop
+draw_score = $2f0
+lives: 300
+"
+    .parse()
+    .unwrap();
+    assert_eq!(symbols.len(), 4);
+    assert_eq!(symbols.address("main"), Some(0x200));
+    assert_eq!(symbols.name(0x23a), Some("game_loop"));
+    assert_eq!(symbols.address("draw_score"), Some(0x2f0));
+    assert_eq!(symbols.address("lives"), Some(0x300));
+    assert_eq!(symbols.name(0x201), None);
+    assert_eq!(
+        symbols
+            .iter()
+            .map(|(address, _)| address)
+            .collect::<Vec<_>>(),
+        vec![0x200, 0x23a, 0x2f0, 0x300]
+    );
+}
+
+#[test]
+fn an_address_keeps_the_first_name_it_was_given() {
+    let symbols: Symbols = "start 0x200\nmain 0x200".parse().unwrap();
+    assert_eq!(symbols.name(0x200), Some("start"));
+    assert_eq!(symbols.address("main"), Some(0x200));
+}
+
+#[test]
+fn lines_that_cant_be_read_say_where_they_are() {
+    let error = "main 0x200\nmain\n".parse::<Symbols>().unwrap_err();
+    assert_eq!(error.line, 2);
+    let error = "main 0x200\n\nplayer enemy".parse::<Symbols>().unwrap_err();
+    assert_eq!(error.line, 3);
+    assert!("main 0xzz".parse::<Symbols>().is_err());
+}
@@ -0,0 +1,269 @@
+//! Runs the roms from the community CHIP-8 test suite
+//! (https://github.com/Timendus/chip8-test-suite) with each set of quirks, and
+//! compares the results they print to the snapshots in `tests/snapshots`.
+//!
+//! The roms aren't kept in this repo, so the tests that need them are ignored
+//! unless they are asked for. To run them, put `3-corax+.ch8`, `4-flags.ch8`,
+//! and `5-quirks.ch8` into `tests/roms`, and run
+//! `cargo test --test test_suite -- --ignored`, which fails if any of them
+//! aren't there. CI downloads them and runs it every time. The quirks
+//! themselves are also checked below with small roms that don't need the
+//! suite.
+//!
+//! The opcodes and flags roms don't depend on the quirks, so a rom that passes
+//! draws the same screen with every set of them, which is checked before the
+//! screen is compared to the snapshot. `BLESS=1` writes the snapshots, which
+//! then have to be looked over to see that every test has it's checkmark.
+
+mod common;
+
+use chip_8::{Chip8, Quirks};
+use common::{assemble, assert_snapshot, run_cycles, screen_to_text, start};
+use std::{fs, path::PathBuf};
+
+/// Every set of quirks that the suite is ran with, along with the number the
+/// quirks rom uses to pick that platform without waiting for a key press.
+/// CHIP-48 doesn't have one of it's own, so it is checked against SUPER-CHIP,
+/// and it's snapshot shows the quirks where it is different.
+const PLATFORMS: [(&str, Quirks, u8); 4] = [
+    ("vip", Quirks::VIP, 1),
+    ("schip", Quirks::SCHIP, 2),
+    ("chip48", Quirks::CHIP48, 2),
+    ("xochip", Quirks::XOCHIP, 3),
+];
+
+/// More than enough instructions for any of the roms to finish
+const CYCLES: usize = 1_000_000;
+
+/// Reads one of the suite's roms from `tests/roms`
+fn suite_rom(name: &str) -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("roms")
+        .join(name);
+    fs::read(&path).unwrap_or_else(|error| {
+        panic!(
+            "{} can't be read, the suite's roms have to be put in tests/roms: {}",
+            path.display(),
+            error
+        )
+    })
+}
+
+/// Runs the rom with every set of quirks, and returns what it printed with
+/// each of them
+fn run_suite_rom(name: &str) -> Vec<(&'static str, Chip8)> {
+    let rom = suite_rom(name);
+    PLATFORMS
+        .iter()
+        .map(|(platform_name, quirks, platform)| {
+            let mut chip8 = start(&rom, *quirks);
+            // The quirks rom reads this to skip it's menu, the others ignore it
+            chip8.memory[0x1ff] = *platform;
+            run_cycles(&mut chip8, CYCLES);
+            (*platform_name, chip8)
+        })
+        .collect()
+}
+
+/// Checks that a rom that doesn't depend on the quirks printed the same thing
+/// with all of them, and that it is what the snapshot has
+fn assert_passes_everywhere(name: &str, snapshot: &str) {
+    let results = run_suite_rom(name);
+    let (first_name, first) = &results[0];
+    for (platform_name, chip8) in &results[1..] {
+        assert!(
+            screen_to_text(chip8) == screen_to_text(first),
+            "{} printed something different with the {} quirks than with the {} quirks\n\n{}:\n{}\n{}:\n{}",
+            name,
+            platform_name,
+            first_name,
+            first_name,
+            screen_to_text(first),
+            platform_name,
+            screen_to_text(chip8)
+        );
+    }
+    assert_snapshot(snapshot, first);
+}
+
+#[test]
+#[ignore = "needs the suite's roms in tests/roms"]
+fn opcodes() {
+    assert_passes_everywhere("3-corax+.ch8", "suite-corax");
+}
+
+#[test]
+#[ignore = "needs the suite's roms in tests/roms"]
+fn flags() {
+    assert_passes_everywhere("4-flags.ch8", "suite-flags");
+}
+
+#[test]
+#[ignore = "needs the suite's roms in tests/roms"]
+fn quirks() {
+    // Every platform gets a different screen here, one with each of the
+    // quirks marked as what that platform should do
+    for (platform_name, chip8) in run_suite_rom("5-quirks.ch8") {
+        assert_snapshot(&format!("suite-quirks-{}", platform_name), &chip8);
+    }
+}
+
+/// Runs the opcodes with each set of quirks, and returns the registers
+fn registers_after(opcodes: &[u16], quirks: Quirks) -> ([u8; 16], usize) {
+    let mut chip8 = start(&assemble(opcodes), quirks);
+    run_cycles(&mut chip8, opcodes.len());
+    (chip8.registers, chip8.index)
+}
+
+#[test]
+fn shift_quirk() {
+    let opcodes = [
+        0x6003, // ld v0, 3
+        0x6110, // ld v1, 0x10
+        0x8016, // shr v0, v1
+    ];
+    // Register y gets shifted into register x
+    assert_eq!(registers_after(&opcodes, Quirks::VIP).0[0], 0x08);
+    // Register x gets shifted in place, and the bit that fell off is in vf
+    let (registers, _) = registers_after(&opcodes, Quirks::SCHIP);
+    assert_eq!(registers[0], 0x01);
+    assert_eq!(registers[0xf], 1);
+}
+
+#[test]
+fn memory_increment_quirk() {
+    let opcodes = [
+        0xa300, // ld i, 0x300
+        0xf255, // ld [i], v2
+    ];
+    assert_eq!(registers_after(&opcodes, Quirks::VIP).1, 0x303);
+    assert_eq!(registers_after(&opcodes, Quirks::SCHIP).1, 0x300);
+}
+
+#[test]
+fn jump_quirk() {
+    let opcodes = [
+        0x6004, // ld v0, 4
+        0x6208, // ld v2, 8
+        0xb200, // jp v0, 0x200, which is jp v2, 0x200 with the quirk
+    ];
+    let mut vip = start(&assemble(&opcodes), Quirks::VIP);
+    run_cycles(&mut vip, opcodes.len());
+    assert_eq!(vip.program_counter, 0x204);
+
+    let mut schip = start(&assemble(&opcodes), Quirks::SCHIP);
+    run_cycles(&mut schip, opcodes.len());
+    assert_eq!(schip.program_counter, 0x208);
+}
+
+#[test]
+fn vf_reset_quirk() {
+    // `8xy1`, `8xy2`, and `8xy3` all reset it
+    for logic in [0x8011, 0x8012, 0x8013] {
+        let opcodes = [
+            0x6f05, // ld vf, 5
+            logic,  // or, and, or xor v0, v1
+        ];
+        assert_eq!(registers_after(&opcodes, Quirks::VIP).0[0xf], 0);
+        assert_eq!(registers_after(&opcodes, Quirks::SCHIP).0[0xf], 5);
+    }
+}
+
+#[test]
+fn display_wait_quirk() {
+    let opcodes = [
+        0xd005, // drw v0, v0, 5
+        0x7001, // add v0, 1
+        0xd005, // drw v0, v0, 5
+        0x7001, // add v0, 1
+        0x1208, // jp 0x208, which stops here
+    ];
+    // Both sprites get drawn in the first frame
+    let mut schip = start(&assemble(&opcodes), Quirks::SCHIP);
+    schip.frame(opcodes.len()).unwrap();
+    assert_eq!(schip.registers[0], 2);
+
+    // The first sprite waits for the timers to tick, and the second one waits
+    // for them to tick again
+    let mut vip = start(&assemble(&opcodes), Quirks::VIP);
+    vip.frame(opcodes.len()).unwrap();
+    assert_eq!((vip.program_counter, vip.registers[0]), (0x200, 0));
+    vip.frame(opcodes.len()).unwrap();
+    assert_eq!((vip.program_counter, vip.registers[0]), (0x204, 1));
+    vip.frame(opcodes.len()).unwrap();
+    assert_eq!((vip.program_counter, vip.registers[0]), (0x208, 2));
+}
+
+#[test]
+fn loads_at_another_address() {
+    // `1602` jumps to the instruction after it, which only works when the rom
+    // is at `0x600` like on the ETI-660
+    let opcodes = [
+        0x1602, // jp 0x602
+        0x6007, // ld v0, 7
+        0x1604, // jp 0x604, which stops here
+    ];
+    let mut chip8 = Chip8::with_load_address(0, 0x600);
+    chip8.load(&assemble(&opcodes)).unwrap();
+    assert_eq!(chip8.program_counter, 0x600);
+    chip8.frame(opcodes.len()).unwrap();
+    assert_eq!((chip8.program_counter, chip8.registers[0]), (0x604, 7));
+    // Nothing gets put where roms usually go
+    assert_eq!(chip8.memory[0x200], 0);
+}
+
+#[test]
+fn saves_and_loads_the_flags() {
+    let opcodes = [
+        0x6005, // ld v0, 5
+        0x6107, // ld v1, 7
+        0xf175, // ldrx v1
+        0x6000, // ld v0, 0
+        0x6100, // ld v1, 0
+        0xf085, // ldxr v0
+        0x120c, // jp 0x20c, which stops here
+    ];
+    let mut chip8 = start(&assemble(&opcodes), Quirks::default());
+    chip8.frame(opcodes.len()).unwrap();
+    assert_eq!(chip8.flags[..3], [5, 7, 0]);
+    // Only register 0 gets loaded back
+    assert_eq!(chip8.registers[..2], [5, 0]);
+}
+
+#[test]
+fn exit_stops_the_rom() {
+    let opcodes = [
+        0x6001, // ld v0, 1
+        0x00fd, // exit
+        0x6002, // ld v0, 2
+    ];
+    let mut chip8 = start(&assemble(&opcodes), Quirks::default());
+    chip8.frame(opcodes.len() + 5).unwrap();
+    assert!(chip8.has_exited());
+    // Nothing after the exit gets ran
+    assert_eq!((chip8.program_counter, chip8.registers[0]), (0x202, 1));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn decode_cache_sees_self_modifying_code() {
+    let opcodes = [
+        0x2210, // call 0x210, which decodes the instruction that gets changed
+        0xa212, // ldi 0x212
+        0x6061, // ld v0, 0x61
+        0x6109, // ld v1, 9
+        0xf155, // ldix v1, which turns `ld v1, 5` into `ld v1, 9`
+        0x2210, // call 0x210
+        0x120c, // jp 0x20c, which stops here
+        0x0000, //
+        0x6300, // ld v3, 0
+        0x6105, // ld v1, 5
+        0x00ee, // ret
+    ];
+    let mut chip8 = Chip8::with_seed(0);
+    chip8.enable_decode_cache();
+    chip8.load(&assemble(&opcodes)).unwrap();
+    run_cycles(&mut chip8, 13);
+    assert_eq!(chip8.registers[1], 9);
+}